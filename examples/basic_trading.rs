@@ -9,7 +9,7 @@
 //! 6. Cancel the order
 
 use fermi_trade_sdk::{
-    ClientConfig, FermiClient, MarginMode, PerpOrder, PositionEffect, Side, TradingKeypair,
+    ClientConfig, Decimal, FermiClient, MarginMode, PerpOrder, PositionEffect, Side, TradingKeypair,
 };
 
 #[tokio::main]
@@ -33,7 +33,7 @@ async fn main() -> anyhow::Result<()> {
     let config = ClientConfig::default();
 
     // Initialize client
-    let mut client = FermiClient::new(keypair, config).await?;
+    let client = FermiClient::new(keypair, config).await?;
 
     // === Step 1: Airdrop testnet USDC ===
     println!("1. Requesting airdrop of 1000 USDC...");
@@ -82,12 +82,13 @@ async fn main() -> anyhow::Result<()> {
         println!("6. Placing a 10x long order...");
         let order = PerpOrder {
             side: Side::Buy,
-            price: 185.50,    // Price in USDC
-            quantity: 0.1,    // Quantity in base asset (e.g., SOL)
+            price: Decimal::new(18550, 2), // 185.50 USDC
+            quantity: Decimal::new(1, 1),  // 0.1 of the base asset (e.g., SOL)
             leverage: 10,
             position_effect: PositionEffect::Open,
             margin_mode: MarginMode::Cross,
             reduce_only: false,
+            ..Default::default()
         };
 
         match client.place_perp_order(&market.uuid, order).await {
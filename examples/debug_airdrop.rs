@@ -16,7 +16,7 @@ async fn main() -> anyhow::Result<()> {
     println!("Trading account: {}\n", pubkey);
 
     let config = ClientConfig::default();
-    let mut client = FermiClient::new(keypair, config).await?;
+    let client = FermiClient::new(keypair, config).await?;
 
     // Check initial state
     println!("1. Initial account state:");
@@ -50,7 +50,7 @@ async fn main() -> anyhow::Result<()> {
     // Try direct airdrop_to with explicit mint
     println!("\n7. Trying direct airdrop_to with TESTNET_USDC mint...");
     println!("   TESTNET_USDC = {}", TESTNET_USDC);
-    let amount_micro = 5000_000_000u64; // 5000 USDC in micro units
+    let amount_micro = 5_000_000_000u64; // 5000 USDC in micro units
     match client.airdrop_to(&pubkey, TESTNET_USDC, amount_micro).await {
         Ok(_) => println!("   airdrop_to succeeded"),
         Err(e) => println!("   airdrop_to error: {:?}", e),
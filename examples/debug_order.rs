@@ -1,7 +1,7 @@
 //! Debug order signing and submission
 
 use fermi_trade_sdk::{
-    ClientConfig, FermiClient, MarginMode, PerpOrder, PositionEffect, Side, TradingKeypair,
+    ClientConfig, Decimal, FermiClient, MarginMode, PerpOrder, PositionEffect, Side, TradingKeypair,
 };
 use std::time::Duration;
 
@@ -17,7 +17,7 @@ async fn main() -> anyhow::Result<()> {
     println!("Trading account: {}\n", pubkey);
 
     let config = ClientConfig::default();
-    let mut client = FermiClient::new(keypair, config).await?;
+    let client = FermiClient::new(keypair, config).await?;
 
     // Airdrop and wait
     println!("1. Airdrop and wait...");
@@ -38,8 +38,7 @@ async fn main() -> anyhow::Result<()> {
 
     // Get market info
     println!("\n2. Market info...");
-    let markets = client.get_markets().await?;
-    let market = markets.iter().find(|m| m.name == "SOL-PERP").unwrap();
+    let market = client.get_market_by_name("SOL-PERP").await?;
     println!("   UUID: {}", market.uuid);
     println!("   Base mint: {}", market.base_mint);
     println!("   Quote mint: {}", market.quote_mint);
@@ -50,12 +49,13 @@ async fn main() -> anyhow::Result<()> {
     println!("\n3. Placing order...");
     let order = PerpOrder {
         side: Side::Sell,
-        price: 200.0,
-        quantity: 1.0,
+        price: Decimal::from(200),
+        quantity: Decimal::ONE,
         leverage: 5,
         position_effect: PositionEffect::Open,
         margin_mode: MarginMode::Cross,
         reduce_only: false,
+        ..Default::default()
     };
 
     // Calculate what the canonical values should be
@@ -1,7 +1,7 @@
 //! Full trading test: airdrop -> place order -> verify on orderbook -> cancel
 
 use fermi_trade_sdk::{
-    ClientConfig, FermiClient, MarginMode, PerpOrder, PositionEffect, Side, TradingKeypair,
+    ClientConfig, Decimal, FermiClient, MarginMode, PerpOrder, PositionEffect, Side, TradingKeypair,
 };
 use std::time::Duration;
 
@@ -16,7 +16,7 @@ async fn main() -> anyhow::Result<()> {
     println!("Trading account: {}\n", pubkey);
 
     let config = ClientConfig::default();
-    let mut client = FermiClient::new(keypair, config).await?;
+    let client = FermiClient::new(keypair, config).await?;
 
     // === STEP 1: Airdrop and wait for processing ===
     println!("STEP 1: Airdrop 10000 USDC and wait for processing...");
@@ -49,9 +49,7 @@ async fn main() -> anyhow::Result<()> {
 
     // === STEP 2: Get market info ===
     println!("STEP 2: Get SOL-PERP market...");
-    let markets = client.get_markets().await?;
-    let market = markets.iter().find(|m| m.name == "SOL-PERP")
-        .expect("SOL-PERP market not found");
+    let market = client.get_market_by_name("SOL-PERP").await?;
     println!("   Market: {} ({})", market.name, market.uuid);
     println!("   Base decimals: {}, Quote decimals: {}\n", market.base_decimals, market.quote_decimals);
 
@@ -61,22 +59,24 @@ async fn main() -> anyhow::Result<()> {
     println!("   Bids: {}, Asks: {}", book_before.buys.len(), book_before.sells.len());
 
     // Show best bid/ask
-    if let Some(best_bid) = book_before.buys.first() {
-        let bid_price = best_bid.price as f64 / 1_000_000.0;
-        println!("   Best bid: ${:.2}", bid_price);
+    let human_before = book_before.to_human(&market);
+    if let Some(best_bid) = human_before.best_bid() {
+        println!("   Best bid: ${:.2}", best_bid.price);
     }
-    if let Some(best_ask) = book_before.sells.first() {
-        let ask_price = best_ask.price as f64 / 1_000_000.0;
-        println!("   Best ask: ${:.2}", ask_price);
+    if let Some(best_ask) = human_before.best_ask() {
+        println!("   Best ask: ${:.2}", best_ask.price);
     }
     println!();
 
     // === STEP 4: Place a SELL order above market ===
     // Use a price that's clearly above market to ensure it rests on book
-    let test_price = 200.00;  // Well above current ~144
-    let test_qty = 1.0;
+    let test_price = Decimal::from(200); // Well above current ~144
+    let test_qty = Decimal::ONE;
 
-    println!("STEP 4: Place SELL order: {} SOL @ ${} (5x leverage)...", test_qty, test_price);
+    println!(
+        "STEP 4: Place SELL order: {} SOL @ ${} (5x leverage)...",
+        test_qty, test_price
+    );
     let order = PerpOrder {
         side: Side::Sell,
         price: test_price,
@@ -85,6 +85,7 @@ async fn main() -> anyhow::Result<()> {
         position_effect: PositionEffect::Open,
         margin_mode: MarginMode::Cross,
         reduce_only: false,
+        ..Default::default()
     };
 
     let result = client.place_perp_order(&market.uuid, order).await?;
@@ -100,7 +101,7 @@ async fn main() -> anyhow::Result<()> {
     tokio::time::sleep(Duration::from_secs(5)).await;
 
     println!("   Checking orderbook...");
-    let book_after = client.get_orderbook(&market.uuid).await?;
+    let book_after = client.get_orderbook(&market.uuid).await?.to_human(&market);
     println!("   Bids: {}, Asks: {}", book_after.buys.len(), book_after.sells.len());
 
     // Search for our order
@@ -109,8 +110,8 @@ async fn main() -> anyhow::Result<()> {
     if let Some(order) = our_order {
         println!("\n   ✓ ORDER FOUND ON ORDERBOOK!");
         println!("   Order ID: {}", order.order_id);
-        println!("   Price: {} (${:.2})", order.price, order.price as f64 / 1_000_000.0);
-        println!("   Quantity: {} ({:.4} SOL)", order.quantity, order.quantity as f64 / 1_000_000_000.0);
+        println!("   Price: ${:.2}", order.price);
+        println!("   Quantity: {:.4} SOL", order.quantity);
         println!("   Owner: {}", order.owner);
     } else {
         println!("\n   ✗ Order not found in orderbook asks.");
@@ -127,9 +128,11 @@ async fn main() -> anyhow::Result<()> {
         // Show asks around our price range
         println!("\n   Asks near $200 range:");
         for ask in book_after.sells.iter() {
-            let price = ask.price as f64 / 1_000_000.0;
-            if price > 190.0 && price < 210.0 {
-                println!("   - ${:.2} qty={} owner={}", price, ask.quantity, ask.owner);
+            if ask.price > 190.0 && ask.price < 210.0 {
+                println!(
+                    "   - ${:.2} qty={} owner={}",
+                    ask.price, ask.quantity, ask.owner
+                );
             }
         }
     }
@@ -0,0 +1,9 @@
+#![no_main]
+
+use fermi_trade_sdk::fuzzing::check_canonical_monotonic;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|input: (f64, f64, u8)| {
+    let (a, b, decimals) = input;
+    check_canonical_monotonic(a, b, decimals % 19);
+});
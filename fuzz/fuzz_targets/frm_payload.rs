@@ -0,0 +1,8 @@
+#![no_main]
+
+use fermi_trade_sdk::fuzzing::check_frm_payload;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    check_frm_payload(data);
+});
@@ -0,0 +1,51 @@
+#![no_main]
+
+use arbitrary::Arbitrary;
+use fermi_trade_sdk::fuzzing::{check_order, OrderFields};
+use fermi_trade_sdk::{MarginMode, PositionEffect, Side};
+use libfuzzer_sys::fuzz_target;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    secret: [u8; 32],
+    order_id: u64,
+    buy: bool,
+    price: u64,
+    quantity: u64,
+    expiry: u64,
+    base_mint: [u8; 32],
+    quote_mint: [u8; 32],
+    leverage: u64,
+    open: bool,
+    cross: bool,
+    margin_amount: Option<u64>,
+    reduce_only: bool,
+    builder_code: Option<String>,
+}
+
+fuzz_target!(|input: Input| {
+    check_order(&OrderFields {
+        secret: input.secret,
+        order_id: input.order_id,
+        side: if input.buy { Side::Buy } else { Side::Sell },
+        price: input.price,
+        quantity: input.quantity,
+        expiry: input.expiry,
+        base_mint: input.base_mint,
+        quote_mint: input.quote_mint,
+        leverage: input.leverage,
+        position_effect: if input.open {
+            PositionEffect::Open
+        } else {
+            PositionEffect::Close
+        },
+        margin_mode: if input.cross {
+            MarginMode::Cross
+        } else {
+            MarginMode::Isolated
+        },
+        margin_amount: input.margin_amount,
+        reduce_only: input.reduce_only,
+        builder_code: input.builder_code,
+    });
+});
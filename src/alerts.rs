@@ -0,0 +1,239 @@
+//! Price-movement alerts.
+//!
+//! An [`AlertRule`] fires when the mid price crosses a level, when it moves
+//! by a percentage within a time window, or when the spread blows out.
+//! [`PriceAlerts`] evaluates rules against [`MarketSample`]s from any source,
+//! streamed or polled; `FermiClient::watch_price_alerts` polls the book and
+//! delivers alerts over a channel like the risk monitors. The same conditions
+//! can gate an entry through `execution::TriggerCondition::Alert`.
+//!
+//! Move and spread rules are edge-triggered: they fire when the condition
+//! starts holding and re-arm once it stops, so a persistent condition is
+//! reported once. A crossing fires every time the level is crossed.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::types::{MarketInfo, Orderbook};
+
+/// Market condition an alert waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum AlertCondition {
+    /// Mid price crosses the level in either direction
+    Crosses(f64),
+    /// Mid price moves by at least `percent` either way within `window`
+    Moves { percent: f64, window: Duration },
+    /// Spread reaches `bps` basis points of the mid
+    SpreadAbove { bps: f64 },
+}
+
+/// A named alert condition.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AlertRule {
+    pub name: String,
+    pub condition: AlertCondition,
+}
+
+impl AlertRule {
+    pub fn new(name: &str, condition: AlertCondition) -> Self {
+        Self {
+            name: name.to_string(),
+            condition,
+        }
+    }
+}
+
+/// Top of book at one point in time, in human quote units.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MarketSample {
+    pub at: Instant,
+    pub bid: f64,
+    pub ask: f64,
+}
+
+impl MarketSample {
+    /// Sample the book's best bid and ask, if both sides exist.
+    pub fn from_book(book: &Orderbook, market: &MarketInfo, at: Instant) -> Option<Self> {
+        let scale = 10f64.powi(market.quote_decimals as i32);
+        Some(Self {
+            at,
+            bid: book.best_bid()?.price as f64 / scale,
+            ask: book.best_ask()?.price as f64 / scale,
+        })
+    }
+
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+
+    pub fn spread_bps(&self) -> f64 {
+        (self.ask - self.bid) / self.mid() * 10_000.0
+    }
+}
+
+/// A rule that fired.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PriceAlert {
+    pub rule: String,
+    pub market_id: String,
+    pub condition: AlertCondition,
+    pub mid: f64,
+    pub spread_bps: f64,
+    /// Mid the move is measured from: the previous sample for a crossing,
+    /// the start of the window for a move
+    pub reference: Option<f64>,
+}
+
+/// Evaluates alert rules for one market over successive samples.
+#[derive(Debug, Clone)]
+pub struct PriceAlerts {
+    market_id: String,
+    /// Each rule with whether it is armed
+    rules: Vec<(AlertRule, bool)>,
+    /// Recent mids, oldest first, kept for the longest move window
+    history: VecDeque<(Instant, f64)>,
+}
+
+impl PriceAlerts {
+    pub fn new(market_id: &str, rules: Vec<AlertRule>) -> Self {
+        Self {
+            market_id: market_id.to_string(),
+            rules: rules.into_iter().map(|rule| (rule, true)).collect(),
+            history: VecDeque::new(),
+        }
+    }
+
+    pub fn market_id(&self) -> &str {
+        &self.market_id
+    }
+
+    pub fn rules(&self) -> impl Iterator<Item = &AlertRule> {
+        self.rules.iter().map(|(rule, _)| rule)
+    }
+
+    /// Evaluate every rule against a new sample, returning those that fire.
+    ///
+    /// Samples must be given in time order.
+    pub fn observe(&mut self, sample: &MarketSample) -> Vec<PriceAlert> {
+        let mid = sample.mid();
+        let spread_bps = sample.spread_bps();
+        let longest = self
+            .rules
+            .iter()
+            .filter_map(|(rule, _)| match rule.condition {
+                AlertCondition::Moves { window, .. } => Some(window),
+                _ => None,
+            })
+            .max()
+            .unwrap_or_default();
+        while self
+            .history
+            .front()
+            .is_some_and(|(at, _)| sample.at.duration_since(*at) > longest)
+        {
+            self.history.pop_front();
+        }
+        let previous = self.history.back().map(|(_, mid)| *mid);
+
+        let mut alerts = Vec::new();
+        for (rule, armed) in &mut self.rules {
+            let (holds, reference) = match rule.condition {
+                AlertCondition::Crosses(level) => {
+                    let crossed = previous.is_some_and(|p| (p < level) != (mid < level));
+                    // Each crossing is its own event
+                    *armed = true;
+                    (crossed, previous)
+                }
+                AlertCondition::Moves { percent, window } => {
+                    let start = self
+                        .history
+                        .iter()
+                        .find(|(at, _)| sample.at.duration_since(*at) <= window)
+                        .map(|(_, mid)| *mid);
+                    let moved = start
+                        .filter(|s| *s > 0.0)
+                        .is_some_and(|s| ((mid - s) / s * 100.0).abs() >= percent);
+                    (moved, start)
+                }
+                AlertCondition::SpreadAbove { bps } => (spread_bps >= bps, None),
+            };
+
+            if !holds {
+                *armed = true;
+                continue;
+            }
+            if !*armed {
+                continue;
+            }
+            *armed = false;
+            alerts.push(PriceAlert {
+                rule: rule.name.clone(),
+                market_id: self.market_id.clone(),
+                condition: rule.condition,
+                mid,
+                spread_bps,
+                reference,
+            });
+        }
+
+        self.history.push_back((sample.at, mid));
+        alerts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alerts_fire_once_per_episode() {
+        let start = Instant::now();
+        let sample = |secs: u64, bid: f64, ask: f64| MarketSample {
+            at: start + Duration::from_secs(secs),
+            bid,
+            ask,
+        };
+        let mut alerts = PriceAlerts::new(
+            "m",
+            vec![
+                AlertRule::new("level", AlertCondition::Crosses(101.0)),
+                AlertRule::new(
+                    "jump",
+                    AlertCondition::Moves {
+                        percent: 2.0,
+                        window: Duration::from_secs(60),
+                    },
+                ),
+                AlertRule::new("wide", AlertCondition::SpreadAbove { bps: 50.0 }),
+            ],
+        );
+        let fired = |alerts: Vec<PriceAlert>| -> Vec<String> {
+            alerts.into_iter().map(|a| a.rule).collect()
+        };
+
+        assert!(alerts.observe(&sample(0, 99.95, 100.05)).is_empty());
+        assert_eq!(
+            fired(alerts.observe(&sample(10, 101.45, 101.55))),
+            vec!["level"]
+        );
+        // Crossed back down, and wide; still within 2% of the start
+        assert_eq!(
+            fired(alerts.observe(&sample(20, 99.5, 100.5))),
+            vec!["level", "wide"]
+        );
+        assert_eq!(
+            fired(alerts.observe(&sample(30, 102.45, 102.55))),
+            vec!["level", "jump"]
+        );
+        // Move still holds and is not reported again
+        assert!(alerts.observe(&sample(40, 102.45, 102.55)).is_empty());
+        // The 100.0 mids have left the window, re-arming the move rule
+        assert!(alerts.observe(&sample(95, 102.45, 102.55)).is_empty());
+        assert_eq!(
+            fired(alerts.observe(&sample(100, 104.95, 105.05))),
+            vec!["jump"]
+        );
+    }
+}
@@ -0,0 +1,134 @@
+//! The trading surface of [`FermiClient`] as a trait.
+//!
+//! Strategies written against [`FermiApi`] rather than `FermiClient` can be
+//! unit tested without a node, using `MockFermiClient` from the `testing`
+//! feature.
+//!
+//! ```rust,ignore
+//! async fn quote(api: &dyn FermiApi, market: &str, order: PerpOrder) -> Result<u64> {
+//!     for order in api.get_my_orders().await? {
+//!         api.cancel_order(market, order.order_id).await?;
+//!     }
+//!     Ok(api.place_perp_order(market, order).await?.order_id)
+//! }
+//! ```
+
+use async_trait::async_trait;
+
+use crate::client::FermiClient;
+use crate::error::Result;
+use crate::types::{
+    AccountSummary, Balances, CancelResult, FundingEvent, MarketInfo, OpenOrder, OrderResult,
+    Orderbook, PerpOrder, Position, ReplaceResult, Trade,
+};
+
+/// Queries and order entry shared by [`FermiClient`] and test doubles.
+///
+/// Markets are named by UUID or symbol, as with the client's own methods.
+#[async_trait]
+pub trait FermiApi: Send + Sync {
+    /// Base58 public key of the trading account.
+    fn pubkey(&self) -> String;
+
+    async fn get_markets(&self) -> Result<Vec<MarketInfo>>;
+
+    async fn get_market(&self, market: &str) -> Result<MarketInfo>;
+
+    async fn get_orderbook(&self, market: &str) -> Result<Orderbook>;
+
+    async fn get_trades(&self, market: &str) -> Result<Vec<Trade>>;
+
+    async fn get_funding(&self, market: &str) -> Result<Vec<FundingEvent>>;
+
+    async fn get_positions(&self) -> Result<Vec<Position>>;
+
+    async fn get_my_orders(&self) -> Result<Vec<OpenOrder>>;
+
+    async fn get_account(&self) -> Result<AccountSummary>;
+
+    async fn get_balances(&self) -> Result<Balances>;
+
+    async fn place_perp_order(&self, market: &str, order: PerpOrder) -> Result<OrderResult>;
+
+    async fn cancel_order(&self, market: &str, order_id: u64) -> Result<CancelResult>;
+
+    /// Cancel every open order, optionally only in one market.
+    async fn cancel_all_orders(
+        &self,
+        market: Option<&str>,
+    ) -> Result<Vec<(u64, Result<CancelResult>)>>;
+
+    async fn replace_order(
+        &self,
+        market: &str,
+        old_order_id: u64,
+        new_order: PerpOrder,
+    ) -> Result<ReplaceResult>;
+}
+
+#[async_trait]
+impl FermiApi for FermiClient {
+    fn pubkey(&self) -> String {
+        FermiClient::pubkey(self)
+    }
+
+    async fn get_markets(&self) -> Result<Vec<MarketInfo>> {
+        FermiClient::get_markets(self).await
+    }
+
+    async fn get_market(&self, market: &str) -> Result<MarketInfo> {
+        FermiClient::get_market(self, market).await
+    }
+
+    async fn get_orderbook(&self, market: &str) -> Result<Orderbook> {
+        FermiClient::get_orderbook(self, market).await
+    }
+
+    async fn get_trades(&self, market: &str) -> Result<Vec<Trade>> {
+        FermiClient::get_trades(self, market).await
+    }
+
+    async fn get_funding(&self, market: &str) -> Result<Vec<FundingEvent>> {
+        FermiClient::get_funding(self, market).await
+    }
+
+    async fn get_positions(&self) -> Result<Vec<Position>> {
+        FermiClient::get_positions(self).await
+    }
+
+    async fn get_my_orders(&self) -> Result<Vec<OpenOrder>> {
+        FermiClient::get_my_orders(self).await
+    }
+
+    async fn get_account(&self) -> Result<AccountSummary> {
+        FermiClient::get_account(self).await
+    }
+
+    async fn get_balances(&self) -> Result<Balances> {
+        FermiClient::get_balances(self).await
+    }
+
+    async fn place_perp_order(&self, market: &str, order: PerpOrder) -> Result<OrderResult> {
+        FermiClient::place_perp_order(self, market, order).await
+    }
+
+    async fn cancel_order(&self, market: &str, order_id: u64) -> Result<CancelResult> {
+        FermiClient::cancel_order(self, market, order_id).await
+    }
+
+    async fn cancel_all_orders(
+        &self,
+        market: Option<&str>,
+    ) -> Result<Vec<(u64, Result<CancelResult>)>> {
+        FermiClient::cancel_all_orders(self, market).await
+    }
+
+    async fn replace_order(
+        &self,
+        market: &str,
+        old_order_id: u64,
+        new_order: PerpOrder,
+    ) -> Result<ReplaceResult> {
+        FermiClient::replace_order(self, market, old_order_id, new_order).await
+    }
+}
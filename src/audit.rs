@@ -0,0 +1,235 @@
+//! Tamper-evident audit journal of everything sent to the venue.
+//!
+//! Each JSONL line records one signed payload, submission result, or
+//! failure. Lines carry the SHA-256 hash of the previous line, so editing,
+//! removing, or reordering any entry breaks the chain from that point on and
+//! is caught by [`verify`].
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::error::{Result, SdkError};
+
+/// `prev_hash` of the first entry in a journal.
+const GENESIS_HASH: &str = "0000000000000000000000000000000000000000000000000000000000000000";
+
+/// What an audit entry records.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditKind {
+    /// Signed order payload, written before submission
+    Order,
+    OrderAck,
+    OrderError,
+    /// Signed cancel payload, written before submission
+    Cancel,
+    CancelAck,
+    CancelError,
+}
+
+/// One line of the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditEntry {
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub kind: AuditKind,
+    pub payload: serde_json::Value,
+    pub prev_hash: String,
+    pub hash: String,
+}
+
+impl AuditEntry {
+    fn compute_hash(&self) -> String {
+        // json! builds a sorted map, so the hashed bytes do not depend on
+        // field order in the file
+        let body = serde_json::json!({
+            "seq": self.seq,
+            "timestamp_ms": self.timestamp_ms,
+            "kind": self.kind,
+            "payload": self.payload,
+            "prev_hash": self.prev_hash,
+        });
+        hex::encode(Sha256::digest(body.to_string().as_bytes()))
+    }
+}
+
+struct Tail {
+    file: File,
+    next_seq: u64,
+    last_hash: String,
+}
+
+/// Append-only, hash-chained JSONL journal.
+pub struct AuditJournal {
+    path: PathBuf,
+    tail: Mutex<Tail>,
+}
+
+impl AuditJournal {
+    /// Open a journal at `path`, continuing the chain if the file exists.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let (next_seq, last_hash) = match last_entry(&path)? {
+            Some(entry) => (entry.seq + 1, entry.hash),
+            None => (0, GENESIS_HASH.to_string()),
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(&path)
+            .map_err(io_error)?;
+
+        Ok(Self {
+            path,
+            tail: Mutex::new(Tail {
+                file,
+                next_seq,
+                last_hash,
+            }),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Append an entry and sync it to disk before returning.
+    pub fn append(&self, kind: AuditKind, payload: serde_json::Value) -> Result<AuditEntry> {
+        let mut tail = self
+            .tail
+            .lock()
+            .map_err(|_| SdkError::Audit("journal lock poisoned".to_string()))?;
+
+        let mut entry = AuditEntry {
+            seq: tail.next_seq,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            kind,
+            payload,
+            prev_hash: tail.last_hash.clone(),
+            hash: String::new(),
+        };
+        entry.hash = entry.compute_hash();
+
+        let mut line = serde_json::to_string(&entry)?;
+        line.push('\n');
+        tail.file.write_all(line.as_bytes()).map_err(io_error)?;
+        tail.file.sync_data().map_err(io_error)?;
+
+        tail.next_seq += 1;
+        tail.last_hash = entry.hash.clone();
+        Ok(entry)
+    }
+}
+
+/// Check every entry's hash and link in the journal at `path`.
+///
+/// Returns the number of entries, or an error naming the first broken line.
+pub fn verify(path: impl AsRef<Path>) -> Result<u64> {
+    let reader = BufReader::new(File::open(path).map_err(io_error)?);
+    let mut expected_prev = GENESIS_HASH.to_string();
+    let mut count = 0;
+
+    for (index, line) in reader.lines().enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let entry: AuditEntry = serde_json::from_str(&line)
+            .map_err(|e| SdkError::Audit(format!("line {}: {}", index + 1, e)))?;
+        if entry.seq != count {
+            return Err(SdkError::Audit(format!(
+                "line {}: expected seq {}, found {}",
+                index + 1,
+                count,
+                entry.seq
+            )));
+        }
+        if entry.prev_hash != expected_prev {
+            return Err(SdkError::Audit(format!("line {}: chain broken", index + 1)));
+        }
+        if entry.compute_hash() != entry.hash {
+            return Err(SdkError::Audit(format!(
+                "line {}: entry modified",
+                index + 1
+            )));
+        }
+        expected_prev = entry.hash;
+        count += 1;
+    }
+
+    Ok(count)
+}
+
+// std::io::Error converts to a keypair error by default
+fn io_error(err: std::io::Error) -> SdkError {
+    SdkError::Audit(err.to_string())
+}
+
+fn last_entry(path: &Path) -> Result<Option<AuditEntry>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let reader = BufReader::new(File::open(path).map_err(io_error)?);
+    let mut last = None;
+    for line in reader.lines() {
+        let line = line.map_err(io_error)?;
+        if !line.trim().is_empty() {
+            last = Some(line);
+        }
+    }
+    match last {
+        Some(line) => Ok(Some(serde_json::from_str(&line)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_journal_chain_detects_tampering() {
+        let path = std::env::temp_dir().join(format!(
+            "fermi-audit-{}-{}.jsonl",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+
+        let journal = AuditJournal::open(&path).unwrap();
+        journal
+            .append(AuditKind::Order, serde_json::json!({"order_id": 1}))
+            .unwrap();
+        journal
+            .append(AuditKind::OrderAck, serde_json::json!({"tx_hash": "h"}))
+            .unwrap();
+        drop(journal);
+
+        // Reopening continues the chain
+        let journal = AuditJournal::open(&path).unwrap();
+        let entry = journal
+            .append(AuditKind::Cancel, serde_json::json!({"order_id": 1}))
+            .unwrap();
+        assert_eq!(entry.seq, 2);
+        assert_eq!(verify(&path).unwrap(), 3);
+
+        let tampered = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"order_id\":1}", "\"order_id\":2}");
+        std::fs::write(&path, tampered).unwrap();
+        assert!(matches!(verify(&path), Err(SdkError::Audit(_))));
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
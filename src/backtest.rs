@@ -0,0 +1,1054 @@
+//! Backtesting strategies against recorded market data.
+//!
+//! [`SimulatedExchange`] implements [`FermiApi`], so a strategy written
+//! against the trait runs unchanged on history. Recorded depth snapshots,
+//! trades, and funding events, such as those written by the `recorder`
+//! module, are replayed in timestamp order with
+//! [`advance`](SimulatedExchange::advance); between steps the strategy
+//! queries and trades as it would live.
+//!
+//! The fill model is deliberately simple. Orders that cross the book take
+//! its levels at their prices and pay the taker fee, consuming that
+//! liquidity until the next snapshot. Resting orders fill as maker when a
+//! recorded trade prints at or through their price, or when a snapshot's
+//! opposite side crosses them. Funding is charged on each funding event at
+//! its mark price, and an account whose equity falls below maintenance
+//! margin is liquidated at mark.
+//!
+//! ```rust,ignore
+//! let exchange = SimulatedExchange::new(vec![sol_perp], BacktestConfig::default());
+//! exchange.load(events);
+//! while exchange.advance().is_some() {
+//!     strategy.on_tick(&exchange).await?;
+//! }
+//! println!("{:#?}", exchange.report());
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+use rust_decimal::prelude::ToPrimitive;
+use tracing::warn;
+
+use crate::api::FermiApi;
+use crate::error::{Result, SdkError};
+use crate::execution::{market_price, plan_take, TakeSlice};
+use crate::orderbook::{DepthLevel, LocalBook};
+use crate::pnl::{CostBasis, PnlEngine};
+pub use crate::recorder::MarketEvent;
+use crate::types::{
+    AccountSummary, Balances, CancelResult, FundingEvent, ImmediateFill, MarketInfo, OpenOrder,
+    OrderResult, OrderType, Orderbook, OrderbookEntry, PerpOrder, Position, ReplaceResult, Side,
+    TimeInForce, TokenBalance, Trade, DEFAULT_MAX_SLIPPAGE_BPS, TESTNET_USDC,
+};
+
+/// Owner of the book levels built from depth snapshots.
+const BOOK_OWNER: &str = "book";
+
+/// Account and fee parameters for a [`SimulatedExchange`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestConfig {
+    /// Owner reported for the simulated account
+    pub owner: String,
+    /// Collateral at the start of the run, in human quote units
+    pub starting_collateral: f64,
+    pub maker_fee_bps: f64,
+    pub taker_fee_bps: f64,
+    /// Maintenance margin as a fraction of position notional
+    pub maintenance_margin_ratio: f64,
+    pub cost_basis: CostBasis,
+}
+
+impl Default for BacktestConfig {
+    fn default() -> Self {
+        Self {
+            owner: "backtest".to_string(),
+            starting_collateral: 10_000.0,
+            maker_fee_bps: 2.0,
+            taker_fee_bps: 5.0,
+            maintenance_margin_ratio: 0.05,
+            cost_basis: CostBasis::Fifo,
+        }
+    }
+}
+
+/// How a simulated fill came about.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    Maker,
+    Taker,
+    /// Forced close at mark after equity fell below maintenance margin
+    Liquidation,
+}
+
+/// One fill of the simulated account, in human units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SimulatedFill {
+    pub timestamp: u64,
+    pub market_id: String,
+    /// 0 for liquidations
+    pub order_id: u64,
+    pub side: Side,
+    pub price: f64,
+    pub quantity: f64,
+    /// Fee paid in quote units
+    pub fee: f64,
+    pub liquidity: Liquidity,
+}
+
+/// Summary of a backtest run, in human quote units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct BacktestReport {
+    pub starting_collateral: f64,
+    pub final_equity: f64,
+    /// Realized trading PnL, before fees and funding
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub fees_paid: f64,
+    /// Funding paid (negative = received)
+    pub funding_paid: f64,
+    /// Notional traded
+    pub volume: f64,
+    /// Largest fall from a peak in equity, as a fraction of the peak
+    pub max_drawdown: f64,
+    pub liquidations: usize,
+    pub fills: Vec<SimulatedFill>,
+}
+
+impl BacktestReport {
+    /// Change in equity over the run.
+    pub fn net_pnl(&self) -> f64 {
+        self.final_equity - self.starting_collateral
+    }
+
+    /// Net PnL as a percentage of starting collateral.
+    pub fn return_pct(&self) -> f64 {
+        if self.starting_collateral == 0.0 {
+            return 0.0;
+        }
+        self.net_pnl() / self.starting_collateral * 100.0
+    }
+}
+
+struct SimMarket {
+    info: MarketInfo,
+    /// Levels from the last snapshot, less the liquidity taken since
+    book: Orderbook,
+    trades: Vec<Trade>,
+    funding: Vec<FundingEvent>,
+    mark: Option<f64>,
+    /// Leverage of the last order placed, applied to the whole position
+    leverage: u64,
+}
+
+struct Resting {
+    order: OpenOrder,
+    side: Side,
+    leverage: u64,
+}
+
+struct SimState {
+    markets: Vec<SimMarket>,
+    events: VecDeque<MarketEvent>,
+    now: u64,
+    orders: Vec<Resting>,
+    pnl: PnlEngine,
+    fills: Vec<SimulatedFill>,
+    next_order_id: u64,
+    sequence_number: u64,
+    peak_equity: f64,
+    max_drawdown: f64,
+    liquidations: usize,
+}
+
+/// An in-memory exchange replaying recorded market data.
+pub struct SimulatedExchange {
+    config: BacktestConfig,
+    state: Mutex<SimState>,
+}
+
+impl SimulatedExchange {
+    pub fn new(markets: Vec<MarketInfo>, config: BacktestConfig) -> Self {
+        let markets = markets
+            .into_iter()
+            .map(|info| SimMarket {
+                info,
+                book: Orderbook {
+                    buys: vec![],
+                    sells: vec![],
+                },
+                trades: vec![],
+                funding: vec![],
+                mark: None,
+                leverage: 1,
+            })
+            .collect();
+        Self {
+            state: Mutex::new(SimState {
+                markets,
+                events: VecDeque::new(),
+                now: 0,
+                orders: vec![],
+                pnl: PnlEngine::new(config.cost_basis),
+                fills: vec![],
+                next_order_id: 1,
+                sequence_number: 0,
+                peak_equity: config.starting_collateral,
+                max_drawdown: 0.0,
+                liquidations: 0,
+            }),
+            config,
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, SimState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Queue recorded events for replay. Events keep their relative order
+    /// within a timestamp.
+    pub fn load(&self, events: impl IntoIterator<Item = MarketEvent>) {
+        let mut state = self.state();
+        state.events.extend(events);
+        state
+            .events
+            .make_contiguous()
+            .sort_by_key(MarketEvent::timestamp);
+    }
+
+    /// Replay every event at the next timestamp, returning it, or `None`
+    /// once the data is exhausted.
+    pub fn advance(&self) -> Option<u64> {
+        let mut state = self.state();
+        let timestamp = state.events.front()?.timestamp();
+        while state
+            .events
+            .front()
+            .is_some_and(|e| e.timestamp() == timestamp)
+        {
+            if let Some(event) = state.events.pop_front() {
+                state.apply(&self.config, event);
+            }
+        }
+        state.track_drawdown(&self.config);
+        Some(timestamp)
+    }
+
+    /// Replay every event up to and including `timestamp`.
+    pub fn run_until(&self, timestamp: u64) {
+        while self
+            .state()
+            .events
+            .front()
+            .is_some_and(|e| e.timestamp() <= timestamp)
+        {
+            self.advance();
+        }
+    }
+
+    /// Timestamp of the last event replayed.
+    pub fn now(&self) -> u64 {
+        self.state().now
+    }
+
+    /// Events not yet replayed.
+    pub fn remaining(&self) -> usize {
+        self.state().events.len()
+    }
+
+    pub fn report(&self) -> BacktestReport {
+        let state = self.state();
+        let total = |figure: fn(&PnlEngine, &str) -> f64| {
+            state
+                .markets
+                .iter()
+                .map(|m| figure(&state.pnl, &m.info.uuid))
+                .sum::<f64>()
+        };
+        BacktestReport {
+            starting_collateral: self.config.starting_collateral,
+            final_equity: state.equity(&self.config),
+            realized_pnl: total(PnlEngine::trading_pnl),
+            unrealized_pnl: state.markets.iter().map(|m| state.unrealized(m)).sum(),
+            fees_paid: total(PnlEngine::fees_paid),
+            funding_paid: total(PnlEngine::funding_paid),
+            volume: state.fills.iter().map(|f| f.price * f.quantity).sum(),
+            max_drawdown: state.max_drawdown,
+            liquidations: state.liquidations,
+            fills: state.fills.clone(),
+        }
+    }
+}
+
+impl SimState {
+    fn market_index(&self, market: &str) -> Result<usize> {
+        self.markets
+            .iter()
+            .position(|m| m.info.uuid == market || m.info.name.eq_ignore_ascii_case(market))
+            .ok_or_else(|| SdkError::MarketNotFound(market.to_string()))
+    }
+
+    fn unrealized(&self, market: &SimMarket) -> f64 {
+        let Some(mark) = market.mark else {
+            return 0.0;
+        };
+        self.pnl
+            .lots(&market.info.uuid)
+            .iter()
+            .map(|lot| (mark - lot.price) * lot.quantity)
+            .sum()
+    }
+
+    fn notional(&self, market: &SimMarket) -> f64 {
+        self.pnl.position(&market.info.uuid).abs() * market.mark.unwrap_or(0.0)
+    }
+
+    fn equity(&self, config: &BacktestConfig) -> f64 {
+        config.starting_collateral
+            + self
+                .markets
+                .iter()
+                .map(|m| self.pnl.realized_pnl(&m.info.uuid) + self.unrealized(m))
+                .sum::<f64>()
+    }
+
+    /// Initial margin of open positions plus margin reserved by resting orders.
+    fn margin_used(&self) -> f64 {
+        let positions: f64 = self
+            .markets
+            .iter()
+            .map(|m| self.notional(m) / m.leverage as f64)
+            .sum();
+        let orders: f64 = self
+            .orders
+            .iter()
+            .filter_map(|r| {
+                let market = &self.markets[self.market_index(&r.order.market_id).ok()?].info;
+                let price = human(r.order.price, market.quote_decimals);
+                Some(price * human(r.order.quantity, market.base_decimals) / r.leverage as f64)
+            })
+            .sum();
+        positions + orders
+    }
+
+    fn maintenance_margin(&self, config: &BacktestConfig) -> f64 {
+        self.markets.iter().map(|m| self.notional(m)).sum::<f64>() * config.maintenance_margin_ratio
+    }
+
+    fn fill(
+        &mut self,
+        config: &BacktestConfig,
+        index: usize,
+        order_id: u64,
+        side: Side,
+        slice: TakeSlice,
+        liquidity: Liquidity,
+    ) {
+        let fee_bps = match liquidity {
+            Liquidity::Maker => config.maker_fee_bps,
+            Liquidity::Taker | Liquidity::Liquidation => config.taker_fee_bps,
+        };
+        let fee = slice.price * slice.quantity * fee_bps / 10_000.0;
+        let market = &mut self.markets[index];
+        market.mark.get_or_insert(slice.price);
+        let market_id = market.info.uuid.clone();
+        self.pnl
+            .apply_fill(&market_id, side, slice.price, slice.quantity, self.now);
+        self.pnl.apply_fee(&market_id, fee);
+        self.fills.push(SimulatedFill {
+            timestamp: self.now,
+            market_id,
+            order_id,
+            side,
+            price: slice.price,
+            quantity: slice.quantity,
+            fee,
+            liquidity,
+        });
+    }
+
+    /// Take liquidity from the book up to `limit`, returning the quantity filled.
+    fn take(
+        &mut self,
+        config: &BacktestConfig,
+        index: usize,
+        order_id: u64,
+        side: Side,
+        quantity: f64,
+        limit: f64,
+    ) -> f64 {
+        let market = &self.markets[index];
+        let slices = plan_take(&market.book, &market.info, side, quantity, limit);
+        let mut filled = 0.0;
+        for slice in slices {
+            let market = &mut self.markets[index];
+            let price = units(slice.price, market.info.quote_decimals);
+            let quantity = units(slice.quantity, market.info.base_decimals);
+            let resting = match side {
+                Side::Buy => &mut market.book.sells,
+                Side::Sell => &mut market.book.buys,
+            };
+            consume(resting, price, quantity);
+            filled += slice.quantity;
+            self.fill(config, index, order_id, side, slice, Liquidity::Taker);
+        }
+        filled
+    }
+
+    /// Fill resting orders on `side` that `price` trades through, best
+    /// priced first, at their own prices. Returns the quantity used.
+    fn fill_resting(
+        &mut self,
+        config: &BacktestConfig,
+        index: usize,
+        side: Side,
+        price: u64,
+        available: u64,
+    ) -> u64 {
+        let info = self.markets[index].info.clone();
+        let mut crossed: Vec<(u64, u64)> = self
+            .orders
+            .iter()
+            .filter(|r| r.order.market_id == info.uuid && r.side == side)
+            .filter(|r| match side {
+                Side::Buy => r.order.price >= price,
+                Side::Sell => r.order.price <= price,
+            })
+            .map(|r| (r.order.price, r.order.order_id))
+            .collect();
+        match side {
+            Side::Buy => crossed.sort_by_key(|(price, id)| (std::cmp::Reverse(*price), *id)),
+            Side::Sell => crossed.sort(),
+        }
+
+        let mut used = 0;
+        for (_, order_id) in crossed {
+            let Some(resting) = self
+                .orders
+                .iter_mut()
+                .find(|r| r.order.order_id == order_id)
+            else {
+                continue;
+            };
+            let take = resting.order.quantity.min(available - used);
+            if take == 0 {
+                break;
+            }
+            resting.order.quantity -= take;
+            used += take;
+            let slice = TakeSlice {
+                price: human(resting.order.price, info.quote_decimals),
+                quantity: human(take, info.base_decimals),
+            };
+            self.fill(config, index, order_id, side, slice, Liquidity::Maker);
+        }
+        self.orders.retain(|r| r.order.quantity > 0);
+        used
+    }
+
+    fn apply(&mut self, config: &BacktestConfig, event: MarketEvent) {
+        self.now = event.timestamp();
+        let index = match self.market_index(event.market_id()) {
+            Ok(index) => index,
+            Err(_) => return,
+        };
+        match event {
+            MarketEvent::Depth { depth, .. } => {
+                let book = match LocalBook::from_snapshot(&depth) {
+                    Ok(book) => book,
+                    Err(e) => {
+                        warn!("Skipping depth snapshot at {}: {}", self.now, e);
+                        return;
+                    }
+                };
+                let market = &mut self.markets[index];
+                if let Some(mid) = book.mid_price().and_then(|mid| mid.to_f64()) {
+                    market.mark = Some(mid);
+                }
+                market.book = to_orderbook(&book, &market.info);
+                self.cross_resting(config, index);
+            }
+            MarketEvent::Trade { trade, .. } => {
+                let market = &mut self.markets[index];
+                market.mark = Some(trade.price_f64(&market.info));
+                market.trades.push(trade.clone());
+                let used = self.fill_resting(config, index, Side::Buy, trade.price, trade.quantity);
+                self.fill_resting(
+                    config,
+                    index,
+                    Side::Sell,
+                    trade.price,
+                    trade.quantity - used,
+                );
+            }
+            MarketEvent::Funding(event) => {
+                let market = &mut self.markets[index];
+                market.mark = Some(human(event.mark_price, market.info.quote_decimals));
+                let info = market.info.clone();
+                self.pnl.apply_funding_event(&info, &event);
+                self.markets[index].funding.push(event);
+            }
+        }
+        self.check_liquidation(config);
+    }
+
+    /// Fill resting orders that a new snapshot's opposite side crosses.
+    fn cross_resting(&mut self, config: &BacktestConfig, index: usize) {
+        for (side, levels) in [
+            (Side::Buy, self.markets[index].book.sells.clone()),
+            (Side::Sell, self.markets[index].book.buys.clone()),
+        ] {
+            for level in levels {
+                let used = self.fill_resting(config, index, side, level.price, level.quantity);
+                if used == 0 {
+                    break;
+                }
+                let book = &mut self.markets[index].book;
+                let resting = match side {
+                    Side::Buy => &mut book.sells,
+                    Side::Sell => &mut book.buys,
+                };
+                consume(resting, level.price, used);
+            }
+        }
+    }
+
+    fn check_liquidation(&mut self, config: &BacktestConfig) {
+        let maintenance = self.maintenance_margin(config);
+        let equity = self.equity(config);
+        if maintenance <= 0.0 || equity >= maintenance {
+            return;
+        }
+        warn!(
+            "Backtest account liquidated at {}: equity {:.2} below maintenance {:.2}",
+            self.now, equity, maintenance
+        );
+        self.orders.clear();
+        for index in 0..self.markets.len() {
+            let position = self.pnl.position(&self.markets[index].info.uuid);
+            let Some(mark) = self.markets[index].mark else {
+                continue;
+            };
+            if position == 0.0 {
+                continue;
+            }
+            let side = if position > 0.0 {
+                Side::Sell
+            } else {
+                Side::Buy
+            };
+            let slice = TakeSlice {
+                price: mark,
+                quantity: position.abs(),
+            };
+            self.fill(config, index, 0, side, slice, Liquidity::Liquidation);
+        }
+        self.liquidations += 1;
+    }
+
+    fn track_drawdown(&mut self, config: &BacktestConfig) {
+        let equity = self.equity(config);
+        self.peak_equity = self.peak_equity.max(equity);
+        if self.peak_equity > 0.0 {
+            self.max_drawdown = self
+                .max_drawdown
+                .max((self.peak_equity - equity) / self.peak_equity);
+        }
+    }
+
+    fn place(
+        &mut self,
+        config: &BacktestConfig,
+        market: &str,
+        order: &PerpOrder,
+    ) -> Result<OrderResult> {
+        let index = self.market_index(market)?;
+        let info = self.markets[index].info.clone();
+        let side = order.side;
+        let mut quantity = order.quantity_f64();
+        if quantity <= 0.0 {
+            return Err(SdkError::Validation("quantity must be positive".into()));
+        }
+        let limit = match order.order_type {
+            OrderType::Limit => order.price_f64(),
+            OrderType::Market => market_price(
+                &self.markets[index].book,
+                &info,
+                side,
+                quantity,
+                order.max_slippage_bps.unwrap_or(DEFAULT_MAX_SLIPPAGE_BPS),
+            )?,
+        };
+        let leverage = order.leverage.max(1);
+        let unit = human(1, info.base_decimals);
+
+        if order.reduce_only {
+            let position = self.pnl.position(&info.uuid);
+            let reducible = match side {
+                Side::Buy => -position,
+                Side::Sell => position,
+            };
+            if reducible <= 0.0 {
+                return Err(SdkError::OrderRejected(
+                    "reduce-only order would not reduce the position".into(),
+                ));
+            }
+            quantity = quantity.min(reducible);
+        } else {
+            let required = limit * quantity / leverage as f64;
+            let free = self.equity(config) - self.margin_used();
+            if required > free {
+                return Err(SdkError::InsufficientMargin(format!(
+                    "order needs {:.2} margin, {:.2} free",
+                    required, free
+                )));
+            }
+        }
+        if order.time_in_force == TimeInForce::Fok {
+            let book = &self.markets[index].book;
+            let available: f64 = plan_take(book, &info, side, quantity, limit)
+                .iter()
+                .map(|slice| slice.quantity)
+                .sum();
+            if quantity - available > unit / 2.0 {
+                return Err(SdkError::OrderRejected(format!(
+                    "fill-or-kill: only {} of {} available at or better than {}",
+                    available, quantity, limit
+                )));
+            }
+        }
+
+        let order_id = self.next_order_id;
+        self.next_order_id += 1;
+        self.sequence_number += 1;
+        self.markets[index].leverage = leverage;
+        let filled = self.take(config, index, order_id, side, quantity, limit);
+        let remaining = quantity - filled;
+        let rests = order.order_type == OrderType::Limit
+            && !order.time_in_force.is_immediate()
+            && remaining > unit / 2.0;
+        if rests {
+            self.orders.push(Resting {
+                order: OpenOrder {
+                    order_id,
+                    market_id: info.uuid.clone(),
+                    market_name: Some(info.name.clone()),
+                    owner: config.owner.clone(),
+                    side: side.to_string(),
+                    price: units(limit, info.quote_decimals),
+                    quantity: units(remaining, info.base_decimals),
+                    expiry: 0,
+                    timestamp: Some(self.now),
+                },
+                side,
+                leverage,
+            });
+        }
+        self.check_liquidation(config);
+        self.track_drawdown(config);
+        Ok(OrderResult {
+            order_id,
+            sequence_number: self.sequence_number,
+            expected_tick: 0,
+            tx_hash: format!("backtest-{}", order_id),
+            immediate: order.time_in_force.is_immediate().then_some(ImmediateFill {
+                filled_quantity: filled,
+                cancelled_quantity: remaining.max(0.0),
+            }),
+        })
+    }
+
+    fn cancel(&mut self, order_id: u64) -> Result<CancelResult> {
+        let index = self
+            .orders
+            .iter()
+            .position(|r| r.order.order_id == order_id)
+            .ok_or(SdkError::OrderNotFound(order_id))?;
+        self.orders.remove(index);
+        self.sequence_number += 1;
+        Ok(CancelResult {
+            order_id,
+            sequence_number: self.sequence_number,
+            expected_tick: 0,
+            tx_hash: format!("backtest-cancel-{}", order_id),
+        })
+    }
+}
+
+/// The snapshot's levels as book entries, one per level.
+fn to_orderbook(book: &LocalBook, market: &MarketInfo) -> Orderbook {
+    Orderbook {
+        buys: entries(book.bids(), Side::Buy, market),
+        sells: entries(book.asks(), Side::Sell, market),
+    }
+}
+
+fn entries(
+    levels: impl Iterator<Item = DepthLevel>,
+    side: Side,
+    market: &MarketInfo,
+) -> Vec<OrderbookEntry> {
+    levels
+        .filter_map(|level| {
+            Some(OrderbookEntry {
+                order_id: 0,
+                owner: BOOK_OWNER.to_string(),
+                price: units(level.price.to_f64()?, market.quote_decimals),
+                quantity: units(level.quantity.to_f64()?, market.base_decimals),
+                side: side.to_string(),
+                expiry: 0,
+            })
+        })
+        .collect()
+}
+
+/// Remove up to `quantity` from the entries at `price`.
+fn consume(entries: &mut Vec<OrderbookEntry>, price: u64, mut quantity: u64) {
+    for entry in entries.iter_mut().filter(|e| e.price == price) {
+        let take = entry.quantity.min(quantity);
+        entry.quantity -= take;
+        quantity -= take;
+    }
+    entries.retain(|e| e.quantity > 0);
+}
+
+fn units(value: f64, decimals: u8) -> u64 {
+    (value * 10f64.powi(decimals as i32)).round() as u64
+}
+
+fn signed_units(value: f64, decimals: u8) -> String {
+    ((value * 10f64.powi(decimals as i32)).round() as i64).to_string()
+}
+
+fn human(value: u64, decimals: u8) -> f64 {
+    value as f64 / 10f64.powi(decimals as i32)
+}
+
+#[async_trait]
+impl FermiApi for SimulatedExchange {
+    fn pubkey(&self) -> String {
+        self.config.owner.clone()
+    }
+
+    async fn get_markets(&self) -> Result<Vec<MarketInfo>> {
+        Ok(self
+            .state()
+            .markets
+            .iter()
+            .map(|m| m.info.clone())
+            .collect())
+    }
+
+    async fn get_market(&self, market: &str) -> Result<MarketInfo> {
+        let state = self.state();
+        Ok(state.markets[state.market_index(market)?].info.clone())
+    }
+
+    /// The replayed book with the simulated account's resting orders added.
+    async fn get_orderbook(&self, market: &str) -> Result<Orderbook> {
+        let state = self.state();
+        let market = &state.markets[state.market_index(market)?];
+        let mut book = market.book.clone();
+        for resting in state
+            .orders
+            .iter()
+            .filter(|r| r.order.market_id == market.info.uuid)
+        {
+            let entry = OrderbookEntry {
+                order_id: resting.order.order_id,
+                owner: resting.order.owner.clone(),
+                price: resting.order.price,
+                quantity: resting.order.quantity,
+                side: resting.order.side.clone(),
+                expiry: resting.order.expiry,
+            };
+            match resting.side {
+                Side::Buy => book.buys.push(entry),
+                Side::Sell => book.sells.push(entry),
+            }
+        }
+        Ok(book)
+    }
+
+    async fn get_trades(&self, market: &str) -> Result<Vec<Trade>> {
+        let state = self.state();
+        Ok(state.markets[state.market_index(market)?].trades.clone())
+    }
+
+    async fn get_funding(&self, market: &str) -> Result<Vec<FundingEvent>> {
+        let state = self.state();
+        Ok(state.markets[state.market_index(market)?].funding.clone())
+    }
+
+    async fn get_positions(&self) -> Result<Vec<Position>> {
+        let state = self.state();
+        Ok(state
+            .markets
+            .iter()
+            .filter_map(|m| {
+                let info = &m.info;
+                let lots = state.pnl.lots(&info.uuid);
+                let size: f64 = lots.iter().map(|l| l.quantity).sum();
+                if size == 0.0 {
+                    return None;
+                }
+                let entry = lots.iter().map(|l| l.price * l.quantity).sum::<f64>() / size;
+                Some(Position {
+                    owner: self.config.owner.clone(),
+                    market_id: info.uuid.clone(),
+                    market_name: Some(info.name.clone()),
+                    base_position: signed_units(size, info.base_decimals),
+                    average_entry_price: signed_units(entry, info.quote_decimals),
+                    mark_price: signed_units(m.mark.unwrap_or(entry), info.quote_decimals),
+                    realized_pnl: signed_units(
+                        state.pnl.realized_pnl(&info.uuid),
+                        info.quote_decimals,
+                    ),
+                    unrealized_pnl: signed_units(state.unrealized(m), info.quote_decimals),
+                    cumulative_funding: None,
+                    margin_mode: None,
+                    leverage: None,
+                })
+            })
+            .collect())
+    }
+
+    async fn get_my_orders(&self) -> Result<Vec<OpenOrder>> {
+        Ok(self
+            .state()
+            .orders
+            .iter()
+            .map(|r| r.order.clone())
+            .collect())
+    }
+
+    async fn get_account(&self) -> Result<AccountSummary> {
+        let state = self.state();
+        let realized: f64 = state
+            .markets
+            .iter()
+            .map(|m| state.pnl.realized_pnl(&m.info.uuid))
+            .sum();
+        let unrealized: f64 = state.markets.iter().map(|m| state.unrealized(m)).sum();
+        let equity = state.equity(&self.config);
+        let initial_margin = state.margin_used();
+        let free = equity - initial_margin;
+        Ok(AccountSummary {
+            owner: Some(self.config.owner.clone()),
+            usdc_collateral: self.config.starting_collateral + realized,
+            equity_snapshot: Some(equity),
+            realized_pnl_snapshot: Some(realized),
+            unrealized_pnl_snapshot: Some(unrealized),
+            initial_margin_snapshot: Some(initial_margin),
+            maintenance_margin_snapshot: Some(state.maintenance_margin(&self.config)),
+            free_collateral_snapshot: Some(free),
+            available_withdrawal_snapshot: Some(free.max(0.0)),
+            portfolio_margin: None,
+            portfolio_margin_offset: None,
+        })
+    }
+
+    async fn get_balances(&self) -> Result<Balances> {
+        let state = self.state();
+        let reserved = state.margin_used();
+        let available = (state.equity(&self.config) - reserved).max(0.0);
+        let balance = TokenBalance {
+            available: units(available, 6).to_string(),
+            reserved: units(reserved, 6).to_string(),
+        };
+        Ok(Balances {
+            tokens: HashMap::from([(TESTNET_USDC.to_string(), balance)]),
+        })
+    }
+
+    async fn place_perp_order(&self, market: &str, order: PerpOrder) -> Result<OrderResult> {
+        self.state().place(&self.config, market, &order)
+    }
+
+    async fn cancel_order(&self, market: &str, order_id: u64) -> Result<CancelResult> {
+        let mut state = self.state();
+        state.market_index(market)?;
+        state.cancel(order_id)
+    }
+
+    async fn cancel_all_orders(
+        &self,
+        market: Option<&str>,
+    ) -> Result<Vec<(u64, Result<CancelResult>)>> {
+        let mut state = self.state();
+        let market_id = match market {
+            Some(market) => Some(state.markets[state.market_index(market)?].info.uuid.clone()),
+            None => None,
+        };
+        let order_ids: Vec<u64> = state
+            .orders
+            .iter()
+            .filter(|r| market_id.as_ref().is_none_or(|id| r.order.market_id == *id))
+            .map(|r| r.order.order_id)
+            .collect();
+        Ok(order_ids
+            .into_iter()
+            .map(|order_id| (order_id, state.cancel(order_id)))
+            .collect())
+    }
+
+    async fn replace_order(
+        &self,
+        market: &str,
+        old_order_id: u64,
+        new_order: PerpOrder,
+    ) -> Result<ReplaceResult> {
+        let mut state = self.state();
+        state.market_index(market)?;
+        let cancel = state.cancel(old_order_id)?;
+        let order = state.place(&self.config, market, &new_order)?;
+        Ok(ReplaceResult { cancel, order })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{test_market, Depth};
+    use rust_decimal::Decimal;
+
+    fn market() -> MarketInfo {
+        MarketInfo {
+            uuid: "m1".into(),
+            ..test_market(3, 2)
+        }
+    }
+
+    fn depth(timestamp: u64, bid: &str, ask: &str) -> MarketEvent {
+        MarketEvent::Depth {
+            market_id: "m1".into(),
+            timestamp,
+            depth: Depth {
+                last_update_id: timestamp,
+                bids: vec![[bid.to_string(), "5".to_string()]],
+                asks: vec![[ask.to_string(), "5".to_string()]],
+            },
+        }
+    }
+
+    fn trade(timestamp: u64, price: u64, quantity: u64) -> MarketEvent {
+        MarketEvent::Trade {
+            market_id: "m1".into(),
+            trade: Trade {
+                buyer_owner: "a".into(),
+                seller_owner: "b".into(),
+                price,
+                quantity,
+                timestamp,
+                base_mint: String::new(),
+                quote_mint: String::new(),
+                liquidated_owner: None,
+                buyer_fee: None,
+                seller_fee: None,
+            },
+        }
+    }
+
+    fn limit(side: Side, price: i64, quantity: i64) -> PerpOrder {
+        PerpOrder {
+            side,
+            price: Decimal::new(price, 0),
+            quantity: Decimal::new(quantity, 0),
+            leverage: 5,
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_fills_funding_and_report() {
+        let config = BacktestConfig {
+            maker_fee_bps: 0.0,
+            taker_fee_bps: 10.0,
+            ..BacktestConfig::default()
+        };
+        let exchange = SimulatedExchange::new(vec![market()], config);
+        exchange.load(vec![
+            trade(2, 9_900, 1_000),
+            depth(1, "99", "101"),
+            MarketEvent::Funding(FundingEvent {
+                market_id: "m1".into(),
+                timestamp: 3,
+                interval_seconds: 3_600,
+                mark_price: 10_000,
+                index_price: 10_000,
+                premium_rate_bps: 0,
+                funding_rate_bps: 10,
+                total_payment: "0".into(),
+            }),
+            depth(4, "110", "111"),
+        ]);
+        assert_eq!(exchange.advance(), Some(1));
+
+        // Rests below the ask, then a trade prints through it
+        let api: &dyn FermiApi = &exchange;
+        let bid = api
+            .place_perp_order("SOL-PERP", limit(Side::Buy, 100, 2))
+            .await
+            .unwrap();
+        assert_eq!(api.get_orderbook("m1").await.unwrap().buys.len(), 2);
+        exchange.advance();
+        let open = api.get_my_orders().await.unwrap();
+        assert_eq!((open[0].order_id, open[0].quantity), (bid.order_id, 1_000));
+
+        // 10 bps funding on 1 long at mark 100
+        exchange.advance();
+        api.cancel_order("m1", bid.order_id).await.unwrap();
+        exchange.run_until(4);
+        assert_eq!(exchange.remaining(), 0);
+
+        // Crosses the 110 bid as taker
+        api.place_perp_order("m1", limit(Side::Sell, 105, 1))
+            .await
+            .unwrap();
+        assert!(api.get_positions().await.unwrap().is_empty());
+        assert!(matches!(
+            api.place_perp_order("m1", limit(Side::Buy, 111, 1_000))
+                .await,
+            Err(SdkError::InsufficientMargin(_))
+        ));
+
+        let report = exchange.report();
+        assert_eq!(report.fills.len(), 2);
+        assert_eq!(report.fills[0].liquidity, Liquidity::Maker);
+        assert_eq!(report.fills[1].liquidity, Liquidity::Taker);
+        assert!((report.realized_pnl - 10.0).abs() < 1e-9);
+        assert!((report.funding_paid - 0.1).abs() < 1e-9);
+        assert!((report.fees_paid - 0.11).abs() < 1e-9);
+        assert!((report.net_pnl() - 9.79).abs() < 1e-9);
+        assert_eq!(report.liquidations, 0);
+    }
+
+    #[tokio::test]
+    async fn test_immediate_order_reports_fill() {
+        let exchange = SimulatedExchange::new(vec![market()], BacktestConfig::default());
+        exchange.load(vec![depth(1, "99", "101")]);
+        exchange.advance();
+
+        let api: &dyn FermiApi = &exchange;
+        let ioc = PerpOrder {
+            time_in_force: TimeInForce::Ioc,
+            ..limit(Side::Buy, 101, 7)
+        };
+        let result = api.place_perp_order("m1", ioc).await.unwrap();
+        assert_eq!(
+            result.immediate,
+            Some(ImmediateFill {
+                filled_quantity: 5.0,
+                cancelled_quantity: 2.0,
+            })
+        );
+        assert!(api.get_my_orders().await.unwrap().is_empty());
+
+        let gtc = api
+            .place_perp_order("m1", limit(Side::Buy, 98, 1))
+            .await
+            .unwrap();
+        assert_eq!(gtc.immediate, None);
+    }
+}
@@ -0,0 +1,140 @@
+//! On-disk cache of market metadata.
+//!
+//! Signing an order or cancel only needs a market's mints and decimals, which
+//! never change once the market is listed. Attaching a [`MarketCache`] with
+//! `FermiClient::set_market_cache` lets the client sign from cached metadata
+//! on a cold start, even while the node is unreachable, and gives offline
+//! signing tools the same data.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::error::{Result, SdkError};
+use crate::types::MarketInfo;
+
+/// Cache file format written by this version of the SDK
+pub const MARKET_CACHE_VERSION: u32 = 1;
+
+#[derive(Serialize, Deserialize)]
+struct CacheFile {
+    version: u32,
+    /// Unix seconds
+    saved_at: u64,
+    markets: HashMap<String, MarketInfo>,
+}
+
+/// Market metadata persisted as a JSON file.
+pub struct MarketCache {
+    path: PathBuf,
+    markets: Mutex<HashMap<String, MarketInfo>>,
+}
+
+impl MarketCache {
+    /// Open the cache at `path`.
+    ///
+    /// A missing file starts an empty cache. A file written by a different
+    /// cache version is ignored and replaced on the next save.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref().to_path_buf();
+        let markets = match std::fs::read_to_string(&path) {
+            Ok(json) => {
+                let file: CacheFile = serde_json::from_str(&json)?;
+                if file.version == MARKET_CACHE_VERSION {
+                    file.markets
+                } else {
+                    warn!(
+                        "Ignoring market cache version {} (expected {})",
+                        file.version, MARKET_CACHE_VERSION
+                    );
+                    HashMap::new()
+                }
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => HashMap::new(),
+            Err(e) => return Err(io_error(e)),
+        };
+
+        Ok(Self {
+            path,
+            markets: Mutex::new(markets),
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Cached metadata for a market UUID.
+    pub fn get(&self, market_id: &str) -> Option<MarketInfo> {
+        self.markets.lock().unwrap().get(market_id).cloned()
+    }
+
+    /// Every cached market.
+    pub fn markets(&self) -> Vec<MarketInfo> {
+        self.markets.lock().unwrap().values().cloned().collect()
+    }
+
+    /// Add or replace `markets` and write the cache to disk.
+    pub fn insert(&self, markets: &[MarketInfo]) -> Result<()> {
+        let mut cached = self.markets.lock().unwrap();
+        for market in markets {
+            cached.insert(market.uuid.clone(), market.clone());
+        }
+        let file = CacheFile {
+            version: MARKET_CACHE_VERSION,
+            saved_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            markets: cached.clone(),
+        };
+        drop(cached);
+
+        // Write then rename so a crash never leaves a truncated cache
+        let tmp = self.path.with_extension("tmp");
+        std::fs::write(&tmp, serde_json::to_string_pretty(&file)?).map_err(io_error)?;
+        std::fs::rename(&tmp, &self.path).map_err(io_error)
+    }
+}
+
+// std::io::Error converts to a keypair error by default
+fn io_error(err: std::io::Error) -> SdkError {
+    SdkError::Config(format!("market cache: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_market;
+
+    #[test]
+    fn test_cache_persists_and_checks_version() {
+        let path = std::env::temp_dir().join(format!(
+            "fermi-markets-{}-{}.json",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+        MarketCache::open(&path)
+            .unwrap()
+            .insert(&[test_market(9, 6)])
+            .unwrap();
+        let cached = MarketCache::open(&path).unwrap().get("m").unwrap();
+        assert_eq!(cached.base_decimals, 9);
+        assert_eq!(cached.quote_mint, "q");
+
+        let stale = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"version\": 1", "\"version\": 0");
+        std::fs::write(&path, stale).unwrap();
+        assert!(MarketCache::open(&path).unwrap().get("m").is_none());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+}
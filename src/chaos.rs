@@ -0,0 +1,215 @@
+//! Fault injection for resilience testing.
+//!
+//! Enabled with the `chaos` feature. A [`FaultInjector`] installed with
+//! `FermiClient::set_fault_injector` sits inside the Continuum and RPC
+//! transports and, at the configured rates, drops submissions or their acks,
+//! holds acks back, serves the previous market-data snapshot instead of a
+//! fresh one, and resets the gRPC connection. Bots can be run against it to
+//! check they recover before a degraded node forces the issue.
+//!
+//! Injected failures use the same error variants as the real ones, so
+//! dropped submissions and acks are indeterminate (`SdkError::is_indeterminate`)
+//! and exercise the same recovery paths.
+
+use std::any::Any;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use tracing::warn;
+
+use crate::error::SdkError;
+
+/// Fault rates, each a probability in `[0, 1]` applied per call.
+#[derive(Debug, Clone, Default)]
+pub struct ChaosConfig {
+    /// Submission is lost before it reaches the sequencer
+    pub drop_submission: f64,
+    /// Submission is sequenced but its ack never arrives
+    pub drop_ack: f64,
+    /// Ack is held back for a delay drawn from `ack_delay`
+    pub delay_ack: f64,
+    /// Shortest and longest injected ack delay
+    pub ack_delay: (Duration, Duration),
+    /// Orderbook, depth, or trades read returns the previous snapshot
+    pub stale_market_data: f64,
+    /// Submission finds the connection reset and the client reconnects
+    pub force_reconnect: f64,
+    /// Seed for a reproducible fault sequence (random if unset)
+    pub seed: Option<u64>,
+}
+
+/// Faults injected so far.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct ChaosStats {
+    pub dropped_submissions: u64,
+    pub dropped_acks: u64,
+    pub delayed_acks: u64,
+    pub stale_reads: u64,
+    pub reconnects: u64,
+}
+
+/// Injects the faults described by a [`ChaosConfig`]. Shared by every
+/// transport of a client.
+pub struct FaultInjector {
+    config: ChaosConfig,
+    rng: Mutex<StdRng>,
+    stats: Mutex<ChaosStats>,
+    /// Last fresh market-data response per request
+    snapshots: Mutex<HashMap<String, Box<dyn Any + Send>>>,
+}
+
+impl std::fmt::Debug for FaultInjector {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FaultInjector")
+            .field("config", &self.config)
+            .field("stats", &self.stats())
+            .finish()
+    }
+}
+
+impl FaultInjector {
+    pub fn new(config: ChaosConfig) -> Self {
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+        Self {
+            config,
+            rng: Mutex::new(rng),
+            stats: Mutex::new(ChaosStats::default()),
+            snapshots: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn config(&self) -> &ChaosConfig {
+        &self.config
+    }
+
+    pub fn stats(&self) -> ChaosStats {
+        *self.stats.lock().unwrap()
+    }
+
+    fn roll(&self, probability: f64) -> bool {
+        probability > 0.0 && self.rng.lock().unwrap().gen::<f64>() < probability
+    }
+
+    fn count(&self, field: impl FnOnce(&mut ChaosStats) -> &mut u64) {
+        *field(&mut self.stats.lock().unwrap()) += 1;
+    }
+
+    /// Error for a submission that never reaches the sequencer.
+    pub(crate) fn drop_submission(&self) -> Option<SdkError> {
+        if !self.roll(self.config.drop_submission) {
+            return None;
+        }
+        self.count(|s| &mut s.dropped_submissions);
+        warn!("Chaos: dropping submission");
+        Some(SdkError::ContinuumSubmission(
+            "unavailable: submission dropped (injected)".into(),
+        ))
+    }
+
+    /// Error replacing the ack of a submission that was sequenced.
+    pub(crate) fn drop_ack(&self) -> Option<SdkError> {
+        if !self.roll(self.config.drop_ack) {
+            return None;
+        }
+        self.count(|s| &mut s.dropped_acks);
+        warn!("Chaos: dropping ack");
+        Some(SdkError::ContinuumSubmission(
+            "deadline exceeded: ack dropped (injected)".into(),
+        ))
+    }
+
+    /// How long to hold an ack back, if at all.
+    pub(crate) fn ack_delay(&self) -> Option<Duration> {
+        if !self.roll(self.config.delay_ack) {
+            return None;
+        }
+        let (min, max) = self.config.ack_delay;
+        let delay = if max > min {
+            let millis = self
+                .rng
+                .lock()
+                .unwrap()
+                .gen_range(min.as_millis() as u64, max.as_millis() as u64 + 1);
+            Duration::from_millis(millis)
+        } else {
+            min
+        };
+        self.count(|s| &mut s.delayed_acks);
+        warn!("Chaos: delaying ack by {:?}", delay);
+        Some(delay)
+    }
+
+    /// Whether to reset the connection before this submission.
+    pub(crate) fn force_reconnect(&self) -> bool {
+        if !self.roll(self.config.force_reconnect) {
+            return false;
+        }
+        self.count(|s| &mut s.reconnects);
+        warn!("Chaos: forcing reconnect");
+        true
+    }
+
+    /// Return `fresh`, or the previous response to `key` when a stale read
+    /// is injected. Only fresh responses are remembered, so staleness can
+    /// compound across consecutive injected reads.
+    pub(crate) fn market_data<T: Clone + Send + 'static>(&self, key: &str, fresh: T) -> T {
+        let mut snapshots = self.snapshots.lock().unwrap();
+        if self.roll(self.config.stale_market_data) {
+            if let Some(stale) = snapshots.get(key).and_then(|s| s.downcast_ref::<T>()) {
+                self.count(|s| &mut s.stale_reads);
+                warn!("Chaos: serving stale {}", key);
+                return stale.clone();
+            }
+        }
+        snapshots.insert(key.to_string(), Box::new(fresh.clone()));
+        fresh
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fault_injection_rates() {
+        let never = FaultInjector::new(ChaosConfig::default());
+        assert!(never.drop_submission().is_none());
+        assert!(never.ack_delay().is_none());
+        assert_eq!(never.market_data("book", 1), 1);
+        assert_eq!(never.market_data("book", 2), 2);
+
+        let always = FaultInjector::new(ChaosConfig {
+            drop_submission: 1.0,
+            drop_ack: 1.0,
+            delay_ack: 1.0,
+            ack_delay: (Duration::from_millis(10), Duration::from_millis(20)),
+            stale_market_data: 1.0,
+            force_reconnect: 1.0,
+            seed: Some(7),
+        });
+        assert!(always.drop_submission().unwrap().is_indeterminate());
+        assert!(always.drop_ack().unwrap().is_indeterminate());
+        let delay = always.ack_delay().unwrap();
+        assert!(delay >= Duration::from_millis(10) && delay <= Duration::from_millis(20));
+        assert!(always.force_reconnect());
+        // Nothing to serve on the first read, then the first snapshot sticks
+        assert_eq!(always.market_data("book", 1), 1);
+        assert_eq!(always.market_data("book", 2), 1);
+        assert_eq!(
+            always.stats(),
+            ChaosStats {
+                dropped_submissions: 1,
+                dropped_acks: 1,
+                delayed_acks: 1,
+                stale_reads: 1,
+                reconnects: 1,
+            }
+        );
+    }
+}
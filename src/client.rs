@@ -2,20 +2,282 @@
 //!
 //! Provides a unified interface for all trading operations.
 
+use futures_util::stream::{self, Stream, StreamExt};
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+use std::collections::{HashMap, HashSet};
 use std::str::FromStr;
-use std::time::{SystemTime, UNIX_EPOCH};
-use tracing::info;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
 
-use crate::continuum::ContinuumClient;
+use crate::alerts::{AlertRule, MarketSample, PriceAlert, PriceAlerts};
+use crate::audit::{AuditJournal, AuditKind};
+use crate::cache::MarketCache;
+#[cfg(feature = "chaos")]
+use crate::chaos::FaultInjector;
+use crate::continuum::{
+    ConnectionState, ContinuumClient, GrpcOptions, SequencerStatus, TransactionStatus,
+};
+use crate::delegation::{Delegation, SessionScope};
 use crate::error::{Result, SdkError};
+use crate::execution::{
+    self, ChaseOutcome, ChaseReport, LadderHandle, LadderRung, LadderSpec, LegOutcome,
+    LegPlacement, LegRollback, QueuePosition, RungState, TakeReport, TakeSlice,
+};
+use crate::hooks::{OrderHook, OrderLimits};
 use crate::keypair::TradingKeypair;
-use crate::rpc::RpcClient;
-use crate::signing::{sign_cancel, sign_perp_order};
+use crate::nonce::NonceManager;
+#[cfg(feature = "store")]
+use crate::reconcile::{self, ReconciliationReport};
+use crate::retry::RetryPolicy;
+use crate::risk::{
+    self, MarginBudget, MarginCallTier, MarginRates, MarginStatus, Portfolio, PortfolioChange,
+    PortfolioPosition, Simulation, StressResult, StressScenario, VarEstimate,
+};
+use crate::rpc::{HttpOptions, NodeStatus, RpcAuth, RpcClient};
+use crate::sequencer::{
+    self, ClockSkew, InclusionProof, InclusionReceipt, SequencerMonitor, SequencerThresholds,
+    SequencerUpdate, TickClock,
+};
+use crate::session::SessionSnapshot;
+use crate::signer::{self, NoSigner, OrderSigner};
+use crate::signing::{
+    sign_cancel, sign_collateral, sign_perp_order, sign_transfer, SignedCancel, SignedOrder,
+};
+#[cfg(feature = "store")]
+use crate::store::{self, OrderStore, SyncReport};
+use crate::stream::{FermiStream, StreamConfig};
+use crate::throttle::{
+    RateLimit, RequestLimiter, SubmissionGate, SubmissionPriority, SubmissionThrottle,
+};
+use crate::tracker::{self, OrderTracker, TrackerSnapshot};
 use crate::types::{
-    AccountSummary, Balances, CancelResult, Depth, FundingEvent, MarketInfo, OpenOrder,
-    Orderbook, OrderResult, PerpOrder, Position, Pubkey, Trade, TESTNET_USDC,
+    known_token_decimals, lossy_decimal, AccountSummary, AdlIndicator, AggTrade, Balances,
+    CancelResult, CollateralAction, CollateralResult, Depth, ExchangeInfo, FeeEstimate, FeeTier,
+    FundingEvent, FundingPayment, FundingQuery, IndexComposition, InsuranceFund, Kline,
+    LiquidationEvent, Liquidity, LiquidityAssumption, MarketInfo, MarketPrices, MarketRef,
+    OpenOrder, OrderExpiry, OrderResult, OrderType, Orderbook, OrderbookEntry, OwnerBookStats,
+    Page, PerpOrder, Position, Pubkey, ReplaceResult, Rounding, Side, TimeInForce, Trade,
+    TradesQuery, TransferResult, Withdrawal, DEFAULT_MAX_SLIPPAGE_BPS, TESTNET_USDC,
 };
 
+/// Lifetime of orders placed with `OrderExpiry::Default`
+const DEFAULT_EXPIRY_SECS: u64 = 3600;
+
+/// Lifetime of IOC and FOK orders, bounding how long a remainder can rest
+/// if its cancel does not land
+const IMMEDIATE_EXPIRY_SECS: u64 = 30;
+
+/// Clock offsets beyond this are logged, as they shift order expiries
+const CLOCK_SKEW_WARN_MS: i64 = 1_000;
+
+/// How long a tick-duration estimate is reused
+const TICK_ESTIMATE_TTL: Duration = Duration::from_secs(60);
+
+/// Extra status samples taken while waiting for the tick clock to see a tick
+const TICK_CLOCK_WARMUP_SAMPLES: usize = 10;
+const TICK_CLOCK_WARMUP_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Recent ticks scanned for this account's last sequenced nonce
+const NONCE_RECOVERY_TICKS: u32 = 256;
+
+/// How often `airdrop_and_wait` re-reads the account
+const AIRDROP_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// How often `wait_for_confirmation` re-reads the sequencer and node
+const CONFIRMATION_POLL_INTERVAL: Duration = Duration::from_millis(250);
+
+/// How long IOC and FOK placement waits for its cancel to apply before measuring fills
+const IMMEDIATE_SETTLE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// What `FermiClient::recover_state` found on the server.
+#[derive(Debug, Clone)]
+pub struct RecoveredState {
+    pub open_orders: Vec<OpenOrder>,
+    /// Highest ID among open orders
+    pub last_open_order_id: Option<u64>,
+    /// Highest nonce from this account in recently sequenced ticks
+    pub last_acked_nonce: Option<u64>,
+    /// Order IDs issued from now on are strictly greater than this
+    pub nonce_floor: u64,
+}
+
+/// How far `FermiClient::wait_for_confirmation` saw a submission get.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Confirmation {
+    /// Applied by the rollup node
+    Applied {
+        /// Tick that sequenced the transaction, if it was looked up in time
+        tick_number: Option<u64>,
+        /// Node block height when the order was seen applied
+        block_height: u64,
+        /// Whether the order was resting on the book; an order that filled
+        /// in full or failed execution leaves nothing resting
+        resting: bool,
+    },
+    /// Sequenced, but not seen applied before the wait ended
+    Sequenced { tick_number: u64 },
+    /// Not seen sequenced or applied before the wait ended
+    Unconfirmed,
+}
+
+impl Confirmation {
+    pub fn is_applied(&self) -> bool {
+        matches!(self, Confirmation::Applied { .. })
+    }
+}
+
+/// What `FermiClient::simulate_perp_order` would have submitted, and how
+/// it would fare against the current book.
+#[derive(Debug, Clone)]
+pub struct OrderSimulation {
+    /// The order after market pricing, rounding, limits, and hooks
+    pub order: PerpOrder,
+    pub order_id: u64,
+    /// Price in canonical quote units, as signed
+    pub price: u64,
+    /// Quantity in canonical base units, as signed
+    pub quantity: u64,
+    /// Margin posted with the order, in canonical quote units
+    pub margin_amount: u64,
+    /// Unix-second expiry, as signed
+    pub expiry: u64,
+    /// Signed request JSON that would be sent to Continuum
+    pub payload: String,
+    /// Fills expected at or better than the limit, one per book level
+    pub fills: Vec<TakeSlice>,
+    pub filled_quantity: f64,
+    /// Volume-weighted price of the expected fills
+    pub average_price: Option<f64>,
+    /// Quantity that would rest, or be cancelled for IOC and FOK orders
+    pub unfilled: f64,
+    /// Fee on the expected fills at your taker rate
+    pub taker_fee: f64,
+}
+
+/// A step of `FermiClient::rotate_key`, in the order they happen.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum KeyRotationEvent {
+    /// The node accepted the new key for the account
+    Registered { new_key: String },
+    /// Orders still open under the old key, which it keeps signing cancels for
+    OrdersMigrated { order_ids: Vec<u64> },
+    /// New orders are signed with the new key from here on
+    SignerSwitched { old_key: String, new_key: String },
+}
+
+/// What `FermiClient::ensure_account` found or did.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AccountInit {
+    /// The node already knows the account
+    Existing,
+    /// The account was registered by this call
+    Registered,
+    /// The node has no registration step; accounts open on first deposit
+    NotRequired,
+}
+
+/// A client acting as one of its named signers; see `FermiClient::as_account`.
+///
+/// The default signer is restored when the guard drops.
+pub struct AccountGuard<'a> {
+    client: &'a mut FermiClient,
+    name: String,
+    default: Option<Arc<dyn OrderSigner>>,
+}
+
+impl std::ops::Deref for AccountGuard<'_> {
+    type Target = FermiClient;
+
+    fn deref(&self) -> &FermiClient {
+        self.client
+    }
+}
+
+impl std::ops::DerefMut for AccountGuard<'_> {
+    fn deref_mut(&mut self) -> &mut FermiClient {
+        self.client
+    }
+}
+
+impl Drop for AccountGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(default) = self.default.take() {
+            let named = self.client.swap_signer(default);
+            self.client
+                .signers
+                .insert(std::mem::take(&mut self.name), named);
+        }
+    }
+}
+
+/// A replaced key kept to cancel the orders it placed.
+struct RetiredKey {
+    signer: Arc<dyn OrderSigner>,
+    order_ids: HashSet<u64>,
+}
+
+/// An order that passed pre-trade checks, in canonical units, ready to sign.
+struct PreparedOrder {
+    order: PerpOrder,
+    price: u64,
+    quantity: u64,
+    margin_amount: u64,
+    base_mint: Pubkey,
+    quote_mint: Pubkey,
+    expiry_secs: u64,
+}
+
+/// What `FermiClient::resolve_pending` did with each unacknowledged order.
+#[cfg(feature = "store")]
+#[derive(Debug, Clone, Default)]
+pub struct PendingResolution {
+    /// Found sequenced or open on the server
+    pub confirmed: Vec<u64>,
+    /// Resent with the original signature and acknowledged
+    pub resubmitted: Vec<OrderResult>,
+    /// Expired or rejected; will never execute
+    pub dead: Vec<u64>,
+    /// Resubmission failed without a definite answer; still pending
+    pub unresolved: Vec<u64>,
+}
+
+/// What `FermiClient::shutdown` does with live orders and positions.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum ShutdownPolicy {
+    /// Leave orders resting and positions open
+    #[default]
+    LeaveResting,
+    /// Cancel every open order
+    CancelAllOrders,
+    /// Cancel every open order, then close every position with reduce-only
+    /// market orders bounded by `slippage_bps`
+    FlattenPositions { slippage_bps: u32 },
+}
+
+/// What `FermiClient::shutdown` did.
+#[derive(Debug, Clone, Default)]
+pub struct ShutdownReport {
+    /// Unacknowledged submissions settled before teardown
+    #[cfg(feature = "store")]
+    pub pending: Option<PendingResolution>,
+    pub cancelled: Vec<CancelResult>,
+    /// Reduce-only orders sent to close positions
+    pub closing_orders: Vec<OrderResult>,
+    /// Teardown steps that failed; shutdown carries on past them
+    pub failures: Vec<String>,
+}
+
+impl ShutdownReport {
+    pub fn is_clean(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
 /// Configuration for the Fermi client
 #[derive(Debug, Clone)]
 pub struct ClientConfig {
@@ -23,6 +285,39 @@ pub struct ClientConfig {
     pub continuum_endpoint: String,
     /// RPC HTTP endpoint (e.g., "http://localhost:8080")
     pub rpc_endpoint: String,
+    /// Margin-call tiers evaluated by `FermiClient::margin_status`
+    pub margin_call_tiers: Vec<MarginCallTier>,
+    /// Optional cap on order and cancel submissions (unlimited if `None`)
+    pub rate_limit: Option<RateLimit>,
+    /// Request rate allowed per endpoint URL: the RPC node, the Continuum
+    /// endpoint, or a race gateway. Clients for the same URL share one
+    /// budget; endpoints not listed are unlimited.
+    pub endpoint_limits: HashMap<String, RateLimit>,
+    /// Times a submission is re-signed and resent after a duplicate-nonce or
+    /// stale-timestamp rejection (0 disables resubmission)
+    pub max_resubmits: u32,
+    /// Additional Continuum gateways. When set, every order and cancel is
+    /// sent to the primary endpoint and all of these at once, and the first
+    /// acknowledgement wins.
+    pub race_endpoints: Vec<String>,
+    /// Compression and message-size limits for the Continuum channel
+    pub grpc: GrpcOptions,
+    /// Credentials for account-scoped RPC reads, for nodes that require them
+    pub rpc_auth: Option<RpcAuth>,
+    /// Referral or builder code attached to every order for attribution
+    pub builder_code: Option<String>,
+    /// How prices and quantities finer than a market's decimals are
+    /// converted to canonical units
+    pub rounding: Rounding,
+    /// Backoff and retry of transient RPC and Continuum failures
+    pub retry: RetryPolicy,
+    /// Timeouts, proxy, and extra headers for RPC requests
+    pub http: HttpOptions,
+    /// Token mint moved by `FermiClient::deposit` and `FermiClient::withdraw`
+    pub collateral_mint: String,
+    /// Reject orders that free collateral cannot margin before signing them.
+    /// Costs an account, open-orders and positions fetch per order.
+    pub margin_check: bool,
 }
 
 impl Default for ClientConfig {
@@ -32,6 +327,20 @@ impl Default for ClientConfig {
                 .unwrap_or_else(|_| "http://localhost:9090".to_string()),
             rpc_endpoint: std::env::var("FERMI_RPC_ENDPOINT")
                 .unwrap_or_else(|_| "http://localhost:8080".to_string()),
+            margin_call_tiers: risk::default_margin_call_tiers(),
+            rate_limit: None,
+            endpoint_limits: HashMap::new(),
+            max_resubmits: 0,
+            race_endpoints: Vec::new(),
+            grpc: GrpcOptions::default(),
+            rpc_auth: std::env::var("FERMI_RPC_TOKEN").ok().map(RpcAuth::Bearer),
+            builder_code: std::env::var("FERMI_BUILDER_CODE").ok(),
+            rounding: Rounding::default(),
+            retry: RetryPolicy::default(),
+            http: HttpOptions::default(),
+            collateral_mint: std::env::var("FERMI_COLLATERAL_MINT")
+                .unwrap_or_else(|_| TESTNET_USDC.to_string()),
+            margin_check: true,
         }
     }
 }
@@ -43,251 +352,3589 @@ impl Default for ClientConfig {
 /// - Querying market data, positions, and account information
 /// - Testnet airdrop functionality
 pub struct FermiClient {
-    keypair: TradingKeypair,
+    signer: Arc<dyn OrderSigner>,
+    /// Created with `read_only`; `signer` cannot sign
+    read_only: bool,
+    /// Previous keys with orders still open, newest last
+    retired_keys: Mutex<Vec<RetiredKey>>,
+    /// Leverage of each order signed by this client, for the margin its
+    /// resting remainder reserves
+    order_leverage: Mutex<HashMap<u64, u64>>,
+    /// Extra signers selectable with `as_account`
+    signers: HashMap<String, Arc<dyn OrderSigner>>,
     continuum: ContinuumClient,
+    /// Primary plus race endpoints; empty when racing is disabled
+    race: Vec<ContinuumClient>,
     rpc: RpcClient,
-    #[allow(dead_code)]
     config: ClientConfig,
+    hooks: Vec<Box<dyn OrderHook>>,
+    order_limits: Option<OrderLimits>,
+    /// Scope this client's session key is limited to, if it is one
+    delegation: Option<Delegation>,
+    throttle: Option<SubmissionThrottle>,
+    /// Last tick-duration estimate and when it was taken
+    tick_duration: Mutex<Option<(Instant, Duration)>>,
+    tick_clock: Mutex<TickClock>,
+    /// Last measured offset from the server clock, applied to expiries
+    clock_skew: Option<ClockSkew>,
+    nonces: NonceManager,
+    #[cfg(feature = "store")]
+    store: Option<std::sync::Arc<OrderStore>>,
+    audit: Option<std::sync::Arc<AuditJournal>>,
+    market_cache: Option<std::sync::Arc<MarketCache>>,
+    /// UUID of each market seen, keyed by upper-cased symbol and UUID
+    market_ids: Mutex<HashMap<String, String>>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<std::sync::Arc<FaultInjector>>,
+    /// Order submissions in flight; closed by `shutdown`, after which only
+    /// reduce-only orders are signed
+    submissions: SubmissionGate,
+    /// Set by `set_quoting_paused`; only risk-reducing orders are signed
+    quoting_paused: AtomicBool,
 }
 
 impl FermiClient {
-    /// Create a new FermiClient with the given keypair and configuration.
-    pub async fn new(keypair: TradingKeypair, config: ClientConfig) -> Result<Self> {
-        let continuum = ContinuumClient::connect(&config.continuum_endpoint).await?;
-        let rpc = RpcClient::new(&config.rpc_endpoint);
+    /// Create a new FermiClient with the given signer and configuration.
+    ///
+    /// `signer` is usually a [`TradingKeypair`]; any [`OrderSigner`], such as
+    /// a hardware wallet or KMS key, can stand in for it.
+    pub async fn new(signer: impl OrderSigner + 'static, config: ClientConfig) -> Result<Self> {
+        Self::with_signer(Arc::new(signer), config).await
+    }
+
+    /// Create a client around a shared signer.
+    pub async fn with_signer(signer: Arc<dyn OrderSigner>, config: ClientConfig) -> Result<Self> {
+        let mut continuum =
+            ContinuumClient::connect(&config.continuum_endpoint, &config.grpc).await?;
+        continuum.set_retry_policy(config.retry.clone());
+        let mut race = Vec::new();
+        if !config.race_endpoints.is_empty() {
+            race.push(continuum.clone());
+            for endpoint in &config.race_endpoints {
+                let mut gateway = ContinuumClient::connect(endpoint, &config.grpc).await?;
+                gateway.set_retry_policy(config.retry.clone());
+                race.push(gateway);
+            }
+        }
 
         info!(
             "FermiClient initialized for account: {}",
-            keypair.pubkey_string()
+            signer.pubkey_string()
         );
+        Self::from_parts(signer, continuum, race, config, false)
+    }
+
+    /// Create a client for market and account queries only.
+    ///
+    /// No key is needed and nothing connects to Continuum until a
+    /// sequencer query is made. Anything that signs, such as placing or
+    /// cancelling orders, fails with `SdkError::Signing`. Calls about "your"
+    /// account refer to no real account; query others by owner, e.g. with
+    /// [`FermiClient::get_positions_of`].
+    pub fn read_only(config: ClientConfig) -> Result<Self> {
+        let mut continuum =
+            ContinuumClient::connect_lazy(&config.continuum_endpoint, &config.grpc)?;
+        continuum.set_retry_policy(config.retry.clone());
+        info!("FermiClient initialized read-only");
+        Self::from_parts(Arc::new(NoSigner), continuum, Vec::new(), config, true)
+    }
+
+    fn from_parts(
+        signer: Arc<dyn OrderSigner>,
+        mut continuum: ContinuumClient,
+        mut race: Vec<ContinuumClient>,
+        config: ClientConfig,
+        read_only: bool,
+    ) -> Result<Self> {
+        let limiters: HashMap<&str, RequestLimiter> = config
+            .endpoint_limits
+            .iter()
+            .map(|(endpoint, limit)| (endpoint.trim_end_matches('/'), RequestLimiter::new(*limit)))
+            .collect();
+        let limiter = |endpoint: &str| limiters.get(endpoint.trim_end_matches('/')).cloned();
+
+        let mut rpc = RpcClient::new(&config.rpc_endpoint, &config.http)?
+            .with_retry_policy(config.retry.clone());
+        if let Some(limiter) = limiter(&config.rpc_endpoint) {
+            rpc = rpc.with_request_limiter(limiter);
+        }
+        if let Some(limiter) = limiter(&config.continuum_endpoint) {
+            continuum.set_request_limiter(limiter);
+        }
+        for gateway in &mut race {
+            if let Some(limiter) = limiter(gateway.endpoint()) {
+                gateway.set_request_limiter(limiter);
+            }
+        }
+        if let Some(auth) = &config.rpc_auth {
+            rpc = rpc.with_auth(auth, &signer);
+        }
+        let throttle = config.rate_limit.map(SubmissionThrottle::new);
 
         Ok(Self {
-            keypair,
+            read_only,
+            signer,
+            retired_keys: Mutex::new(Vec::new()),
+            order_leverage: Mutex::new(HashMap::new()),
+            signers: HashMap::new(),
             continuum,
+            race,
             rpc,
             config,
+            hooks: Vec::new(),
+            order_limits: None,
+            delegation: None,
+            throttle,
+            tick_duration: Mutex::new(None),
+            tick_clock: Mutex::new(TickClock::default()),
+            clock_skew: None,
+            nonces: NonceManager::default(),
+            #[cfg(feature = "store")]
+            store: None,
+            audit: None,
+            market_cache: None,
+            market_ids: Mutex::new(HashMap::new()),
+            #[cfg(feature = "chaos")]
+            chaos: None,
+            submissions: SubmissionGate::default(),
+            quoting_paused: AtomicBool::new(false),
         })
     }
 
-    /// Get the public key of the trading account as a string.
-    pub fn pubkey(&self) -> String {
-        self.keypair.pubkey_string()
+    /// Whether this client was created with [`FermiClient::read_only`].
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
     }
 
-    /// Get the public key as a Pubkey type.
-    pub fn pubkey_bytes(&self) -> Pubkey {
-        self.keypair.pubkey()
+    /// Create a client that signs with a delegated session key.
+    ///
+    /// The delegation must be signed by its owner for `session_key` and not
+    /// yet expired. Every order and cancel is checked against its scope
+    /// before signing, after any hooks have run.
+    pub async fn with_session_key(
+        session_key: impl OrderSigner + 'static,
+        delegation: Delegation,
+        config: ClientConfig,
+    ) -> Result<Self> {
+        delegation.verify()?;
+        if delegation.session_key != session_key.pubkey_string() {
+            return Err(SdkError::Config(format!(
+                "delegation is for session key {}, not {}",
+                delegation.session_key,
+                session_key.pubkey_string()
+            )));
+        }
+        let mut client = Self::new(session_key, config).await?;
+        if delegation.is_expired_at(client.server_now_secs()?) {
+            return Err(SdkError::Config(format!(
+                "delegation expired at {}",
+                delegation.scope.expires_at
+            )));
+        }
+        client.delegation = Some(delegation);
+        Ok(client)
     }
 
-    // =========================================================================
-    // Trading operations (via Continuum)
-    // =========================================================================
+    /// Generate a session key limited to `scope`, sign its delegation with
+    /// this client's key, and register it with the node.
+    ///
+    /// Hand the returned key and delegation to `with_session_key`.
+    pub async fn create_session_key(
+        &self,
+        scope: SessionScope,
+    ) -> Result<(TradingKeypair, Delegation)> {
+        if self.delegation.is_some() {
+            return Err(SdkError::Config(
+                "a session key cannot delegate further".into(),
+            ));
+        }
+        let session_key = TradingKeypair::generate();
+        let delegation =
+            Delegation::sign(self.signer.as_ref(), &session_key.pubkey(), scope).await?;
+        self.rpc.register_delegation(&delegation).await?;
+        info!(
+            "Registered session key {} until {}",
+            delegation.session_key, delegation.scope.expires_at
+        );
+        Ok((session_key, delegation))
+    }
 
-    /// Place a perpetual order.
+    /// Reconcile the local order-ID generator with server state.
     ///
-    /// This method:
-    /// 1. Fetches market decimals to convert price/quantity to canonical units
-    /// 2. Calculates margin amount if not specified
-    /// 3. Signs the order
-    /// 4. Submits to Continuum
-    pub async fn place_perp_order(
-        &mut self,
-        market_id: &str,
-        order: PerpOrder,
-    ) -> Result<OrderResult> {
-        // Fetch market info for decimal conversion
-        let market = self.rpc.get_market(market_id).await?;
+    /// Call once after startup, before placing orders. The generator is
+    /// raised above the highest open order ID and the highest nonce this
+    /// account has had sequenced recently, so a restarted process cannot
+    /// reuse or regress a nonce even if the clock moved backwards.
+    pub async fn recover_state(&mut self) -> Result<RecoveredState> {
+        let open_orders = self.get_my_orders().await?;
+        let last_open_order_id = open_orders.iter().map(|o| o.order_id).max();
+        let owner = self.pubkey_bytes();
+        let last_acked_nonce = self
+            .continuum
+            .latest_nonce(owner.as_ref(), NONCE_RECOVERY_TICKS)
+            .await?;
 
-        // Convert human-readable price/quantity to canonical units
-        let (price_canonical, qty_canonical) =
-            self.to_canonical(&market, order.price, order.quantity)?;
+        for nonce in last_open_order_id.iter().chain(&last_acked_nonce) {
+            self.nonces.observe(*nonce);
+        }
+        info!(
+            "Recovered {} open orders, nonce floor {}",
+            open_orders.len(),
+            self.nonces.last()
+        );
 
-        // Calculate margin amount if not provided
-        let margin_amount = self.calculate_margin(order.price, order.quantity, order.leverage);
+        Ok(RecoveredState {
+            open_orders,
+            last_open_order_id,
+            last_acked_nonce,
+            nonce_floor: self.nonces.last(),
+        })
+    }
 
-        // Parse mints
-        let base_mint = Pubkey::from_str(&market.base_mint)
-            .map_err(|e| SdkError::InvalidPubkey(format!("base_mint: {}", e)))?;
-        let quote_mint = Pubkey::from_str(&market.quote_mint)
-            .map_err(|e| SdkError::InvalidPubkey(format!("quote_mint: {}", e)))?;
+    /// Capture open orders, the nonce floor, and risk settings so a
+    /// redeployed strategy can continue with [`FermiClient::resume`].
+    ///
+    /// Ladders are owned by the caller; add them with
+    /// [`SessionSnapshot::add_ladder`] before saving.
+    pub async fn export_state(&self) -> Result<SessionSnapshot> {
+        let open_orders = self.get_my_orders().await?;
+        let mut snapshot = SessionSnapshot::new(self.pubkey(), self.nonces.last(), open_orders);
+        snapshot.order_limits = self.order_limits.clone();
+        snapshot.margin_call_tiers = self.config.margin_call_tiers.clone();
+        snapshot.rate_limit = self.config.rate_limit;
+        snapshot.max_resubmits = self.config.max_resubmits;
+        Ok(snapshot)
+    }
 
-        // Generate order ID
-        let order_id = generate_order_id();
+    /// Connect a client that continues the session in `snapshot`.
+    ///
+    /// Risk settings from the snapshot replace those in `config`, the nonce
+    /// generator starts above the snapshot's floor and the server's state
+    /// (see [`FermiClient::recover_state`]), and the snapshot's ladders are
+    /// returned with rungs that are no longer open marked filled.
+    pub async fn resume(
+        signer: impl OrderSigner + 'static,
+        mut config: ClientConfig,
+        snapshot: SessionSnapshot,
+    ) -> Result<(Self, Vec<LadderHandle>)> {
+        if snapshot.owner != signer.pubkey_string() {
+            return Err(SdkError::Config(format!(
+                "session snapshot belongs to {}",
+                snapshot.owner
+            )));
+        }
 
-        // Calculate expiry (1 hour from now)
-        let expiry = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| SdkError::Signing(e.to_string()))?
-            .as_secs()
-            + 3600;
+        config.margin_call_tiers = snapshot.margin_call_tiers;
+        config.rate_limit = snapshot.rate_limit;
+        config.max_resubmits = snapshot.max_resubmits;
+        let mut client = Self::new(signer, config).await?;
+        client.order_limits = snapshot.order_limits;
+        client.nonces.observe(snapshot.nonce_floor);
 
-        // Sign the order
-        let signed_order = sign_perp_order(
-            &self.keypair,
-            order_id,
-            order.side,
-            price_canonical,
-            qty_canonical,
-            expiry,
-            &base_mint,
-            &quote_mint,
-            order.leverage,
-            order.position_effect,
-            order.margin_mode,
-            Some(margin_amount),
-            order.reduce_only,
-        )?;
+        let recovered = client.recover_state().await?;
+        let open: HashSet<u64> = recovered.open_orders.iter().map(|o| o.order_id).collect();
+        let mut ladders = snapshot.ladders;
+        for ladder in &mut ladders {
+            ladder.retain_open(&open);
+        }
 
-        info!(
-            "Placing {} perp order: price={}, qty={}, leverage={}x",
-            order.side, order.price, order.quantity, order.leverage
-        );
+        Ok((client, ladders))
+    }
 
-        // Submit to Continuum
-        let result = self.continuum.submit_order(&signed_order).await?;
+    /// Switch the signing key to `new_signer`.
+    ///
+    /// Both keys sign the rotation and it is registered with the node. A
+    /// node without key rotation fails with `SdkError::Config` and the old
+    /// key stays in use: switching anyway would move orders and account
+    /// queries to an unrelated, unfunded account. Orders still open under
+    /// the old key are tracked, and cancels for them keep being signed with
+    /// the old key until they are gone. The switch itself happens last, so a
+    /// failure part-way leaves the old key in use. Each step is logged and
+    /// returned.
+    pub async fn rotate_key(
+        &mut self,
+        new_signer: impl OrderSigner + 'static,
+    ) -> Result<Vec<KeyRotationEvent>> {
+        if self.delegation.is_some() {
+            return Err(SdkError::Config("session keys cannot be rotated".into()));
+        }
+        let old_key = self.pubkey();
+        let new_key = new_signer.pubkey_string();
+        let message = format!("fermi-key-rotation:v1:{}:{}", old_key, new_key);
+        let mut events = Vec::new();
 
-        info!(
-            "Order {} placed successfully, tx_hash: {}",
-            result.order_id, result.tx_hash
-        );
+        let registered = self
+            .rpc
+            .register_key_rotation(
+                &old_key,
+                &new_key,
+                &signer::sign_hex(self.signer.as_ref(), message.as_bytes()).await?,
+                &signer::sign_hex(&new_signer, message.as_bytes()).await?,
+            )
+            .await?;
+        if !registered {
+            return Err(SdkError::Config(
+                "the node does not support key rotation; the new key would be a separate account"
+                    .into(),
+            ));
+        }
+        events.push(KeyRotationEvent::Registered {
+            new_key: new_key.clone(),
+        });
+        info!("Key rotation {}: {:?}", new_key, events[0]);
 
-        Ok(result)
+        let order_ids: Vec<u64> = self
+            .get_my_orders()
+            .await?
+            .iter()
+            .map(|o| o.order_id)
+            .collect();
+        let migrated = KeyRotationEvent::OrdersMigrated {
+            order_ids: order_ids.clone(),
+        };
+        info!("Key rotation {}: {:?}", new_key, migrated);
+        events.push(migrated);
+
+        let old_signer = self.swap_signer(Arc::new(new_signer));
+        if !order_ids.is_empty() {
+            self.retired_keys.get_mut().unwrap().push(RetiredKey {
+                signer: old_signer,
+                order_ids: order_ids.into_iter().collect(),
+            });
+        }
+        let switched = KeyRotationEvent::SignerSwitched {
+            old_key,
+            new_key: new_key.clone(),
+        };
+        info!("Key rotation {}: {:?}", new_key, switched);
+        events.push(switched);
+
+        Ok(events)
     }
 
-    /// Cancel an existing order.
-    pub async fn cancel_order(&mut self, market_id: &str, order_id: u64) -> Result<CancelResult> {
-        // Fetch market info for mints
-        let market = self.rpc.get_market(market_id).await?;
+    /// Load an extra signer under `name`, for use with `as_account`.
+    pub fn add_signer(
+        &mut self,
+        name: impl Into<String>,
+        signer: impl OrderSigner + 'static,
+    ) -> Result<()> {
+        if self.delegation.is_some() {
+            return Err(SdkError::Config(
+                "session-key clients cannot hold other signers".into(),
+            ));
+        }
+        self.signers.insert(name.into(), Arc::new(signer));
+        Ok(())
+    }
 
-        let base_mint = Pubkey::from_str(&market.base_mint)
-            .map_err(|e| SdkError::InvalidPubkey(format!("base_mint: {}", e)))?;
-        let quote_mint = Pubkey::from_str(&market.quote_mint)
-            .map_err(|e| SdkError::InvalidPubkey(format!("quote_mint: {}", e)))?;
+    /// Names of the signers loaded with `add_signer`.
+    pub fn signer_names(&self) -> Vec<&str> {
+        self.signers.keys().map(String::as_str).collect()
+    }
 
-        // Sign the cancel
-        let signed_cancel = sign_cancel(&self.keypair, order_id, &base_mint, &quote_mint)?;
+    /// Act as the signer loaded under `name` until the returned guard drops.
+    ///
+    /// The guard derefs to this client, so connections, caches, hooks and
+    /// the throttle are shared; only the signing key and the account used
+    /// for account queries change.
+    ///
+    /// ```rust,ignore
+    /// client.as_account("mm-2")?.place_perp_order(&market_id, order).await?;
+    /// ```
+    pub fn as_account(&mut self, name: &str) -> Result<AccountGuard<'_>> {
+        let signer = self
+            .signers
+            .remove(name)
+            .ok_or_else(|| SdkError::AccountNotFound(format!("no signer named {}", name)))?;
+        let default = self.swap_signer(signer);
+        Ok(AccountGuard {
+            client: self,
+            name: name.to_string(),
+            default: Some(default),
+        })
+    }
 
-        info!("Cancelling order {}", order_id);
+    /// Replace the signing key, returning the previous one.
+    fn swap_signer(&mut self, signer: Arc<dyn OrderSigner>) -> Arc<dyn OrderSigner> {
+        if let Some(RpcAuth::Signed) = &self.config.rpc_auth {
+            self.rpc = self.rpc.clone().with_auth(&RpcAuth::Signed, &signer);
+        }
+        std::mem::replace(&mut self.signer, signer)
+    }
 
-        // Submit to Continuum
-        let result = self.continuum.submit_cancel(&signed_cancel).await?;
+    /// Get the public key of the trading account as a string.
+    pub fn pubkey(&self) -> String {
+        self.signer.pubkey_string()
+    }
 
-        info!(
-            "Order {} cancelled successfully, tx_hash: {}",
-            result.order_id, result.tx_hash
-        );
+    /// Get the public key as a Pubkey type.
+    pub fn pubkey_bytes(&self) -> Pubkey {
+        self.signer.pubkey()
+    }
 
-        Ok(result)
+    /// The signer orders, cancels, and transfers are signed with.
+    pub fn signer(&self) -> &Arc<dyn OrderSigner> {
+        &self.signer
     }
 
-    // =========================================================================
-    // Testnet funding
-    // =========================================================================
+    /// Get the client configuration.
+    pub fn config(&self) -> &ClientConfig {
+        &self.config
+    }
 
-    /// Airdrop USDC to your own account (testnet only).
-    ///
-    /// Amount is in human-readable USDC (e.g., 1000.0 for 1000 USDC).
-    pub async fn airdrop(&self, amount: f64) -> Result<()> {
-        // Convert to micro-USDC (6 decimals)
-        let amount_micro = (amount * 1_000_000.0) as u64;
-        self.rpc
-            .airdrop(&self.pubkey(), TESTNET_USDC, amount_micro)
-            .await
+    /// Snapshot of the tick clock fed by tick-aligned submissions.
+    pub fn tick_clock(&self) -> TickClock {
+        self.tick_clock.lock().unwrap().clone()
     }
 
-    /// Airdrop tokens to a specific recipient (testnet only).
-    pub async fn airdrop_to(&self, recipient: &str, token_mint: &str, amount: u64) -> Result<()> {
-        self.rpc.airdrop(recipient, token_mint, amount).await
+    /// Offset from the server clock measured by `measure_clock_skew`.
+    pub fn clock_skew(&self) -> Option<ClockSkew> {
+        self.clock_skew
     }
 
-    // =========================================================================
-    // Read operations (via RPC)
-    // =========================================================================
+    /// Register a pre-trade hook run on every order and cancel before signing.
+    pub fn add_order_hook(&mut self, hook: Box<dyn OrderHook>) {
+        self.hooks.push(hook);
+    }
 
-    /// Get all available markets.
-    pub async fn get_markets(&self) -> Result<Vec<MarketInfo>> {
-        self.rpc.list_markets().await
+    /// Enforce `limits` on every order, ahead of registered hooks. Unlike a
+    /// hook, these limits are kept in exported session snapshots.
+    pub fn set_order_limits(&mut self, limits: OrderLimits) {
+        self.order_limits = Some(limits);
     }
 
-    /// Get a specific market by UUID.
-    pub async fn get_market(&self, market_id: &str) -> Result<MarketInfo> {
-        self.rpc.get_market(market_id).await
+    pub fn order_limits(&self) -> Option<&OrderLimits> {
+        self.order_limits.as_ref()
     }
 
-    /// Get the orderbook for a market.
-    pub async fn get_orderbook(&self, market_id: &str) -> Result<Orderbook> {
-        self.rpc.get_orderbook(market_id).await
+    /// Delegation this client signs under, when built with `with_session_key`.
+    pub fn delegation(&self) -> Option<&Delegation> {
+        self.delegation.as_ref()
     }
 
-    /// Get depth data (Binance-style format).
-    pub async fn get_depth(&self, market_id: &str) -> Result<Depth> {
-        self.rpc.get_depth(market_id).await
+    /// Share a submission throttle with other clients, replacing the one built
+    /// from `ClientConfig::rate_limit`.
+    pub fn set_submission_throttle(&mut self, throttle: SubmissionThrottle) {
+        self.config.rate_limit = Some(throttle.limit());
+        self.throttle = Some(throttle);
     }
 
-    /// Get recent trades for a market.
-    pub async fn get_trades(&self, market_id: &str) -> Result<Vec<Trade>> {
-        self.rpc.get_trades(market_id).await
+    /// Persist every order intent, ack, rejection, cancel, and position
+    /// snapshot from this client to `store`.
+    #[cfg(feature = "store")]
+    pub fn set_store(&mut self, store: std::sync::Arc<OrderStore>) {
+        self.store = Some(store);
     }
 
-    /// Get funding events for a market.
-    pub async fn get_funding(&self, market_id: &str) -> Result<Vec<FundingEvent>> {
-        self.rpc.get_funding(market_id).await
+    /// The attached local store, if any.
+    #[cfg(feature = "store")]
+    pub fn store(&self) -> Option<&std::sync::Arc<OrderStore>> {
+        self.store.as_ref()
     }
 
-    /// Get your positions.
-    pub async fn get_positions(&self) -> Result<Vec<Position>> {
-        self.rpc.get_positions(Some(&self.pubkey())).await
+    /// Journal every signed order and cancel, and its outcome, to `journal`.
+    ///
+    /// Signed payloads are written before submission; if that write fails
+    /// the submission is aborted so nothing reaches the venue unrecorded.
+    pub fn set_audit_journal(&mut self, journal: std::sync::Arc<AuditJournal>) {
+        self.audit = Some(journal);
     }
 
-    /// Get all positions (all users).
-    pub async fn get_all_positions(&self) -> Result<Vec<Position>> {
-        self.rpc.get_positions(None).await
+    /// The attached audit journal, if any.
+    pub fn audit_journal(&self) -> Option<&std::sync::Arc<AuditJournal>> {
+        self.audit.as_ref()
     }
 
-    /// Get your open orders.
-    pub async fn get_my_orders(&self) -> Result<Vec<OpenOrder>> {
-        self.rpc.get_user_orders(&self.pubkey()).await
+    /// Read market metadata for signing from `cache` before asking the node,
+    /// and keep it updated from every market fetch.
+    pub fn set_market_cache(&mut self, cache: std::sync::Arc<MarketCache>) {
+        self.market_cache = Some(cache);
     }
 
-    /// Get your account summary (balances and margin metrics).
-    pub async fn get_account(&self) -> Result<AccountSummary> {
-        self.rpc.get_account(&self.pubkey()).await
+    /// Inject faults from `injector` into every Continuum submission and
+    /// market-data read made by this client.
+    #[cfg(feature = "chaos")]
+    pub fn set_fault_injector(&mut self, injector: std::sync::Arc<FaultInjector>) {
+        self.continuum.set_fault_injector(injector.clone());
+        for client in &mut self.race {
+            client.set_fault_injector(injector.clone());
+        }
+        self.rpc.set_fault_injector(injector.clone());
+        self.chaos = Some(injector);
     }
 
-    /// Get your token balances.
-    pub async fn get_balances(&self) -> Result<Balances> {
-        self.rpc.get_balances(&self.pubkey()).await
+    /// The installed fault injector, if any.
+    #[cfg(feature = "chaos")]
+    pub fn fault_injector(&self) -> Option<&std::sync::Arc<FaultInjector>> {
+        self.chaos.as_ref()
+    }
+
+    /// Replace the margin-call tiers for this account.
+    pub fn set_margin_call_tiers(&mut self, tiers: Vec<MarginCallTier>) {
+        self.config.margin_call_tiers = tiers;
+    }
+
+    /// Reject new quotes while paused. Reduce-only and closing orders, and
+    /// cancels, are still accepted.
+    pub fn set_quoting_paused(&self, paused: bool) {
+        if paused != self.quoting_paused.swap(paused, Ordering::Relaxed) {
+            info!("Quoting {}", if paused { "paused" } else { "resumed" });
+        }
+    }
+
+    pub fn is_quoting_paused(&self) -> bool {
+        self.quoting_paused.load(Ordering::Relaxed)
     }
 
     // =========================================================================
-    // Helper methods
+    // Trading operations (via Continuum)
     // =========================================================================
 
-    /// Convert human-readable price/quantity to canonical units.
-    fn to_canonical(&self, market: &MarketInfo, price: f64, quantity: f64) -> Result<(u64, u64)> {
-        let quote_multiplier = 10f64.powi(market.quote_decimals as i32);
-        let base_multiplier = 10f64.powi(market.base_decimals as i32);
+    /// Place a perpetual order.
+    ///
+    /// This method:
+    /// 1. Fetches market decimals to convert price/quantity to canonical units
+    /// 2. Prices market orders from the book, within their slippage bound
+    /// 3. Runs registered order hooks
+    /// 4. Checks free collateral can margin the order, with the hedge
+    ///    offset under portfolio margining (see `max_position_size`)
+    /// 5. Waits for the submission throttle, if one is configured
+    /// 6. Calculates margin amount if not specified
+    /// 7. Signs the order
+    /// 8. Submits to Continuum
+    /// 9. Cancels any unfilled remainder of IOC and FOK orders and reports
+    ///    what filled in `OrderResult::immediate`
+    ///
+    /// IOC and FOK are emulated client-side: the cancel is sequenced right
+    /// behind the order, so it only removes what did not match on arrival.
+    /// The fill is measured from the account's position before placement
+    /// and after the cancel is applied, so these orders return only once
+    /// it is, and fills of your other orders in the market meanwhile count.
+    /// A FOK order is checked against the book before signing, but the book
+    /// can still move before it is sequenced, leaving a partial fill.
+    pub async fn place_perp_order(
+        &self,
+        market: impl Into<MarketRef>,
+        order: PerpOrder,
+    ) -> Result<OrderResult> {
+        let market_id = &self.market_id(market).await?;
+        // Fetch market info for decimal conversion
+        let market = self.market_info(market_id).await?;
+        self.place_order_in_market(&market, order).await
+    }
+
+    /// Run everything `place_perp_order` does short of submitting: market
+    /// pricing, rounding, limits, hooks, margin, and signing.
+    ///
+    /// Nothing is sent to Continuum, recorded in the store or audit journal,
+    /// or counted against the submission throttle. The expected fills walk
+    /// the current book, excluding your own orders, up to the order's price.
+    pub async fn simulate_perp_order(
+        &self,
+        market: impl Into<MarketRef>,
+        order: PerpOrder,
+    ) -> Result<OrderSimulation> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        let prepared = self.check_order(&market, order, &mut None).await?;
+        let (signed, expiry) = self.sign_unrecorded(&prepared).await?;
+        let order = prepared.order;
 
-        let price_canonical = (price * quote_multiplier) as u64;
-        let qty_canonical = (quantity * base_multiplier) as u64;
+        let book = self
+            .get_orderbook(market_id)
+            .await?
+            .without_owner(&self.pubkey());
+        let fills = execution::plan_take(
+            &book,
+            &market,
+            order.side,
+            order.quantity_f64(),
+            order.price_f64(),
+        );
+        let filled_quantity: f64 = fills.iter().map(|s| s.quantity).sum();
+        let filled_notional: f64 = fills.iter().map(|s| s.price * s.quantity).sum();
+        let average_price = (filled_quantity > 0.0).then(|| filled_notional / filled_quantity);
+        let taker_fee = if filled_notional > 0.0 {
+            let schedule = self.get_my_fee_tier().await?.schedule();
+            schedule.fee(filled_notional, Liquidity::Taker)
+        } else {
+            0.0
+        };
 
-        Ok((price_canonical, qty_canonical))
+        info!(
+            "Simulated {} perp order {}: {} of {} would fill",
+            order.side, signed.order_id, filled_quantity, order.quantity
+        );
+        Ok(OrderSimulation {
+            order_id: signed.order_id,
+            price: prepared.price,
+            quantity: prepared.quantity,
+            margin_amount: prepared.margin_amount,
+            expiry,
+            payload: signed.to_json()?,
+            unfilled: (order.quantity_f64() - filled_quantity).max(0.0),
+            order,
+            fills,
+            filled_quantity,
+            average_price,
+            taker_fee,
+        })
     }
 
-    /// Calculate margin amount based on price, quantity, and leverage.
-    /// Returns amount in quote token base units (micro-USDC).
-    fn calculate_margin(&self, price: f64, quantity: f64, leverage: u64) -> u64 {
-        let notional = price * quantity;
-        let margin = notional / (leverage as f64);
-        // Convert to micro-USDC (6 decimals)
-        (margin * 1_000_000.0) as u64
+    /// Place a perp order against market metadata the caller already holds.
+    pub(crate) async fn place_order_in_market(
+        &self,
+        market: &MarketInfo,
+        order: PerpOrder,
+    ) -> Result<OrderResult> {
+        let _in_flight = self.submissions.enter();
+        let prepared = self.prepare_order(market, order, &mut None).await?;
+        let before = self.immediate_baseline(market, [&prepared.order]).await;
+
+        let mut attempt = 0;
+        let mut result = loop {
+            let signed_order = self.sign_prepared(market, &prepared).await?;
+            let order_id = signed_order.order_id;
+
+            // Submit to Continuum, re-signing with a fresh ID on recoverable rejections
+            let submitted = Self::submit_signed(&self.continuum, &self.race, &signed_order).await;
+            self.record_submission(order_id, submitted.as_ref());
+
+            match submitted {
+                Err(e) if attempt < self.config.max_resubmits && e.is_recoverable_rejection() => {
+                    attempt += 1;
+                    warn!("Order {} rejected ({}), resubmitting", order_id, e);
+                }
+                other => break other?,
+            }
+        };
+
+        info!(
+            "Order {} placed successfully, tx_hash: {}",
+            result.order_id, result.tx_hash
+        );
+
+        self.settle_immediate(market, before, vec![(&prepared.order, &mut result)])
+            .await;
+
+        Ok(result)
     }
+
+    /// The account's position in `market` before placing `orders`, in
+    /// canonical base units, if any of them is IOC or FOK. A failed read is
+    /// logged and leaves their fills unmeasured.
+    async fn immediate_baseline<'a>(
+        &self,
+        market: &MarketInfo,
+        orders: impl IntoIterator<Item = &'a PerpOrder>,
+    ) -> Option<i128> {
+        if !orders.into_iter().any(|o| o.time_in_force.is_immediate()) {
+            return None;
+        }
+        match self.position_units(market).await {
+            Ok(units) => Some(units),
+            Err(e) => {
+                warn!(
+                    "Position in {} not read ({}); IOC/FOK fills will not be measured",
+                    market.name, e
+                );
+                None
+            }
+        }
+    }
+
+    /// This account's signed position in `market`, in canonical base units.
+    async fn position_units(&self, market: &MarketInfo) -> Result<i128> {
+        Ok(self
+            .get_positions()
+            .await?
+            .iter()
+            .find(|p| p.market_id == market.uuid)
+            .and_then(|p| tracker::canonical_position(&p.base_position))
+            .unwrap_or(0))
+    }
+
+    /// Cancel whatever is left of the IOC and FOK orders among `placed` in
+    /// one batch, then measure what they filled from how far the account's
+    /// position moved from `before` once the cancels are applied.
+    ///
+    /// The move is shared out by [`execution::share_immediate_fills`]. A
+    /// fully filled order has nothing to cancel, so cancel failures are
+    /// logged rather than returned, as are fills that cannot be measured;
+    /// those orders keep `immediate` unset.
+    async fn settle_immediate(
+        &self,
+        market: &MarketInfo,
+        before: Option<i128>,
+        placed: Vec<(&PerpOrder, &mut OrderResult)>,
+    ) {
+        let placed: Vec<_> = placed
+            .into_iter()
+            .filter(|(order, _)| order.time_in_force.is_immediate())
+            .collect();
+        let Some((_, last_order)) = placed.last() else {
+            return;
+        };
+        let mut last = (last_order.tx_hash.clone(), last_order.expected_tick);
+        let order_ids: Vec<u64> = placed.iter().map(|(_, r)| r.order_id).collect();
+        // The cancel batch is sequenced after every order; once its last
+        // transaction is applied, so is everything before it
+        match self.cancel_orders_in_market(market, &order_ids).await {
+            Ok(cancels) => {
+                if let Some(cancel) = cancels.last() {
+                    last = (cancel.tx_hash.clone(), cancel.expected_tick);
+                }
+            }
+            Err(e) => warn!(
+                "Remainders of orders {:?} not cancelled ({}); they lapse in {}s unless filled",
+                order_ids, e, IMMEDIATE_EXPIRY_SECS
+            ),
+        }
+
+        let Some(before) = before else {
+            return;
+        };
+        let applied = self
+            .wait_until_applied(&last.0, last.1, IMMEDIATE_SETTLE_TIMEOUT)
+            .await;
+        let after = match applied {
+            Ok(true) => self.position_units(market).await,
+            Ok(false) => {
+                warn!(
+                    "IOC/FOK fills in {} not measured: not applied within {:?}",
+                    market.name, IMMEDIATE_SETTLE_TIMEOUT
+                );
+                return;
+            }
+            Err(e) => Err(e),
+        };
+        let after = match after {
+            Ok(after) => after,
+            Err(e) => {
+                warn!("IOC/FOK fills in {} not measured: {}", market.name, e);
+                return;
+            }
+        };
+
+        let moved = (after - before) as f64 / 10f64.powi(market.base_decimals as i32);
+        let (orders, results): (Vec<_>, Vec<_>) = placed.into_iter().unzip();
+        let fills = execution::share_immediate_fills(moved, &orders);
+        for (result, fill) in results.into_iter().zip(fills) {
+            result.immediate = Some(fill);
+        }
+    }
+
+    /// Wait until `tx_hash` is sequenced and the node has produced a block
+    /// since, or `timeout` elapses. Returns whether it was applied.
+    async fn wait_until_applied(
+        &self,
+        tx_hash: &str,
+        expected_tick: u64,
+        timeout: Duration,
+    ) -> Result<bool> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        if let Some(eta) = self.tick_clock().eta(expected_tick) {
+            tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + eta)).await;
+        }
+
+        // Node height when the transaction was seen sequenced
+        let mut sequenced_at: Option<u64> = None;
+        loop {
+            match sequenced_at {
+                None => {
+                    if let TransactionStatus::Sequenced { .. } =
+                        self.get_transaction_status(tx_hash).await?
+                    {
+                        sequenced_at = Some(self.get_node_status().await?.block_height);
+                    }
+                }
+                Some(height) => {
+                    if self.get_node_status().await?.block_height > height {
+                        return Ok(true);
+                    }
+                }
+            }
+            if tokio::time::Instant::now() + CONFIRMATION_POLL_INTERVAL > deadline {
+                return Ok(false);
+            }
+            tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Replace a resting order: cancel `old_order_id` and place `new_order`
+    /// in one Continuum batch, the cancel first.
+    ///
+    /// Both are checked and signed before anything is sent, and the batch
+    /// is acknowledged as a whole, so a quote refresh never leaves both
+    /// orders or neither submitted. The sequencer still executes each
+    /// separately: if the old order filled before its cancel lands, the new
+    /// order rests anyway. There is no resubmission on rejection.
+    pub async fn replace_order(
+        &self,
+        market: impl Into<MarketRef>,
+        old_order_id: u64,
+        new_order: PerpOrder,
+    ) -> Result<ReplaceResult> {
+        let market = self.market_info(&self.market_id(market).await?).await?;
+        let _in_flight = self.submissions.enter();
+        let signed_cancel = self.prepare_cancel(&market, old_order_id).await?;
+        let prepared = self.prepare_order(&market, new_order, &mut None).await?;
+        let signed_order = self.sign_prepared(&market, &prepared).await?;
+        let before = self.immediate_baseline(&market, [&prepared.order]).await;
+
+        let submitted = self
+            .continuum
+            .submit_replace(&signed_cancel, &signed_order)
+            .await;
+        self.record_cancel(old_order_id, submitted.as_ref().map(|(cancel, _)| cancel));
+        self.record_submission(
+            signed_order.order_id,
+            submitted.as_ref().map(|(_, order)| order),
+        );
+        let (cancel, mut order) = submitted?;
+
+        info!(
+            "Order {} replaced by {} in {}",
+            old_order_id, order.order_id, market.name
+        );
+        self.settle_immediate(&market, before, vec![(&prepared.order, &mut order)])
+            .await;
+        Ok(ReplaceResult { cancel, order })
+    }
+
+    /// Place several orders in one market as a single batched Continuum
+    /// transaction, saving a round trip per order.
+    ///
+    /// Every order passes the same checks and hooks as `place_perp_order`
+    /// and is signed before anything is sent; if one is rejected up front,
+    /// none are submitted. The margin check fetches your account once and
+    /// charges each order against what the earlier ones leave free, so the
+    /// batch as a whole must fit. The batch goes to the primary endpoint
+    /// only and is not resubmitted on rejection. Results are in the order
+    /// given.
+    pub async fn place_perp_orders(
+        &self,
+        market: impl Into<MarketRef>,
+        orders: Vec<PerpOrder>,
+    ) -> Result<Vec<OrderResult>> {
+        if orders.is_empty() {
+            return Ok(Vec::new());
+        }
+        let market = self.market_info(&self.market_id(market).await?).await?;
+        let _in_flight = self.submissions.enter();
+
+        let mut signed_orders = Vec::with_capacity(orders.len());
+        let mut placed = Vec::with_capacity(orders.len());
+        let mut budget = None;
+        for order in orders {
+            let prepared = self.prepare_order(&market, order, &mut budget).await?;
+            signed_orders.push(self.sign_prepared(&market, &prepared).await?);
+            placed.push(prepared.order);
+        }
+        let before = self.immediate_baseline(&market, &placed).await;
+
+        let submitted = self.continuum.submit_orders(&signed_orders).await;
+        for (i, signed_order) in signed_orders.iter().enumerate() {
+            let outcome = match &submitted {
+                Ok(results) => Ok(&results[i]),
+                Err(e) => Err(e),
+            };
+            self.record_submission(signed_order.order_id, outcome);
+        }
+        let mut results = submitted?;
+
+        info!(
+            "{} orders placed in {} as one batch",
+            results.len(),
+            market.name
+        );
+        self.settle_immediate(&market, before, placed.iter().zip(&mut results).collect())
+            .await;
+        Ok(results)
+    }
+
+    /// Run pre-trade checks and hooks, wait for the throttle, and convert
+    /// the order to canonical units.
+    async fn prepare_order(
+        &self,
+        market: &MarketInfo,
+        order: PerpOrder,
+        budget: &mut Option<MarginBudget>,
+    ) -> Result<PreparedOrder> {
+        let prepared = self.check_order(market, order, budget).await?;
+        if let Some(throttle) = &self.throttle {
+            throttle
+                .acquire(SubmissionPriority::for_order(&prepared.order))
+                .await;
+        }
+
+        let order = &prepared.order;
+        info!(
+            "Placing {} perp order: price={}, qty={}, leverage={}x",
+            order.side, order.price, order.quantity, order.leverage
+        );
+        Ok(prepared)
+    }
+
+    /// Run pre-trade checks and hooks and convert the order to canonical
+    /// units, without waiting for the throttle.
+    ///
+    /// The margin check draws on `budget`, fetched by the first order that
+    /// needs it; pass the same budget for every order of a batch.
+    async fn check_order(
+        &self,
+        market: &MarketInfo,
+        mut order: PerpOrder,
+        budget: &mut Option<MarginBudget>,
+    ) -> Result<PreparedOrder> {
+        if self.submissions.is_closed() && !order.reduce_only {
+            return Err(SdkError::OrderRejected(
+                "client is shut down; only reduce-only orders are accepted".into(),
+            ));
+        }
+        if self.is_quoting_paused()
+            && SubmissionPriority::for_order(&order) == SubmissionPriority::Quote
+        {
+            return Err(SdkError::OrderRejected(
+                "quoting is paused; only risk-reducing orders are accepted".into(),
+            ));
+        }
+        if order.order_type == OrderType::Market {
+            let book = self
+                .get_orderbook(&market.uuid)
+                .await?
+                .without_owner(&self.pubkey());
+            let max_slippage_bps = order.max_slippage_bps.unwrap_or(DEFAULT_MAX_SLIPPAGE_BPS);
+            order.price = lossy_decimal(execution::market_price(
+                &book,
+                market,
+                order.side,
+                order.quantity_f64(),
+                max_slippage_bps,
+            )?)?;
+        }
+        // Rounded before hooks, so they see what will be signed
+        market.fit_order(&mut order, self.config.rounding)?;
+        if order.time_in_force == TimeInForce::Fok {
+            self.check_fill_or_kill(market, &order).await?;
+        }
+        if let Some(limits) = &self.order_limits {
+            limits.before_order(market, &mut order)?;
+        }
+        for hook in &self.hooks {
+            hook.before_order(market, &mut order)?;
+        }
+        // Checked after hooks, which may resize the order
+        if let Some(delegation) = &self.delegation {
+            delegation.check_order(&market.uuid, &order, self.server_now_secs()?)?;
+        }
+        // Risk-reducing orders free margin rather than consuming it
+        if self.config.margin_check
+            && SubmissionPriority::for_order(&order) == SubmissionPriority::Quote
+        {
+            if budget.is_none() {
+                *budget = Some(self.margin_budget(order.leverage).await?);
+            }
+            if let Some(budget) = budget {
+                budget.check(market, &order)?;
+            }
+        }
+
+        // Convert human-readable price/quantity to canonical units
+        let (price, quantity) = self.to_canonical(market, order.price, order.quantity)?;
+
+        // Calculate margin amount if not provided
+        let margin_amount = self.calculate_margin(order.price, order.quantity, order.leverage)?;
+
+        // Parse mints
+        let base_mint = Pubkey::from_str(&market.base_mint)
+            .map_err(|e| SdkError::InvalidPubkey(format!("base_mint: {}", e)))?;
+        let quote_mint = Pubkey::from_str(&market.quote_mint)
+            .map_err(|e| SdkError::InvalidPubkey(format!("quote_mint: {}", e)))?;
+
+        let expiry_secs = match order.time_in_force {
+            TimeInForce::Gtc => self.expiry_seconds(order.expiry).await?,
+            TimeInForce::Gtt(duration) => duration.as_secs_f64().ceil().max(1.0) as u64,
+            TimeInForce::Ioc | TimeInForce::Fok => IMMEDIATE_EXPIRY_SECS,
+        };
+        Ok(PreparedOrder {
+            order,
+            price,
+            quantity,
+            margin_amount,
+            base_mint,
+            quote_mint,
+            expiry_secs,
+        })
+    }
+
+    /// Sign a prepared order under a fresh ID and record it in the audit
+    /// journal and store before it is submitted.
+    async fn sign_prepared(
+        &self,
+        market: &MarketInfo,
+        prepared: &PreparedOrder,
+    ) -> Result<SignedOrder> {
+        #[cfg_attr(not(feature = "store"), allow(unused_variables))]
+        let (signed_order, expiry) = self.sign_unrecorded(prepared).await?;
+
+        if let Some(journal) = &self.audit {
+            journal.append(
+                AuditKind::Order,
+                serde_json::json!({
+                    "market_id": market.uuid,
+                    "request": signed_order.request,
+                }),
+            )?;
+        }
+
+        // Nothing is submitted unless `resolve_pending` could find it after a crash
+        #[cfg(feature = "store")]
+        if let Some(store) = &self.store {
+            store.record_intent(&market.uuid, signed_order.order_id, &prepared.order)?;
+            store.record_pending(
+                signed_order.order_id,
+                &serde_json::to_string(&signed_order.request)?,
+                expiry,
+            )?;
+        }
+
+        Ok(signed_order)
+    }
+
+    /// Sign a prepared order under a fresh ID, returning it with its expiry.
+    async fn sign_unrecorded(&self, prepared: &PreparedOrder) -> Result<(SignedOrder, u64)> {
+        let order = &prepared.order;
+        let order_id = self.nonces.next();
+        let expiry = self.server_now_secs()? + prepared.expiry_secs;
+
+        let mut signed_order = sign_perp_order(
+            self.signer.as_ref(),
+            order_id,
+            order.side,
+            prepared.price,
+            prepared.quantity,
+            expiry,
+            &prepared.base_mint,
+            &prepared.quote_mint,
+            order.leverage,
+            order.position_effect,
+            order.margin_mode,
+            Some(prepared.margin_amount),
+            order.reduce_only,
+        )
+        .await?;
+        signed_order.request.builder_code = self.config.builder_code.clone();
+        self.order_leverage
+            .lock()
+            .unwrap()
+            .insert(order_id, order.leverage);
+        Ok((signed_order, expiry))
+    }
+
+    /// Submit a signed order, racing it across endpoints when configured.
+    async fn submit_signed(
+        continuum: &ContinuumClient,
+        race: &[ContinuumClient],
+        signed_order: &SignedOrder,
+    ) -> Result<OrderResult> {
+        if race.is_empty() {
+            continuum.submit_order(signed_order).await
+        } else {
+            ContinuumClient::race_order(race, signed_order).await
+        }
+    }
+
+    /// Record a submission outcome in the store and audit journal.
+    fn record_submission(
+        &self,
+        order_id: u64,
+        submitted: std::result::Result<&OrderResult, &SdkError>,
+    ) {
+        // Indeterminate failures stay pending for `resolve_pending`
+        #[cfg(feature = "store")]
+        match submitted {
+            Ok(result) => self.with_store(|s| s.record_ack(result)),
+            Err(e) if e.is_indeterminate() => {}
+            Err(_) => self.with_store(|s| s.record_rejection(order_id)),
+        }
+
+        match submitted {
+            Ok(result) => self.audit_outcome(
+                AuditKind::OrderAck,
+                serde_json::json!({
+                    "order_id": result.order_id,
+                    "sequence_number": result.sequence_number,
+                    "expected_tick": result.expected_tick,
+                    "tx_hash": result.tx_hash,
+                }),
+            ),
+            Err(e) => self.audit_outcome(
+                AuditKind::OrderError,
+                serde_json::json!({ "order_id": order_id, "error": e.to_string() }),
+            ),
+        }
+    }
+
+    /// Cross the spread, consuming book levels up to `limit_price`.
+    ///
+    /// One IOC order is submitted per price level, priced at that level, so
+    /// no slice can fill worse than the level it was planned against, and
+    /// a slice the book no longer holds is cancelled rather than left
+    /// resting. Each slice's fill is measured before the next is sent (see
+    /// [`OrderResult::immediate`]); the report totals them.
+    ///
+    /// If a slice fails after others were submitted, the sweep stops and
+    /// the report carries the error alongside what was filled so far.
+    ///
+    /// This is an `f64` convenience wrapper: `quantity`, `limit_price` and
+    /// the planned slices are converted with [`lossy_decimal`] before
+    /// signing. Build [`PerpOrder`]s directly when exact amounts matter.
+    pub async fn take_liquidity(
+        &self,
+        market: impl Into<MarketRef>,
+        side: Side,
+        quantity: f64,
+        limit_price: f64,
+        leverage: u64,
+    ) -> Result<TakeReport> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        let book = self
+            .get_orderbook(market_id)
+            .await?
+            .without_owner(&self.pubkey());
+        let slices = execution::plan_take(&book, &market, side, quantity, limit_price);
+
+        let orders = slices
+            .iter()
+            .map(|slice| {
+                Ok(PerpOrder {
+                    side,
+                    price: lossy_decimal(slice.price)?,
+                    quantity: lossy_decimal(slice.quantity)?,
+                    leverage,
+                    time_in_force: TimeInForce::Ioc,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut submissions = Vec::with_capacity(slices.len());
+        for (slice, order) in slices.into_iter().zip(orders) {
+            match self.place_order_in_market(&market, order).await {
+                Ok(result) => submissions.push((slice, result)),
+                Err(e) if submissions.is_empty() => return Err(e),
+                Err(e) => {
+                    warn!(
+                        "Sweep in {} stopped after {} slices: {}",
+                        market.name,
+                        submissions.len(),
+                        e
+                    );
+                    return Ok(TakeReport::new(quantity, submissions, Some(e.to_string())));
+                }
+            }
+        }
+
+        Ok(TakeReport::new(quantity, submissions, None))
+    }
+
+    /// Keep repricing a resting order to the touch until it fills.
+    ///
+    /// `order` is the order as it was placed. Every `reprice_interval` it is
+    /// replaced (see [`replace_order`](Self::replace_order)) at the best
+    /// price on its own side of the book (ignoring your orders), never
+    /// moving more than `max_chase_distance` quote units from its original
+    /// price. Replacements keep the order's leverage, margin mode,
+    /// reduce-only flag, position effect and expiry, for the remaining
+    /// quantity.
+    ///
+    /// Returns once the order leaves the book or the touch runs past the
+    /// limit, in which case the order is left resting there. As with
+    /// [`OrderTracker`], whether an order that
+    /// left the book filled is inferred from position changes and trades.
+    ///
+    /// `max_chase_distance` is an `f64` convenience; repriced orders are
+    /// converted with [`lossy_decimal`] and then fitted to the market's tick.
+    pub async fn chase_order(
+        &self,
+        order_id: u64,
+        order: &PerpOrder,
+        max_chase_distance: f64,
+        reprice_interval: Duration,
+    ) -> Result<ChaseReport> {
+        let open = self.find_open_order(order_id).await?;
+        let market = self.market_info(&open.market_id).await?;
+        let side = order.side;
+
+        let price_scale = 10f64.powi(market.quote_decimals as i32);
+        let anchor = open.price as f64 / price_scale;
+        let limit = match side {
+            Side::Buy => anchor + max_chase_distance,
+            Side::Sell => anchor - max_chase_distance,
+        };
+
+        // Fills are measured against the first snapshot
+        let markets = vec![market.uuid.clone()];
+        let mut tracker = OrderTracker::new(self.pubkey());
+        tracker.track_open(&market, &open);
+        let mut snapshot = TrackerSnapshot::fetch(self, markets.clone()).await?;
+
+        let mut report = ChaseReport {
+            order_id,
+            final_price: anchor,
+            reprices: 0,
+            outcome: ChaseOutcome::Filled,
+        };
+        loop {
+            if let Some(outcome) =
+                execution::chase_outcome(&tracker.observe(&snapshot), report.order_id)
+            {
+                report.outcome = outcome;
+                return Ok(report);
+            }
+            tokio::time::sleep(reprice_interval).await;
+            snapshot = TrackerSnapshot::fetch(self, markets.clone()).await?;
+
+            let Some(open) = snapshot
+                .open_orders
+                .iter()
+                .find(|o| o.order_id == report.order_id)
+            else {
+                continue;
+            };
+
+            let book = self
+                .get_orderbook(&market.uuid)
+                .await?
+                .without_owner(&self.pubkey());
+            let touch = match side {
+                Side::Buy => book.best_bid(),
+                Side::Sell => book.best_ask(),
+            };
+            let Some(touch) = touch.map(|o| o.price as f64 / price_scale) else {
+                continue;
+            };
+            let beyond_limit = match side {
+                Side::Buy => touch > limit,
+                Side::Sell => touch < limit,
+            };
+
+            if let Some(price) = execution::chase_price(side, report.final_price, touch, limit) {
+                info!(
+                    "Chasing order {} from {:.4} to {:.4}",
+                    report.order_id, report.final_price, price
+                );
+                let replacement = PerpOrder {
+                    price: lossy_decimal(price)?,
+                    quantity: Decimal::new(open.quantity as i64, market.base_decimals as u32),
+                    ..order.clone()
+                };
+                match self
+                    .replace_order(&market.uuid, report.order_id, replacement.clone())
+                    .await
+                {
+                    Ok(replaced) => {
+                        tracker.untrack(report.order_id);
+                        tracker.track(&market, &replaced.order, &replacement)?;
+                        report.order_id = replaced.order.order_id;
+                        report.final_price = price;
+                        report.reprices += 1;
+                    }
+                    Err(e) => {
+                        // The order may have been cancelled without being replaced
+                        snapshot = TrackerSnapshot::fetch(self, markets.clone()).await?;
+                        let events = tracker.observe(&snapshot);
+                        return match execution::chase_outcome(&events, report.order_id) {
+                            Some(ChaseOutcome::Filled) => Ok(report),
+                            Some(_) => {
+                                warn!(
+                                    "Order {} was cancelled but not replaced: {}",
+                                    report.order_id, e
+                                );
+                                report.outcome = ChaseOutcome::ReplacementFailed;
+                                Ok(report)
+                            }
+                            None => Err(e),
+                        };
+                    }
+                }
+            }
+
+            if beyond_limit {
+                report.outcome = ChaseOutcome::LimitReached;
+                return Ok(report);
+            }
+        }
+    }
+
+    /// Hold an order until just before `target_tick` starts, then place it.
+    ///
+    /// The order is checked, priced and cleared with the throttle up front,
+    /// and signed once the tick is a few ticks away, so only the submission
+    /// itself is left for the boundary. The sequencer is sampled to time the
+    /// tick, and submission is sent early by the observed round-trip time so
+    /// it lands in the target tick. The signed order is not re-signed if
+    /// rejected. Fails without submitting if the target tick has already
+    /// started.
+    pub async fn submit_at_tick(
+        &self,
+        market: impl Into<MarketRef>,
+        order: PerpOrder,
+        target_tick: u64,
+    ) -> Result<OrderResult> {
+        let market = self.market_info(&self.market_id(market).await?).await?;
+        let _in_flight = self.submissions.enter();
+        let prepared = self.prepare_order(&market, order, &mut None).await?;
+        let before = self.immediate_baseline(&market, [&prepared.order]).await;
+
+        let mut latency = self.sample_tick_clock().await?;
+        for _ in 0..TICK_CLOCK_WARMUP_SAMPLES {
+            if self.tick_clock().tick_duration().is_some() {
+                break;
+            }
+            tokio::time::sleep(TICK_CLOCK_WARMUP_INTERVAL).await;
+            latency = self.sample_tick_clock().await?;
+        }
+        let tick = self.tick_clock().tick_duration().ok_or_else(|| {
+            SdkError::ContinuumSubmission("sequencer tick is not advancing".into())
+        })?;
+
+        let mut signed_order: Option<SignedOrder> = None;
+        let signed_order = loop {
+            let clock = self.tick_clock();
+            let start = clock
+                .instant_of(target_tick)
+                .ok_or_else(|| SdkError::ContinuumSubmission("tick clock unavailable".into()))?;
+            let remaining = start
+                .checked_sub(latency)
+                .unwrap_or(start)
+                .saturating_duration_since(Instant::now());
+            if clock.tick_at(Instant::now()).unwrap_or(0.0) >= target_tick as f64 {
+                let e = SdkError::ContinuumSubmission(format!(
+                    "tick {} has already started",
+                    target_tick
+                ));
+                // Settle the recorded order so it is never resubmitted
+                if let Some(signed_order) = &signed_order {
+                    self.record_submission(signed_order.order_id, Err(&e));
+                }
+                return Err(e);
+            }
+
+            // Re-sample while far out so drift in the tick rate is corrected
+            if remaining > tick * 4 {
+                tokio::time::sleep(remaining / 2).await;
+                latency = self.sample_tick_clock().await?;
+                continue;
+            }
+            match signed_order.take() {
+                Some(signed_order) => {
+                    tokio::time::sleep(remaining).await;
+                    break signed_order;
+                }
+                // Time left after signing is measured on the next pass
+                None => signed_order = Some(self.sign_prepared(&market, &prepared).await?),
+            }
+        };
+
+        info!("Submitting order for tick {}", target_tick);
+        let submitted = Self::submit_signed(&self.continuum, &self.race, &signed_order).await;
+        self.record_submission(signed_order.order_id, submitted.as_ref());
+        let mut result = submitted?;
+        self.settle_immediate(&market, before, vec![(&prepared.order, &mut result)])
+            .await;
+        Ok(result)
+    }
+
+    /// Place a ladder of orders between two prices as one batch.
+    ///
+    /// The rungs go through `place_perp_orders`, so if any rung fails its
+    /// checks or the batch is rejected, no rung is placed and the error is
+    /// returned.
+    pub async fn place_ladder(
+        &self,
+        market: impl Into<MarketRef>,
+        spec: LadderSpec,
+    ) -> Result<LadderHandle> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        let mut rungs = execution::plan_ladder(&spec)?;
+        self.place_rungs(&market, &spec, &mut rungs).await?;
+
+        Ok(LadderHandle {
+            market,
+            spec,
+            rungs,
+        })
+    }
+
+    /// Place every unplaced rung in one batch. On error no rung is placed.
+    pub(crate) async fn place_rungs(
+        &self,
+        market: &MarketInfo,
+        spec: &LadderSpec,
+        rungs: &mut [LadderRung],
+    ) -> Result<()> {
+        let orders = rungs
+            .iter()
+            .filter(|r| r.state == RungState::Unplaced)
+            .map(|rung| {
+                Ok(PerpOrder {
+                    side: spec.side,
+                    price: lossy_decimal(rung.price)?,
+                    quantity: lossy_decimal(rung.quantity)?,
+                    leverage: spec.leverage,
+                    ..Default::default()
+                })
+            })
+            .collect::<Result<_>>()?;
+        let results = self.place_perp_orders(market, orders).await?;
+        for (rung, result) in rungs
+            .iter_mut()
+            .filter(|r| r.state == RungState::Unplaced)
+            .zip(results)
+        {
+            rung.state = RungState::Live(result.order_id);
+        }
+        Ok(())
+    }
+
+    /// Cancel several orders in one market as a single batched Continuum
+    /// transaction. Every cancel is signed before anything is sent; the
+    /// batch goes to the primary endpoint only and is not resubmitted.
+    pub(crate) async fn cancel_orders_in_market(
+        &self,
+        market: &MarketInfo,
+        order_ids: &[u64],
+    ) -> Result<Vec<CancelResult>> {
+        if order_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+        let mut signed_cancels = Vec::with_capacity(order_ids.len());
+        for &order_id in order_ids {
+            signed_cancels.push(self.prepare_cancel(market, order_id).await?);
+        }
+
+        info!(
+            "Cancelling {} orders in {} as one batch",
+            order_ids.len(),
+            market.name
+        );
+        let submitted = self.continuum.submit_cancels(&signed_cancels).await;
+        for (i, &order_id) in order_ids.iter().enumerate() {
+            let outcome = match &submitted {
+                Ok(results) => Ok(&results[i]),
+                Err(e) => Err(e),
+            };
+            self.record_cancel(order_id, outcome);
+        }
+        submitted
+    }
+
+    /// Enter several markets near-simultaneously, e.g. a pair trade.
+    ///
+    /// Every leg is checked and signed before anything is sent, so a leg
+    /// failing its checks means nothing is submitted. The legs are then
+    /// submitted concurrently so they aim for the same tick; there is no
+    /// resubmission. Submission failures are recorded per leg rather than
+    /// returned; undo the placed legs with [`FermiClient::rollback_legs`].
+    pub async fn place_legs(&self, legs: &[(&str, PerpOrder)]) -> Result<LegPlacement> {
+        let mut markets = Vec::with_capacity(legs.len());
+        for (market, _) in legs {
+            let market_id = self.market_id(*market).await?;
+            markets.push(self.market_info(&market_id).await?);
+        }
+        let _in_flight = self.submissions.enter();
+        let mut prepared = Vec::with_capacity(legs.len());
+        let mut budget = None;
+        for (market, (_, order)) in markets.iter().zip(legs) {
+            prepared.push(
+                self.prepare_order(market, order.clone(), &mut budget)
+                    .await?,
+            );
+        }
+        let mut signed = Vec::with_capacity(legs.len());
+        for (market, order) in markets.iter().zip(&prepared) {
+            signed.push(self.sign_prepared(market, order).await?);
+        }
+
+        let mut pending = tokio::task::JoinSet::new();
+        for (index, signed_order) in signed.iter().enumerate() {
+            let continuum = self.continuum.clone();
+            let race = self.race.clone();
+            let signed_order = signed_order.clone();
+            pending.spawn(async move {
+                let submitted = Self::submit_signed(&continuum, &race, &signed_order).await;
+                (index, submitted)
+            });
+        }
+        let mut submitted: Vec<Option<Result<OrderResult>>> = signed.iter().map(|_| None).collect();
+        while let Some(joined) = pending.join_next().await {
+            match joined {
+                Ok((index, result)) => submitted[index] = Some(result),
+                Err(e) => warn!("Leg submission task failed: {}", e),
+            }
+        }
+
+        let mut placement = LegPlacement::default();
+        for (((market, prepared), signed_order), result) in markets
+            .into_iter()
+            .zip(prepared)
+            .zip(&signed)
+            .zip(submitted)
+        {
+            // A lost task may still have reached the sequencer
+            let result = result.unwrap_or_else(|| {
+                Err(SdkError::ContinuumSubmission(
+                    "unavailable: submission task failed".into(),
+                ))
+            });
+            self.record_submission(signed_order.order_id, result.as_ref());
+            let (placed, error, indeterminate) = match result {
+                Ok(placed) => (Some(placed), None, false),
+                Err(e) => {
+                    warn!(
+                        "Leg {} in {} failed: {}",
+                        signed_order.order_id, market.name, e
+                    );
+                    (None, Some(e.to_string()), e.is_indeterminate())
+                }
+            };
+            placement.legs.push(LegOutcome {
+                market,
+                order: prepared.order,
+                order_id: signed_order.order_id,
+                placed,
+                error,
+                indeterminate,
+            });
+        }
+        Ok(placement)
+    }
+
+    /// Undo a multi-leg placement: cancel whatever still rests and close
+    /// whatever filled.
+    ///
+    /// Legs found resting are cancelled and their filled part is closed. A
+    /// placed leg no longer resting is taken as fully filled and closed.
+    /// Indeterminate legs are cancelled if found resting but never closed,
+    /// since they may not have landed. Closing orders are reduce-only,
+    /// priced `slippage_bps` through the mark price, and clamped to the
+    /// position. Failed steps are recorded and the rest carry on.
+    pub async fn rollback_legs(
+        &self,
+        placement: &LegPlacement,
+        slippage_bps: u32,
+    ) -> Result<LegRollback> {
+        let open = self.get_my_orders().await?;
+        let mut report = LegRollback::default();
+
+        for leg in &placement.legs {
+            if leg.placed.is_none() && !leg.indeterminate {
+                continue;
+            }
+            let resting = open.iter().find(|o| o.order_id == leg.order_id);
+            if resting.is_some() {
+                match self.cancel_order_in_market(&leg.market, leg.order_id).await {
+                    Ok(cancelled) => report.cancelled.push(cancelled),
+                    Err(e) => {
+                        report
+                            .failures
+                            .push(format!("cancel of leg {}: {}", leg.order_id, e));
+                        continue;
+                    }
+                }
+            }
+            let Some(filled) = leg.filled_quantity(resting) else {
+                report.failures.push(format!(
+                    "leg {} is indeterminate and not resting; not closed",
+                    leg.order_id
+                ));
+                continue;
+            };
+            if filled <= 0.0 {
+                continue;
+            }
+
+            match self.close_leg_fill(leg, filled, slippage_bps).await {
+                Ok(Some(closing)) => report.closing_orders.push(closing),
+                Ok(None) => {}
+                Err(e) => report
+                    .failures
+                    .push(format!("closing leg {}: {}", leg.order_id, e)),
+            }
+        }
+        Ok(report)
+    }
+
+    /// Submit a reduce-only order against `filled` of a leg.
+    async fn close_leg_fill(
+        &self,
+        leg: &LegOutcome,
+        filled: f64,
+        slippage_bps: u32,
+    ) -> Result<Option<OrderResult>> {
+        let positions = self.get_positions().await?;
+        let Some(position) = positions.iter().find(|p| p.market_id == leg.market.uuid) else {
+            return Ok(None);
+        };
+        let size = position.size(&leg.market)?;
+        let mark = position.mark(&leg.market)?;
+        let Some(order) = leg.closing_order(filled, size, mark, slippage_bps)? else {
+            return Ok(None);
+        };
+        warn!(
+            "Rolling back leg {} in {}: {} {} @ {:.4}",
+            leg.order_id, leg.market.name, order.side, order.quantity, order.price
+        );
+        self.place_order_in_market(&leg.market, order)
+            .await
+            .map(Some)
+    }
+
+    /// Cancel an existing order.
+    pub async fn cancel_order(
+        &self,
+        market: impl Into<MarketRef>,
+        order_id: u64,
+    ) -> Result<CancelResult> {
+        let market_id = &self.market_id(market).await?;
+        // Fetch market info for mints
+        let market = self.market_info(market_id).await?;
+        self.cancel_order_in_market(&market, order_id).await
+    }
+
+    /// Cancel an order against market metadata the caller already holds.
+    pub(crate) async fn cancel_order_in_market(
+        &self,
+        market: &MarketInfo,
+        order_id: u64,
+    ) -> Result<CancelResult> {
+        let signed_cancel = self.prepare_cancel(market, order_id).await?;
+
+        info!("Cancelling order {}", order_id);
+
+        // Submit to Continuum. The cancel nonce is the order ID, so only
+        // stale-timestamp rejections can be fixed by resending.
+        let mut attempt = 0;
+        let result = loop {
+            let submitted =
+                Self::submit_signed_cancel(&self.continuum, &self.race, &signed_cancel).await;
+            self.record_cancel(order_id, submitted.as_ref());
+            match submitted {
+                Err(e) if attempt < self.config.max_resubmits && e.is_stale_timestamp() => {
+                    attempt += 1;
+                    warn!("Cancel {} rejected ({}), resubmitting", order_id, e);
+                }
+                other => break other?,
+            }
+        };
+
+        info!(
+            "Order {} cancelled successfully, tx_hash: {}",
+            result.order_id, result.tx_hash
+        );
+
+        Ok(result)
+    }
+
+    /// Cancel every open order, or every open order in one market, with all
+    /// cancels signed first and then submitted concurrently.
+    ///
+    /// Returns each order ID with its outcome. A cancel that fails to sign
+    /// or submit does not stop the others; only failing to list the open
+    /// orders is an error. Cancels are not resubmitted on rejection.
+    /// The market may be given by UUID or symbol.
+    pub async fn cancel_all_orders(
+        &self,
+        market: Option<&str>,
+    ) -> Result<Vec<(u64, Result<CancelResult>)>> {
+        let market_id = match market {
+            Some(market) => Some(self.market_id(market).await?),
+            None => None,
+        };
+        let orders: Vec<OpenOrder> = self
+            .get_my_orders()
+            .await?
+            .into_iter()
+            .filter(|o| market_id.as_ref().is_none_or(|id| o.market_id == *id))
+            .collect();
+        info!("Cancelling {} open orders", orders.len());
+
+        let mut markets: HashMap<String, MarketInfo> = HashMap::new();
+        let mut outcomes: Vec<(u64, Option<Result<CancelResult>>)> = Vec::new();
+        let mut pending = tokio::task::JoinSet::new();
+        for order in &orders {
+            let market = match markets.get(&order.market_id) {
+                Some(market) => Ok(market.clone()),
+                None => self.market_info(&order.market_id).await,
+            };
+            let signed = match market {
+                Ok(market) => {
+                    let signed = self.prepare_cancel(&market, order.order_id).await;
+                    markets.insert(order.market_id.clone(), market);
+                    signed
+                }
+                Err(e) => Err(e),
+            };
+            let index = outcomes.len();
+            match signed {
+                Ok(signed_cancel) => {
+                    let continuum = self.continuum.clone();
+                    let race = self.race.clone();
+                    pending.spawn(async move {
+                        let submitted =
+                            Self::submit_signed_cancel(&continuum, &race, &signed_cancel).await;
+                        (index, submitted)
+                    });
+                    outcomes.push((order.order_id, None));
+                }
+                Err(e) => outcomes.push((order.order_id, Some(Err(e)))),
+            }
+        }
+        while let Some(joined) = pending.join_next().await {
+            match joined {
+                Ok((index, submitted)) => {
+                    self.record_cancel(outcomes[index].0, submitted.as_ref());
+                    outcomes[index].1 = Some(submitted);
+                }
+                Err(e) => warn!("Cancel submission task failed: {}", e),
+            }
+        }
+
+        Ok(outcomes
+            .into_iter()
+            .map(|(order_id, outcome)| {
+                let outcome = outcome.unwrap_or_else(|| {
+                    Err(SdkError::ContinuumSubmission(
+                        "unavailable: cancel task failed".into(),
+                    ))
+                });
+                if let Err(e) = &outcome {
+                    warn!("Cancel of order {} failed: {}", order_id, e);
+                }
+                (order_id, outcome)
+            })
+            .collect())
+    }
+
+    /// Close `close_pct` percent (0 to 100) of the position in a market.
+    ///
+    /// Reads the position, then submits a reduce-only market order on the
+    /// opposing side, in the position's margin mode, filling within
+    /// `DEFAULT_MAX_SLIPPAGE_BPS` of the best price. Returns `None` when
+    /// there is no position in the market.
+    pub async fn close_position(
+        &self,
+        market: impl Into<MarketRef>,
+        close_pct: f64,
+    ) -> Result<Option<OrderResult>> {
+        let market_id = &self.market_id(market).await?;
+        if !(close_pct > 0.0 && close_pct <= 100.0) {
+            return Err(SdkError::OrderRejected(format!(
+                "close percentage {} is not in (0, 100]",
+                close_pct
+            )));
+        }
+        let market = self.get_market(market_id).await?;
+        let positions = self.get_positions().await?;
+        let Some(position) = positions.iter().find(|p| p.market_id == market.uuid) else {
+            return Ok(None);
+        };
+        risk::reduce_position(self, position, close_pct / 100.0, DEFAULT_MAX_SLIPPAGE_BPS).await
+    }
+
+    /// Run cancel hooks and checks, wait for the throttle, then sign and
+    /// journal the cancel.
+    async fn prepare_cancel(&self, market: &MarketInfo, order_id: u64) -> Result<SignedCancel> {
+        for hook in &self.hooks {
+            hook.before_cancel(market, order_id)?;
+        }
+        if let Some(delegation) = &self.delegation {
+            delegation.check_cancel(&market.uuid, self.server_now_secs()?)?;
+        }
+
+        if let Some(throttle) = &self.throttle {
+            throttle.acquire(SubmissionPriority::Cancel).await;
+        }
+
+        let base_mint = Pubkey::from_str(&market.base_mint)
+            .map_err(|e| SdkError::InvalidPubkey(format!("base_mint: {}", e)))?;
+        let quote_mint = Pubkey::from_str(&market.quote_mint)
+            .map_err(|e| SdkError::InvalidPubkey(format!("quote_mint: {}", e)))?;
+
+        // Sign the cancel, with the retired key that placed the order if any
+        let signer = self
+            .retired_keys
+            .lock()
+            .unwrap()
+            .iter()
+            .rev()
+            .find(|k| k.order_ids.contains(&order_id))
+            .map_or_else(|| self.signer.clone(), |k| k.signer.clone());
+        let signed_cancel = sign_cancel(signer.as_ref(), order_id, &base_mint, &quote_mint).await?;
+
+        if let Some(journal) = &self.audit {
+            journal.append(
+                AuditKind::Cancel,
+                serde_json::json!({
+                    "market_id": market.uuid,
+                    "request": signed_cancel.request,
+                }),
+            )?;
+        }
+
+        Ok(signed_cancel)
+    }
+
+    /// Submit a signed cancel, racing it across endpoints when configured.
+    async fn submit_signed_cancel(
+        continuum: &ContinuumClient,
+        race: &[ContinuumClient],
+        signed_cancel: &SignedCancel,
+    ) -> Result<CancelResult> {
+        if race.is_empty() {
+            continuum.submit_cancel(signed_cancel).await
+        } else {
+            ContinuumClient::race_cancel(race, signed_cancel).await
+        }
+    }
+
+    /// Journal a cancel's outcome and, once acknowledged, forget the order.
+    fn record_cancel(
+        &self,
+        order_id: u64,
+        submitted: std::result::Result<&CancelResult, &SdkError>,
+    ) {
+        let result = match submitted {
+            Ok(result) => result,
+            Err(e) => {
+                self.audit_outcome(
+                    AuditKind::CancelError,
+                    serde_json::json!({ "order_id": order_id, "error": e.to_string() }),
+                );
+                return;
+            }
+        };
+        self.audit_outcome(
+            AuditKind::CancelAck,
+            serde_json::json!({
+                "order_id": result.order_id,
+                "sequence_number": result.sequence_number,
+                "expected_tick": result.expected_tick,
+                "tx_hash": result.tx_hash,
+            }),
+        );
+
+        #[cfg(feature = "store")]
+        self.with_store(|s| s.record_cancel(result));
+
+        let mut retired_keys = self.retired_keys.lock().unwrap();
+        for key in retired_keys.iter_mut() {
+            key.order_ids.remove(&order_id);
+        }
+        retired_keys.retain(|k| !k.order_ids.is_empty());
+    }
+
+    // =========================================================================
+    // Server time
+    // =========================================================================
+
+    /// Get the node's wall-clock time in Unix milliseconds.
+    pub async fn get_server_time(&self) -> Result<u64> {
+        self.rpc.get_server_time().await
+    }
+
+    /// Measure the local clock's offset from the server over `samples`
+    /// requests, keeping the estimate with the shortest round trip.
+    ///
+    /// The result is stored and applied to the expiry of every order signed
+    /// afterwards, so a drifting local clock cannot produce orders the server
+    /// treats as already expired. Offsets over a second are logged.
+    pub async fn measure_clock_skew(&mut self, samples: usize) -> Result<ClockSkew> {
+        let mut measured = Vec::with_capacity(samples.max(1));
+        for _ in 0..samples.max(1) {
+            let sent_ms = unix_millis()?;
+            let server_ms = self.get_server_time().await?;
+            measured.push(ClockSkew::from_exchange(sent_ms, server_ms, unix_millis()?));
+        }
+        let skew = ClockSkew::best(&measured).expect("at least one sample");
+        if skew.offset_ms.abs() > CLOCK_SKEW_WARN_MS {
+            warn!(
+                "Local clock is {}ms {} the server (+/- {:?})",
+                skew.offset_ms.abs(),
+                if skew.offset_ms > 0 {
+                    "behind"
+                } else {
+                    "ahead of"
+                },
+                skew.one_way_latency
+            );
+        }
+        self.clock_skew = Some(skew);
+        Ok(skew)
+    }
+
+    // =========================================================================
+    // Sequencer status (via Continuum)
+    // =========================================================================
+
+    /// Get the current Continuum sequencer status.
+    pub async fn get_sequencer_status(&self) -> Result<SequencerStatus> {
+        self.continuum.clone().get_status().await
+    }
+
+    /// Get the rollup node's status, including its block height.
+    pub async fn get_node_status(&self) -> Result<NodeStatus> {
+        self.rpc.get_status().await
+    }
+
+    /// Check whether a submitted transaction has been sequenced yet.
+    pub async fn get_transaction_status(&self, tx_hash: &str) -> Result<TransactionStatus> {
+        self.continuum.clone().get_transaction(tx_hash).await
+    }
+
+    /// Fetch the tick that sequenced `tx_hash`, if it has been sequenced.
+    pub async fn get_inclusion_proof(&self, tx_hash: &str) -> Result<Option<InclusionProof>> {
+        self.continuum.clone().get_inclusion_proof(tx_hash).await
+    }
+
+    /// Wait until a submitted order is applied by the rollup node, or
+    /// `timeout` elapses.
+    ///
+    /// Sleeps until the order's expected tick if the tick clock can place
+    /// it, then polls the sequencer for the transaction and the node for
+    /// open orders and block height. The order counts as applied once it
+    /// rests among this account's open orders, or once the node has produced
+    /// a block after the transaction was seen sequenced. Ending the wait
+    /// unconfirmed is not an error; the result says how far the order got.
+    pub async fn wait_for_confirmation(
+        &self,
+        result: &OrderResult,
+        timeout: Duration,
+    ) -> Result<Confirmation> {
+        let deadline = tokio::time::Instant::now() + timeout;
+        if let Some(eta) = self.tick_clock().eta(result.expected_tick) {
+            tokio::time::sleep_until(deadline.min(tokio::time::Instant::now() + eta)).await;
+        }
+
+        // Tick that sequenced the transaction, and the node height then
+        let mut sequenced: Option<(u64, u64)> = None;
+        loop {
+            if sequenced.is_none() {
+                if let TransactionStatus::Sequenced { tick_number, .. } =
+                    self.get_transaction_status(&result.tx_hash).await?
+                {
+                    let status = self.get_node_status().await?;
+                    sequenced = Some((tick_number, status.block_height));
+                }
+            }
+
+            let resting = self
+                .get_my_orders()
+                .await?
+                .iter()
+                .any(|o| o.order_id == result.order_id);
+            let status = self.get_node_status().await?;
+            let applied_since = sequenced.is_some_and(|(_, height)| status.block_height > height);
+            if resting || applied_since {
+                info!(
+                    "Order {} applied at block {}",
+                    result.order_id, status.block_height
+                );
+                return Ok(Confirmation::Applied {
+                    tick_number: sequenced.map(|(tick, _)| tick),
+                    block_height: status.block_height,
+                    resting,
+                });
+            }
+
+            if tokio::time::Instant::now() + CONFIRMATION_POLL_INTERVAL > deadline {
+                return Ok(match sequenced {
+                    Some((tick_number, _)) => Confirmation::Sequenced { tick_number },
+                    None => Confirmation::Unconfirmed,
+                });
+            }
+            tokio::time::sleep(CONFIRMATION_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Check that `proof` records `order_result` as sequenced from this
+    /// account. See [`sequencer::check_inclusion`] for what is and is not
+    /// checked.
+    pub fn check_inclusion(
+        &self,
+        order_result: &OrderResult,
+        proof: &InclusionProof,
+    ) -> Result<InclusionReceipt> {
+        sequencer::check_inclusion(order_result, &self.pubkey_bytes(), proof)
+    }
+
+    /// Health of the primary Continuum channel as of its last call.
+    ///
+    /// Strategies can pause quoting while this is not `Connected`.
+    pub fn connection_state(&self) -> ConnectionState {
+        self.continuum.connection_state()
+    }
+
+    /// Check the primary Continuum channel every `interval` with a status
+    /// call, emitting the connection state whenever it changes.
+    ///
+    /// A failed check reconnects the channel shared with the client, so an
+    /// idle client recovers from a sequencer restart before its next order.
+    /// The task stops when the returned receiver is dropped.
+    pub fn watch_connection(
+        &self,
+        interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<ConnectionState>, JoinHandle<()>) {
+        let continuum = self.continuum.clone();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut last = None;
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                if let Err(e) = continuum.get_status().await {
+                    warn!("Continuum health check failed: {}", e);
+                }
+                let state = continuum.connection_state();
+                if last == Some(state) {
+                    continue;
+                }
+                last = Some(state);
+                if tx.send(state).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+
+    /// Poll sequencer status in the background using the default congestion
+    /// thresholds. See [`FermiClient::watch_sequencer_with`].
+    pub fn watch_sequencer(
+        &self,
+        interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<SequencerUpdate>, JoinHandle<()>) {
+        self.watch_sequencer_with(interval, SequencerThresholds::default())
+    }
+
+    /// Poll sequencer status every `interval`, emitting each snapshot with its
+    /// tick rate, backlog growth, and any threshold crossings.
+    ///
+    /// The task uses its own handle to the Continuum channel and stops when
+    /// the returned receiver is dropped.
+    pub fn watch_sequencer_with(
+        &self,
+        interval: Duration,
+        thresholds: SequencerThresholds,
+    ) -> (mpsc::UnboundedReceiver<SequencerUpdate>, JoinHandle<()>) {
+        SequencerMonitor::watch(self.continuum.clone(), interval, thresholds, None)
+    }
+
+    /// Like [`FermiClient::watch_sequencer_with`], but also slows new quotes on
+    /// the submission throttle by `slowdown` (e.g. 4.0 for a quarter of the
+    /// normal rate) while any threshold is breached. Cancels and
+    /// risk-reducing orders keep the full rate, and quotes get it back when
+    /// the task ends.
+    ///
+    /// Requires `ClientConfig::rate_limit` or a shared throttle to be set.
+    pub fn watch_backpressure(
+        &self,
+        interval: Duration,
+        thresholds: SequencerThresholds,
+        slowdown: f64,
+    ) -> Result<(mpsc::UnboundedReceiver<SequencerUpdate>, JoinHandle<()>)> {
+        let throttle = self
+            .throttle
+            .clone()
+            .ok_or_else(|| SdkError::Config("adaptive throttling requires a rate limit".into()))?;
+        Ok(SequencerMonitor::watch(
+            self.continuum.clone(),
+            interval,
+            thresholds,
+            Some((throttle, slowdown)),
+        ))
+    }
+
+    // =========================================================================
+    // Testnet funding
+    // =========================================================================
+
+    /// Airdrop USDC to your own account (testnet only).
+    ///
+    /// Amount is in human-readable USDC (e.g., 1000.0 for 1000 USDC).
+    pub async fn airdrop(&self, amount: f64) -> Result<()> {
+        // Convert to micro-USDC (6 decimals)
+        let amount_micro = (amount * 1_000_000.0) as u64;
+        self.rpc
+            .airdrop(&self.pubkey(), TESTNET_USDC, amount_micro)
+            .await
+    }
+
+    /// Airdrop USDC and wait until the credit shows in the account's
+    /// collateral, returning the confirmed collateral.
+    ///
+    /// A fresh account that the node has not seen yet counts as zero
+    /// collateral. Fails with `SdkError::Airdrop` if the credit has not
+    /// landed within `timeout`.
+    pub async fn airdrop_and_wait(&self, amount: f64, timeout: Duration) -> Result<f64> {
+        let owner = self.pubkey();
+        let collateral =
+            |account: Option<AccountSummary>| account.map(|a| a.usdc_collateral).unwrap_or(0.0);
+        let baseline = collateral(self.rpc.find_account(&owner).await?);
+        self.airdrop(amount).await?;
+
+        // Allow for rounding to micro-USDC
+        let target = baseline + amount - 1e-6;
+        let deadline = tokio::time::Instant::now() + timeout;
+        loop {
+            let current = collateral(self.rpc.find_account(&owner).await?);
+            if current >= target {
+                info!(
+                    "Airdrop of {} USDC confirmed, collateral {}",
+                    amount, current
+                );
+                return Ok(current);
+            }
+            if tokio::time::Instant::now() + AIRDROP_POLL_INTERVAL > deadline {
+                return Err(SdkError::Airdrop(format!(
+                    "{} USDC not credited within {:?} (collateral {}, expected {})",
+                    amount,
+                    timeout,
+                    current,
+                    baseline + amount
+                )));
+            }
+            tokio::time::sleep(AIRDROP_POLL_INTERVAL).await;
+        }
+    }
+
+    /// Move `amount` of `token_mint` (human units) from one of your accounts
+    /// to `to`.
+    ///
+    /// `from` is this client's public key, or the name or public key of a
+    /// signer loaded with `add_signer`. The token's decimals come from the
+    /// listed markets, and the transfer is refused locally if `from` has
+    /// less than `amount` available.
+    pub async fn transfer(
+        &self,
+        from: &str,
+        to: &str,
+        token_mint: &str,
+        amount: Decimal,
+    ) -> Result<TransferResult> {
+        let to_key =
+            Pubkey::from_str(to).map_err(|e| SdkError::InvalidPubkey(format!("to: {}", e)))?;
+        let mint = Pubkey::from_str(token_mint)
+            .map_err(|e| SdkError::InvalidPubkey(format!("token_mint: {}", e)))?;
+        if amount <= Decimal::ZERO {
+            return Err(SdkError::DecimalConversion(format!(
+                "transfer amount must be positive, got {}",
+                amount
+            )));
+        }
+        let decimals = self.token_decimals(token_mint).await?;
+        let amount_canonical = to_canonical_units(amount, decimals, Rounding::Exact)?;
+
+        let signer = if from == self.pubkey() {
+            &self.signer
+        } else {
+            self.signers
+                .get(from)
+                .or_else(|| self.signers.values().find(|k| k.pubkey_string() == from))
+                .ok_or_else(|| SdkError::AccountNotFound(format!("no signer for {}", from)))?
+        };
+        let from_key = signer.pubkey_string();
+
+        let available: u64 = self
+            .rpc
+            .get_balances(&from_key)
+            .await?
+            .tokens
+            .get(token_mint)
+            .and_then(|b| b.available.trim().parse().ok())
+            .unwrap_or(0);
+        if available < amount_canonical {
+            return Err(SdkError::InsufficientBalance(format!(
+                "{} has {} of {} available, transfer needs {}",
+                from_key, available, token_mint, amount_canonical
+            )));
+        }
+
+        let signed = sign_transfer(
+            signer.as_ref(),
+            self.nonces.next(),
+            &to_key,
+            &mint,
+            amount_canonical,
+        )
+        .await?;
+        info!(
+            "Transferring {} of {} from {} to {}",
+            amount, token_mint, from_key, to
+        );
+        self.continuum.submit_transfer(&signed).await
+    }
+
+    /// Deposit `amount` of the configured collateral mint, in human units,
+    /// into your margin account.
+    pub async fn deposit(&self, amount: Decimal) -> Result<CollateralResult> {
+        self.move_collateral(CollateralAction::Deposit, amount)
+            .await
+    }
+
+    /// Withdraw `amount` of the configured collateral mint, in human units,
+    /// from your margin account.
+    ///
+    /// Refused locally if less than `amount` is available. The withdrawal is
+    /// settled after it is sequenced; follow it with `get_withdrawal_status`.
+    pub async fn withdraw(&self, amount: Decimal) -> Result<CollateralResult> {
+        self.move_collateral(CollateralAction::Withdraw, amount)
+            .await
+    }
+
+    async fn move_collateral(
+        &self,
+        action: CollateralAction,
+        amount: Decimal,
+    ) -> Result<CollateralResult> {
+        let token_mint = &self.config.collateral_mint;
+        let mint = Pubkey::from_str(token_mint)
+            .map_err(|e| SdkError::InvalidPubkey(format!("collateral_mint: {}", e)))?;
+        if amount <= Decimal::ZERO {
+            return Err(SdkError::DecimalConversion(format!(
+                "{} amount must be positive, got {}",
+                action, amount
+            )));
+        }
+        let decimals = self.token_decimals(token_mint).await?;
+        let amount_canonical = to_canonical_units(amount, decimals, Rounding::Exact)?;
+
+        if action == CollateralAction::Withdraw {
+            let available: u64 = self
+                .get_balances()
+                .await?
+                .tokens
+                .get(token_mint)
+                .and_then(|b| b.available.trim().parse().ok())
+                .unwrap_or(0);
+            if available < amount_canonical {
+                return Err(SdkError::InsufficientBalance(format!(
+                    "{} of {} available, withdrawal needs {}",
+                    available, token_mint, amount_canonical
+                )));
+            }
+        }
+
+        let signed = sign_collateral(
+            self.signer.as_ref(),
+            action,
+            self.nonces.next(),
+            &mint,
+            amount_canonical,
+        )
+        .await?;
+        info!("Submitting {} of {} {}", action, amount, token_mint);
+        self.continuum.submit_collateral(&signed).await
+    }
+
+    /// Get your withdrawals and how far each has settled.
+    pub async fn get_withdrawal_status(&self) -> Result<Vec<Withdrawal>> {
+        self.rpc.get_withdrawals(&self.pubkey()).await
+    }
+
+    /// Airdrop `amount` of any listed token to your own account, in human
+    /// units (testnet only).
+    ///
+    /// Decimals come from the markets that use the token, falling back to
+    /// the default SOL and USDC mints. Amounts with more places than the
+    /// token has are refused rather than rounded.
+    pub async fn airdrop_token(&self, token_mint: &str, amount: Decimal) -> Result<()> {
+        let decimals = self.token_decimals(token_mint).await?;
+        let amount_canonical = to_canonical_units(amount, decimals, Rounding::Exact)?;
+        self.rpc
+            .airdrop(&self.pubkey(), token_mint, amount_canonical)
+            .await
+    }
+
+    /// Airdrop tokens to a specific recipient (testnet only).
+    ///
+    /// Amount is in canonical base units; see `airdrop_token` for human units.
+    pub async fn airdrop_to(&self, recipient: &str, token_mint: &str, amount: u64) -> Result<()> {
+        self.rpc.airdrop(recipient, token_mint, amount).await
+    }
+
+    // =========================================================================
+    // Read operations (via RPC)
+    // =========================================================================
+
+    /// Get all available markets.
+    pub async fn get_markets(&self) -> Result<Vec<MarketInfo>> {
+        let markets = self.rpc.list_markets().await?;
+        self.cache_markets(&markets);
+        Ok(markets)
+    }
+
+    /// Get a market by UUID or symbol.
+    pub async fn get_market(&self, market: impl Into<MarketRef>) -> Result<MarketInfo> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        self.cache_markets(std::slice::from_ref(&market));
+        Ok(market)
+    }
+
+    /// Get a market by symbol, such as "SOL-PERP" (case-insensitive).
+    pub async fn get_market_by_name(&self, name: &str) -> Result<MarketInfo> {
+        self.get_market(MarketRef::Name(name.to_string())).await
+    }
+
+    /// Get the orderbook for a market.
+    ///
+    /// The snapshot is checked with [`Orderbook::validate`], so a corrupt or
+    /// crossed book fails with `SdkError::InvalidOrderbook` instead of
+    /// reaching a strategy.
+    pub async fn get_orderbook(&self, market: impl Into<MarketRef>) -> Result<Orderbook> {
+        let market_id = &self.market_id(market).await?;
+        let book = self.rpc.get_orderbook(market_id).await?;
+        book.validate()?;
+        Ok(book)
+    }
+
+    /// Open a push-based market-data stream on the WebSocket endpoint
+    /// served alongside the configured RPC endpoint. Subscribe to markets on
+    /// the returned stream; it reconnects and resubscribes on its own.
+    pub fn market_stream(&self) -> FermiStream {
+        FermiStream::connect(StreamConfig::for_rpc_endpoint(&self.config.rpc_endpoint))
+    }
+
+    /// Get `owner`'s resting orders in a market's book, buys then sells.
+    pub async fn get_owner_orders_on_book(
+        &self,
+        market: impl Into<MarketRef>,
+        owner: &str,
+    ) -> Result<Vec<OrderbookEntry>> {
+        let book = self.get_orderbook(market).await?.only_owner(owner);
+        Ok(book.buys.into_iter().chain(book.sells).collect())
+    }
+
+    /// Get resting orders and size per owner in a market's book, largest
+    /// first. Useful for watching counterparties or your own sub-accounts.
+    pub async fn get_owner_book_stats(
+        &self,
+        market: impl Into<MarketRef>,
+    ) -> Result<Vec<OwnerBookStats>> {
+        let market_id = &self.market_id(market).await?;
+        Ok(self.get_orderbook(market_id).await?.owner_stats())
+    }
+
+    /// Get depth data (Binance-style format).
+    pub async fn get_depth(&self, market: impl Into<MarketRef>) -> Result<Depth> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_depth(market_id).await
+    }
+
+    /// Get recent trades for a market.
+    pub async fn get_trades(&self, market: impl Into<MarketRef>) -> Result<Vec<Trade>> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_trades(market_id).await
+    }
+
+    /// Get one page of trades for a market, filtered by `query`.
+    pub async fn get_trades_with(
+        &self,
+        market: impl Into<MarketRef>,
+        query: &TradesQuery,
+    ) -> Result<Page<Trade>> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_trades_page(market_id, query).await
+    }
+
+    /// Every trade matching `query`, fetched page by page as the stream is
+    /// read, for bulk historical downloads.
+    ///
+    /// Pages follow the node's cursor when it sends one; otherwise set
+    /// `limit` and each full page continues before its oldest trade. The
+    /// stream ends after the first error.
+    pub fn trades_paginated(
+        &self,
+        market: impl Into<MarketRef>,
+        query: TradesQuery,
+    ) -> impl Stream<Item = Result<Trade>> + '_ {
+        let market = market.into();
+        stream::unfold(Some(query), move |query| {
+            let market = market.clone();
+            async move {
+                let query = query?;
+                match self.get_trades_with(market, &query).await {
+                    Ok(page) => {
+                        let next = query.next_page(&page);
+                        Some((page.items.into_iter().map(Ok).collect::<Vec<_>>(), next))
+                    }
+                    Err(e) => Some((vec![Err(e)], None)),
+                }
+            }
+        })
+        .flat_map(stream::iter)
+    }
+
+    /// Get recent trades for a market merged into aggregate prints.
+    ///
+    /// Consecutive trades at the same price and inferred side within the
+    /// same `window` of timestamp units become one print with a count and
+    /// total size. See [`AggTrade::from_trades`].
+    pub async fn get_agg_trades(
+        &self,
+        market: impl Into<MarketRef>,
+        window: u64,
+    ) -> Result<Vec<AggTrade>> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        let trades = self.rpc.get_trades(market_id).await?;
+        Ok(AggTrade::from_trades(&trades, &market, window))
+    }
+
+    /// Get OHLCV candles of `interval` seconds for a market, in human units.
+    ///
+    /// `start` and `end` are unix-second bounds. Nodes that do not serve
+    /// klines fall back to aggregating recent trades with
+    /// [`Kline::from_trades`], which only covers the trades the node still
+    /// returns.
+    pub async fn get_klines(
+        &self,
+        market: impl Into<MarketRef>,
+        interval: u64,
+        start: Option<u64>,
+        end: Option<u64>,
+    ) -> Result<Vec<Kline>> {
+        let market_id = &self.market_id(market).await?;
+        if let Some(klines) = self.rpc.get_klines(market_id, interval, start, end).await? {
+            return Ok(klines);
+        }
+        let market = self.market_info(market_id).await?;
+        let trades: Vec<Trade> = self
+            .rpc
+            .get_trades(market_id)
+            .await?
+            .into_iter()
+            .filter(|t| start.is_none_or(|s| t.timestamp >= s))
+            .filter(|t| end.is_none_or(|e| t.timestamp < e))
+            .collect();
+        Ok(Kline::from_trades(&trades, &market, interval))
+    }
+
+    /// Get the constituent sources and weights behind a market's index
+    /// price, or `None` if the node does not publish them.
+    pub async fn get_index_composition(
+        &self,
+        market: impl Into<MarketRef>,
+    ) -> Result<Option<IndexComposition>> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_index_composition(market_id).await
+    }
+
+    /// Get recent liquidations in a market.
+    pub async fn get_recent_liquidations(
+        &self,
+        market: impl Into<MarketRef>,
+    ) -> Result<Vec<LiquidationEvent>> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_liquidations(market_id).await
+    }
+
+    /// Poll a market's liquidations every `interval`, emitting each one once.
+    ///
+    /// Only liquidations newer than those returned by the first poll are
+    /// sent. Failed polls are logged and retried. The task stops when the
+    /// receiver is dropped.
+    pub fn watch_liquidations(
+        &self,
+        market_id: &str,
+        interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<LiquidationEvent>, JoinHandle<()>) {
+        let rpc = self.rpc.clone();
+        let market_id = market_id.to_string();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            // Newest timestamp seen and the events at it, to skip repeats
+            let mut seen: Option<(u64, Vec<LiquidationEvent>)> = None;
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                let mut events = match rpc.get_liquidations(&market_id).await {
+                    Ok(events) => events,
+                    Err(e) => {
+                        warn!("Liquidation poll for {} failed: {}", market_id, e);
+                        continue;
+                    }
+                };
+                events.sort_by_key(|e| e.timestamp);
+
+                let Some((latest, at_latest)) = &mut seen else {
+                    let latest = events.last().map_or(0, |e| e.timestamp);
+                    events.retain(|e| e.timestamp == latest);
+                    seen = Some((latest, events));
+                    continue;
+                };
+                for event in events {
+                    if event.timestamp < *latest
+                        || (event.timestamp == *latest && at_latest.contains(&event))
+                    {
+                        continue;
+                    }
+                    if event.timestamp > *latest {
+                        *latest = event.timestamp;
+                        at_latest.clear();
+                    }
+                    at_latest.push(event.clone());
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+
+    /// Poll the book every `interval` and send each price alert that fires.
+    ///
+    /// See [`PriceAlerts`] for how rules fire. Failed polls, invalid books,
+    /// and one-sided books are skipped. The task stops when the receiver is dropped.
+    pub fn watch_price_alerts(
+        &self,
+        market: &MarketInfo,
+        rules: Vec<AlertRule>,
+        interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<PriceAlert>, JoinHandle<()>) {
+        let rpc = self.rpc.clone();
+        let market = market.clone();
+        let mut alerts = PriceAlerts::new(&market.uuid, rules);
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                let polled = rpc.get_orderbook(&market.uuid).await;
+                let book = match polled.and_then(|book| book.validate().map(|_| book)) {
+                    Ok(book) => book,
+                    Err(e) => {
+                        warn!("Price alert poll for {} failed: {}", market.uuid, e);
+                        continue;
+                    }
+                };
+                let Some(sample) = MarketSample::from_book(&book, &market, Instant::now()) else {
+                    continue;
+                };
+                for alert in alerts.observe(&sample) {
+                    info!(
+                        "Price alert '{}' in {} at mid {:.4}",
+                        alert.rule, market.name, alert.mid
+                    );
+                    if tx.send(alert).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+
+    /// Get funding events for a market.
+    pub async fn get_funding(&self, market: impl Into<MarketRef>) -> Result<Vec<FundingEvent>> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_funding(market_id).await
+    }
+
+    /// Get the mark and index price of a market.
+    ///
+    /// Nodes that do not publish prices fall back to the latest funding
+    /// event, which may be up to one funding interval old.
+    pub async fn get_market_prices(&self, market: impl Into<MarketRef>) -> Result<MarketPrices> {
+        let market_id = &self.market_id(market).await?;
+        if let Some(prices) = self.rpc.get_prices(market_id).await? {
+            return Ok(prices);
+        }
+        self.rpc
+            .get_funding(market_id)
+            .await?
+            .iter()
+            .max_by_key(|e| e.timestamp)
+            .map(MarketPrices::from)
+            .ok_or_else(|| SdkError::Rpc(format!("No mark or index price for {}", market_id)))
+    }
+
+    /// Get a market's mark price in human quote units.
+    pub async fn get_mark_price(&self, market: impl Into<MarketRef>) -> Result<f64> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        Ok(self.get_market_prices(market_id).await?.mark_f64(&market))
+    }
+
+    /// Get a market's index price in human quote units.
+    pub async fn get_index_price(&self, market: impl Into<MarketRef>) -> Result<f64> {
+        let market_id = &self.market_id(market).await?;
+        let market = self.market_info(market_id).await?;
+        Ok(self.get_market_prices(market_id).await?.index_f64(&market))
+    }
+
+    /// Get one page of funding events for a market, filtered by `query`.
+    pub async fn get_funding_with(
+        &self,
+        market: impl Into<MarketRef>,
+        query: &FundingQuery,
+    ) -> Result<Page<FundingEvent>> {
+        let market_id = &self.market_id(market).await?;
+        self.rpc.get_funding_page(market_id, query).await
+    }
+
+    /// Get the funding you paid and received, one entry per market and
+    /// interval, oldest first.
+    pub async fn get_my_funding_payments(&self) -> Result<Vec<FundingPayment>> {
+        let mut payments = self
+            .rpc
+            .get_account_funding(&self.pubkey(), None, None)
+            .await?;
+        payments.sort_by_key(|p| p.timestamp);
+        Ok(payments)
+    }
+
+    /// Get your auto-deleveraging ranking in a market.
+    ///
+    /// Returns `None` when you hold no ranked position there.
+    pub async fn get_adl_indicator(
+        &self,
+        market: impl Into<MarketRef>,
+    ) -> Result<Option<AdlIndicator>> {
+        let market_id = &self.market_id(market).await?;
+        let owner = self.pubkey();
+        let ranking = self.rpc.get_adl_ranking(market_id).await?;
+        Ok(ranking.into_iter().find(|entry| entry.owner == owner))
+    }
+
+    /// Get your account summary together with every open position,
+    /// resolved to human units and annotated with ADL risk where available.
+    pub async fn get_portfolio(&self) -> Result<Portfolio> {
+        let account = self.get_account().await?;
+        let positions = self.get_positions().await?;
+        let markets = self.get_markets().await?;
+
+        let mut resolved = Vec::with_capacity(positions.len());
+        for position in positions {
+            let market = markets
+                .iter()
+                .find(|m| m.uuid == position.market_id)
+                .cloned()
+                .ok_or_else(|| SdkError::MarketNotFound(position.market_id.clone()))?;
+            let mut entry = PortfolioPosition::new(market, position)?;
+            // The market is listed, so not found means the node has no ADL
+            // support; the indicator is left empty
+            entry.adl = match self.get_adl_indicator(&entry.market.uuid).await {
+                Err(SdkError::MarketNotFound(_)) => None,
+                other => other?,
+            };
+            resolved.push(entry);
+        }
+
+        Ok(Portfolio {
+            account,
+            positions: resolved,
+        })
+    }
+
+    /// Largest order (in base units) you can place right now on `side` at
+    /// `price` and `leverage`.
+    ///
+    /// This is the limit the pre-trade margin check applies (see
+    /// `ClientConfig::margin_check`). Resting orders reserve margin at the
+    /// leverage they were signed with, unless the node's free collateral
+    /// snapshot already nets them out, and any opposing position in the
+    /// market is added back since closing it frees margin rather than
+    /// consuming it. Under portfolio margining, the part of the order that
+    /// offsets the portfolio's net exposure is charged at the reduced hedge
+    /// rate.
+    ///
+    /// The result is an `f64` estimate; the check itself runs on the order's
+    /// exact `Decimal` margin, so convert with [`lossy_decimal`] and round
+    /// down to the market's step rather than ordering exactly this size.
+    pub async fn max_position_size(
+        &self,
+        market: impl Into<MarketRef>,
+        side: Side,
+        leverage: u64,
+        price: f64,
+    ) -> Result<f64> {
+        let market = self.market_info(&self.market_id(market).await?).await?;
+        let budget = self.margin_budget(leverage).await?;
+        Ok(budget.max_order_size(&market, side, leverage, price))
+    }
+
+    /// Fetch the collateral, resting orders and positions the pre-trade
+    /// margin check runs against, once per submission.
+    ///
+    /// Resting orders with no recorded leverage are charged at
+    /// `default_leverage`.
+    async fn margin_budget(&self, default_leverage: u64) -> Result<MarginBudget> {
+        let account = self.get_account().await?;
+        let orders = self.get_my_orders().await?;
+        let mut markets = Vec::new();
+        for market_id in orders.iter().map(|o| &o.market_id).collect::<HashSet<_>>() {
+            markets.push(self.market_info(market_id).await?);
+        }
+        let reserved = {
+            let mut leverages = self.order_leverage.lock().unwrap();
+            // Orders newer than every listed one may not be listed yet
+            let newest = orders.iter().map(|o| o.order_id).max().unwrap_or(0);
+            leverages.retain(|id, _| *id > newest || orders.iter().any(|o| o.order_id == *id));
+            risk::reserved_margin(&orders, &markets, &leverages, default_leverage)
+        };
+
+        let net_notional = match risk::portfolio_margin_offset(&account) {
+            Some(_) => self
+                .get_portfolio()
+                .await?
+                .positions
+                .iter()
+                .map(|p| p.size * p.mark_price)
+                .sum(),
+            None => 0.0,
+        };
+
+        let mut positions = HashMap::new();
+        for position in self.get_positions().await? {
+            let market = self.market_info(&position.market_id).await?;
+            positions.insert(position.market_id.clone(), position.size(&market)?);
+        }
+
+        Ok(MarginBudget::new(
+            account,
+            reserved,
+            positions,
+            net_notional,
+        ))
+    }
+
+    /// Enable or disable portfolio margining for your account.
+    ///
+    /// The change is signed with the account key. Fails with
+    /// `SdkError::Config` when the node does not support portfolio
+    /// margining or the client runs on a session key.
+    pub async fn set_portfolio_margin(&self, enabled: bool) -> Result<()> {
+        if self.delegation.is_some() {
+            return Err(SdkError::Config(
+                "session keys cannot change the margin mode".into(),
+            ));
+        }
+        let owner = self.pubkey();
+        let message = format!("fermi-portfolio-margin:v1:{}:{}", owner, enabled);
+        let signature = signer::sign_hex(self.signer.as_ref(), message.as_bytes()).await?;
+        let supported = self
+            .rpc
+            .set_portfolio_margin(&owner, enabled, &signature)
+            .await?;
+        if !supported {
+            return Err(SdkError::Config(
+                "node does not support portfolio margin".into(),
+            ));
+        }
+        info!(
+            "Portfolio margin {} for {}",
+            if enabled { "enabled" } else { "disabled" },
+            owner
+        );
+        Ok(())
+    }
+
+    /// Evaluate your account against the configured margin-call tiers.
+    pub async fn margin_status(&self) -> Result<MarginStatus> {
+        let account = self.get_account().await?;
+        Ok(risk::margin_status(
+            &account,
+            &self.config.margin_call_tiers,
+        ))
+    }
+
+    /// Simulate hypothetical trades, closes, margin mode switches, or
+    /// collateral movements against your current portfolio.
+    ///
+    /// Margin rates are calibrated from the node's current snapshots, so the
+    /// unchanged portfolio reproduces the node's margin figures.
+    pub async fn simulate(&self, changes: &[PortfolioChange]) -> Result<Simulation> {
+        let portfolio = self.get_portfolio().await?;
+        let rates = MarginRates::from_portfolio(&portfolio);
+        Ok(risk::simulate(&portfolio, changes, rates))
+    }
+
+    /// Stress your current portfolio against price-shock scenarios.
+    pub async fn stress_test(&self, scenarios: &[StressScenario]) -> Result<Vec<StressResult>> {
+        let portfolio = self.get_portfolio().await?;
+        Ok(risk::stress(&portfolio, scenarios))
+    }
+
+    /// Historical-simulation Value-at-Risk of your portfolio in USDC.
+    ///
+    /// Candles of `interval` timestamp units are built from each held
+    /// market's recent trades; `horizon` is measured in those candles.
+    pub async fn portfolio_var(
+        &self,
+        confidence: f64,
+        horizon: usize,
+        interval: u64,
+    ) -> Result<Option<VarEstimate>> {
+        let portfolio = self.get_portfolio().await?;
+        let mut candles = HashMap::new();
+        for position in &portfolio.positions {
+            let trades = self.get_trades(&position.market.uuid).await?;
+            let klines = Kline::from_trades(&trades, &position.market, interval);
+            candles.insert(position.market.uuid.clone(), klines);
+        }
+        Ok(risk::var(&portfolio, &candles, confidence, horizon))
+    }
+
+    /// Get your positions.
+    pub async fn get_positions(&self) -> Result<Vec<Position>> {
+        let positions = self.rpc.get_positions(Some(&self.pubkey())).await?;
+        #[cfg(feature = "store")]
+        self.with_store(|s| s.save_positions(&positions));
+        Ok(positions)
+    }
+
+    /// Get the positions of any account, by base58 public key.
+    pub async fn get_positions_of(&self, owner: &str) -> Result<Vec<Position>> {
+        self.rpc.get_positions(Some(owner)).await
+    }
+
+    /// Get all positions (all users).
+    pub async fn get_all_positions(&self) -> Result<Vec<Position>> {
+        self.rpc.get_positions(None).await
+    }
+
+    /// Get your open orders.
+    pub async fn get_my_orders(&self) -> Result<Vec<OpenOrder>> {
+        self.rpc.get_user_orders(&self.pubkey()).await
+    }
+
+    /// Measure the queue ahead of one of your resting orders.
+    ///
+    /// Returns `None` if the order is open but not visible in the book.
+    pub async fn estimate_queue_position(&self, order_id: u64) -> Result<Option<QueuePosition>> {
+        let open = self.find_open_order(order_id).await?;
+        let market = self.market_info(&open.market_id).await?;
+        let book = self.get_orderbook(&market.uuid).await?;
+        Ok(execution::queue_position(&book, &market, order_id))
+    }
+
+    /// Get the insurance fund's current balance and balance history.
+    pub async fn get_insurance_fund(&self) -> Result<InsuranceFund> {
+        self.rpc.get_insurance_fund().await
+    }
+
+    /// Get the exchange's trading rules.
+    ///
+    /// The result implements [`OrderHook`], so adding it with `add_order_hook`
+    /// rejects orders that break tick, step, size or leverage limits before
+    /// they are signed. `MarginRates::from_tier` turns a market's margin
+    /// tiers into rates for the `risk` module.
+    pub async fn get_exchange_info(&self) -> Result<ExchangeInfo> {
+        self.rpc.get_exchange_info().await
+    }
+
+    /// Make sure this account is initialized on the protocol.
+    ///
+    /// `get_account` reports a missing account as an all-zero summary, which
+    /// hides why a first order fails. Call this once at startup instead: it
+    /// registers the account with a signed request if the node does not
+    /// know it yet.
+    pub async fn ensure_account(&self) -> Result<AccountInit> {
+        let owner = self.pubkey();
+        if self.rpc.find_account(&owner).await?.is_some() {
+            return Ok(AccountInit::Existing);
+        }
+
+        let timestamp = unix_millis()?;
+        let message = format!("fermi-register:v1:{}:{}", owner, timestamp);
+        let signature = signer::sign_hex(self.signer.as_ref(), message.as_bytes()).await?;
+        if !self
+            .rpc
+            .register_account(&owner, timestamp, &signature)
+            .await?
+        {
+            return Ok(AccountInit::NotRequired);
+        }
+        info!("Registered account {}", owner);
+        Ok(AccountInit::Registered)
+    }
+
+    /// Get your fee tier.
+    ///
+    /// Nodes that do not publish per-account tiers fall back to the
+    /// exchange-wide schedule from `get_exchange_info`, as tier "default".
+    pub async fn get_my_fee_tier(&self) -> Result<FeeTier> {
+        if let Some(tier) = self.rpc.get_fee_tier(&self.pubkey()).await? {
+            return Ok(tier);
+        }
+        let fees = self.rpc.get_exchange_info().await?.fees;
+        Ok(FeeTier {
+            tier: "default".to_string(),
+            maker_bps: fees.maker_bps,
+            taker_bps: fees.taker_bps,
+            volume_30d: None,
+        })
+    }
+
+    /// Estimate the fee `order` would pay at your fee tier.
+    ///
+    /// With `LiquidityAssumption::FromBook` the order counts as taker when
+    /// its price crosses the best opposite price in the current book.
+    pub async fn estimate_fees(
+        &self,
+        market: impl Into<MarketRef>,
+        order: &PerpOrder,
+        liquidity: LiquidityAssumption,
+    ) -> Result<FeeEstimate> {
+        let market_id = &self.market_id(market).await?;
+        let schedule = self.get_my_fee_tier().await?.schedule();
+        let liquidity = match liquidity {
+            LiquidityAssumption::Maker => Liquidity::Maker,
+            LiquidityAssumption::Taker => Liquidity::Taker,
+            LiquidityAssumption::FromBook => {
+                let market = self.market_info(market_id).await?;
+                let book = self.get_orderbook(market_id).await?;
+                let price_scale = 10f64.powi(market.quote_decimals as i32);
+                let crosses = match order.side {
+                    Side::Buy => book
+                        .best_ask()
+                        .is_some_and(|a| order.price_f64() >= a.price as f64 / price_scale),
+                    Side::Sell => book
+                        .best_bid()
+                        .is_some_and(|b| order.price_f64() <= b.price as f64 / price_scale),
+                };
+                if crosses {
+                    Liquidity::Taker
+                } else {
+                    Liquidity::Maker
+                }
+            }
+        };
+        let notional = order.price_f64() * order.quantity_f64();
+        Ok(FeeEstimate {
+            liquidity,
+            notional,
+            rate_bps: schedule.rate_bps(liquidity),
+            fee: schedule.fee(notional, liquidity),
+        })
+    }
+
+    /// Get your account summary (balances and margin metrics).
+    pub async fn get_account(&self) -> Result<AccountSummary> {
+        self.rpc.get_account(&self.pubkey()).await
+    }
+
+    /// Get your token balances.
+    pub async fn get_balances(&self) -> Result<Balances> {
+        self.rpc.get_balances(&self.pubkey()).await
+    }
+
+    // =========================================================================
+    // Local history (store feature)
+    // =========================================================================
+
+    /// Pull your new trades and the funding events of `market_ids` into the
+    /// attached store, resuming from each market's sync cursor.
+    #[cfg(feature = "store")]
+    pub async fn sync_history(&self, market_ids: &[String]) -> Result<SyncReport> {
+        let store = self.require_store()?;
+        store::sync_history(&self.rpc, &store, &self.pubkey(), market_ids).await
+    }
+
+    /// Run [`FermiClient::sync_history`] every `interval` in the background,
+    /// emitting each pass's report. Failed passes are logged and retried on
+    /// the next interval. The task stops when the receiver is dropped.
+    #[cfg(feature = "store")]
+    pub fn watch_history_sync(
+        &self,
+        market_ids: Vec<String>,
+        interval: Duration,
+    ) -> Result<(mpsc::UnboundedReceiver<SyncReport>, JoinHandle<()>)> {
+        let store = self.require_store()?;
+        let rpc = self.rpc.clone();
+        let owner = self.pubkey();
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                match store::sync_history(&rpc, &store, &owner, &market_ids).await {
+                    Ok(report) => {
+                        if !report.possible_gaps.is_empty() {
+                            warn!("Trade history may have gaps in {:?}", report.possible_gaps);
+                        }
+                        if tx.send(report).is_err() {
+                            break;
+                        }
+                    }
+                    Err(e) => warn!("History sync failed: {}", e),
+                }
+            }
+        });
+
+        Ok((rx, handle))
+    }
+
+    /// Settle orders that were persisted as signed but never acknowledged,
+    /// e.g. after a crash mid-submit.
+    ///
+    /// Each pending order is confirmed if its nonce appears in recent ticks
+    /// or among open orders, marked dead if it has expired, and otherwise
+    /// resubmitted with its original signature and nonce, so it can be
+    /// sequenced at most once. Orders whose resubmission fails
+    /// indeterminately stay pending.
+    #[cfg(feature = "store")]
+    pub async fn resolve_pending(&mut self) -> Result<PendingResolution> {
+        self.settle_pending(true).await
+    }
+
+    /// `resolve_pending`, with orders that would be resubmitted marked dead
+    /// instead when `resubmit` is false.
+    #[cfg(feature = "store")]
+    async fn settle_pending(&self, resubmit: bool) -> Result<PendingResolution> {
+        let store = self.require_store()?;
+        let pending = store.pending_submissions()?;
+        let mut resolution = PendingResolution::default();
+        if pending.is_empty() {
+            return Ok(resolution);
+        }
+
+        let owner = self.pubkey_bytes();
+        let sequenced = self
+            .continuum
+            .sequenced_nonces(owner.as_ref(), NONCE_RECOVERY_TICKS)
+            .await?;
+        let open: Vec<u64> = self
+            .get_my_orders()
+            .await?
+            .iter()
+            .map(|o| o.order_id)
+            .collect();
+        let now = self.server_now_secs()?;
+
+        for entry in pending {
+            let order_id = entry.order_id;
+            self.nonces.observe(order_id);
+            if sequenced.contains(&order_id) || open.contains(&order_id) {
+                store.record_sequenced(order_id)?;
+                resolution.confirmed.push(order_id);
+                continue;
+            }
+            if entry.expiry <= now {
+                store.record_rejection(order_id)?;
+                resolution.dead.push(order_id);
+                continue;
+            }
+            if !resubmit {
+                info!("Pending order {} abandoned without resubmitting", order_id);
+                store.record_rejection(order_id)?;
+                resolution.dead.push(order_id);
+                continue;
+            }
+
+            let signed_order = SignedOrder {
+                order_id,
+                request: serde_json::from_str(&entry.request)?,
+                owner_bytes: owner.0,
+            };
+            match self.continuum.submit_order(&signed_order).await {
+                Ok(result) => {
+                    store.record_ack(&result)?;
+                    resolution.resubmitted.push(result);
+                }
+                Err(e) if e.is_duplicate_nonce() => {
+                    store.record_sequenced(order_id)?;
+                    resolution.confirmed.push(order_id);
+                }
+                Err(e) if e.is_indeterminate() => {
+                    warn!("Pending order {} still unresolved: {}", order_id, e);
+                    resolution.unresolved.push(order_id);
+                }
+                Err(e) => {
+                    warn!("Pending order {} rejected on resubmit: {}", order_id, e);
+                    store.record_rejection(order_id)?;
+                    resolution.dead.push(order_id);
+                }
+            }
+        }
+
+        info!(
+            "Resolved pending orders: {} confirmed, {} resubmitted, {} dead, {} unresolved",
+            resolution.confirmed.len(),
+            resolution.resubmitted.len(),
+            resolution.dead.len(),
+            resolution.unresolved.len()
+        );
+        Ok(resolution)
+    }
+
+    /// Compare the store's outstanding orders and last position snapshot
+    /// with the server, returning every discrepancy.
+    ///
+    /// With `repair`, unknown server orders are cancelled, orders missing on
+    /// the server are closed in the store, and the position snapshot is
+    /// refreshed. Failed cancels are logged and left out of the report's
+    /// `cancelled` list.
+    #[cfg(feature = "store")]
+    pub async fn reconcile(&mut self, repair: bool) -> Result<ReconciliationReport> {
+        let store = self.require_store()?;
+        let local_orders = store.outstanding_orders()?;
+        let local_positions = store.positions()?;
+        let server_orders = self.get_my_orders().await?;
+        let server_positions = self.rpc.get_positions(Some(&self.pubkey())).await?;
+
+        let mut report = reconcile::compare(
+            &local_orders,
+            &server_orders,
+            &local_positions,
+            &server_positions,
+        );
+        if report.is_clean() || !repair {
+            return Ok(report);
+        }
+
+        for order in &report.unknown_orders {
+            match self.cancel_order(&order.market_id, order.order_id).await {
+                Ok(result) => report.cancelled.push(result),
+                Err(e) => warn!("Reconcile cancel of {} failed: {}", order.order_id, e),
+            }
+        }
+        for order in &report.missing_orders {
+            store.record_closed(order.order_id)?;
+        }
+        store.save_positions(&server_positions)?;
+
+        Ok(report)
+    }
+
+    // =========================================================================
+    // Shutdown
+    // =========================================================================
+
+    /// Tear the client down according to `policy`.
+    ///
+    /// New orders other than reduce-only ones are refused from the start,
+    /// and order submissions already past their checks, from any task
+    /// sharing the client, are waited for. Then, in order:
+    /// 1. unacknowledged submissions are resolved (`store` feature); ones
+    ///    not yet sequenced are marked dead rather than resubmitted, so
+    ///    shutdown never places new orders
+    /// 2. open orders are cancelled and positions flattened, per `policy`
+    /// 3. a final position snapshot is saved to the store
+    /// 4. the Continuum channels and RPC connections are released
+    ///
+    /// Failed steps are recorded in the report rather than aborting the
+    /// rest. Afterwards the client only signs reduce-only orders; reads and
+    /// cancels still work and reconnect on demand. Pair with
+    /// [`shutdown_signal`] to run this on SIGTERM or Ctrl-C.
+    pub async fn shutdown(&self, policy: ShutdownPolicy) -> Result<ShutdownReport> {
+        info!("Shutting down {} ({:?})", self.pubkey(), policy);
+        self.submissions.close().await;
+        let mut report = ShutdownReport::default();
+
+        #[cfg(feature = "store")]
+        if self.store.is_some() {
+            match self.settle_pending(false).await {
+                Ok(resolution) => report.pending = Some(resolution),
+                Err(e) => report
+                    .failures
+                    .push(format!("resolving pending submissions: {}", e)),
+            }
+        }
+
+        if policy != ShutdownPolicy::LeaveResting {
+            report.cancelled = self.cancel_open_orders(&mut report.failures).await;
+        }
+
+        if let ShutdownPolicy::FlattenPositions { slippage_bps } = policy {
+            match self.get_positions().await {
+                Ok(positions) => {
+                    for position in positions {
+                        match risk::reduce_position(self, &position, 1.0, slippage_bps).await {
+                            Ok(Some(result)) => report.closing_orders.push(result),
+                            Ok(None) => {}
+                            Err(e) => report
+                                .failures
+                                .push(format!("closing position in {}: {}", position.market_id, e)),
+                        }
+                    }
+                }
+                Err(e) => report.failures.push(format!("listing positions: {}", e)),
+            }
+        }
+
+        #[cfg(feature = "store")]
+        if let Some(store) = self.store.clone() {
+            let saved = match self.get_positions().await {
+                Ok(positions) => store.save_positions(&positions),
+                Err(e) => Err(e),
+            };
+            if let Err(e) = saved {
+                report
+                    .failures
+                    .push(format!("saving final positions: {}", e));
+            }
+        }
+
+        for client in std::iter::once(&self.continuum).chain(&self.race) {
+            if let Err(e) = client.disconnect() {
+                report.failures.push(format!("closing Continuum: {}", e));
+            }
+        }
+        self.rpc.close_connections();
+
+        info!(
+            "Shutdown complete: {} cancelled, {} closing orders, {} failures",
+            report.cancelled.len(),
+            report.closing_orders.len(),
+            report.failures.len()
+        );
+        Ok(report)
+    }
+
+    /// Whether `shutdown` has run.
+    pub fn is_shut_down(&self) -> bool {
+        self.submissions.is_closed()
+    }
+
+    /// Cancel every open order, recording what failed in `failures` and
+    /// carrying on past it.
+    pub(crate) async fn cancel_open_orders(&self, failures: &mut Vec<String>) -> Vec<CancelResult> {
+        let outcomes = match self.cancel_all_orders(None).await {
+            Ok(outcomes) => outcomes,
+            Err(e) => {
+                failures.push(format!("listing open orders: {}", e));
+                return Vec::new();
+            }
+        };
+        let mut cancelled = Vec::new();
+        for (order_id, outcome) in outcomes {
+            match outcome {
+                Ok(result) => cancelled.push(result),
+                Err(e) => failures.push(format!("cancelling order {}: {}", order_id, e)),
+            }
+        }
+        cancelled
+    }
+
+    // =========================================================================
+    // Helper methods
+    // =========================================================================
+
+    /// Market metadata for signing: the attached cache first, then the node.
+    pub(crate) async fn market_info(&self, market_id: &str) -> Result<MarketInfo> {
+        let Some(cache) = &self.market_cache else {
+            return self.rpc.get_market(market_id).await;
+        };
+        if let Some(market) = cache.get(market_id) {
+            return Ok(market);
+        }
+        self.get_markets()
+            .await?
+            .into_iter()
+            .find(|m| m.uuid == market_id)
+            .ok_or_else(|| SdkError::MarketNotFound(market_id.to_string()))
+    }
+
+    /// Decimals of a token, from the first listed market that uses it or
+    /// the default mints.
+    async fn token_decimals(&self, mint: &str) -> Result<u8> {
+        let cached = self
+            .market_cache
+            .as_ref()
+            .map(|c| c.markets())
+            .unwrap_or_default();
+        let markets = if cached.is_empty() {
+            self.get_markets().await?
+        } else {
+            cached
+        };
+        markets
+            .iter()
+            .find_map(|m| {
+                if m.base_mint == mint {
+                    Some(m.base_decimals)
+                } else if m.quote_mint == mint {
+                    Some(m.quote_decimals)
+                } else {
+                    None
+                }
+            })
+            .or_else(|| known_token_decimals(mint))
+            .ok_or_else(|| {
+                SdkError::DecimalConversion(format!("no listed market uses token {}", mint))
+            })
+    }
+
+    /// Write fetched markets to the attached cache, logging failures.
+    fn cache_markets(&self, markets: &[MarketInfo]) {
+        self.remember_market_ids(markets);
+        if let Some(cache) = &self.market_cache {
+            if let Err(e) = cache.insert(markets) {
+                warn!("Market cache write failed: {}", e);
+            }
+        }
+    }
+
+    fn remember_market_ids(&self, markets: &[MarketInfo]) {
+        let mut ids = self.market_ids.lock().unwrap();
+        for market in markets {
+            ids.insert(market.name.to_ascii_uppercase(), market.uuid.clone());
+            ids.insert(market.uuid.to_ascii_uppercase(), market.uuid.clone());
+        }
+    }
+
+    fn known_market_id(&self, name: &str) -> Option<String> {
+        let ids = self.market_ids.lock().unwrap();
+        ids.get(&name.to_ascii_uppercase()).cloned()
+    }
+
+    /// UUID of `market`. Names are matched case-insensitively against
+    /// markets seen so far, then the attached market cache, then a fresh
+    /// market list.
+    async fn market_id(&self, market: impl Into<MarketRef>) -> Result<String> {
+        let name = match market.into() {
+            MarketRef::Uuid(uuid) => return Ok(uuid),
+            MarketRef::Name(name) => name,
+        };
+        if let Some(uuid) = self.known_market_id(&name) {
+            return Ok(uuid);
+        }
+        if let Some(cache) = &self.market_cache {
+            self.remember_market_ids(&cache.markets());
+            if let Some(uuid) = self.known_market_id(&name) {
+                return Ok(uuid);
+            }
+        }
+        self.get_markets().await?;
+        self.known_market_id(&name)
+            .ok_or(SdkError::MarketNotFound(name))
+    }
+
+    /// Look up one of your open orders by ID.
+    async fn find_open_order(&self, order_id: u64) -> Result<OpenOrder> {
+        self.get_my_orders()
+            .await?
+            .into_iter()
+            .find(|o| o.order_id == order_id)
+            .ok_or(SdkError::OrderNotFound(order_id))
+    }
+
+    /// Journal a submission outcome. The venue has already acted on the
+    /// request, so a failed write is logged rather than returned.
+    fn audit_outcome(&self, kind: AuditKind, payload: serde_json::Value) {
+        if let Some(journal) = &self.audit {
+            if let Err(e) = journal.append(kind, payload) {
+                warn!("Audit journal write failed: {}", e);
+            }
+        }
+    }
+
+    #[cfg(feature = "store")]
+    fn require_store(&self) -> Result<std::sync::Arc<OrderStore>> {
+        self.store
+            .clone()
+            .ok_or_else(|| SdkError::Config("no local store attached".into()))
+    }
+
+    /// Write to the attached store, logging rather than propagating failures.
+    #[cfg(feature = "store")]
+    fn with_store(&self, write: impl FnOnce(&OrderStore) -> Result<()>) {
+        if let Some(store) = &self.store {
+            if let Err(e) = write(store) {
+                warn!("Order store write failed: {}", e);
+            }
+        }
+    }
+
+    /// Record the current tick in the client's tick clock, returning the
+    /// round-trip time of the status call.
+    async fn sample_tick_clock(&self) -> Result<Duration> {
+        let sent = Instant::now();
+        let status = self.get_sequencer_status().await?;
+        let round_trip = sent.elapsed();
+        self.tick_clock
+            .lock()
+            .unwrap()
+            .record_at(status.current_tick, sent + round_trip / 2);
+        Ok(round_trip)
+    }
+
+    /// Current Unix time in seconds, corrected by the measured clock skew.
+    fn server_now_secs(&self) -> Result<u64> {
+        let local_ms = unix_millis()?;
+        let now_ms = self
+            .clock_skew
+            .map_or(local_ms, |skew| skew.to_server_ms(local_ms));
+        Ok(now_ms / 1000)
+    }
+
+    /// Reject a fill-or-kill order unless others' resting liquidity at or
+    /// better than its price covers the whole quantity.
+    async fn check_fill_or_kill(&self, market: &MarketInfo, order: &PerpOrder) -> Result<()> {
+        let book = self
+            .get_orderbook(&market.uuid)
+            .await?
+            .without_owner(&self.pubkey());
+        let quantity = order.quantity_f64();
+        let available: f64 =
+            execution::plan_take(&book, market, order.side, quantity, order.price_f64())
+                .iter()
+                .map(|slice| slice.quantity)
+                .sum();
+        // Allow for f64 rounding below half a canonical unit
+        let unit = 10f64.powi(-(market.base_decimals as i32));
+        if quantity - available > unit / 2.0 {
+            return Err(SdkError::OrderRejected(format!(
+                "fill-or-kill: only {} of {} available at or better than {}",
+                available, order.quantity, order.price
+            )));
+        }
+        Ok(())
+    }
+
+    /// Seconds from now until an order with `expiry` should lapse.
+    async fn expiry_seconds(&self, expiry: OrderExpiry) -> Result<u64> {
+        match expiry {
+            OrderExpiry::Default => Ok(DEFAULT_EXPIRY_SECS),
+            OrderExpiry::Ticks(ticks) => {
+                let tick = self.tick_duration().await?;
+                Ok((tick.as_secs_f64() * ticks as f64).ceil().max(1.0) as u64)
+            }
+        }
+    }
+
+    /// Cached sequencer tick duration, refreshed once it is a minute old.
+    async fn tick_duration(&self) -> Result<Duration> {
+        if let Some((taken, duration)) = *self.tick_duration.lock().unwrap() {
+            if taken.elapsed() < TICK_ESTIMATE_TTL {
+                return Ok(duration);
+            }
+        }
+        let duration = self
+            .continuum
+            .estimate_tick_duration()
+            .await?
+            .ok_or_else(|| {
+                SdkError::ContinuumSubmission(
+                    "not enough recent ticks to estimate tick rate".into(),
+                )
+            })?;
+        *self.tick_duration.lock().unwrap() = Some((Instant::now(), duration));
+        Ok(duration)
+    }
+
+    /// Convert human-readable price/quantity to canonical units, rounding
+    /// per `ClientConfig::rounding`.
+    fn to_canonical(
+        &self,
+        market: &MarketInfo,
+        price: Decimal,
+        quantity: Decimal,
+    ) -> Result<(u64, u64)> {
+        let rounding = self.config.rounding;
+        let price_canonical = to_canonical_units(price, market.quote_decimals, rounding)?;
+        let qty_canonical = to_canonical_units(quantity, market.base_decimals, rounding)?;
+
+        Ok((price_canonical, qty_canonical))
+    }
+
+    /// Calculate margin amount based on price, quantity, and leverage.
+    /// Returns amount in quote token base units (micro-USDC), rounded up.
+    fn calculate_margin(&self, price: Decimal, quantity: Decimal, leverage: u64) -> Result<u64> {
+        price
+            .checked_mul(quantity)
+            .and_then(|notional| notional.checked_div(Decimal::from(leverage.max(1))))
+            // Convert to micro-USDC (6 decimals)
+            .and_then(|margin| margin.checked_mul(Decimal::from(1_000_000)))
+            .and_then(|micros| micros.ceil().to_u64())
+            .ok_or_else(|| {
+                SdkError::DecimalConversion(format!(
+                    "margin for {} @ {} at {}x is out of range",
+                    quantity, price, leverage
+                ))
+            })
+    }
+}
+
+/// Resolve on Ctrl-C, or on SIGTERM on Unix.
+///
+/// Race it against a strategy loop with `tokio::select!`, then call
+/// `FermiClient::shutdown`.
+pub async fn shutdown_signal() -> Result<()> {
+    let signal_error = |e: std::io::Error| SdkError::Config(format!("signal handler: {}", e));
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{signal, SignalKind};
+        let mut terminate = signal(SignalKind::terminate()).map_err(signal_error)?;
+        tokio::select! {
+            result = tokio::signal::ctrl_c() => result.map_err(signal_error)?,
+            _ = terminate.recv() => {}
+        }
+    }
+    #[cfg(not(unix))]
+    tokio::signal::ctrl_c().await.map_err(signal_error)?;
+    info!("Shutdown signal received");
+    Ok(())
+}
+
+/// `value` in units of `10^-decimals`. Digits past `decimals` are rounded
+/// per `rounding`; negative values and values past `u64::MAX` are errors.
+pub(crate) fn to_canonical_units(value: Decimal, decimals: u8, rounding: Rounding) -> Result<u64> {
+    let rounded = value.round_dp_with_strategy(decimals as u32, rounding.strategy());
+    if rounding == Rounding::Exact && rounded != value {
+        return Err(SdkError::DecimalConversion(format!(
+            "{} has more than {} decimal places",
+            value, decimals
+        )));
+    }
+    if rounded.is_sign_negative() && !rounded.is_zero() {
+        return Err(SdkError::DecimalConversion(format!(
+            "{} is negative",
+            value
+        )));
+    }
+    10u64
+        .checked_pow(decimals as u32)
+        .and_then(|scale| rounded.checked_mul(Decimal::from(scale)))
+        .and_then(|units| units.to_u64())
+        .ok_or_else(|| {
+            SdkError::DecimalConversion(format!(
+                "{} at {} decimals is out of range",
+                value, decimals
+            ))
+        })
 }
 
-/// Generate a unique order ID based on timestamp.
-fn generate_order_id() -> u64 {
+fn unix_millis() -> Result<u64> {
     SystemTime::now()
         .duration_since(UNIX_EPOCH)
-        .unwrap()
-        .as_micros() as u64
+        .map(|d| d.as_millis() as u64)
+        .map_err(|e| SdkError::Signing(e.to_string()))
 }
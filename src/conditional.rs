@@ -0,0 +1,585 @@
+//! Client-side stop-loss and take-profit orders.
+//!
+//! The venue has no native trigger orders, so a [`ConditionalEngine`] holds
+//! them locally and watches the mark price. When a [`StopOrder`] or
+//! [`TakeProfitOrder`] triggers, the engine submits its reduce-only closing
+//! [`PerpOrder`] in the position's margin mode. [`ConditionalEngine::observe`]
+//! works with marks from any source. [`ConditionalEngine::spawn`] polls the
+//! mark of every market with pending orders and submits the orders it
+//! triggers.
+//!
+//! Pending orders only exist while the process runs unless a
+//! [`ConditionalStore`] is attached. The engine writes the full pending set
+//! to the store after every change and reloads it on construction, so
+//! triggers survive a restart. [`JsonFileStore`] is a ready-made store.
+//!
+//! A trigger whose submission fails stays pending and is retried on the
+//! next mark that still meets its condition.
+
+use std::collections::HashSet;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::Duration;
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::types::{MarginMode, OrderResult, OrderType, PerpOrder, Position, PositionEffect, Side};
+
+/// Whether a conditional order limits a loss or locks in a gain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConditionalKind {
+    StopLoss,
+    TakeProfit,
+}
+
+/// A pending conditional order as the engine tracks and persists it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ConditionalOrder {
+    /// Local ID assigned by the engine; never sent to the venue
+    pub id: u64,
+    pub kind: ConditionalKind,
+    pub market_id: String,
+    /// Side of the closing order: `Sell` protects a long, `Buy` a short
+    pub side: Side,
+    /// Mark price, in quote units, at which the order triggers
+    pub trigger_price: f64,
+    pub quantity: Decimal,
+    /// Limit price of the closing order; `None` submits a market order
+    pub limit_price: Option<Decimal>,
+    /// Slippage bound for market closing orders (default 100)
+    pub max_slippage_bps: Option<u32>,
+    /// Margin mode of the position; `None` uses the one the node reports
+    #[serde(default)]
+    pub margin_mode: Option<MarginMode>,
+    /// Leverage of the position; `None` uses the one the node reports
+    #[serde(default)]
+    pub leverage: Option<u64>,
+}
+
+impl ConditionalOrder {
+    /// Whether `mark` meets the trigger.
+    ///
+    /// A stop triggers when the mark moves against the position (down for a
+    /// long, up for a short); a take-profit when it moves in its favour.
+    pub fn is_triggered(&self, mark: f64) -> bool {
+        match (self.kind, self.side) {
+            (ConditionalKind::StopLoss, Side::Sell) | (ConditionalKind::TakeProfit, Side::Buy) => {
+                mark <= self.trigger_price
+            }
+            (ConditionalKind::StopLoss, Side::Buy) | (ConditionalKind::TakeProfit, Side::Sell) => {
+                mark >= self.trigger_price
+            }
+        }
+    }
+
+    /// The reduce-only order submitted when the trigger fires, closing
+    /// `position`.
+    ///
+    /// Margin mode and leverage set on the order win over those reported
+    /// for the position; with neither, the order closes at Cross and 1x.
+    pub fn to_perp_order(&self, position: Option<&Position>) -> PerpOrder {
+        let (order_type, price) = match self.limit_price {
+            Some(price) => (OrderType::Limit, price),
+            None => (OrderType::Market, Decimal::ZERO),
+        };
+        let margin_mode = self
+            .margin_mode
+            .or(position.and_then(|p| p.margin_mode))
+            .unwrap_or(MarginMode::Cross);
+        let leverage = self
+            .leverage
+            .or(position.and_then(|p| p.leverage))
+            .unwrap_or(1);
+        PerpOrder {
+            side: self.side,
+            price,
+            quantity: self.quantity,
+            leverage,
+            position_effect: PositionEffect::Close,
+            margin_mode,
+            reduce_only: true,
+            order_type,
+            max_slippage_bps: self.max_slippage_bps,
+            ..Default::default()
+        }
+    }
+}
+
+/// Closes a position once the mark moves against it past `trigger_price`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StopOrder {
+    pub market_id: String,
+    pub side: Side,
+    pub trigger_price: f64,
+    pub quantity: Decimal,
+    pub limit_price: Option<Decimal>,
+    pub max_slippage_bps: Option<u32>,
+    pub margin_mode: Option<MarginMode>,
+    pub leverage: Option<u64>,
+}
+
+impl StopOrder {
+    /// A stop that closes with a market order. `side` is the side of the
+    /// closing order.
+    pub fn new(market_id: &str, side: Side, trigger_price: f64, quantity: Decimal) -> Self {
+        Self {
+            market_id: market_id.to_string(),
+            side,
+            trigger_price,
+            quantity,
+            limit_price: None,
+            max_slippage_bps: None,
+            margin_mode: None,
+            leverage: None,
+        }
+    }
+
+    /// Close with a limit order at `price` instead of a market order.
+    pub fn with_limit(mut self, price: Decimal) -> Self {
+        self.limit_price = Some(price);
+        self
+    }
+
+    pub fn with_max_slippage_bps(mut self, bps: u32) -> Self {
+        self.max_slippage_bps = Some(bps);
+        self
+    }
+
+    /// Close in `mode` at `leverage`, for nodes that do not report the
+    /// position's own.
+    pub fn with_margin(mut self, mode: MarginMode, leverage: u64) -> Self {
+        self.margin_mode = Some(mode);
+        self.leverage = Some(leverage);
+        self
+    }
+}
+
+/// Closes a position once the mark moves in its favour past `trigger_price`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TakeProfitOrder {
+    pub market_id: String,
+    pub side: Side,
+    pub trigger_price: f64,
+    pub quantity: Decimal,
+    pub limit_price: Option<Decimal>,
+    pub max_slippage_bps: Option<u32>,
+    pub margin_mode: Option<MarginMode>,
+    pub leverage: Option<u64>,
+}
+
+impl TakeProfitOrder {
+    /// A take-profit that closes with a market order. `side` is the side
+    /// of the closing order.
+    pub fn new(market_id: &str, side: Side, trigger_price: f64, quantity: Decimal) -> Self {
+        Self {
+            market_id: market_id.to_string(),
+            side,
+            trigger_price,
+            quantity,
+            limit_price: None,
+            max_slippage_bps: None,
+            margin_mode: None,
+            leverage: None,
+        }
+    }
+
+    /// Close with a limit order at `price` instead of a market order.
+    pub fn with_limit(mut self, price: Decimal) -> Self {
+        self.limit_price = Some(price);
+        self
+    }
+
+    pub fn with_max_slippage_bps(mut self, bps: u32) -> Self {
+        self.max_slippage_bps = Some(bps);
+        self
+    }
+
+    /// Close in `mode` at `leverage`, for nodes that do not report the
+    /// position's own.
+    pub fn with_margin(mut self, mode: MarginMode, leverage: u64) -> Self {
+        self.margin_mode = Some(mode);
+        self.leverage = Some(leverage);
+        self
+    }
+}
+
+impl From<StopOrder> for ConditionalOrder {
+    fn from(order: StopOrder) -> Self {
+        Self {
+            id: 0,
+            kind: ConditionalKind::StopLoss,
+            market_id: order.market_id,
+            side: order.side,
+            trigger_price: order.trigger_price,
+            quantity: order.quantity,
+            limit_price: order.limit_price,
+            max_slippage_bps: order.max_slippage_bps,
+            margin_mode: order.margin_mode,
+            leverage: order.leverage,
+        }
+    }
+}
+
+impl From<TakeProfitOrder> for ConditionalOrder {
+    fn from(order: TakeProfitOrder) -> Self {
+        Self {
+            id: 0,
+            kind: ConditionalKind::TakeProfit,
+            market_id: order.market_id,
+            side: order.side,
+            trigger_price: order.trigger_price,
+            quantity: order.quantity,
+            limit_price: order.limit_price,
+            max_slippage_bps: order.max_slippage_bps,
+            margin_mode: order.margin_mode,
+            leverage: order.leverage,
+        }
+    }
+}
+
+/// Where pending conditional orders are kept between runs.
+pub trait ConditionalStore: Send + Sync {
+    /// Pending orders from the last run.
+    fn load(&self) -> Result<Vec<ConditionalOrder>>;
+
+    /// Replace the stored orders with `orders`.
+    fn save(&self, orders: &[ConditionalOrder]) -> Result<()>;
+}
+
+/// Keeps pending orders in a JSON file. A missing file holds no orders.
+#[derive(Debug, Clone)]
+pub struct JsonFileStore {
+    path: PathBuf,
+}
+
+impl JsonFileStore {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self { path: path.into() }
+    }
+}
+
+impl ConditionalStore for JsonFileStore {
+    fn load(&self) -> Result<Vec<ConditionalOrder>> {
+        match std::fs::read_to_string(&self.path) {
+            Ok(json) => Ok(serde_json::from_str(&json)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(Vec::new()),
+            Err(e) => Err(SdkError::Config(format!(
+                "failed to read conditional orders: {}",
+                e
+            ))),
+        }
+    }
+
+    fn save(&self, orders: &[ConditionalOrder]) -> Result<()> {
+        std::fs::write(&self.path, serde_json::to_string_pretty(orders)?)
+            .map_err(|e| SdkError::Config(format!("failed to write conditional orders: {}", e)))
+    }
+}
+
+/// Outcome of a triggered order, sent by [`ConditionalEngine::spawn`].
+#[derive(Debug, Clone)]
+pub enum ConditionalEvent {
+    /// The closing order was submitted and the conditional order removed
+    Submitted {
+        order: ConditionalOrder,
+        mark: f64,
+        result: OrderResult,
+    },
+    /// Submission failed; the order stays pending
+    Failed {
+        order: ConditionalOrder,
+        mark: f64,
+        error: String,
+    },
+}
+
+/// Pending stop-loss and take-profit orders.
+pub struct ConditionalEngine {
+    orders: Vec<ConditionalOrder>,
+    next_id: u64,
+    store: Option<Arc<dyn ConditionalStore>>,
+}
+
+impl std::fmt::Debug for ConditionalEngine {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("ConditionalEngine")
+            .field("orders", &self.orders)
+            .field("next_id", &self.next_id)
+            .field("store", &self.store.is_some())
+            .finish()
+    }
+}
+
+impl Default for ConditionalEngine {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ConditionalEngine {
+    /// An engine without persistence.
+    pub fn new() -> Self {
+        Self {
+            orders: Vec::new(),
+            next_id: 1,
+            store: None,
+        }
+    }
+
+    /// An engine that persists to `store`, starting from the orders it holds.
+    pub fn with_store(store: Arc<dyn ConditionalStore>) -> Result<Self> {
+        let orders = store.load()?;
+        let next_id = orders.iter().map(|o| o.id).max().unwrap_or(0) + 1;
+        if !orders.is_empty() {
+            info!("Loaded {} pending conditional orders", orders.len());
+        }
+        Ok(Self {
+            orders,
+            next_id,
+            store: Some(store),
+        })
+    }
+
+    /// Orders waiting for their trigger.
+    pub fn pending(&self) -> &[ConditionalOrder] {
+        &self.orders
+    }
+
+    /// Start watching an order, returning its local ID.
+    pub fn register(&mut self, order: impl Into<ConditionalOrder>) -> Result<u64> {
+        let mut order = order.into();
+        if order.quantity <= Decimal::ZERO {
+            return Err(SdkError::OrderRejected(
+                "conditional order quantity must be positive".into(),
+            ));
+        }
+        if !(order.trigger_price.is_finite() && order.trigger_price > 0.0) {
+            return Err(SdkError::OrderRejected(format!(
+                "invalid trigger price {}",
+                order.trigger_price
+            )));
+        }
+        order.id = self.next_id;
+        self.next_id += 1;
+        self.orders.push(order);
+        self.persist()?;
+        Ok(self.next_id - 1)
+    }
+
+    /// Stop watching an order. Returns whether it was pending.
+    pub fn cancel(&mut self, id: u64) -> Result<bool> {
+        let before = self.orders.len();
+        self.orders.retain(|o| o.id != id);
+        if self.orders.len() == before {
+            return Ok(false);
+        }
+        self.persist()?;
+        Ok(true)
+    }
+
+    /// Pending orders in `market_id` that `mark` triggers. They stay
+    /// pending until passed to [`ConditionalEngine::complete`].
+    pub fn observe(&self, market_id: &str, mark: f64) -> Vec<ConditionalOrder> {
+        self.orders
+            .iter()
+            .filter(|o| o.market_id == market_id && o.is_triggered(mark))
+            .cloned()
+            .collect()
+    }
+
+    /// Remove a triggered order once its closing order is submitted.
+    pub fn complete(&mut self, id: u64) -> Result<()> {
+        self.cancel(id).map(|_| ())
+    }
+
+    fn persist(&self) -> Result<()> {
+        match &self.store {
+            Some(store) => store.save(&self.orders),
+            None => Ok(()),
+        }
+    }
+
+    /// Poll the mark price of every market with pending orders each
+    /// `poll_interval` and submit the orders it triggers.
+    ///
+    /// Closing orders take the margin mode and leverage of the open
+    /// position (see [`ConditionalOrder::to_perp_order`]). Orders can still
+    /// be registered and cancelled through `engine` while the task runs. The
+    /// task stops when the returned receiver is dropped.
+    pub fn spawn(
+        engine: Arc<Mutex<ConditionalEngine>>,
+        client: Arc<FermiClient>,
+        poll_interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<ConditionalEvent>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                let markets: HashSet<String> = engine
+                    .lock()
+                    .await
+                    .pending()
+                    .iter()
+                    .map(|o| o.market_id.clone())
+                    .collect();
+
+                for market_id in markets {
+                    let market = match client.market_info(&market_id).await {
+                        Ok(market) => market,
+                        Err(e) => {
+                            warn!("Conditional orders failed to fetch market: {}", e);
+                            continue;
+                        }
+                    };
+                    let mark = match client.get_market_prices(&market_id).await {
+                        Ok(prices) => prices.mark_f64(&market),
+                        Err(e) => {
+                            warn!("Conditional orders failed to fetch mark: {}", e);
+                            continue;
+                        }
+                    };
+                    let triggered = engine.lock().await.observe(&market_id, mark);
+                    if triggered.is_empty() {
+                        continue;
+                    }
+
+                    let position = match client.get_positions().await {
+                        Ok(positions) => positions.into_iter().find(|p| p.market_id == market_id),
+                        Err(e) => {
+                            warn!("Conditional orders failed to fetch positions: {}", e);
+                            None
+                        }
+                    };
+                    for order in triggered {
+                        info!(
+                            "{:?} {} in {} triggered at mark {:.4}",
+                            order.kind, order.id, market.name, mark
+                        );
+                        let event = match client
+                            .place_order_in_market(&market, order.to_perp_order(position.as_ref()))
+                            .await
+                        {
+                            Ok(result) => {
+                                if let Err(e) = engine.lock().await.complete(order.id) {
+                                    warn!("Failed to persist conditional orders: {}", e);
+                                }
+                                ConditionalEvent::Submitted {
+                                    order,
+                                    mark,
+                                    result,
+                                }
+                            }
+                            Err(e) => {
+                                warn!("Conditional order {} failed to submit: {}", order.id, e);
+                                ConditionalEvent::Failed {
+                                    order,
+                                    mark,
+                                    error: e.to_string(),
+                                }
+                            }
+                        };
+                        if tx.send(event).is_err() {
+                            return;
+                        }
+                    }
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Default)]
+    struct MemoryStore(std::sync::Mutex<Vec<ConditionalOrder>>);
+
+    impl ConditionalStore for MemoryStore {
+        fn load(&self) -> Result<Vec<ConditionalOrder>> {
+            Ok(self.0.lock().unwrap().clone())
+        }
+
+        fn save(&self, orders: &[ConditionalOrder]) -> Result<()> {
+            *self.0.lock().unwrap() = orders.to_vec();
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_triggers_fire_on_the_right_side_and_persist() {
+        let store = Arc::new(MemoryStore::default());
+        let mut engine = ConditionalEngine::with_store(store.clone()).unwrap();
+        // Protecting a long: stop below, take-profit above
+        let stop = engine
+            .register(StopOrder::new("m", Side::Sell, 95.0, Decimal::ONE))
+            .unwrap();
+        let take = engine
+            .register(
+                TakeProfitOrder::new("m", Side::Sell, 110.0, Decimal::ONE)
+                    .with_limit(Decimal::from(109)),
+            )
+            .unwrap();
+        // Protecting a short in another market
+        engine
+            .register(StopOrder::new("other", Side::Buy, 50.0, Decimal::TWO))
+            .unwrap();
+        assert!(engine
+            .register(StopOrder::new("m", Side::Sell, f64::NAN, Decimal::ONE))
+            .is_err());
+
+        assert!(engine.observe("m", 100.0).is_empty());
+        let fired: Vec<u64> = engine.observe("m", 94.0).iter().map(|o| o.id).collect();
+        assert_eq!(fired, vec![stop]);
+        let fired = engine.observe("m", 110.0);
+        assert_eq!(fired.len(), 1);
+        assert_eq!(fired[0].id, take);
+        assert_eq!(engine.observe("other", 51.0).len(), 1);
+
+        let close = fired[0].to_perp_order(None);
+        assert!(close.reduce_only);
+        assert_eq!(close.position_effect, PositionEffect::Close);
+        assert_eq!(close.order_type, OrderType::Limit);
+        assert_eq!(close.price, Decimal::from(109));
+        assert_eq!((close.margin_mode, close.leverage), (MarginMode::Cross, 1));
+        // An isolated 5x position closes in kind
+        let isolated: Position = serde_json::from_value(serde_json::json!({
+            "owner": "o", "market_id": "m", "base_position": "1000",
+            "average_entry_price": "0", "mark_price": "0",
+            "realized_pnl": "0", "unrealized_pnl": "0",
+            "margin_mode": "isolated", "leverage": 5,
+        }))
+        .unwrap();
+        let close = fired[0].to_perp_order(Some(&isolated));
+        assert_eq!(
+            (close.margin_mode, close.leverage),
+            (MarginMode::Isolated, 5)
+        );
+
+        engine.complete(take).unwrap();
+        assert!(engine.cancel(stop).unwrap());
+        assert!(!engine.cancel(stop).unwrap());
+
+        // A restarted engine picks up where this one left off
+        let mut resumed = ConditionalEngine::with_store(store).unwrap();
+        assert_eq!(resumed.pending().len(), 1);
+        assert_eq!(resumed.pending()[0].market_id, "other");
+        let next = resumed
+            .register(StopOrder::new("m", Side::Sell, 90.0, Decimal::ONE))
+            .unwrap();
+        assert!(next > take);
+    }
+}
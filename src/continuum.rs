@@ -2,13 +2,21 @@
 //!
 //! Handles order and cancel submission via the Continuum ordering service.
 
-use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashSet;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
 use tonic::transport::Channel;
-use tracing::{debug, info};
+use tracing::{debug, info, warn};
 
+#[cfg(feature = "chaos")]
+use crate::chaos::FaultInjector;
 use crate::error::{Result, SdkError};
-use crate::signing::{SignedCancel, SignedOrder};
-use crate::types::{CancelResult, OrderResult};
+use crate::retry::RetryPolicy;
+use crate::sequencer::{InclusionProof, ProofEntry};
+use crate::signing::{SignedCancel, SignedCollateral, SignedOrder, SignedTransfer};
+use crate::throttle::RequestLimiter;
+use crate::types::{CancelResult, CollateralResult, OrderResult, TransferResult};
 
 // Include the generated protobuf types
 pub mod proto {
@@ -16,10 +24,14 @@ pub mod proto {
 }
 
 use proto::{
-    sequencer_service_client::SequencerServiceClient, GetStatusRequest, SubmitTransactionRequest,
-    Transaction,
+    sequencer_service_client::SequencerServiceClient, GetChainStateRequest, GetStatusRequest,
+    GetTickRequest, GetTransactionRequest, SubmitBatchRequest, SubmitTransactionRequest,
+    SubmitTransactionResponse, Transaction,
 };
 
+/// Number of recent ticks sampled when estimating the tick rate
+const TICK_SAMPLE_SIZE: u32 = 32;
+
 /// Sequencer status information
 #[derive(Debug, Clone)]
 pub struct SequencerStatus {
@@ -30,97 +42,203 @@ pub struct SequencerStatus {
     pub transactions_per_second: f64,
 }
 
-/// gRPC client for Continuum sequencer
+/// Sequencing state of a submitted transaction
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TransactionStatus {
+    /// Not in any tick yet: still queued, dropped, or never received.
+    /// Rejections are reported synchronously by the submit call itself.
+    NotFound,
+    /// Included in a tick
+    Sequenced {
+        tick_number: u64,
+        /// Position within the tick
+        sequence_number: u64,
+        /// When the sequencer ingested the transaction
+        ingestion_timestamp: u64,
+    },
+}
+
+/// Compression applied to Continuum requests and accepted on responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GrpcCompression {
+    /// Requires the `gzip` crate feature
+    Gzip,
+}
+
+/// Transport options for the Continuum gRPC channel
+#[derive(Debug, Clone, Default)]
+pub struct GrpcOptions {
+    pub compression: Option<GrpcCompression>,
+    /// Largest response the client will decode (tonic default: 4 MiB)
+    pub max_decoding_message_size: Option<usize>,
+    /// Largest request the client will send (tonic default: unlimited)
+    pub max_encoding_message_size: Option<usize>,
+}
+
+/// Health of the channel to a Continuum endpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConnectionState {
+    /// The last call reached the sequencer
+    Connected,
+    /// A call failed as unreachable; the channel is being replaced
+    Reconnecting,
+    /// Not connected yet, or released with `disconnect`; the next call
+    /// connects on demand
+    Disconnected,
+}
+
+/// Channel shared by every clone of a `ContinuumClient`, so one reconnect
+/// repairs them all.
+struct Link {
+    /// The current client and how many times it has been replaced
+    client: Mutex<(u64, SequencerServiceClient<Channel>)>,
+    state: Mutex<ConnectionState>,
+    /// Held while reconnecting, so concurrent failures reconnect once
+    reconnecting: tokio::sync::Mutex<()>,
+}
+
+impl Link {
+    fn new(client: SequencerServiceClient<Channel>, state: ConnectionState) -> Arc<Self> {
+        Arc::new(Self {
+            client: Mutex::new((0, client)),
+            state: Mutex::new(state),
+            reconnecting: tokio::sync::Mutex::new(()),
+        })
+    }
+
+    fn current(&self) -> (u64, SequencerServiceClient<Channel>) {
+        self.client.lock().unwrap().clone()
+    }
+
+    fn replace(&self, client: SequencerServiceClient<Channel>, state: ConnectionState) {
+        let mut current = self.client.lock().unwrap();
+        *current = (current.0 + 1, client);
+        self.set_state(state);
+    }
+
+    fn state(&self) -> ConnectionState {
+        *self.state.lock().unwrap()
+    }
+
+    fn set_state(&self, state: ConnectionState) {
+        *self.state.lock().unwrap() = state;
+    }
+}
+
+/// gRPC client for Continuum sequencer.
+///
+/// Clones share one channel. A call that finds the sequencer unreachable
+/// replaces the channel with a fresh connection before it is retried, so a
+/// sequencer restart does not leave the client failing for good.
+#[derive(Clone)]
 pub struct ContinuumClient {
-    client: SequencerServiceClient<Channel>,
+    link: Arc<Link>,
     endpoint: String,
+    /// Kept to reconnect with the same settings
+    options: GrpcOptions,
+    retry: RetryPolicy,
+    limiter: Option<RequestLimiter>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<FaultInjector>>,
 }
 
 impl ContinuumClient {
     /// Connect to a Continuum endpoint
-    pub async fn connect(endpoint: &str) -> Result<Self> {
+    pub async fn connect(endpoint: &str, options: &GrpcOptions) -> Result<Self> {
         info!("Connecting to Continuum sequencer at: {}", endpoint);
 
-        let channel = Channel::from_shared(endpoint.to_string())
-            .map_err(|e| SdkError::ContinuumConnection(format!("Invalid endpoint: {}", e)))?
-            .connect()
-            .await
-            .map_err(|e| SdkError::ContinuumConnection(format!("Connection failed: {}", e)))?;
-
-        let client = SequencerServiceClient::new(channel);
+        let client = connect_client(endpoint, options).await?;
 
         info!("Successfully connected to Continuum sequencer");
 
         Ok(Self {
-            client,
+            link: Link::new(client, ConnectionState::Connected),
             endpoint: endpoint.to_string(),
+            options: options.clone(),
+            retry: RetryPolicy::default(),
+            limiter: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
         })
     }
 
-    /// Submit a signed order to Continuum
-    pub async fn submit_order(&mut self, signed_order: &SignedOrder) -> Result<OrderResult> {
-        let order_json = signed_order.to_json()?;
-
-        // Extract signature from the request
-        let signature_bytes = hex::decode(&signed_order.request.signature)
-            .map_err(|e| SdkError::Signing(format!("Invalid signature hex: {}", e)))?;
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| SdkError::Signing(e.to_string()))?
-            .as_micros() as u64;
-
-        // Generate transaction ID
-        let tx_id = format!("frm_order_{}_{}", signed_order.order_id, timestamp);
-
-        // Build FRM transaction payload
-        let mut order_value: serde_json::Value = serde_json::from_str(&order_json)?;
-        if let Some(obj) = order_value.as_object_mut() {
-            obj.insert(
-                "local_sequencer_id".to_string(),
-                serde_json::Value::String("fermi_trade_sdk".to_string()),
-            );
-            obj.entry("type".to_string())
-                .or_insert_with(|| serde_json::Value::String("order".to_string()));
-            obj.insert(
-                "timestamp_ms".to_string(),
-                serde_json::Value::String((timestamp / 1000).to_string()),
-            );
-        }
+    /// A client that connects on first use rather than now.
+    pub fn connect_lazy(endpoint: &str, options: &GrpcOptions) -> Result<Self> {
+        let channel = Channel::from_shared(endpoint.to_string())
+            .map_err(|e| SdkError::ContinuumConnection(format!("Invalid endpoint: {}", e)))?
+            .connect_lazy();
 
-        let mut frm_fields = serde_json::Map::new();
-        frm_fields.insert(
-            "version".to_string(),
-            serde_json::Value::String("1.0".to_string()),
-        );
-        if let Some(obj) = order_value.as_object() {
-            frm_fields.extend(obj.clone().into_iter());
-        }
-        let frm_transaction = serde_json::Value::Object(frm_fields);
-
-        let payload_str = format!("FRM_v1.0:{}", frm_transaction);
-        debug!("Order FRM payload: {}", payload_str);
-        let payload = payload_str.into_bytes();
-
-        let transaction = Transaction {
-            tx_id: tx_id.clone(),
-            payload,
-            signature: signature_bytes,
-            public_key: signed_order.owner_bytes.to_vec(),
-            nonce: signed_order.order_id,
-            timestamp,
-        };
+        Ok(Self {
+            link: Link::new(
+                service_client(channel, options)?,
+                ConnectionState::Disconnected,
+            ),
+            endpoint: endpoint.to_string(),
+            options: options.clone(),
+            retry: RetryPolicy::default(),
+            limiter: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        })
+    }
 
-        let request = tonic::Request::new(SubmitTransactionRequest {
-            transaction: Some(transaction),
-        });
+    /// Release the channel shared with this client's clones. Later calls
+    /// from any of them connect again on demand.
+    pub fn disconnect(&self) -> Result<()> {
+        let client = Self::connect_lazy(&self.endpoint, &self.options)?
+            .link
+            .current()
+            .1;
+        self.link.replace(client, ConnectionState::Disconnected);
+        debug!("Disconnected from Continuum endpoint {}", self.endpoint);
+        Ok(())
+    }
 
-        debug!(
-            "Submitting order {} to Continuum endpoint {}",
-            tx_id, self.endpoint
-        );
+    /// Retry calls that fail with a transient gRPC code under `policy`.
+    pub fn set_retry_policy(&mut self, policy: RetryPolicy) {
+        self.retry = policy;
+    }
+
+    /// The endpoint this client talks to
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Make every call wait on `limiter`'s token bucket.
+    pub fn set_request_limiter(&mut self, limiter: RequestLimiter) {
+        self.limiter = Some(limiter);
+    }
 
-        let response = self.client.submit_transaction(request).await?.into_inner();
+    /// Route submissions through `injector`.
+    #[cfg(feature = "chaos")]
+    pub fn set_fault_injector(&mut self, injector: Arc<FaultInjector>) {
+        self.chaos = Some(injector);
+    }
+
+    /// Health of the channel as of the last call.
+    pub fn connection_state(&self) -> ConnectionState {
+        self.link.state()
+    }
+
+    /// Replace the shared channel with a fresh connection, unless another
+    /// caller has already replaced the one that failed (`failed`).
+    async fn reconnect(&self, failed: u64) -> Result<()> {
+        let _reconnecting = self.link.reconnecting.lock().await;
+        if self.link.current().0 != failed {
+            return Ok(());
+        }
+        self.link.set_state(ConnectionState::Reconnecting);
+        let client = connect_client(&self.endpoint, &self.options).await?;
+        self.link.replace(client, ConnectionState::Connected);
+        info!("Reconnected to Continuum endpoint {}", self.endpoint);
+        Ok(())
+    }
+
+    /// Submit a signed order to Continuum
+    pub async fn submit_order(&self, signed_order: &SignedOrder) -> Result<OrderResult> {
+        let transaction = order_transaction(signed_order)?;
+        let tx_id = transaction.tx_id.clone();
+        let response = self.submit_transaction(transaction).await?;
 
         info!(
             "Order {} submitted successfully, sequence: {}, expected_tick: {}, hash: {}",
@@ -132,71 +250,135 @@ impl ContinuumClient {
             sequence_number: response.sequence_number,
             expected_tick: response.expected_tick,
             tx_hash: response.tx_hash,
+            immediate: None,
         })
     }
 
-    /// Submit a signed cancel to Continuum
-    pub async fn submit_cancel(&mut self, signed_cancel: &SignedCancel) -> Result<CancelResult> {
-        let cancel_json = signed_cancel.to_json()?;
-
-        // Extract signature from the request
-        let signature_bytes = hex::decode(&signed_cancel.request.signature)
-            .map_err(|e| SdkError::Signing(format!("Invalid signature hex: {}", e)))?;
-
-        let timestamp = SystemTime::now()
-            .duration_since(UNIX_EPOCH)
-            .map_err(|e| SdkError::Signing(e.to_string()))?
-            .as_micros() as u64;
-
-        // Generate transaction ID
-        let tx_id = format!("frm_cancel_{}_{}", signed_cancel.order_id, timestamp);
-
-        // Build FRM transaction payload
-        let mut cancel_value: serde_json::Value = serde_json::from_str(&cancel_json)?;
-        if let Some(obj) = cancel_value.as_object_mut() {
-            obj.insert(
-                "local_sequencer_id".to_string(),
-                serde_json::Value::String("fermi_trade_sdk".to_string()),
-            );
-            obj.entry("type".to_string())
-                .or_insert_with(|| serde_json::Value::String("cancel".to_string()));
-            obj.insert(
-                "timestamp_ms".to_string(),
-                serde_json::Value::String((timestamp / 1000).to_string()),
-            );
+    /// Submit signed orders to Continuum as one batch transaction, in order.
+    ///
+    /// The batch is acknowledged as a whole, so a failure leaves every order
+    /// in the same state.
+    pub async fn submit_orders(&self, signed_orders: &[SignedOrder]) -> Result<Vec<OrderResult>> {
+        let transactions = signed_orders
+            .iter()
+            .map(order_transaction)
+            .collect::<Result<Vec<_>>>()?;
+        let responses = self.submit_batch(transactions).await?;
+        if responses.len() != signed_orders.len() {
+            return Err(SdkError::ContinuumSubmission(format!(
+                "unknown error: batch of {} orders acknowledged with {} responses",
+                signed_orders.len(),
+                responses.len()
+            )));
         }
 
-        let mut frm_fields = serde_json::Map::new();
-        frm_fields.insert(
-            "version".to_string(),
-            serde_json::Value::String("1.0".to_string()),
+        info!(
+            "Batch of {} orders submitted successfully",
+            signed_orders.len()
         );
-        if let Some(obj) = cancel_value.as_object() {
-            frm_fields.extend(obj.clone().into_iter());
+
+        Ok(signed_orders
+            .iter()
+            .zip(responses)
+            .map(|(signed_order, response)| OrderResult {
+                order_id: signed_order.order_id,
+                sequence_number: response.sequence_number,
+                expected_tick: response.expected_tick,
+                tx_hash: response.tx_hash,
+                immediate: None,
+            })
+            .collect())
+    }
+
+    /// Submit signed cancels to Continuum as one batch transaction, in order.
+    ///
+    /// The batch is acknowledged as a whole, so a failure leaves every
+    /// cancel in the same state.
+    pub async fn submit_cancels(
+        &self,
+        signed_cancels: &[SignedCancel],
+    ) -> Result<Vec<CancelResult>> {
+        let transactions = signed_cancels
+            .iter()
+            .map(cancel_transaction)
+            .collect::<Result<Vec<_>>>()?;
+        let responses = self.submit_batch(transactions).await?;
+        if responses.len() != signed_cancels.len() {
+            return Err(SdkError::ContinuumSubmission(format!(
+                "unknown error: batch of {} cancels acknowledged with {} responses",
+                signed_cancels.len(),
+                responses.len()
+            )));
         }
-        let frm_transaction = serde_json::Value::Object(frm_fields);
 
-        let payload = format!("FRM_v1.0:{}", frm_transaction).into_bytes();
+        info!(
+            "Batch of {} cancels submitted successfully",
+            signed_cancels.len()
+        );
+
+        Ok(signed_cancels
+            .iter()
+            .zip(responses)
+            .map(|(signed_cancel, response)| CancelResult {
+                order_id: signed_cancel.order_id,
+                sequence_number: response.sequence_number,
+                expected_tick: response.expected_tick,
+                tx_hash: response.tx_hash,
+            })
+            .collect())
+    }
 
-        let transaction = Transaction {
-            tx_id: tx_id.clone(),
-            payload,
-            signature: signature_bytes,
-            public_key: signed_cancel.owner_bytes.to_vec(),
-            nonce: signed_cancel.order_id,
-            timestamp,
+    /// Submit a signed cancel and a signed order as one batch transaction,
+    /// the cancel first.
+    ///
+    /// The batch is acknowledged as a whole, so a failure leaves both in the
+    /// same state.
+    pub async fn submit_replace(
+        &self,
+        signed_cancel: &SignedCancel,
+        signed_order: &SignedOrder,
+    ) -> Result<(CancelResult, OrderResult)> {
+        let transactions = vec![
+            cancel_transaction(signed_cancel)?,
+            order_transaction(signed_order)?,
+        ];
+        let mut responses = self.submit_batch(transactions).await?.into_iter();
+        let (Some(cancel), Some(order), None) =
+            (responses.next(), responses.next(), responses.next())
+        else {
+            return Err(SdkError::ContinuumSubmission(
+                "unknown error: replace batch acknowledged with the wrong number of responses"
+                    .into(),
+            ));
         };
 
-        let request = tonic::Request::new(SubmitTransactionRequest {
-            transaction: Some(transaction),
-        });
-
-        debug!(
-            "Submitting cancel {} to Continuum endpoint {}",
-            tx_id, self.endpoint
+        info!(
+            "Replace of order {} with {} submitted successfully",
+            signed_cancel.order_id, signed_order.order_id
         );
 
-        let response = self.client.submit_transaction(request).await?.into_inner();
+        Ok((
+            CancelResult {
+                order_id: signed_cancel.order_id,
+                sequence_number: cancel.sequence_number,
+                expected_tick: cancel.expected_tick,
+                tx_hash: cancel.tx_hash,
+            },
+            OrderResult {
+                order_id: signed_order.order_id,
+                sequence_number: order.sequence_number,
+                expected_tick: order.expected_tick,
+                tx_hash: order.tx_hash,
+                immediate: None,
+            },
+        ))
+    }
+
+    /// Submit a signed cancel to Continuum
+    pub async fn submit_cancel(&self, signed_cancel: &SignedCancel) -> Result<CancelResult> {
+        let transaction = cancel_transaction(signed_cancel)?;
+        let tx_id = transaction.tx_id.clone();
+        let response = self.submit_transaction(transaction).await?;
 
         info!(
             "Cancel {} submitted successfully, sequence: {}, expected_tick: {}, hash: {}",
@@ -211,11 +393,184 @@ impl ContinuumClient {
         })
     }
 
+    /// Submit a signed transfer to Continuum
+    pub async fn submit_transfer(
+        &self,
+        signed_transfer: &SignedTransfer,
+    ) -> Result<TransferResult> {
+        let transaction = transfer_transaction(signed_transfer)?;
+        let tx_id = transaction.tx_id.clone();
+        let response = self.submit_transaction(transaction).await?;
+
+        info!(
+            "Transfer {} submitted successfully, sequence: {}, expected_tick: {}, hash: {}",
+            tx_id, response.sequence_number, response.expected_tick, response.tx_hash
+        );
+
+        Ok(TransferResult {
+            nonce: signed_transfer.nonce,
+            sequence_number: response.sequence_number,
+            expected_tick: response.expected_tick,
+            tx_hash: response.tx_hash,
+        })
+    }
+
+    /// Submit a signed deposit or withdrawal to Continuum
+    pub async fn submit_collateral(
+        &self,
+        signed_collateral: &SignedCollateral,
+    ) -> Result<CollateralResult> {
+        let transaction = collateral_transaction(signed_collateral)?;
+        let tx_id = transaction.tx_id.clone();
+        let response = self.submit_transaction(transaction).await?;
+
+        info!(
+            "Collateral {} {} submitted successfully, sequence: {}, expected_tick: {}, hash: {}",
+            signed_collateral.action,
+            tx_id,
+            response.sequence_number,
+            response.expected_tick,
+            response.tx_hash
+        );
+
+        Ok(CollateralResult {
+            action: signed_collateral.action,
+            nonce: signed_collateral.nonce,
+            sequence_number: response.sequence_number,
+            expected_tick: response.expected_tick,
+            tx_hash: response.tx_hash,
+        })
+    }
+
+    /// Submit the same signed order to every client, returning the first ack.
+    pub async fn race_order(
+        clients: &[ContinuumClient],
+        signed_order: &SignedOrder,
+    ) -> Result<OrderResult> {
+        let transaction = order_transaction(signed_order)?;
+        let response = race_transaction(clients, transaction).await?;
+
+        Ok(OrderResult {
+            order_id: signed_order.order_id,
+            sequence_number: response.sequence_number,
+            expected_tick: response.expected_tick,
+            tx_hash: response.tx_hash,
+            immediate: None,
+        })
+    }
+
+    /// Submit the same signed cancel to every client, returning the first ack.
+    pub async fn race_cancel(
+        clients: &[ContinuumClient],
+        signed_cancel: &SignedCancel,
+    ) -> Result<CancelResult> {
+        let transaction = cancel_transaction(signed_cancel)?;
+        let response = race_transaction(clients, transaction).await?;
+
+        Ok(CancelResult {
+            order_id: signed_cancel.order_id,
+            sequence_number: response.sequence_number,
+            expected_tick: response.expected_tick,
+            tx_hash: response.tx_hash,
+        })
+    }
+
+    async fn submit_transaction(
+        &self,
+        transaction: Transaction,
+    ) -> Result<SubmitTransactionResponse> {
+        debug!(
+            "Submitting {} to Continuum endpoint {}",
+            transaction.tx_id, self.endpoint
+        );
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = self.chaos.clone() {
+            if chaos.force_reconnect() {
+                self.reconnect(self.link.current().0).await?;
+                return Err(SdkError::ContinuumConnection(
+                    "connection reset (injected)".into(),
+                ));
+            }
+            if let Some(e) = chaos.drop_submission() {
+                return Err(e);
+            }
+        }
+
+        let response = self
+            .call("Continuum submission", true, |mut client| {
+                let request = SubmitTransactionRequest {
+                    transaction: Some(transaction.clone()),
+                };
+                async move { client.submit_transaction(request).await }
+            })
+            .await?;
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = &self.chaos {
+            if let Some(delay) = chaos.ack_delay() {
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(e) = chaos.drop_ack() {
+                return Err(e);
+            }
+        }
+
+        Ok(response)
+    }
+
+    async fn submit_batch(
+        &self,
+        transactions: Vec<Transaction>,
+    ) -> Result<Vec<SubmitTransactionResponse>> {
+        debug!(
+            "Submitting batch of {} to Continuum endpoint {}",
+            transactions.len(),
+            self.endpoint
+        );
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = self.chaos.clone() {
+            if chaos.force_reconnect() {
+                self.reconnect(self.link.current().0).await?;
+                return Err(SdkError::ContinuumConnection(
+                    "connection reset (injected)".into(),
+                ));
+            }
+            if let Some(e) = chaos.drop_submission() {
+                return Err(e);
+            }
+        }
+
+        let response = self
+            .call("Continuum batch submission", true, |mut client| {
+                let request = SubmitBatchRequest {
+                    transactions: transactions.clone(),
+                };
+                async move { client.submit_batch(request).await }
+            })
+            .await?;
+
+        #[cfg(feature = "chaos")]
+        if let Some(chaos) = &self.chaos {
+            if let Some(delay) = chaos.ack_delay() {
+                tokio::time::sleep(delay).await;
+            }
+            if let Some(e) = chaos.drop_ack() {
+                return Err(e);
+            }
+        }
+
+        Ok(response.responses)
+    }
+
     /// Get the current sequencer status
-    #[allow(dead_code)]
-    pub async fn get_status(&mut self) -> Result<SequencerStatus> {
-        let request = tonic::Request::new(GetStatusRequest {});
-        let response = self.client.get_status(request).await?.into_inner();
+    pub async fn get_status(&self) -> Result<SequencerStatus> {
+        let response = self
+            .call("Sequencer status", false, |mut client| async move {
+                client.get_status(GetStatusRequest {}).await
+            })
+            .await?;
 
         Ok(SequencerStatus {
             current_tick: response.current_tick,
@@ -225,4 +580,575 @@ impl ContinuumClient {
             transactions_per_second: response.transactions_per_second,
         })
     }
+
+    /// Look up whether a transaction has been sequenced, and where.
+    pub async fn get_transaction(&self, tx_hash: &str) -> Result<TransactionStatus> {
+        let response = self
+            .call("Transaction lookup", false, |mut client| {
+                let request = GetTransactionRequest {
+                    tx_hash: tx_hash.to_string(),
+                };
+                async move { client.get_transaction(request).await }
+            })
+            .await?;
+        if !response.found {
+            return Ok(TransactionStatus::NotFound);
+        }
+
+        let ordered = response.transaction.unwrap_or_default();
+        Ok(TransactionStatus::Sequenced {
+            tick_number: response.tick_number,
+            sequence_number: ordered.sequence_number,
+            ingestion_timestamp: ordered.ingestion_timestamp,
+        })
+    }
+
+    /// Fetch the tick containing `tx_hash` as an inclusion proof.
+    ///
+    /// Returns `None` if the sequencer does not know the transaction yet.
+    pub async fn get_inclusion_proof(&self, tx_hash: &str) -> Result<Option<InclusionProof>> {
+        let TransactionStatus::Sequenced { tick_number, .. } =
+            self.get_transaction(tx_hash).await?
+        else {
+            return Ok(None);
+        };
+
+        let response = self
+            .call("Tick lookup", false, |mut client| async move {
+                client.get_tick(GetTickRequest { tick_number }).await
+            })
+            .await?;
+        let Some(tick) = response.tick.filter(|_| response.found) else {
+            return Ok(None);
+        };
+
+        let vdf_proof = tick.vdf_proof.unwrap_or_default();
+        Ok(Some(InclusionProof {
+            tick_number: tick.tick_number,
+            timestamp: tick.timestamp,
+            transaction_batch_hash: tick.transaction_batch_hash,
+            previous_output: tick.previous_output,
+            vdf_input: vdf_proof.input,
+            vdf_output: vdf_proof.output,
+            entries: tick
+                .transactions
+                .into_iter()
+                .map(|t| {
+                    let tx = t.transaction.unwrap_or_default();
+                    ProofEntry {
+                        tx_hash: t.tx_hash,
+                        sequence_number: t.sequence_number,
+                        public_key: tx.public_key,
+                        nonce: tx.nonce,
+                    }
+                })
+                .collect(),
+        }))
+    }
+
+    /// Highest nonce from `owner` found in the recently sequenced ticks.
+    pub async fn latest_nonce(&self, owner: &[u8], tick_limit: u32) -> Result<Option<u64>> {
+        Ok(self
+            .sequenced_nonces(owner, tick_limit)
+            .await?
+            .into_iter()
+            .max())
+    }
+
+    /// Every nonce from `owner` found in the recently sequenced ticks.
+    pub async fn sequenced_nonces(&self, owner: &[u8], tick_limit: u32) -> Result<HashSet<u64>> {
+        let response = self
+            .call("Chain state", false, |mut client| async move {
+                client
+                    .get_chain_state(GetChainStateRequest { tick_limit })
+                    .await
+            })
+            .await?;
+
+        Ok(response
+            .recent_ticks
+            .iter()
+            .flat_map(|tick| &tick.transactions)
+            .filter_map(|ordered| ordered.transaction.as_ref())
+            .filter(|tx| tx.public_key == owner)
+            .map(|tx| tx.nonce)
+            .collect())
+    }
+
+    /// Estimate the average time between ticks from recently produced ticks.
+    ///
+    /// Returns `None` if the sequencer reports fewer than two recent ticks.
+    pub async fn estimate_tick_duration(&self) -> Result<Option<Duration>> {
+        let response = self
+            .call("Chain state", false, |mut client| async move {
+                let request = GetChainStateRequest {
+                    tick_limit: TICK_SAMPLE_SIZE,
+                };
+                client.get_chain_state(request).await
+            })
+            .await?;
+
+        let samples: Vec<(u64, u64)> = response
+            .recent_ticks
+            .iter()
+            .map(|t| (t.tick_number, t.timestamp))
+            .collect();
+        Ok(tick_duration(&samples))
+    }
+
+    /// Make a sequencer call, retrying the policy's transient gRPC codes.
+    /// An unreachable sequencer is reconnected to before the next attempt.
+    ///
+    /// `resend` marks submissions, which are resent unchanged. If a resent
+    /// copy is rejected as a duplicate nonce, an earlier attempt was
+    /// sequenced without an ack, so the earlier indeterminate error is
+    /// returned instead.
+    async fn call<T, F, Fut>(&self, what: &str, resend: bool, call: F) -> Result<T>
+    where
+        F: Fn(SequencerServiceClient<Channel>) -> Fut,
+        Fut: Future<Output = std::result::Result<tonic::Response<T>, tonic::Status>>,
+    {
+        let mut attempt = 1;
+        let mut earlier: Option<SdkError> = None;
+        loop {
+            if let Some(limiter) = &self.limiter {
+                limiter.acquire().await;
+            }
+            let (generation, client) = self.link.current();
+            let status = match call(client).await {
+                Ok(response) => {
+                    self.link.set_state(ConnectionState::Connected);
+                    return Ok(response.into_inner());
+                }
+                Err(status) => status,
+            };
+            let code = status.code();
+            let retry_after = (code == tonic::Code::ResourceExhausted).then(|| {
+                let delay = retry_after(&status).unwrap_or_else(|| self.retry.delay(attempt));
+                if let Some(limiter) = &self.limiter {
+                    limiter.pause_for(delay);
+                }
+                delay
+            });
+            if code == tonic::Code::Unavailable {
+                if let Err(e) = self.reconnect(generation).await {
+                    debug!("Reconnecting to {} failed: {}", self.endpoint, e);
+                }
+            }
+            let error = SdkError::from(status);
+            if let Some(earlier) = earlier
+                .take()
+                .filter(|_| resend && error.is_duplicate_nonce())
+            {
+                return Err(earlier);
+            }
+            let retryable = retry_after.is_some() || self.retry.retries_code(code);
+            if !retryable || !self.retry.allows_retry(attempt) {
+                return Err(error);
+            }
+            match retry_after {
+                Some(delay) => self.retry.backoff_for(delay, attempt, what, &error).await,
+                None => self.retry.backoff(attempt, what, &error).await,
+            }
+            earlier = Some(error);
+            attempt += 1;
+        }
+    }
+}
+
+/// Delay asked for by a `retry-after` metadata entry in seconds
+fn retry_after(status: &tonic::Status) -> Option<Duration> {
+    status
+        .metadata()
+        .get("retry-after")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Open a channel to `endpoint` and wrap it in a client.
+async fn connect_client(
+    endpoint: &str,
+    options: &GrpcOptions,
+) -> Result<SequencerServiceClient<Channel>> {
+    let channel = Channel::from_shared(endpoint.to_string())
+        .map_err(|e| SdkError::ContinuumConnection(format!("Invalid endpoint: {}", e)))?
+        .connect()
+        .await
+        .map_err(|e| SdkError::ContinuumConnection(format!("Connection failed: {}", e)))?;
+    service_client(channel, options)
+}
+
+/// Apply transport `options` to a client on `channel`.
+fn service_client(
+    channel: Channel,
+    options: &GrpcOptions,
+) -> Result<SequencerServiceClient<Channel>> {
+    let mut client = SequencerServiceClient::new(channel);
+    if let Some(limit) = options.max_decoding_message_size {
+        client = client.max_decoding_message_size(limit);
+    }
+    if let Some(limit) = options.max_encoding_message_size {
+        client = client.max_encoding_message_size(limit);
+    }
+    match options.compression {
+        None => {}
+        #[cfg(feature = "gzip")]
+        Some(GrpcCompression::Gzip) => {
+            use tonic::codec::CompressionEncoding;
+            client = client
+                .send_compressed(CompressionEncoding::Gzip)
+                .accept_compressed(CompressionEncoding::Gzip);
+        }
+        #[cfg(not(feature = "gzip"))]
+        Some(GrpcCompression::Gzip) => {
+            return Err(SdkError::Config(
+                "gzip compression requires the `gzip` feature".to_string(),
+            ));
+        }
+    }
+    Ok(client)
+}
+
+/// Fields of an FRM transaction payload (`FRM_v1.0:{json}`).
+#[cfg(any(test, feature = "fuzzing"))]
+pub(crate) fn parse_frm_payload(
+    payload: &[u8],
+) -> Result<serde_json::Map<String, serde_json::Value>> {
+    let json = payload
+        .strip_prefix(b"FRM_v1.0:")
+        .ok_or_else(|| SdkError::Serialization("payload lacks FRM_v1.0 prefix".into()))?;
+    match serde_json::from_slice(json)? {
+        serde_json::Value::Object(fields) => Ok(fields),
+        other => Err(SdkError::Serialization(format!(
+            "FRM payload is not an object: {}",
+            other
+        ))),
+    }
+}
+
+/// Build the FRM transaction for a signed order, stamped with the current time.
+pub(crate) fn order_transaction(signed_order: &SignedOrder) -> Result<Transaction> {
+    let order_json = signed_order.to_json()?;
+
+    // Extract signature from the request
+    let signature_bytes = hex::decode(&signed_order.request.signature)
+        .map_err(|e| SdkError::Signing(format!("Invalid signature hex: {}", e)))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| SdkError::Signing(e.to_string()))?
+        .as_micros() as u64;
+
+    // Generate transaction ID
+    let tx_id = format!("frm_order_{}_{}", signed_order.order_id, timestamp);
+
+    // Build FRM transaction payload
+    let mut order_value: serde_json::Value = serde_json::from_str(&order_json)?;
+    if let Some(obj) = order_value.as_object_mut() {
+        obj.insert(
+            "local_sequencer_id".to_string(),
+            serde_json::Value::String("fermi_trade_sdk".to_string()),
+        );
+        obj.entry("type".to_string())
+            .or_insert_with(|| serde_json::Value::String("order".to_string()));
+        obj.insert(
+            "timestamp_ms".to_string(),
+            serde_json::Value::String((timestamp / 1000).to_string()),
+        );
+    }
+
+    let mut frm_fields = serde_json::Map::new();
+    frm_fields.insert(
+        "version".to_string(),
+        serde_json::Value::String("1.0".to_string()),
+    );
+    if let Some(obj) = order_value.as_object() {
+        frm_fields.extend(obj.clone());
+    }
+    let frm_transaction = serde_json::Value::Object(frm_fields);
+
+    let payload_str = format!("FRM_v1.0:{}", frm_transaction);
+    debug!("Order FRM payload: {}", payload_str);
+    let payload = payload_str.into_bytes();
+
+    Ok(Transaction {
+        tx_id,
+        payload,
+        signature: signature_bytes,
+        public_key: signed_order.owner_bytes.to_vec(),
+        nonce: signed_order.order_id,
+        timestamp,
+    })
+}
+
+/// Build the FRM transaction for a signed cancel, stamped with the current time.
+fn cancel_transaction(signed_cancel: &SignedCancel) -> Result<Transaction> {
+    let cancel_json = signed_cancel.to_json()?;
+
+    // Extract signature from the request
+    let signature_bytes = hex::decode(&signed_cancel.request.signature)
+        .map_err(|e| SdkError::Signing(format!("Invalid signature hex: {}", e)))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| SdkError::Signing(e.to_string()))?
+        .as_micros() as u64;
+
+    // Generate transaction ID
+    let tx_id = format!("frm_cancel_{}_{}", signed_cancel.order_id, timestamp);
+
+    // Build FRM transaction payload
+    let mut cancel_value: serde_json::Value = serde_json::from_str(&cancel_json)?;
+    if let Some(obj) = cancel_value.as_object_mut() {
+        obj.insert(
+            "local_sequencer_id".to_string(),
+            serde_json::Value::String("fermi_trade_sdk".to_string()),
+        );
+        obj.entry("type".to_string())
+            .or_insert_with(|| serde_json::Value::String("cancel".to_string()));
+        obj.insert(
+            "timestamp_ms".to_string(),
+            serde_json::Value::String((timestamp / 1000).to_string()),
+        );
+    }
+
+    let mut frm_fields = serde_json::Map::new();
+    frm_fields.insert(
+        "version".to_string(),
+        serde_json::Value::String("1.0".to_string()),
+    );
+    if let Some(obj) = cancel_value.as_object() {
+        frm_fields.extend(obj.clone());
+    }
+    let frm_transaction = serde_json::Value::Object(frm_fields);
+
+    let payload = format!("FRM_v1.0:{}", frm_transaction).into_bytes();
+
+    Ok(Transaction {
+        tx_id,
+        payload,
+        signature: signature_bytes,
+        public_key: signed_cancel.owner_bytes.to_vec(),
+        nonce: signed_cancel.order_id,
+        timestamp,
+    })
+}
+
+/// Build the FRM transaction for a signed transfer, stamped with the current time.
+fn transfer_transaction(signed_transfer: &SignedTransfer) -> Result<Transaction> {
+    let transfer_json = signed_transfer.to_json()?;
+
+    // Extract signature from the request
+    let signature_bytes = hex::decode(&signed_transfer.request.signature)
+        .map_err(|e| SdkError::Signing(format!("Invalid signature hex: {}", e)))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| SdkError::Signing(e.to_string()))?
+        .as_micros() as u64;
+
+    // Generate transaction ID
+    let tx_id = format!("frm_transfer_{}_{}", signed_transfer.nonce, timestamp);
+
+    // Build FRM transaction payload
+    let mut transfer_value: serde_json::Value = serde_json::from_str(&transfer_json)?;
+    if let Some(obj) = transfer_value.as_object_mut() {
+        obj.insert(
+            "local_sequencer_id".to_string(),
+            serde_json::Value::String("fermi_trade_sdk".to_string()),
+        );
+        obj.insert(
+            "type".to_string(),
+            serde_json::Value::String("transfer".to_string()),
+        );
+        obj.insert(
+            "timestamp_ms".to_string(),
+            serde_json::Value::String((timestamp / 1000).to_string()),
+        );
+    }
+
+    let mut frm_fields = serde_json::Map::new();
+    frm_fields.insert(
+        "version".to_string(),
+        serde_json::Value::String("1.0".to_string()),
+    );
+    if let Some(obj) = transfer_value.as_object() {
+        frm_fields.extend(obj.clone());
+    }
+    let frm_transaction = serde_json::Value::Object(frm_fields);
+
+    let payload = format!("FRM_v1.0:{}", frm_transaction).into_bytes();
+
+    Ok(Transaction {
+        tx_id,
+        payload,
+        signature: signature_bytes,
+        public_key: signed_transfer.owner_bytes.to_vec(),
+        nonce: signed_transfer.nonce,
+        timestamp,
+    })
+}
+
+fn collateral_transaction(signed_collateral: &SignedCollateral) -> Result<Transaction> {
+    let collateral_json = signed_collateral.to_json()?;
+
+    // Extract signature from the request
+    let signature_bytes = hex::decode(&signed_collateral.request.signature)
+        .map_err(|e| SdkError::Signing(format!("Invalid signature hex: {}", e)))?;
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_err(|e| SdkError::Signing(e.to_string()))?
+        .as_micros() as u64;
+
+    // Generate transaction ID
+    let tx_id = format!(
+        "frm_{}_{}_{}",
+        signed_collateral.action, signed_collateral.nonce, timestamp
+    );
+
+    // Build FRM transaction payload
+    let mut collateral_value: serde_json::Value = serde_json::from_str(&collateral_json)?;
+    if let Some(obj) = collateral_value.as_object_mut() {
+        obj.insert(
+            "local_sequencer_id".to_string(),
+            serde_json::Value::String("fermi_trade_sdk".to_string()),
+        );
+        obj.insert(
+            "type".to_string(),
+            serde_json::Value::String(signed_collateral.action.to_string()),
+        );
+        obj.insert(
+            "timestamp_ms".to_string(),
+            serde_json::Value::String((timestamp / 1000).to_string()),
+        );
+    }
+
+    let mut frm_fields = serde_json::Map::new();
+    frm_fields.insert(
+        "version".to_string(),
+        serde_json::Value::String("1.0".to_string()),
+    );
+    if let Some(obj) = collateral_value.as_object() {
+        frm_fields.extend(obj.clone());
+    }
+    let frm_transaction = serde_json::Value::Object(frm_fields);
+
+    let payload = format!("FRM_v1.0:{}", frm_transaction).into_bytes();
+
+    Ok(Transaction {
+        tx_id,
+        payload,
+        signature: signature_bytes,
+        public_key: signed_collateral.owner_bytes.to_vec(),
+        nonce: signed_collateral.nonce,
+        timestamp,
+    })
+}
+
+/// Send one transaction to every client concurrently and return the first ack.
+///
+/// The copies share a tx_id and nonce, so the sequencer keeps only one; the
+/// duplicate-nonce rejections from the slower endpoints are discarded. If
+/// every endpoint fails, the most informative error is returned.
+async fn race_transaction(
+    clients: &[ContinuumClient],
+    transaction: Transaction,
+) -> Result<SubmitTransactionResponse> {
+    let mut pending = tokio::task::JoinSet::new();
+    for client in clients {
+        let client = client.clone();
+        let transaction = transaction.clone();
+        pending.spawn(async move { client.submit_transaction(transaction).await });
+    }
+
+    let mut error: Option<SdkError> = None;
+    while let Some(joined) = pending.join_next().await {
+        match joined {
+            Ok(Ok(response)) => {
+                // Dropping the set aborts the slower submissions
+                return Ok(response);
+            }
+            Ok(Err(e)) => {
+                debug!("Raced submission of {} failed: {}", transaction.tx_id, e);
+                if error.as_ref().is_none_or(|prev| prev.is_duplicate_nonce()) {
+                    error = Some(e);
+                }
+            }
+            Err(e) => warn!("Raced submission task failed: {}", e),
+        }
+    }
+
+    Err(error.unwrap_or_else(|| {
+        SdkError::ContinuumSubmission("no Continuum endpoints to submit to".into())
+    }))
+}
+
+/// Average tick duration from `(tick_number, timestamp_micros)` samples.
+fn tick_duration(samples: &[(u64, u64)]) -> Option<Duration> {
+    let first = samples.iter().min_by_key(|(tick, _)| *tick)?;
+    let last = samples.iter().max_by_key(|(tick, _)| *tick)?;
+    let ticks = last.0.checked_sub(first.0).filter(|t| *t > 0)?;
+    let elapsed = last.1.checked_sub(first.1)?;
+    Some(Duration::from_micros(elapsed / ticks))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_tick_duration() {
+        let samples = [(12, 1_300_000), (10, 1_000_000), (11, 1_150_000)];
+        assert_eq!(tick_duration(&samples), Some(Duration::from_millis(150)));
+        assert_eq!(tick_duration(&samples[..1]), None);
+    }
+
+    #[tokio::test]
+    async fn test_unreachable_sequencer_marks_clones_reconnecting() {
+        // Nothing listens on port 1, so connections are refused at once
+        let mut client =
+            ContinuumClient::connect_lazy("http://127.0.0.1:1", &GrpcOptions::default()).unwrap();
+        client.set_retry_policy(RetryPolicy::none());
+        let watcher = client.clone();
+        assert_eq!(watcher.connection_state(), ConnectionState::Disconnected);
+
+        assert!(client.get_status().await.is_err());
+        assert_eq!(watcher.connection_state(), ConnectionState::Reconnecting);
+    }
+
+    #[tokio::test]
+    async fn test_order_payload_carries_builder_code() {
+        use crate::keypair::TradingKeypair;
+        use crate::signing::sign_perp_order;
+        use crate::types::{MarginMode, PositionEffect, Pubkey, Side};
+
+        let mint = Pubkey::new_from_array([1; 32]);
+        let mut signed = sign_perp_order(
+            &TradingKeypair::generate(),
+            1,
+            Side::Buy,
+            1,
+            1,
+            0,
+            &mint,
+            &mint,
+            1,
+            PositionEffect::Open,
+            MarginMode::Cross,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+
+        let payload = |signed: &SignedOrder| {
+            parse_frm_payload(&order_transaction(signed).unwrap().payload).unwrap()
+        };
+        assert!(payload(&signed).get("builder_code").is_none());
+
+        signed.request.builder_code = Some("frontend-7".to_string());
+        assert_eq!(payload(&signed)["builder_code"], "frontend-7");
+    }
 }
@@ -0,0 +1,176 @@
+//! Scoped session keys.
+//!
+//! A [`Delegation`] is signed by an account's main key and lets a separate
+//! session key trade for it within a [`SessionScope`]: a set of markets, a
+//! maximum order size, and an expiry. `FermiClient::create_session_key`
+//! generates the key and registers the delegation with the node, so the main
+//! key can stay offline. A client built with `FermiClient::with_session_key`
+//! refuses to sign any order or cancel outside the scope.
+
+use std::str::FromStr;
+
+use ed25519_dalek::{PublicKey, Signature, Verifier};
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SdkError};
+use crate::signer::{self, OrderSigner};
+use crate::types::{PerpOrder, Pubkey};
+
+/// Domain separator so a delegation signature cannot be replayed as an order
+const DELEGATION_DOMAIN: &[u8] = b"fermi-delegation:v1:";
+
+/// What a session key may do.
+#[derive(Debug, Clone, PartialEq, Default, Serialize, Deserialize)]
+pub struct SessionScope {
+    /// Market UUIDs the key may trade (empty = every market)
+    pub markets: Vec<String>,
+    /// Largest order quantity in human base units
+    pub max_quantity: Option<f64>,
+    /// Largest order notional (price * quantity) in quote units
+    pub max_notional: Option<f64>,
+    /// Unix seconds after which the key may no longer sign
+    pub expires_at: u64,
+}
+
+/// A session key's authorization, signed by the account owner.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Delegation {
+    /// Base58 public key of the delegating account
+    pub owner: String,
+    /// Base58 public key of the session key
+    pub session_key: String,
+    pub scope: SessionScope,
+    /// Hex ed25519 signature by `owner`
+    pub signature: String,
+}
+
+impl Delegation {
+    /// Authorize `session_key` to trade for `owner` within `scope`.
+    pub async fn sign<S: OrderSigner + ?Sized>(
+        owner: &S,
+        session_key: &Pubkey,
+        scope: SessionScope,
+    ) -> Result<Self> {
+        let owner_str = owner.pubkey_string();
+        let session_key = session_key.to_string();
+        let message = signing_message(&owner_str, &session_key, &scope)?;
+        let signature = signer::sign_hex(owner, &message).await?;
+        Ok(Self {
+            owner: owner_str,
+            session_key,
+            scope,
+            signature,
+        })
+    }
+
+    /// Check the owner's signature over the session key and scope.
+    pub fn verify(&self) -> Result<()> {
+        let owner = Pubkey::from_str(&self.owner)
+            .map_err(|e| SdkError::InvalidPubkey(format!("delegation owner: {}", e)))?;
+        let public = PublicKey::from_bytes(owner.as_ref())
+            .map_err(|e| SdkError::Signing(format!("delegation owner: {}", e)))?;
+        let signature = hex::decode(&self.signature)
+            .ok()
+            .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+            .ok_or_else(|| SdkError::Signing("malformed delegation signature".into()))?;
+        let message = signing_message(&self.owner, &self.session_key, &self.scope)?;
+        public
+            .verify(&message, &signature)
+            .map_err(|_| SdkError::Signing("delegation signature does not match".into()))
+    }
+
+    pub fn is_expired_at(&self, now: u64) -> bool {
+        now >= self.scope.expires_at
+    }
+
+    /// Refuse an order outside the scope at Unix time `now`.
+    pub fn check_order(&self, market_id: &str, order: &PerpOrder, now: u64) -> Result<()> {
+        self.check_market(market_id, now)?;
+        if let Some(max) = self.scope.max_quantity {
+            if order.quantity_f64() > max {
+                return Err(out_of_scope(format!(
+                    "quantity {} exceeds {}",
+                    order.quantity, max
+                )));
+            }
+        }
+        if let Some(max) = self.scope.max_notional {
+            let notional = order.price_f64() * order.quantity_f64();
+            if notional > max {
+                return Err(out_of_scope(format!(
+                    "notional {:.2} exceeds {:.2}",
+                    notional, max
+                )));
+            }
+        }
+        Ok(())
+    }
+
+    /// Refuse a cancel outside the scope at Unix time `now`.
+    pub fn check_cancel(&self, market_id: &str, now: u64) -> Result<()> {
+        self.check_market(market_id, now)
+    }
+
+    fn check_market(&self, market_id: &str, now: u64) -> Result<()> {
+        if self.is_expired_at(now) {
+            return Err(out_of_scope(format!(
+                "expired at {}",
+                self.scope.expires_at
+            )));
+        }
+        if !self.scope.markets.is_empty() && !self.scope.markets.iter().any(|m| m == market_id) {
+            return Err(out_of_scope(format!("market {} not delegated", market_id)));
+        }
+        Ok(())
+    }
+}
+
+fn signing_message(owner: &str, session_key: &str, scope: &SessionScope) -> Result<Vec<u8>> {
+    let body = serde_json::to_vec(&(owner, session_key, scope))?;
+    Ok([DELEGATION_DOMAIN, body.as_slice()].concat())
+}
+
+fn out_of_scope(reason: String) -> SdkError {
+    SdkError::OrderRejected(format!("outside session key scope: {}", reason))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::keypair::TradingKeypair;
+    use rust_decimal::Decimal;
+
+    #[tokio::test]
+    async fn test_delegation_signature_and_scope() {
+        let owner = TradingKeypair::generate();
+        let session = TradingKeypair::generate();
+        let scope = SessionScope {
+            markets: vec!["m".to_string()],
+            max_quantity: Some(2.0),
+            max_notional: None,
+            expires_at: 1_000,
+        };
+        let delegation = Delegation::sign(&owner, &session.pubkey(), scope)
+            .await
+            .unwrap();
+        assert!(delegation.verify().is_ok());
+
+        let mut widened = delegation.clone();
+        widened.scope.max_quantity = Some(20.0);
+        assert!(widened.verify().is_err());
+
+        let order = PerpOrder {
+            price: Decimal::from(100),
+            quantity: Decimal::ONE,
+            ..Default::default()
+        };
+        assert!(delegation.check_order("m", &order, 999).is_ok());
+        assert!(delegation.check_order("other", &order, 999).is_err());
+        assert!(delegation.check_cancel("m", 1_000).is_err());
+        let large = PerpOrder {
+            quantity: Decimal::from(3),
+            ..order
+        };
+        assert!(delegation.check_order("m", &large, 999).is_err());
+    }
+}
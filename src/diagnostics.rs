@@ -0,0 +1,250 @@
+//! End-to-end smoke tests against a live node.
+//!
+//! [`order_echo_test`] places a small sell far above the market, checks that
+//! it shows up in the book and in the account's open orders, cancels it, and
+//! checks that it is gone again. The [`EchoReport`] records each step so CI
+//! and onboarding checks can tell which leg of the pipeline is broken.
+
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::types::{lossy_decimal, MarginMode, Orderbook, PerpOrder, PositionEffect, Side};
+
+/// How the echo order is sized and how long each check may take.
+#[derive(Debug, Clone)]
+pub struct EchoOptions {
+    /// Order size in human base units
+    pub quantity: f64,
+    pub leverage: u64,
+    /// The order is priced at this multiple of the highest resting price
+    pub price_multiple: f64,
+    /// Explicit price, required when the book is empty
+    pub price: Option<f64>,
+    /// Limit for the order to appear, and again for it to disappear
+    pub timeout: Duration,
+    pub poll_interval: Duration,
+}
+
+impl Default for EchoOptions {
+    fn default() -> Self {
+        Self {
+            quantity: 0.1,
+            leverage: 5,
+            price_multiple: 3.0,
+            price: None,
+            timeout: Duration::from_secs(10),
+            poll_interval: Duration::from_millis(500),
+        }
+    }
+}
+
+/// Outcome of an [`order_echo_test`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EchoReport {
+    pub market_id: String,
+    pub order_id: u64,
+    pub price: f64,
+    pub quantity: f64,
+    pub tx_hash: String,
+    /// Time from submission to acknowledgement
+    pub ack_latency: Duration,
+    /// Time from acknowledgement until the order was seen in the book
+    pub book_latency: Option<Duration>,
+    /// Time from acknowledgement until the order was listed in open orders
+    pub open_orders_latency: Option<Duration>,
+    pub cancel_tx_hash: Option<String>,
+    /// Time from the cancel ack until the order left both views
+    pub removal_latency: Option<Duration>,
+    /// Checks that failed, in the order they ran
+    pub failures: Vec<String>,
+}
+
+impl EchoReport {
+    pub fn passed(&self) -> bool {
+        self.failures.is_empty()
+    }
+}
+
+/// Run the place → verify → cancel → verify cycle with default options.
+pub async fn order_echo_test(client: &FermiClient, market_id: &str) -> Result<EchoReport> {
+    order_echo_test_with(client, market_id, &EchoOptions::default()).await
+}
+
+/// Run the place → verify → cancel → verify cycle.
+///
+/// Errors before the order is acknowledged are returned as `Err`. Once the
+/// order is live, failed checks are recorded in the report instead, so the
+/// order ID is never lost; a failed cancel leaves the order resting.
+pub async fn order_echo_test_with(
+    client: &FermiClient,
+    market_id: &str,
+    options: &EchoOptions,
+) -> Result<EchoReport> {
+    let market = client.get_market(market_id).await?;
+    let price = match options.price {
+        Some(price) => price,
+        None => {
+            let book = client.get_orderbook(market_id).await?;
+            let scale = 10f64.powi(market.quote_decimals as i32);
+            echo_price(&book, scale, options.price_multiple).ok_or_else(|| {
+                SdkError::InvalidOrderbook(format!(
+                    "{} has no resting orders to price the echo order from",
+                    market_id
+                ))
+            })?
+        }
+    };
+
+    let order = PerpOrder {
+        side: Side::Sell,
+        price: lossy_decimal(price)?,
+        quantity: lossy_decimal(options.quantity)?,
+        leverage: options.leverage,
+        position_effect: PositionEffect::Open,
+        margin_mode: MarginMode::Cross,
+        reduce_only: false,
+        ..Default::default()
+    };
+    let submitted = Instant::now();
+    let placed = client.place_order_in_market(&market, order).await?;
+    let acked = Instant::now();
+    info!(
+        "Echo order {} placed at {} in {}",
+        placed.order_id, price, market_id
+    );
+
+    let mut report = EchoReport {
+        market_id: market_id.to_string(),
+        order_id: placed.order_id,
+        price,
+        quantity: options.quantity,
+        tx_hash: placed.tx_hash.clone(),
+        ack_latency: acked - submitted,
+        book_latency: None,
+        open_orders_latency: None,
+        cancel_tx_hash: None,
+        removal_latency: None,
+        failures: Vec::new(),
+    };
+
+    // Wait for the order to show up in both views
+    while acked.elapsed() < options.timeout {
+        // A failed query counts as not seen and is retried on the next poll
+        let (on_book, in_open_orders) = visibility(client, market_id, placed.order_id)
+            .await
+            .unwrap_or_else(|e| {
+                warn!("Echo test query failed: {}", e);
+                (false, false)
+            });
+        if on_book && report.book_latency.is_none() {
+            report.book_latency = Some(acked.elapsed());
+        }
+        if in_open_orders && report.open_orders_latency.is_none() {
+            report.open_orders_latency = Some(acked.elapsed());
+        }
+        if report.book_latency.is_some() && report.open_orders_latency.is_some() {
+            break;
+        }
+        tokio::time::sleep(options.poll_interval).await;
+    }
+    if report.book_latency.is_none() {
+        report
+            .failures
+            .push(format!("not on the book after {:?}", options.timeout));
+    }
+    if report.open_orders_latency.is_none() {
+        report
+            .failures
+            .push(format!("not in open orders after {:?}", options.timeout));
+    }
+
+    let cancelled = match client
+        .cancel_order_in_market(&market, placed.order_id)
+        .await
+    {
+        Ok(cancel) => cancel,
+        Err(e) => {
+            warn!("Echo order {} cancel failed: {}", placed.order_id, e);
+            report.failures.push(format!("cancel failed: {}", e));
+            return Ok(report);
+        }
+    };
+    report.cancel_tx_hash = Some(cancelled.tx_hash);
+
+    let cancel_acked = Instant::now();
+    while cancel_acked.elapsed() < options.timeout {
+        match visibility(client, market_id, placed.order_id).await {
+            Ok((false, false)) => {
+                report.removal_latency = Some(cancel_acked.elapsed());
+                break;
+            }
+            Ok(_) => {}
+            Err(e) => warn!("Echo test query failed: {}", e),
+        }
+        tokio::time::sleep(options.poll_interval).await;
+    }
+    if report.removal_latency.is_none() {
+        report
+            .failures
+            .push(format!("still listed {:?} after cancel", options.timeout));
+    }
+
+    Ok(report)
+}
+
+/// A whole quote unit at or above `multiple` times the highest resting
+/// price, so the sell cannot cross.
+fn echo_price(book: &Orderbook, quote_scale: f64, multiple: f64) -> Option<f64> {
+    let highest = book.buys.iter().chain(&book.sells).map(|o| o.price).max()?;
+    Some((highest as f64 / quote_scale * multiple).ceil())
+}
+
+/// Whether `order_id` is in the book and in open orders.
+async fn visibility(client: &FermiClient, market_id: &str, order_id: u64) -> Result<(bool, bool)> {
+    let book = client.get_orderbook(market_id).await?;
+    let on_book = book
+        .buys
+        .iter()
+        .chain(&book.sells)
+        .any(|o| o.order_id == order_id);
+    let in_open_orders = client
+        .get_my_orders()
+        .await?
+        .iter()
+        .any(|o| o.order_id == order_id);
+    Ok((on_book, in_open_orders))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::OrderbookEntry;
+
+    #[test]
+    fn test_echo_price_clears_the_book() {
+        let entry = |price| OrderbookEntry {
+            order_id: price,
+            owner: String::new(),
+            price,
+            quantity: 1,
+            side: String::new(),
+            expiry: 0,
+        };
+        let book = Orderbook {
+            buys: vec![entry(180_500_000)],
+            sells: vec![entry(185_250_000), entry(190_000_000)],
+        };
+        assert_eq!(echo_price(&book, 1_000_000.0, 3.0), Some(570.0));
+        assert_eq!(echo_price(&book, 1_000_000.0, 1.001), Some(191.0));
+
+        let empty = Orderbook {
+            buys: vec![],
+            sells: vec![],
+        };
+        assert_eq!(echo_price(&empty, 1_000_000.0, 3.0), None);
+    }
+}
@@ -1,3 +1,7 @@
+use std::time::Duration;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
 use thiserror::Error;
 
 /// SDK-specific errors
@@ -21,6 +25,9 @@ pub enum SdkError {
     #[error("Market not found: {0}")]
     MarketNotFound(String),
 
+    #[error("Order not found: {0}")]
+    OrderNotFound(u64),
+
     #[error("Account not found: {0}")]
     AccountNotFound(String),
 
@@ -38,6 +45,90 @@ pub enum SdkError {
 
     #[error("Configuration error: {0}")]
     Config(String),
+
+    #[error("Order rejected by pre-trade check: {0}")]
+    OrderRejected(String),
+
+    #[error("Order validation failed: {0}")]
+    Validation(String),
+
+    #[error("Invalid order for market: {0}")]
+    InvalidOrder(String),
+
+    #[error("Inclusion check failed: {0}")]
+    InclusionCheck(String),
+
+    #[error("Local store error: {0}")]
+    Store(String),
+
+    #[error("Audit journal error: {0}")]
+    Audit(String),
+
+    #[error("Market data recorder error: {0}")]
+    Recorder(String),
+
+    #[error("Inconsistent orderbook snapshot: {0}")]
+    InvalidOrderbook(String),
+
+    #[error("Insufficient balance: {0}")]
+    InsufficientBalance(String),
+
+    #[error("Stream error: {0}")]
+    Stream(String),
+
+    #[error("Insufficient margin: {0}")]
+    InsufficientMargin(String),
+
+    #[error("Invalid signature: {0}")]
+    InvalidSignature(String),
+
+    #[error("Rate limited by the node (retry after {retry_after:?})")]
+    RateLimited { retry_after: Option<Duration> },
+
+    /// A structured error from the node with no more specific variant
+    #[error("Node returned {status}: {message}")]
+    Api {
+        status: u16,
+        /// Machine-readable error code, if the node sent one
+        code: Option<String>,
+        message: String,
+    },
+}
+
+/// JSON error payload from the rollup node, either flat or nested under
+/// `error`
+#[derive(Debug, Default, Deserialize)]
+struct ErrorBody {
+    #[serde(default)]
+    code: Option<String>,
+    #[serde(default)]
+    message: Option<String>,
+    #[serde(default)]
+    error: Option<serde_json::Value>,
+    #[serde(default)]
+    order_id: Option<u64>,
+    #[serde(default)]
+    retry_after_ms: Option<u64>,
+}
+
+impl ErrorBody {
+    fn parse(body: &str) -> Option<Self> {
+        let mut parsed: ErrorBody = serde_json::from_str(body).ok()?;
+        match parsed.error.take() {
+            Some(serde_json::Value::String(message)) => {
+                parsed.message.get_or_insert(message);
+            }
+            Some(nested @ serde_json::Value::Object(_)) => {
+                let inner: ErrorBody = serde_json::from_value(nested).unwrap_or_default();
+                parsed.code = parsed.code.or(inner.code);
+                parsed.message = parsed.message.or(inner.message);
+                parsed.order_id = parsed.order_id.or(inner.order_id);
+                parsed.retry_after_ms = parsed.retry_after_ms.or(inner.retry_after_ms);
+            }
+            _ => {}
+        }
+        Some(parsed)
+    }
 }
 
 impl From<reqwest::Error> for SdkError {
@@ -58,16 +149,180 @@ impl From<tonic::Status> for SdkError {
     }
 }
 
+impl From<tokio_tungstenite::tungstenite::Error> for SdkError {
+    fn from(err: tokio_tungstenite::tungstenite::Error) -> Self {
+        SdkError::Stream(err.to_string())
+    }
+}
+
 impl From<serde_json::Error> for SdkError {
     fn from(err: serde_json::Error) -> Self {
         SdkError::Serialization(err.to_string())
     }
 }
 
+#[cfg(feature = "store")]
+impl From<rusqlite::Error> for SdkError {
+    fn from(err: rusqlite::Error) -> Self {
+        SdkError::Store(err.to_string())
+    }
+}
+
 impl From<std::io::Error> for SdkError {
     fn from(err: std::io::Error) -> Self {
         SdkError::Keypair(err.to_string())
     }
 }
 
+impl SdkError {
+    /// The typed error for a failed RPC response.
+    ///
+    /// Known error codes in the node's JSON payload map to their own
+    /// variants; other payloads become `Api`. A body that is not an error
+    /// payload falls back to `Rpc` prefixed with `context`.
+    pub(crate) fn from_rpc_response(
+        context: &str,
+        status: StatusCode,
+        body: &str,
+        retry_after: Option<Duration>,
+    ) -> SdkError {
+        let parsed = ErrorBody::parse(body).unwrap_or_default();
+        let code = parsed.code.as_deref().map(str::to_lowercase);
+        let retry_after = retry_after.or(parsed.retry_after_ms.map(Duration::from_millis));
+
+        if status == StatusCode::TOO_MANY_REQUESTS
+            || matches!(code.as_deref(), Some("rate_limited" | "too_many_requests"))
+        {
+            return SdkError::RateLimited { retry_after };
+        }
+
+        let Some(message) = parsed.message.or_else(|| parsed.code.clone()) else {
+            let body = body.trim();
+            return SdkError::Rpc(if body.is_empty() {
+                format!("{}: {}", context, status)
+            } else {
+                format!("{}: {} {}", context, status, body)
+            });
+        };
+
+        match (code.as_deref(), parsed.order_id) {
+            (Some("insufficient_margin"), _) => SdkError::InsufficientMargin(message),
+            (Some("insufficient_balance" | "insufficient_funds"), _) => {
+                SdkError::InsufficientBalance(message)
+            }
+            (Some("invalid_signature"), _) => SdkError::InvalidSignature(message),
+            (Some("order_not_found"), Some(order_id)) => SdkError::OrderNotFound(order_id),
+            (Some("market_not_found"), _) => SdkError::MarketNotFound(message),
+            _ => SdkError::Api {
+                status: status.as_u16(),
+                code: parsed.code,
+                message,
+            },
+        }
+    }
+
+    /// Whether Continuum rejected a submission because its nonce was already
+    /// used, which it reports with the `ALREADY_EXISTS` status code.
+    ///
+    /// Other rejections mentioning the nonce, such as one too far in the
+    /// future, do not count: the order was not sequenced.
+    pub fn is_duplicate_nonce(&self) -> bool {
+        match self {
+            SdkError::ContinuumSubmission(msg) => {
+                msg.starts_with(tonic::Code::AlreadyExists.description())
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether Continuum rejected a submission because its timestamp was out of range.
+    pub fn is_stale_timestamp(&self) -> bool {
+        match self {
+            SdkError::ContinuumSubmission(msg) => {
+                let msg = msg.to_lowercase();
+                msg.contains("stale") || msg.contains("timestamp")
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether a submission failed without a definite answer, so the
+    /// sequencer may or may not have accepted it.
+    pub fn is_indeterminate(&self) -> bool {
+        match self {
+            SdkError::ContinuumConnection(_) => true,
+            SdkError::ContinuumSubmission(msg) => {
+                let msg = msg.to_lowercase();
+                msg.contains("unavailable")
+                    || msg.contains("deadline")
+                    || msg.contains("operation was cancelled")
+                    || msg.starts_with("unknown error")
+            }
+            _ => false,
+        }
+    }
+
+    /// Whether re-signing with a fresh order ID and timestamp may succeed.
+    pub fn is_recoverable_rejection(&self) -> bool {
+        self.is_duplicate_nonce() || self.is_stale_timestamp()
+    }
+}
+
 pub type Result<T> = std::result::Result<T, SdkError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_recoverable_rejections() {
+        let dup = SdkError::from(tonic::Status::already_exists("duplicate nonce 42"));
+        assert!(dup.is_duplicate_nonce());
+        assert!(!dup.is_stale_timestamp());
+        let future = SdkError::from(tonic::Status::invalid_argument("nonce too far in future"));
+        assert!(!future.is_duplicate_nonce());
+
+        let stale = SdkError::from(tonic::Status::invalid_argument("stale timestamp"));
+        assert!(stale.is_stale_timestamp());
+        assert!(stale.is_recoverable_rejection());
+
+        let other = SdkError::from(tonic::Status::invalid_argument("insufficient margin"));
+        assert!(!other.is_recoverable_rejection());
+        assert!(!SdkError::Rpc("stale timestamp".into()).is_recoverable_rejection());
+
+        assert!(SdkError::from(tonic::Status::unavailable("connection reset")).is_indeterminate());
+        assert!(!other.is_indeterminate());
+    }
+
+    #[test]
+    fn test_rpc_error_payloads() {
+        let bad = StatusCode::BAD_REQUEST;
+        let parse = |status, body| {
+            SdkError::from_rpc_response("Failed to fetch account", status, body, None)
+        };
+
+        assert!(matches!(
+            parse(bad, r#"{"code":"INSUFFICIENT_MARGIN","message":"needs 12.5 USDC"}"#),
+            SdkError::InsufficientMargin(m) if m == "needs 12.5 USDC"
+        ));
+        assert!(matches!(
+            parse(
+                bad,
+                r#"{"error":{"code":"order_not_found","message":"gone","order_id":42}}"#
+            ),
+            SdkError::OrderNotFound(42)
+        ));
+        assert!(matches!(
+            parse(bad, r#"{"error":"invalid limit"}"#),
+            SdkError::Api { status: 400, code: None, message } if message == "invalid limit"
+        ));
+        assert!(matches!(
+            parse(StatusCode::TOO_MANY_REQUESTS, r#"{"retry_after_ms":1500}"#),
+            SdkError::RateLimited { retry_after: Some(d) } if d == Duration::from_millis(1500)
+        ));
+        assert_eq!(
+            parse(StatusCode::BAD_GATEWAY, "").to_string(),
+            "RPC error: Failed to fetch account: 502 Bad Gateway"
+        );
+    }
+}
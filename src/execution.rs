@@ -0,0 +1,1241 @@
+//! Order management helpers built on top of `FermiClient`.
+
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::alerts::{AlertCondition, AlertRule, MarketSample, PriceAlerts};
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::tracker::{OrderEvent, OrderState};
+use crate::types::{
+    lossy_decimal, CancelResult, ImmediateFill, MarketInfo, OpenOrder, OrderResult, Orderbook,
+    PerpOrder, PositionEffect, Side,
+};
+
+/// Book price a pegged order tracks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PegReference {
+    BestBid,
+    BestAsk,
+    Mid,
+}
+
+/// An order kept at a book reference price plus an offset.
+///
+/// Call [`PeggedOrder::refresh`] periodically; it cancels and re-places the
+/// order whenever the target price drifts beyond the tolerance. Your own
+/// resting orders are excluded from the reference so the peg does not
+/// chase itself.
+#[derive(Debug, Clone)]
+pub struct PeggedOrder {
+    market: MarketInfo,
+    template: PerpOrder,
+    reference: PegReference,
+    /// Added to the reference price, in quote units
+    offset: f64,
+    /// Maximum drift in quote units before the order is repriced
+    tolerance: f64,
+    live: Option<(u64, f64)>,
+}
+
+impl PeggedOrder {
+    /// `template` supplies side, quantity, leverage, and flags; its price is
+    /// replaced on every placement.
+    pub fn new(
+        market: MarketInfo,
+        template: PerpOrder,
+        reference: PegReference,
+        offset: f64,
+        tolerance: f64,
+    ) -> Self {
+        Self {
+            market,
+            template,
+            reference,
+            offset,
+            tolerance,
+            live: None,
+        }
+    }
+
+    /// ID and price of the currently resting order, if any.
+    pub fn live_order(&self) -> Option<(u64, f64)> {
+        self.live
+    }
+
+    /// Price the order should rest at for this book, if the reference exists.
+    pub fn target_price(&self, book: &Orderbook) -> Option<f64> {
+        let scale = 10f64.powi(self.market.quote_decimals as i32);
+        let bid = book.best_bid().map(|o| o.price as f64 / scale);
+        let ask = book.best_ask().map(|o| o.price as f64 / scale);
+
+        let reference = match self.reference {
+            PegReference::BestBid => bid?,
+            PegReference::BestAsk => ask?,
+            PegReference::Mid => (bid? + ask?) / 2.0,
+        };
+        Some(reference + self.offset)
+    }
+
+    /// Fetch the book and place or reprice the order if needed.
+    ///
+    /// Returns the new placement when an order was submitted.
+    pub async fn refresh(&mut self, client: &FermiClient) -> Result<Option<OrderResult>> {
+        let book = client
+            .get_orderbook(&self.market.uuid)
+            .await?
+            .without_owner(&client.pubkey());
+        let Some(target) = self.target_price(&book) else {
+            return Ok(None);
+        };
+
+        if let Some((order_id, price)) = self.live {
+            if (target - price).abs() <= self.tolerance {
+                return Ok(None);
+            }
+            info!(
+                "Repricing pegged order {} from {:.4} to {:.4}",
+                order_id, price, target
+            );
+            client.cancel_order(&self.market.uuid, order_id).await?;
+            self.live = None;
+        }
+
+        let order = PerpOrder {
+            price: lossy_decimal(target)?,
+            ..self.template.clone()
+        };
+        let result = client.place_perp_order(&self.market.uuid, order).await?;
+        self.live = Some((result.order_id, target));
+        Ok(Some(result))
+    }
+
+    /// Cancel the resting order, if any.
+    pub async fn cancel(&mut self, client: &FermiClient) -> Result<()> {
+        if let Some((order_id, _)) = self.live.take() {
+            client.cancel_order(&self.market.uuid, order_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Forget the resting order if it is not in `open_order_ids`, so the
+    /// next refresh places a new one. Returns whether it was forgotten.
+    pub fn retain_open(&mut self, open_order_ids: &HashSet<u64>) -> bool {
+        let gone = self
+            .live
+            .is_some_and(|(order_id, _)| !open_order_ids.contains(&order_id));
+        if gone {
+            self.live = None;
+        }
+        gone
+    }
+}
+
+/// Market condition a [`TriggerOrder`] waits for.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum TriggerCondition {
+    /// Mid at or above the level
+    Above(f64),
+    /// Mid at or below the level
+    Below(f64),
+    /// A price alert fires, evaluated over the trigger's own checks
+    Alert(AlertCondition),
+}
+
+/// An order held locally and submitted once, the first time its condition
+/// is met.
+#[derive(Debug, Clone)]
+pub struct TriggerOrder {
+    market: MarketInfo,
+    condition: TriggerCondition,
+    order: PerpOrder,
+    /// Evaluates `TriggerCondition::Alert`; empty otherwise
+    alerts: PriceAlerts,
+    fired: Option<OrderResult>,
+}
+
+impl TriggerOrder {
+    pub fn new(market: MarketInfo, condition: TriggerCondition, order: PerpOrder) -> Self {
+        let rules = match condition {
+            TriggerCondition::Alert(alert) => vec![AlertRule::new("trigger", alert)],
+            _ => Vec::new(),
+        };
+        Self {
+            alerts: PriceAlerts::new(&market.uuid, rules),
+            market,
+            condition,
+            order,
+            fired: None,
+        }
+    }
+
+    pub fn condition(&self) -> TriggerCondition {
+        self.condition
+    }
+
+    /// Placement made when the trigger fired, if it has.
+    pub fn fired(&self) -> Option<&OrderResult> {
+        self.fired.as_ref()
+    }
+
+    /// Fetch the book and submit the order if the condition is met.
+    ///
+    /// Returns the placement when the trigger fires; later calls do nothing.
+    pub async fn check(&mut self, client: &FermiClient) -> Result<Option<OrderResult>> {
+        if self.fired.is_some() {
+            return Ok(None);
+        }
+        let book = client.get_orderbook(&self.market.uuid).await?;
+        let Some(sample) = MarketSample::from_book(&book, &self.market, Instant::now()) else {
+            return Ok(None);
+        };
+        let mid = sample.mid();
+        let met = match self.condition {
+            TriggerCondition::Above(level) => mid >= level,
+            TriggerCondition::Below(level) => mid <= level,
+            TriggerCondition::Alert(_) => !self.alerts.observe(&sample).is_empty(),
+        };
+        if !met {
+            return Ok(None);
+        }
+
+        info!(
+            "Trigger {:?} in {} fired at mid {:.4}",
+            self.condition, self.market.name, mid
+        );
+        let result = client
+            .place_order_in_market(&self.market, self.order.clone())
+            .await?;
+        self.fired = Some(result.clone());
+        Ok(Some(result))
+    }
+}
+
+/// Why an order chase ended.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ChaseOutcome {
+    /// The order filled
+    Filled,
+    /// The order left the book unfilled, cancelled or expired by something
+    /// other than the chase
+    Cancelled,
+    /// The chase cancelled the order but its replacement was not placed;
+    /// nothing from the chase rests on the book
+    ReplacementFailed,
+    /// The touch moved past the chase limit; the order rests at the limit
+    LimitReached,
+}
+
+/// Summary of a completed order chase.
+#[derive(Debug, Clone)]
+pub struct ChaseReport {
+    /// ID of the last order placed by the chase (the original if never repriced)
+    pub order_id: u64,
+    /// Price the order was resting at when the chase ended
+    pub final_price: f64,
+    pub reprices: u32,
+    pub outcome: ChaseOutcome,
+}
+
+/// How a chase ends, given the tracker events for the order it is chasing.
+/// `None` while the order is still open.
+pub(crate) fn chase_outcome(events: &[OrderEvent], order_id: u64) -> Option<ChaseOutcome> {
+    let event = events
+        .iter()
+        .find(|e| e.order_id == order_id && e.state.is_final())?;
+    Some(match event.state {
+        OrderState::Filled => ChaseOutcome::Filled,
+        _ => ChaseOutcome::Cancelled,
+    })
+}
+
+/// Price a chased order should move to for the given touch, capped at
+/// `limit`. Buys only chase upward and sells only downward.
+///
+/// Returns `None` when the order is already at or through the target.
+pub fn chase_price(side: Side, current: f64, touch: f64, limit: f64) -> Option<f64> {
+    let target = match side {
+        Side::Buy => touch.min(limit),
+        Side::Sell => touch.max(limit),
+    };
+    let improves = match side {
+        Side::Buy => target > current,
+        Side::Sell => target < current,
+    };
+    improves.then_some(target)
+}
+
+/// Where a resting order sits in its price level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct QueuePosition {
+    pub order_id: u64,
+    pub side: Side,
+    pub price: f64,
+    /// Remaining quantity of the order itself
+    pub quantity: f64,
+    /// Quantity resting ahead of the order at the same price
+    pub size_ahead: f64,
+    pub orders_ahead: usize,
+    /// Total quantity at the price level, including the order
+    pub level_size: f64,
+}
+
+/// Price that fills `quantity` immediately against the opposite side of
+/// `book`: the worst level a [`plan_take`] sweep reaches.
+///
+/// Fails with `SdkError::OrderRejected` if the levels within
+/// `max_slippage_bps` of the best opposing price cannot fill the quantity.
+pub fn market_price(
+    book: &Orderbook,
+    market: &MarketInfo,
+    side: Side,
+    quantity: f64,
+    max_slippage_bps: u32,
+) -> Result<f64> {
+    let price_scale = 10f64.powi(market.quote_decimals as i32);
+    let best = match side {
+        Side::Buy => book.best_ask(),
+        Side::Sell => book.best_bid(),
+    }
+    .ok_or_else(|| SdkError::OrderRejected(format!("no liquidity to {} against", side)))?
+    .price as f64
+        / price_scale;
+    let slippage = max_slippage_bps as f64 / 10_000.0;
+    let limit = match side {
+        Side::Buy => best * (1.0 + slippage),
+        Side::Sell => best * (1.0 - slippage),
+    };
+
+    let slices = plan_take(book, market, side, quantity, limit);
+    let filled: f64 = slices.iter().map(|s| s.quantity).sum();
+    match slices.last() {
+        Some(last) if filled >= quantity => Ok(last.price),
+        _ => Err(SdkError::OrderRejected(format!(
+            "only {} of {} available within {} bps of {}",
+            filled, quantity, max_slippage_bps, best
+        ))),
+    }
+}
+
+/// Locate `order_id` in an L3 book and measure the queue in front of it.
+///
+/// Order IDs are each client's own nonces, not arrival order, so they say
+/// nothing about priority between owners. Orders at the same price are
+/// taken to be ahead when the book lists them first, as the node lists
+/// each level in time priority.
+pub fn queue_position(
+    book: &Orderbook,
+    market: &MarketInfo,
+    order_id: u64,
+) -> Option<QueuePosition> {
+    let price_scale = 10f64.powi(market.quote_decimals as i32);
+    let qty_scale = 10f64.powi(market.base_decimals as i32);
+
+    let (side, level) = [(Side::Buy, &book.buys), (Side::Sell, &book.sells)]
+        .into_iter()
+        .find(|(_, orders)| orders.iter().any(|o| o.order_id == order_id))?;
+    let mine = level.iter().find(|o| o.order_id == order_id)?;
+    let same_price = level.iter().filter(|o| o.price == mine.price);
+
+    let (mut size_ahead, mut orders_ahead, mut level_size) = (0u64, 0usize, 0u64);
+    let mut ahead = true;
+    for order in same_price {
+        level_size += order.quantity;
+        ahead &= order.order_id != order_id;
+        if ahead {
+            size_ahead += order.quantity;
+            orders_ahead += 1;
+        }
+    }
+
+    Some(QueuePosition {
+        order_id,
+        side,
+        price: mine.price as f64 / price_scale,
+        quantity: mine.quantity as f64 / qty_scale,
+        size_ahead: size_ahead as f64 / qty_scale,
+        orders_ahead,
+        level_size: level_size as f64 / qty_scale,
+    })
+}
+
+/// Tracks how an order's queue position evolves across observations.
+#[derive(Debug, Clone)]
+pub struct QueueTracker {
+    order_id: u64,
+    history: Vec<(Instant, QueuePosition)>,
+}
+
+impl QueueTracker {
+    pub fn new(order_id: u64) -> Self {
+        Self {
+            order_id,
+            history: Vec::new(),
+        }
+    }
+
+    pub fn order_id(&self) -> u64 {
+        self.order_id
+    }
+
+    /// Every observation so far, oldest first.
+    pub fn history(&self) -> &[(Instant, QueuePosition)] {
+        &self.history
+    }
+
+    pub fn latest(&self) -> Option<&QueuePosition> {
+        self.history.last().map(|(_, p)| p)
+    }
+
+    /// Record an observation taken now.
+    pub fn record(&mut self, position: QueuePosition) {
+        self.history.push((Instant::now(), position));
+    }
+
+    /// Fetch the book and record the current position.
+    ///
+    /// Returns `None` once the order is no longer on the book.
+    pub async fn update(&mut self, client: &FermiClient) -> Result<Option<QueuePosition>> {
+        let position = client.estimate_queue_position(self.order_id).await?;
+        if let Some(position) = &position {
+            self.record(position.clone());
+        }
+        Ok(position)
+    }
+
+    /// Change in size ahead since the first observation (negative = moved up).
+    pub fn size_ahead_change(&self) -> f64 {
+        match (self.history.first(), self.history.last()) {
+            (Some((_, first)), Some((_, last))) => last.size_ahead - first.size_ahead,
+            _ => 0.0,
+        }
+    }
+
+    /// Average rate at which the queue ahead has been shrinking, per second.
+    pub fn depletion_rate(&self) -> Option<f64> {
+        let (start, _) = self.history.first()?;
+        let (end, _) = self.history.last()?;
+        let elapsed = end.duration_since(*start).as_secs_f64();
+        (elapsed > 0.0).then(|| -self.size_ahead_change() / elapsed)
+    }
+
+    /// Time until the order reaches the front at the observed depletion rate.
+    pub fn time_to_front(&self) -> Option<Duration> {
+        let rate = self.depletion_rate().filter(|r| *r > 0.0)?;
+        Some(Duration::from_secs_f64(self.latest()?.size_ahead / rate))
+    }
+}
+
+/// Share a position move of `moved` base units (positive when bought) among
+/// IOC and FOK `orders` in placement order.
+///
+/// Each order takes what the move covers on its side up to its quantity,
+/// so fills of other orders in the market meanwhile are attributed too.
+pub(crate) fn share_immediate_fills(mut moved: f64, orders: &[&PerpOrder]) -> Vec<ImmediateFill> {
+    orders
+        .iter()
+        .map(|order| {
+            let direction = match order.side {
+                Side::Buy => 1.0,
+                Side::Sell => -1.0,
+            };
+            let quantity = order.quantity_f64();
+            let filled = (moved * direction).clamp(0.0, quantity);
+            moved -= direction * filled;
+            ImmediateFill {
+                filled_quantity: filled,
+                cancelled_quantity: quantity - filled,
+            }
+        })
+        .collect()
+}
+
+/// A single submission planned against one book price level.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TakeSlice {
+    pub price: f64,
+    pub quantity: f64,
+}
+
+/// Outcome of a liquidity-taking sweep.
+#[derive(Debug, Clone)]
+pub struct TakeReport {
+    /// Every submitted slice with its placement result
+    pub submissions: Vec<(TakeSlice, OrderResult)>,
+    /// Base quantity the slices filled
+    pub filled_quantity: f64,
+    /// Volume-weighted price of the fills, each at its slice's price
+    pub average_price: Option<f64>,
+    /// Requested quantity that did not fill, including unmeasured slices
+    pub unfilled: f64,
+    /// Slices whose fills could not be measured
+    pub unmeasured: usize,
+    /// Why the sweep stopped early; the slices submitted before stand
+    pub error: Option<String>,
+}
+
+impl TakeReport {
+    /// Tally the fills of IOC slices, as reported in
+    /// [`OrderResult::immediate`], against the requested `quantity`.
+    pub fn new(
+        quantity: f64,
+        submissions: Vec<(TakeSlice, OrderResult)>,
+        error: Option<String>,
+    ) -> Self {
+        let mut filled_quantity = 0.0;
+        let mut notional = 0.0;
+        let mut unmeasured = 0;
+        for (slice, result) in &submissions {
+            match result.immediate {
+                Some(fill) => {
+                    filled_quantity += fill.filled_quantity;
+                    notional += fill.filled_quantity * slice.price;
+                }
+                None => unmeasured += 1,
+            }
+        }
+        Self {
+            submissions,
+            average_price: (filled_quantity > 0.0).then(|| notional / filled_quantity),
+            unfilled: (quantity - filled_quantity).max(0.0),
+            filled_quantity,
+            unmeasured,
+            error,
+        }
+    }
+}
+
+/// Plan a sweep of the opposite side of the book, one slice per price
+/// level, stopping at `limit_price` or once `quantity` is covered.
+pub fn plan_take(
+    book: &Orderbook,
+    market: &MarketInfo,
+    side: Side,
+    quantity: f64,
+    limit_price: f64,
+) -> Vec<TakeSlice> {
+    let price_scale = 10f64.powi(market.quote_decimals as i32);
+    let qty_scale = 10f64.powi(market.base_decimals as i32);
+
+    let mut levels: Vec<(u64, u64)> = Vec::new();
+    let resting = match side {
+        Side::Buy => &book.sells,
+        Side::Sell => &book.buys,
+    };
+    for order in resting {
+        match levels.iter_mut().find(|(price, _)| *price == order.price) {
+            Some(level) => level.1 += order.quantity,
+            None => levels.push((order.price, order.quantity)),
+        }
+    }
+    match side {
+        Side::Buy => levels.sort_by_key(|(price, _)| *price),
+        Side::Sell => levels.sort_by_key(|(price, _)| std::cmp::Reverse(*price)),
+    }
+
+    let mut remaining = quantity;
+    let mut slices = Vec::new();
+    for (price, size) in levels {
+        if remaining <= 0.0 {
+            break;
+        }
+        let price = price as f64 / price_scale;
+        let within_limit = match side {
+            Side::Buy => price <= limit_price,
+            Side::Sell => price >= limit_price,
+        };
+        if !within_limit {
+            break;
+        }
+        let take = remaining.min(size as f64 / qty_scale);
+        slices.push(TakeSlice {
+            price,
+            quantity: take,
+        });
+        remaining -= take;
+    }
+    slices
+}
+
+/// One leg of a multi-leg placement.
+#[derive(Debug, Clone)]
+pub struct LegOutcome {
+    pub market: MarketInfo,
+    /// The order as submitted, after hooks and limits
+    pub order: PerpOrder,
+    pub order_id: u64,
+    pub placed: Option<OrderResult>,
+    pub error: Option<String>,
+    /// The submission failed without a definite answer and may have landed
+    pub indeterminate: bool,
+}
+
+impl LegOutcome {
+    /// Base quantity of the leg that filled, given the order found resting
+    /// under its ID.
+    ///
+    /// A placed leg no longer resting is taken as fully filled. `None` for
+    /// an indeterminate leg that is not resting, which may never have
+    /// landed.
+    pub fn filled_quantity(&self, resting: Option<&OpenOrder>) -> Option<f64> {
+        match resting {
+            Some(resting) => {
+                let remaining =
+                    resting.quantity as f64 / 10f64.powi(self.market.base_decimals as i32);
+                Some((self.order.quantity_f64() - remaining).max(0.0))
+            }
+            None if self.placed.is_some() => Some(self.order.quantity_f64()),
+            None => None,
+        }
+    }
+
+    /// Reduce-only order closing `filled` of the leg against the market's
+    /// position of signed `size`, priced `slippage_bps` through `mark`.
+    ///
+    /// The order keeps the leg's margin mode and leverage, so an isolated
+    /// leg is closed from its isolated account. It is clamped to the part of
+    /// the position on the leg's side; `None` when nothing is left to close.
+    pub fn closing_order(
+        &self,
+        filled: f64,
+        size: f64,
+        mark: f64,
+        slippage_bps: u32,
+    ) -> Result<Option<PerpOrder>> {
+        let slippage = slippage_bps as f64 / 10_000.0;
+        let (side, price, held) = match self.order.side {
+            Side::Buy => (Side::Sell, mark * (1.0 - slippage), size.max(0.0)),
+            Side::Sell => (Side::Buy, mark * (1.0 + slippage), (-size).max(0.0)),
+        };
+        let quantity = filled.min(held);
+        if quantity <= 0.0 {
+            return Ok(None);
+        }
+        Ok(Some(PerpOrder {
+            side,
+            price: lossy_decimal(price)?,
+            quantity: lossy_decimal(quantity)?,
+            leverage: self.order.leverage,
+            position_effect: PositionEffect::Close,
+            margin_mode: self.order.margin_mode,
+            reduce_only: true,
+            ..Default::default()
+        }))
+    }
+}
+
+/// Outcome of `FermiClient::place_legs`, in the order the legs were given.
+#[derive(Debug, Clone, Default)]
+pub struct LegPlacement {
+    pub legs: Vec<LegOutcome>,
+}
+
+impl LegPlacement {
+    pub fn all_placed(&self) -> bool {
+        self.legs.iter().all(|leg| leg.placed.is_some())
+    }
+
+    /// Whether every leg was placed and expected in the same tick.
+    pub fn same_tick(&self) -> bool {
+        let mut ticks = self
+            .legs
+            .iter()
+            .map(|leg| leg.placed.as_ref().map(|p| p.expected_tick));
+        match ticks.next() {
+            Some(Some(first)) => ticks.all(|tick| tick == Some(first)),
+            _ => false,
+        }
+    }
+}
+
+/// What `FermiClient::rollback_legs` did.
+#[derive(Debug, Clone, Default)]
+pub struct LegRollback {
+    pub cancelled: Vec<CancelResult>,
+    /// Reduce-only orders sent against filled legs
+    pub closing_orders: Vec<OrderResult>,
+    /// Steps that failed; rollback carries on past them
+    pub failures: Vec<String>,
+}
+
+/// How quantity is spread across ladder rungs, from `price_from` to `price_to`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum LadderDistribution {
+    /// Equal size on every rung
+    Flat,
+    /// Sizes grow linearly: 1, 2, 3, ...
+    Linear,
+    /// Each rung is `ratio` times the previous one
+    Geometric(f64),
+}
+
+/// Parameters for a laddered placement.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LadderSpec {
+    pub side: Side,
+    pub total_quantity: f64,
+    pub price_from: f64,
+    pub price_to: f64,
+    pub num_orders: usize,
+    pub distribution: LadderDistribution,
+    pub leverage: u64,
+}
+
+/// Where a ladder rung stands.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RungState {
+    /// Not on the book; placed by the next `reprice`
+    Unplaced,
+    /// Resting under this order ID
+    Live(u64),
+    /// Left the book other than by our cancel, so taken as filled; never
+    /// placed again
+    Filled,
+}
+
+/// One rung of a ladder.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LadderRung {
+    pub price: f64,
+    pub quantity: f64,
+    pub state: RungState,
+}
+
+/// Evenly spaced prices from `price_from` to `price_to` (inclusive) with
+/// quantities following the distribution and summing to `total_quantity`.
+///
+/// Fails with `SdkError::Validation` unless there is at least one rung, the
+/// quantity and prices are positive and finite, and a geometric ratio is
+/// positive and finite.
+pub fn plan_ladder(spec: &LadderSpec) -> Result<Vec<LadderRung>> {
+    let n = spec.num_orders;
+    let positive = |value: f64| value.is_finite() && value > 0.0;
+    let invalid = |reason: String| Err(SdkError::Validation(reason));
+    if n == 0 {
+        return invalid("a ladder needs at least one rung".into());
+    }
+    if !positive(spec.total_quantity) {
+        return invalid(format!(
+            "ladder quantity {} must be positive",
+            spec.total_quantity
+        ));
+    }
+    if !positive(spec.price_from) || !positive(spec.price_to) {
+        return invalid(format!(
+            "ladder prices {} to {} must be positive",
+            spec.price_from, spec.price_to
+        ));
+    }
+    if let LadderDistribution::Geometric(ratio) = spec.distribution {
+        if !positive(ratio) {
+            return invalid(format!("geometric ratio {} must be positive", ratio));
+        }
+    }
+
+    let weights: Vec<f64> = (0..n)
+        .map(|i| match spec.distribution {
+            LadderDistribution::Flat => 1.0,
+            LadderDistribution::Linear => (i + 1) as f64,
+            LadderDistribution::Geometric(ratio) => ratio.powi(i as i32),
+        })
+        .collect();
+    let total_weight: f64 = weights.iter().sum();
+    if !total_weight.is_finite() {
+        return invalid(format!("ladder weights overflow across {} rungs", n));
+    }
+    let step = if n > 1 {
+        (spec.price_to - spec.price_from) / (n - 1) as f64
+    } else {
+        0.0
+    };
+
+    Ok(weights
+        .iter()
+        .enumerate()
+        .map(|(i, weight)| LadderRung {
+            price: spec.price_from + step * i as f64,
+            quantity: spec.total_quantity * weight / total_weight,
+            state: RungState::Unplaced,
+        })
+        .collect())
+}
+
+/// A placed ladder, tracked for group operations.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LadderHandle {
+    pub(crate) market: MarketInfo,
+    pub(crate) spec: LadderSpec,
+    pub(crate) rungs: Vec<LadderRung>,
+}
+
+impl LadderHandle {
+    pub fn market_id(&self) -> &str {
+        &self.market.uuid
+    }
+
+    pub fn spec(&self) -> &LadderSpec {
+        &self.spec
+    }
+
+    pub fn rungs(&self) -> &[LadderRung] {
+        &self.rungs
+    }
+
+    /// IDs of every live rung.
+    pub fn order_ids(&self) -> Vec<u64> {
+        self.rungs
+            .iter()
+            .filter_map(|r| match r.state {
+                RungState::Live(id) => Some(id),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Mark every live rung not in `open_order_ids` as filled, so it is
+    /// neither cancelled nor placed again. Returns the number of rungs
+    /// marked.
+    pub fn retain_open(&mut self, open_order_ids: &HashSet<u64>) -> usize {
+        let mut filled = 0;
+        for rung in &mut self.rungs {
+            if matches!(rung.state, RungState::Live(id) if !open_order_ids.contains(&id)) {
+                rung.state = RungState::Filled;
+                filled += 1;
+            }
+        }
+        filled
+    }
+
+    /// Cancel every live rung in one batch, leaving them unplaced.
+    ///
+    /// If the batch fails every rung stays live so the call can be retried.
+    /// Mark filled rungs with `retain_open` first, since a cancel of a
+    /// filled order may fail the batch.
+    pub async fn cancel_all(&mut self, client: &FermiClient) -> Result<Vec<CancelResult>> {
+        let results = client
+            .cancel_orders_in_market(&self.market, &self.order_ids())
+            .await?;
+        self.mark_cancelled();
+        Ok(results)
+    }
+
+    /// Mark every live rung unplaced once its cancel has gone through.
+    fn mark_cancelled(&mut self) {
+        for rung in &mut self.rungs {
+            if let RungState::Live(_) = rung.state {
+                rung.state = RungState::Unplaced;
+            }
+        }
+    }
+
+    /// Shift the whole ladder by `shift_bps` basis points.
+    ///
+    /// Live rungs are cancelled in one batch, then the unplaced rungs are
+    /// placed in a second, so the ladder is never doubled up. Rungs marked
+    /// filled by `retain_open` are not placed again. If the cancel fails
+    /// nothing is re-placed; if the re-placement fails the error is returned
+    /// and the rungs are left unplaced at the new prices, so calling
+    /// `reprice` with a zero shift retries them.
+    pub async fn reprice(&mut self, client: &FermiClient, shift_bps: f64) -> Result<()> {
+        self.cancel_all(client).await?;
+
+        let factor = 1.0 + shift_bps / 10_000.0;
+        self.spec.price_from *= factor;
+        self.spec.price_to *= factor;
+        for rung in &mut self.rungs {
+            rung.price *= factor;
+        }
+
+        info!(
+            "Repricing ladder in {} by {} bps",
+            self.market.name, shift_bps
+        );
+        client
+            .place_rungs(&self.market, &self.spec, &mut self.rungs)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{test_market, MarginMode, OrderbookEntry};
+
+    fn entry(owner: &str, price: u64) -> OrderbookEntry {
+        OrderbookEntry {
+            order_id: price,
+            owner: owner.to_string(),
+            price,
+            quantity: 1,
+            side: String::new(),
+            expiry: 0,
+        }
+    }
+
+    fn market() -> MarketInfo {
+        test_market(0, 6)
+    }
+
+    #[test]
+    fn test_peg_target_ignores_own_orders() {
+        let market = market();
+        let book = Orderbook {
+            buys: vec![entry("other", 99_000_000), entry("me", 99_500_000)],
+            sells: vec![entry("other", 101_000_000)],
+        };
+
+        let peg = PeggedOrder::new(market, PerpOrder::default(), PegReference::Mid, -0.5, 0.01);
+        assert_eq!(peg.target_price(&book), Some(99.75));
+        assert_eq!(peg.target_price(&book.without_owner("me")), Some(99.5));
+    }
+
+    #[test]
+    fn test_retain_open_clears_closed_orders() {
+        let mut peg =
+            PeggedOrder::new(market(), PerpOrder::default(), PegReference::Mid, 0.0, 0.01);
+        peg.live = Some((7, 100.0));
+        let open: HashSet<u64> = [1, 7].into_iter().collect();
+        assert!(!peg.retain_open(&open));
+        assert!(peg.retain_open(&HashSet::new()));
+        assert_eq!(peg.live_order(), None);
+
+        let spec = LadderSpec {
+            side: Side::Buy,
+            total_quantity: 3.0,
+            price_from: 100.0,
+            price_to: 98.0,
+            num_orders: 3,
+            distribution: LadderDistribution::Flat,
+            leverage: 5,
+        };
+        let mut rungs = plan_ladder(&spec).unwrap();
+        for (rung, id) in rungs.iter_mut().zip([1, 2, 3]) {
+            rung.state = RungState::Live(id);
+        }
+        let mut ladder = LadderHandle {
+            market: market(),
+            spec,
+            rungs,
+        };
+        assert_eq!(ladder.retain_open(&open), 2);
+        assert_eq!(ladder.order_ids(), vec![1]);
+
+        // Only the rung that was live before the cancel is placed again
+        ladder.mark_cancelled();
+        let states: Vec<_> = ladder.rungs().iter().map(|r| r.state).collect();
+        assert_eq!(
+            states,
+            vec![RungState::Unplaced, RungState::Filled, RungState::Filled]
+        );
+        assert!(ladder.order_ids().is_empty());
+    }
+
+    #[test]
+    fn test_leg_fill_and_close() {
+        let mut leg = LegOutcome {
+            market: test_market(3, 6),
+            order: PerpOrder {
+                leverage: 5,
+                margin_mode: MarginMode::Isolated,
+                ..PerpOrder::from_f64(Side::Buy, 100.0, 2.0).unwrap()
+            },
+            order_id: 7,
+            placed: None,
+            error: None,
+            indeterminate: true,
+        };
+        let resting = OpenOrder {
+            order_id: 7,
+            market_id: "m".into(),
+            market_name: None,
+            owner: "me".into(),
+            side: "buy".into(),
+            price: 100_000_000,
+            quantity: 500,
+            expiry: 0,
+            timestamp: None,
+        };
+
+        // 0.5 of 2 still rests, so 1.5 filled
+        assert_eq!(leg.filled_quantity(Some(&resting)), Some(1.5));
+        // Not resting and never acknowledged: it may not have landed
+        assert_eq!(leg.filled_quantity(None), None);
+        leg.placed = Some(OrderResult {
+            order_id: 7,
+            sequence_number: 1,
+            expected_tick: 1,
+            tx_hash: String::new(),
+            immediate: None,
+        });
+        assert_eq!(leg.filled_quantity(None), Some(2.0));
+
+        // Closed in the leg's own mode and leverage, clamped to the long
+        let close = leg.closing_order(1.5, 1.0, 100.0, 100).unwrap().unwrap();
+        assert_eq!(close.side, Side::Sell);
+        assert_eq!(close.price_f64(), 99.0);
+        assert_eq!(close.quantity_f64(), 1.0);
+        assert_eq!(close.leverage, 5);
+        assert_eq!(close.margin_mode, MarginMode::Isolated);
+        assert!(close.reduce_only);
+        // A short is not the buy leg's fill to close
+        assert!(leg.closing_order(1.5, -1.0, 100.0, 100).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_chase_price_caps_at_limit() {
+        assert_eq!(chase_price(Side::Buy, 100.0, 100.5, 101.0), Some(100.5));
+        assert_eq!(chase_price(Side::Buy, 100.5, 102.0, 101.0), Some(101.0));
+        assert_eq!(chase_price(Side::Buy, 101.0, 102.0, 101.0), None);
+        assert_eq!(chase_price(Side::Buy, 100.0, 99.0, 101.0), None);
+        assert_eq!(chase_price(Side::Sell, 100.0, 99.5, 99.0), Some(99.5));
+        assert_eq!(chase_price(Side::Sell, 100.0, 98.0, 99.0), Some(99.0));
+    }
+
+    #[test]
+    fn test_queue_position_counts_orders_listed_first() {
+        let mut book = Orderbook {
+            buys: vec![
+                entry("a", 100_000_000),
+                entry("b", 100_000_000),
+                entry("c", 99_000_000),
+            ],
+            sells: vec![],
+        };
+        for (id, order) in book.buys.iter_mut().enumerate() {
+            order.order_id = id as u64 + 11;
+        }
+        book.buys.insert(
+            2,
+            OrderbookEntry {
+                order_id: 10,
+                quantity: 3,
+                ..entry("me", 100_000_000)
+            },
+        );
+        // Listed behind mine, so queued behind it despite the lower ID
+        book.buys.insert(3, entry("d", 100_000_000));
+        book.buys[3].order_id = 1;
+
+        let position = queue_position(&book, &market(), 10).unwrap();
+        assert_eq!(position.side, Side::Buy);
+        assert_eq!(position.price, 100.0);
+        assert_eq!(position.size_ahead, 2.0);
+        assert_eq!(position.orders_ahead, 2);
+        assert_eq!(position.level_size, 6.0);
+        assert!(queue_position(&book, &market(), 42).is_none());
+    }
+
+    #[test]
+    fn test_plan_take_stops_at_limit() {
+        let book = Orderbook {
+            buys: vec![],
+            sells: vec![
+                entry("a", 102_000_000),
+                entry("b", 100_000_000),
+                entry("c", 100_000_000),
+                entry("d", 101_000_000),
+            ],
+        };
+
+        let slices = plan_take(&book, &market(), Side::Buy, 5.0, 101.0);
+        assert_eq!(
+            slices,
+            vec![
+                TakeSlice {
+                    price: 100.0,
+                    quantity: 2.0
+                },
+                TakeSlice {
+                    price: 101.0,
+                    quantity: 1.0
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_take_report_tallies_measured_fills() {
+        let submission = |price, quantity, filled: Option<f64>| {
+            (
+                TakeSlice { price, quantity },
+                OrderResult {
+                    order_id: 1,
+                    sequence_number: 1,
+                    expected_tick: 1,
+                    tx_hash: String::new(),
+                    immediate: filled.map(|filled_quantity| ImmediateFill {
+                        filled_quantity,
+                        cancelled_quantity: quantity - filled_quantity,
+                    }),
+                },
+            )
+        };
+
+        // The 101 level had thinned to 1 of 3 by the time its slice landed
+        let report = TakeReport::new(
+            6.0,
+            vec![
+                submission(100.0, 2.0, Some(2.0)),
+                submission(101.0, 3.0, Some(1.0)),
+            ],
+            None,
+        );
+        assert_eq!(report.filled_quantity, 3.0);
+        assert!((report.average_price.unwrap() - 301.0 / 3.0).abs() < 1e-9);
+        assert_eq!(report.unfilled, 3.0);
+        assert_eq!(report.unmeasured, 0);
+
+        // A failed slice keeps what was submitted before it
+        let report = TakeReport::new(
+            6.0,
+            vec![submission(100.0, 2.0, None)],
+            Some("rejected".into()),
+        );
+        assert_eq!(report.filled_quantity, 0.0);
+        assert_eq!(report.average_price, None);
+        assert_eq!(report.unfilled, 6.0);
+        assert_eq!(report.unmeasured, 1);
+        assert_eq!(report.submissions.len(), 1);
+    }
+
+    #[test]
+    fn test_share_immediate_fills_in_placement_order() {
+        let buy = |quantity| PerpOrder::from_f64(Side::Buy, 100.0, quantity).unwrap();
+        let sell = PerpOrder::from_f64(Side::Sell, 101.0, 1.0).unwrap();
+        let (first, second) = (buy(2.0), buy(3.0));
+
+        // A 3 unit buy fills the first order, then part of the second; the
+        // sell is on the other side of the move and gets nothing
+        let fills = share_immediate_fills(3.0, &[&first, &sell, &second]);
+        let filled: Vec<f64> = fills.iter().map(|f| f.filled_quantity).collect();
+        let cancelled: Vec<f64> = fills.iter().map(|f| f.cancelled_quantity).collect();
+        assert_eq!(filled, vec![2.0, 0.0, 1.0]);
+        assert_eq!(cancelled, vec![0.0, 1.0, 2.0]);
+
+        // More movement than was ordered is capped at each order's quantity
+        let fills = share_immediate_fills(-5.0, &[&sell]);
+        assert_eq!(fills[0].filled_quantity, 1.0);
+        assert_eq!(fills[0].cancelled_quantity, 0.0);
+    }
+
+    #[test]
+    fn test_market_price_respects_slippage() {
+        let book = Orderbook {
+            buys: vec![entry("a", 99_000_000)],
+            sells: vec![
+                entry("b", 100_000_000),
+                entry("c", 100_500_000),
+                entry("d", 102_000_000),
+            ],
+        };
+
+        assert_eq!(
+            market_price(&book, &market(), Side::Buy, 2.0, 100).unwrap(),
+            100.5
+        );
+        assert_eq!(
+            market_price(&book, &market(), Side::Sell, 1.0, 0).unwrap(),
+            99.0
+        );
+        // The third level is 2% through the best ask
+        assert!(market_price(&book, &market(), Side::Buy, 3.0, 100).is_err());
+        assert!(market_price(&book, &market(), Side::Buy, 3.0, 200).is_ok());
+        assert!(market_price(
+            &Orderbook {
+                buys: vec![],
+                sells: vec![]
+            },
+            &market(),
+            Side::Buy,
+            1.0,
+            100
+        )
+        .is_err());
+    }
+
+    #[test]
+    fn test_leg_placement_same_tick() {
+        let leg = |tick: Option<u64>| LegOutcome {
+            market: market(),
+            order: PerpOrder::default(),
+            order_id: 1,
+            placed: tick.map(|expected_tick| OrderResult {
+                order_id: 1,
+                sequence_number: 0,
+                expected_tick,
+                tx_hash: String::new(),
+                immediate: None,
+            }),
+            error: None,
+            indeterminate: false,
+        };
+        let placement = |ticks: &[Option<u64>]| LegPlacement {
+            legs: ticks.iter().map(|t| leg(*t)).collect(),
+        };
+
+        assert!(placement(&[Some(7), Some(7)]).same_tick());
+        assert!(!placement(&[Some(7), Some(8)]).same_tick());
+        assert!(!placement(&[Some(7), None]).same_tick());
+        assert!(!placement(&[Some(7), None]).all_placed());
+        assert!(!placement(&[]).same_tick());
+    }
+
+    #[test]
+    fn test_plan_ladder_distributions() {
+        let mut spec = LadderSpec {
+            side: Side::Buy,
+            total_quantity: 6.0,
+            price_from: 100.0,
+            price_to: 98.0,
+            num_orders: 3,
+            distribution: LadderDistribution::Linear,
+            leverage: 5,
+        };
+
+        let rungs = plan_ladder(&spec).unwrap();
+        let prices: Vec<f64> = rungs.iter().map(|r| r.price).collect();
+        let sizes: Vec<f64> = rungs.iter().map(|r| r.quantity).collect();
+        assert_eq!(prices, vec![100.0, 99.0, 98.0]);
+        assert_eq!(sizes, vec![1.0, 2.0, 3.0]);
+
+        spec.total_quantity = 7.0;
+        spec.distribution = LadderDistribution::Geometric(2.0);
+        let sizes: Vec<f64> = plan_ladder(&spec)
+            .unwrap()
+            .iter()
+            .map(|r| r.quantity)
+            .collect();
+        assert_eq!(sizes, vec![1.0, 2.0, 4.0]);
+    }
+
+    #[test]
+    fn test_plan_ladder_rejects_degenerate_specs() {
+        let spec = LadderSpec {
+            side: Side::Buy,
+            total_quantity: 6.0,
+            price_from: 100.0,
+            price_to: 98.0,
+            num_orders: 2,
+            distribution: LadderDistribution::Geometric(-1.0),
+            leverage: 5,
+        };
+        assert!(matches!(plan_ladder(&spec), Err(SdkError::Validation(_))));
+
+        let no_rungs = LadderSpec {
+            num_orders: 0,
+            distribution: LadderDistribution::Flat,
+            ..spec.clone()
+        };
+        assert!(plan_ladder(&no_rungs).is_err());
+
+        let nan_quantity = LadderSpec {
+            total_quantity: f64::NAN,
+            distribution: LadderDistribution::Flat,
+            ..spec
+        };
+        assert!(plan_ladder(&nan_quantity).is_err());
+    }
+}
@@ -0,0 +1,371 @@
+//! Funding rate analytics.
+//!
+//! Rates follow the usual perp convention: a positive funding rate means
+//! longs pay shorts.
+
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::risk::PortfolioPosition;
+use crate::types::FundingEvent;
+
+/// Interval assumed when the history does not report one.
+const DEFAULT_FUNDING_INTERVAL_SECS: u64 = 3600;
+
+/// How recent funding rates are projected forward.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RateEstimator {
+    /// Hold the most recent rate constant
+    Last,
+    /// Simple average of the lookback window
+    Mean,
+    /// Exponentially weighted average with the given half-life in events
+    Ewma { half_life: f64 },
+}
+
+/// Assumptions used by the funding forecaster.
+#[derive(Debug, Clone)]
+pub struct FundingAssumptions {
+    /// Number of most recent events considered
+    pub lookback: usize,
+    pub estimator: RateEstimator,
+    /// Added to the estimated rate, in basis points, to model a view or buffer
+    pub rate_adjustment_bps: f64,
+}
+
+impl Default for FundingAssumptions {
+    fn default() -> Self {
+        Self {
+            lookback: 24,
+            estimator: RateEstimator::Ewma { half_life: 8.0 },
+            rate_adjustment_bps: 0.0,
+        }
+    }
+}
+
+/// Expected funding over a holding period.
+#[derive(Debug, Clone)]
+pub struct FundingForecast {
+    /// Projected rate per funding interval, in basis points
+    pub rate_bps: f64,
+    pub interval_seconds: u64,
+    /// Number of funding intervals in the horizon (may be fractional)
+    pub intervals: f64,
+    /// Expected USDC paid per interval (negative = received)
+    pub cost_per_interval: f64,
+    /// Expected USDC paid over the horizon (negative = received)
+    pub total_cost: f64,
+}
+
+/// Forecast funding paid by a position over `horizon` using the default
+/// assumptions.
+///
+/// Returns `None` when the history is empty.
+pub fn forecast_cost(
+    position: &PortfolioPosition,
+    funding_history: &[FundingEvent],
+    horizon: Duration,
+) -> Option<FundingForecast> {
+    forecast_cost_with(
+        position,
+        funding_history,
+        horizon,
+        &FundingAssumptions::default(),
+    )
+}
+
+/// Forecast funding paid by a position over `horizon`.
+pub fn forecast_cost_with(
+    position: &PortfolioPosition,
+    funding_history: &[FundingEvent],
+    horizon: Duration,
+    assumptions: &FundingAssumptions,
+) -> Option<FundingForecast> {
+    let mut history: Vec<&FundingEvent> = funding_history.iter().collect();
+    history.sort_by_key(|e| e.timestamp);
+    let latest = *history.last()?;
+
+    let start = history.len().saturating_sub(assumptions.lookback.max(1));
+    let rates: Vec<f64> = history[start..]
+        .iter()
+        .map(|e| e.funding_rate_bps as f64)
+        .collect();
+    let rate_bps = estimate_rate(&rates, assumptions.estimator) + assumptions.rate_adjustment_bps;
+
+    let interval_seconds = if latest.interval_seconds > 0 {
+        latest.interval_seconds
+    } else {
+        DEFAULT_FUNDING_INTERVAL_SECS
+    };
+    let intervals = horizon.as_secs_f64() / interval_seconds as f64;
+
+    // Longs pay when the rate is positive
+    let cost_per_interval = position.size * position.mark_price * rate_bps / 10_000.0;
+
+    Some(FundingForecast {
+        rate_bps,
+        interval_seconds,
+        intervals,
+        cost_per_interval,
+        total_cost: cost_per_interval * intervals,
+    })
+}
+
+/// One point of a resampled funding series.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FundingSample {
+    /// Start of the bucket, in the events' timestamp units (seconds)
+    pub timestamp: u64,
+    /// Funding rate per resample interval, in basis points
+    pub rate_bps: f64,
+    /// No event fell in the bucket; the previous rate was carried forward
+    pub filled: bool,
+}
+
+/// Summary statistics of a resampled funding series.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct FundingStats {
+    pub mean_bps: f64,
+    pub std_dev_bps: f64,
+    pub min_bps: f64,
+    pub max_bps: f64,
+    pub median_bps: f64,
+    /// Share of samples with a positive rate (longs paying)
+    pub positive_fraction: f64,
+    /// Longest run of consecutive positive samples
+    pub longest_positive_streak: usize,
+    /// Longest run of consecutive negative samples
+    pub longest_negative_streak: usize,
+    /// Length of the run ending at the latest sample: positive for a run of
+    /// positive rates, negative for negative rates, 0 if the latest is zero
+    pub current_streak: i64,
+}
+
+/// Regular funding-rate series for one market.
+#[derive(Debug, Clone)]
+pub struct FundingSeries {
+    pub market_id: String,
+    pub interval_seconds: u64,
+    pub samples: Vec<FundingSample>,
+    pub stats: FundingStats,
+}
+
+impl FundingSeries {
+    /// Rate at percentile `p` (0-100) of the samples, interpolating between
+    /// neighbours.
+    pub fn percentile(&self, p: f64) -> Option<f64> {
+        let mut rates: Vec<f64> = self.samples.iter().map(|s| s.rate_bps).collect();
+        rates.sort_by(f64::total_cmp);
+        percentile_of(&rates, p)
+    }
+}
+
+/// Resample funding history into one regular series per market.
+///
+/// Each event's rate is scaled from its own funding interval to `interval`,
+/// and events sharing a bucket are averaged, so the series reads as "rate
+/// paid per `interval`" whatever the market's funding cadence. Buckets with
+/// no event carry the previous rate forward. Series are ordered by market ID.
+pub fn resample(events: &[FundingEvent], interval: Duration) -> Vec<FundingSeries> {
+    let interval_seconds = interval.as_secs().max(1);
+    let mut by_market: BTreeMap<&str, Vec<&FundingEvent>> = BTreeMap::new();
+    for event in events {
+        by_market.entry(&event.market_id).or_default().push(event);
+    }
+
+    by_market
+        .into_iter()
+        .map(|(market_id, mut history)| {
+            history.sort_by_key(|e| e.timestamp);
+            let mut buckets: BTreeMap<u64, (f64, usize)> = BTreeMap::new();
+            for event in &history {
+                let event_interval = if event.interval_seconds > 0 {
+                    event.interval_seconds
+                } else {
+                    DEFAULT_FUNDING_INTERVAL_SECS
+                };
+                let rate =
+                    event.funding_rate_bps as f64 * interval_seconds as f64 / event_interval as f64;
+                let bucket = buckets
+                    .entry(event.timestamp / interval_seconds * interval_seconds)
+                    .or_default();
+                bucket.0 += rate;
+                bucket.1 += 1;
+            }
+
+            let mut samples = Vec::new();
+            if let (Some(&first), Some(&last)) = (buckets.keys().next(), buckets.keys().last()) {
+                let mut previous = 0.0;
+                for timestamp in (first..=last).step_by(interval_seconds as usize) {
+                    let sample = match buckets.get(&timestamp) {
+                        Some((sum, count)) => FundingSample {
+                            timestamp,
+                            rate_bps: sum / *count as f64,
+                            filled: false,
+                        },
+                        None => FundingSample {
+                            timestamp,
+                            rate_bps: previous,
+                            filled: true,
+                        },
+                    };
+                    previous = sample.rate_bps;
+                    samples.push(sample);
+                }
+            }
+
+            FundingSeries {
+                market_id: market_id.to_string(),
+                interval_seconds,
+                stats: stats(&samples),
+                samples,
+            }
+        })
+        .collect()
+}
+
+fn stats(samples: &[FundingSample]) -> FundingStats {
+    if samples.is_empty() {
+        return FundingStats::default();
+    }
+    let n = samples.len() as f64;
+    let mut rates: Vec<f64> = samples.iter().map(|s| s.rate_bps).collect();
+    let mean_bps = rates.iter().sum::<f64>() / n;
+    let variance = rates.iter().map(|r| (r - mean_bps).powi(2)).sum::<f64>() / n;
+
+    let (mut positive, mut negative) = (0usize, 0usize);
+    let mut stats = FundingStats {
+        mean_bps,
+        std_dev_bps: variance.sqrt(),
+        positive_fraction: rates.iter().filter(|r| **r > 0.0).count() as f64 / n,
+        ..Default::default()
+    };
+    for rate in &rates {
+        positive = if *rate > 0.0 { positive + 1 } else { 0 };
+        negative = if *rate < 0.0 { negative + 1 } else { 0 };
+        stats.longest_positive_streak = stats.longest_positive_streak.max(positive);
+        stats.longest_negative_streak = stats.longest_negative_streak.max(negative);
+    }
+    stats.current_streak = positive as i64 - negative as i64;
+
+    rates.sort_by(f64::total_cmp);
+    stats.min_bps = rates[0];
+    stats.max_bps = rates[rates.len() - 1];
+    stats.median_bps = percentile_of(&rates, 50.0).unwrap_or(0.0);
+    stats
+}
+
+/// Linear-interpolated percentile of sorted values.
+fn percentile_of(sorted: &[f64], p: f64) -> Option<f64> {
+    let last = sorted.len().checked_sub(1)?;
+    let rank = p.clamp(0.0, 100.0) / 100.0 * last as f64;
+    let (lower, upper) = (rank.floor() as usize, rank.ceil() as usize);
+    Some(sorted[lower] + (sorted[upper] - sorted[lower]) * (rank - lower as f64))
+}
+
+/// Collapse a chronological series of rates into a single projection.
+fn estimate_rate(rates: &[f64], estimator: RateEstimator) -> f64 {
+    match estimator {
+        RateEstimator::Last => rates.last().copied().unwrap_or(0.0),
+        RateEstimator::Mean => rates.iter().sum::<f64>() / rates.len().max(1) as f64,
+        RateEstimator::Ewma { half_life } => {
+            let decay = 0.5f64.powf(1.0 / half_life.max(f64::EPSILON));
+            let (mut weighted, mut total) = (0.0, 0.0);
+            let mut weight = 1.0;
+            for rate in rates.iter().rev() {
+                weighted += rate * weight;
+                total += weight;
+                weight *= decay;
+            }
+            if total > 0.0 {
+                weighted / total
+            } else {
+                0.0
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_market;
+
+    fn event(timestamp: u64, rate_bps: i64) -> FundingEvent {
+        FundingEvent {
+            market_id: "m".to_string(),
+            timestamp,
+            interval_seconds: 3600,
+            mark_price: 0,
+            index_price: 0,
+            premium_rate_bps: 0,
+            funding_rate_bps: rate_bps,
+            total_payment: "0".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_forecast_cost_mean() {
+        let position = PortfolioPosition {
+            market: test_market(0, 0),
+            position: serde_json::from_value(serde_json::json!({
+                "owner": "o", "market_id": "m", "base_position": "0",
+                "average_entry_price": "0", "mark_price": "0",
+                "realized_pnl": "0", "unrealized_pnl": "0",
+            }))
+            .unwrap(),
+            size: -10.0,
+            entry_price: 100.0,
+            mark_price: 100.0,
+            adl: None,
+        };
+        let history = [event(7200, 3), event(0, 1)];
+        let assumptions = FundingAssumptions {
+            estimator: RateEstimator::Mean,
+            ..Default::default()
+        };
+
+        let forecast = forecast_cost_with(
+            &position,
+            &history,
+            Duration::from_secs(86_400),
+            &assumptions,
+        )
+        .unwrap();
+
+        // Short 1000 USDC notional at +2 bps receives 0.2 USDC per hour
+        assert_eq!(forecast.rate_bps, 2.0);
+        assert!((forecast.cost_per_interval + 0.2).abs() < 1e-9);
+        assert!((forecast.total_cost + 4.8).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_resample_fills_gaps_and_counts_streaks() {
+        let mut other = event(0, -4);
+        other.market_id = "n".to_string();
+        // Hourly events resampled to 2h; the 4h bucket has no events
+        let history = [
+            event(0, 1),
+            event(3600, 3),
+            event(7200, 5),
+            event(21600, -1),
+            other,
+        ];
+
+        let series = resample(&history, Duration::from_secs(7200));
+        assert_eq!(series.len(), 2);
+        let m = &series[0];
+        let rates: Vec<f64> = m.samples.iter().map(|s| s.rate_bps).collect();
+        assert_eq!(rates, vec![4.0, 10.0, 10.0, -2.0]);
+        assert!(m.samples[2].filled);
+        assert_eq!(m.samples[3].timestamp, 21600);
+
+        assert_eq!(m.stats.mean_bps, 5.5);
+        assert_eq!(m.stats.median_bps, 7.0);
+        assert_eq!(m.stats.positive_fraction, 0.75);
+        assert_eq!(m.stats.longest_positive_streak, 3);
+        assert_eq!(m.stats.current_streak, -1);
+        assert_eq!(m.percentile(100.0), Some(10.0));
+        assert_eq!(series[1].stats.current_streak, -1);
+    }
+}
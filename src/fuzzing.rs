@@ -0,0 +1,239 @@
+//! Invariant checks for signing and payload encoding.
+//!
+//! Enabled with the `fuzzing` feature and driven by the `cargo fuzz`
+//! targets in `fuzz/`; the property tests below run the same checks over
+//! seeded random inputs on every `cargo test`. Each check panics when an
+//! invariant breaks. Not a stable API.
+//!
+//! Invariants covered:
+//! - a signed order always verifies against its own JSON intent, and stops
+//!   verifying when any signed field changes
+//! - intent bytes survive a Borsh → JSON → Borsh round trip unchanged
+//! - FRM payloads always parse back to the signed request
+//! - canonical decimal conversion is monotonic under every rounding mode
+
+use futures_util::FutureExt;
+use rust_decimal::prelude::FromPrimitive;
+use rust_decimal::Decimal;
+
+use crate::client::to_canonical_units;
+use crate::continuum::{order_transaction, parse_frm_payload};
+use crate::keypair::TradingKeypair;
+use crate::signing::{decode_intent, encode_intent, sign_perp_order, verify_order, OrderIntentDto};
+use crate::types::{MarginMode, PositionEffect, Pubkey, Rounding, Side};
+
+/// Inputs to `sign_perp_order`, with the keypair given as its secret key.
+#[derive(Debug, Clone)]
+pub struct OrderFields {
+    pub secret: [u8; 32],
+    pub order_id: u64,
+    pub side: Side,
+    pub price: u64,
+    pub quantity: u64,
+    pub expiry: u64,
+    pub base_mint: [u8; 32],
+    pub quote_mint: [u8; 32],
+    pub leverage: u64,
+    pub position_effect: PositionEffect,
+    pub margin_mode: MarginMode,
+    pub margin_amount: Option<u64>,
+    pub reduce_only: bool,
+    pub builder_code: Option<String>,
+}
+
+/// Sign `fields`, then check the signature, the intent encoding, and the FRM
+/// payload built from it.
+pub fn check_order(fields: &OrderFields) {
+    let keypair = TradingKeypair::from_base58_secret(&bs58::encode(fields.secret).into_string())
+        .expect("any 32 bytes are a valid secret key");
+    let mut signed = sign_perp_order(
+        &keypair,
+        fields.order_id,
+        fields.side,
+        fields.price,
+        fields.quantity,
+        fields.expiry,
+        &Pubkey::new_from_array(fields.base_mint),
+        &Pubkey::new_from_array(fields.quote_mint),
+        fields.leverage,
+        fields.position_effect,
+        fields.margin_mode,
+        fields.margin_amount,
+        fields.reduce_only,
+    )
+    .now_or_never()
+    .expect("keypair signing completes immediately")
+    .expect("signing never fails");
+    signed.request.builder_code = fields.builder_code.clone();
+
+    verify_order(&signed.request).expect("signature verifies against its own intent");
+    let mut tampered = signed.request.clone();
+    tampered.intent.price ^= 1;
+    assert!(
+        verify_order(&tampered).is_err(),
+        "signature still verifies after the price changed"
+    );
+
+    let bytes = encode_intent(&signed.request.intent).expect("signed intent encodes");
+    assert_eq!(
+        decode_intent(&bytes).expect("encoded intent decodes"),
+        signed.request.intent
+    );
+
+    let transaction = order_transaction(&signed).expect("transaction builds");
+    let payload = parse_frm_payload(&transaction.payload).expect("FRM payload parses");
+    let intent: OrderIntentDto =
+        serde_json::from_value(payload["intent"].clone()).expect("payload intent deserializes");
+    assert_eq!(intent, signed.request.intent);
+    assert_eq!(payload["signature"], signed.request.signature);
+    assert_eq!(
+        payload.get("builder_code").and_then(|c| c.as_str()),
+        fields.builder_code.as_deref()
+    );
+    assert_eq!(
+        hex::encode(&transaction.signature),
+        signed.request.signature
+    );
+    assert_eq!(transaction.nonce, fields.order_id);
+}
+
+/// Decode arbitrary bytes as intent bytes; anything that decodes must
+/// re-encode to the same bytes.
+pub fn check_intent_bytes(bytes: &[u8]) {
+    if let Ok(dto) = decode_intent(bytes) {
+        assert_eq!(
+            encode_intent(&dto).expect("decoded intent re-encodes"),
+            bytes
+        );
+    }
+}
+
+/// Parse arbitrary bytes as an FRM payload. Must return rather than panic.
+pub fn check_frm_payload(bytes: &[u8]) {
+    let _ = parse_frm_payload(bytes);
+}
+
+/// Canonical conversion never reorders values that both convert.
+pub fn check_canonical_monotonic(a: f64, b: f64, decimals: u8) {
+    let (Some(a), Some(b)) = (Decimal::from_f64(a), Decimal::from_f64(b)) else {
+        return;
+    };
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    for rounding in [Rounding::Down, Rounding::Nearest, Rounding::Up] {
+        if let (Ok(low_units), Ok(high_units)) = (
+            to_canonical_units(low, decimals, rounding),
+            to_canonical_units(high, decimals, rounding),
+        ) {
+            assert!(
+                low_units <= high_units,
+                "{} and {} reorder at {} decimals rounding {:?}",
+                low,
+                high,
+                decimals,
+                rounding
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+
+    const CASES: usize = 256;
+
+    /// Small values, boundaries, and anything in between.
+    fn arbitrary_u64(rng: &mut StdRng) -> u64 {
+        match rng.gen_range(0, 4) {
+            0 => [0, 1, u64::MAX - 1, u64::MAX][rng.gen_range(0, 4)],
+            1 => rng.gen_range(0, 1_000),
+            _ => rng.gen(),
+        }
+    }
+
+    fn arbitrary_order(rng: &mut StdRng) -> OrderFields {
+        OrderFields {
+            secret: rng.gen(),
+            order_id: arbitrary_u64(rng),
+            side: if rng.gen() { Side::Buy } else { Side::Sell },
+            price: arbitrary_u64(rng),
+            quantity: arbitrary_u64(rng),
+            expiry: arbitrary_u64(rng),
+            base_mint: rng.gen(),
+            quote_mint: rng.gen(),
+            leverage: arbitrary_u64(rng),
+            position_effect: if rng.gen() {
+                PositionEffect::Open
+            } else {
+                PositionEffect::Close
+            },
+            margin_mode: if rng.gen() {
+                MarginMode::Cross
+            } else {
+                MarginMode::Isolated
+            },
+            margin_amount: rng.gen::<bool>().then(|| arbitrary_u64(rng)),
+            reduce_only: rng.gen(),
+            builder_code: rng
+                .gen::<bool>()
+                .then(|| format!("code-\"{}\"\u{e9}", rng.gen::<u32>())),
+        }
+    }
+
+    #[test]
+    fn test_signing_and_payload_invariants() {
+        let mut rng = StdRng::seed_from_u64(0x5eed);
+        for _ in 0..CASES {
+            check_order(&arbitrary_order(&mut rng));
+
+            let len = rng.gen_range(0, 200);
+            let bytes: Vec<u8> = (0..len).map(|_| rng.gen()).collect();
+            check_intent_bytes(&bytes);
+            check_frm_payload(&bytes);
+            check_frm_payload(&[b"FRM_v1.0:".as_slice(), &bytes].concat());
+        }
+    }
+
+    #[test]
+    fn test_canonical_conversion_is_monotonic() {
+        let mut rng = StdRng::seed_from_u64(0xdec);
+        let specials = [
+            0.0,
+            -0.0,
+            -1.0,
+            1e-12,
+            0.1,
+            185.5,
+            1e19,
+            f64::MAX,
+            f64::INFINITY,
+        ];
+        for _ in 0..CASES * 16 {
+            let mut value = || match rng.gen_range(0, 3) {
+                0 => specials[rng.gen_range(0, specials.len())],
+                1 => rng.gen_range(0.0, 1_000.0),
+                _ => f64::from_bits(rng.gen()),
+            };
+            let (a, b) = (value(), value());
+            check_canonical_monotonic(a, b, rng.gen_range(0, 19));
+        }
+        let price = Decimal::new(29, 2);
+        assert_eq!(
+            to_canonical_units(price, 6, Rounding::Exact).unwrap(),
+            290_000
+        );
+        assert!(to_canonical_units(Decimal::new(2_900_001, 7), 6, Rounding::Exact).is_err());
+        assert_eq!(
+            to_canonical_units(Decimal::new(2_900_005, 7), 6, Rounding::Nearest).unwrap(),
+            290_001
+        );
+        assert_eq!(
+            to_canonical_units(Decimal::new(2_900_005, 7), 6, Rounding::Down).unwrap(),
+            290_000
+        );
+        assert!(to_canonical_units(Decimal::NEGATIVE_ONE, 6, Rounding::Nearest).is_err());
+        assert!(to_canonical_units(Decimal::from(u64::MAX), 6, Rounding::Nearest).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+//! Pre-trade hooks run on every outgoing order and cancel before signing.
+
+use rust_decimal::Decimal;
+use serde::{Deserialize, Serialize};
+
+use crate::error::{Result, SdkError};
+use crate::types::{lossy_decimal, ExchangeInfo, MarketInfo, PerpOrder};
+
+/// A pre-trade check that can inspect, modify, or reject outgoing requests.
+///
+/// Hooks run in registration order. Returning an error aborts the request
+/// before anything is signed or submitted.
+pub trait OrderHook: Send + Sync {
+    /// Called before a perp order is signed. The order may be modified in place.
+    fn before_order(&self, _market: &MarketInfo, _order: &mut PerpOrder) -> Result<()> {
+        Ok(())
+    }
+
+    /// Called before a cancel is signed.
+    fn before_cancel(&self, _market: &MarketInfo, _order_id: u64) -> Result<()> {
+        Ok(())
+    }
+}
+
+/// Static per-order limits enforced before signing.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct OrderLimits {
+    /// Maximum price * quantity in quote units
+    pub max_notional: Option<f64>,
+    pub max_quantity: Option<f64>,
+    pub max_leverage: Option<u64>,
+    /// Market UUIDs or names orders may be sent to (empty allows all)
+    pub allowed_markets: Vec<String>,
+}
+
+impl OrderHook for OrderLimits {
+    fn before_order(&self, market: &MarketInfo, order: &mut PerpOrder) -> Result<()> {
+        if !self.allowed_markets.is_empty()
+            && !self
+                .allowed_markets
+                .iter()
+                .any(|m| *m == market.uuid || *m == market.name)
+        {
+            return Err(SdkError::OrderRejected(format!(
+                "market {} is not allowed",
+                market.name
+            )));
+        }
+
+        if let Some(max) = self.max_leverage {
+            if order.leverage > max {
+                return Err(SdkError::OrderRejected(format!(
+                    "leverage {}x exceeds limit {}x",
+                    order.leverage, max
+                )));
+            }
+        }
+
+        if let Some(max) = self.max_quantity {
+            if order.quantity_f64() > max {
+                return Err(SdkError::OrderRejected(format!(
+                    "quantity {} exceeds limit {}",
+                    order.quantity, max
+                )));
+            }
+        }
+
+        if let Some(max) = self.max_notional {
+            let notional = order.price_f64() * order.quantity_f64();
+            if notional > max {
+                return Err(SdkError::OrderRejected(format!(
+                    "notional {:.2} exceeds limit {:.2}",
+                    notional, max
+                )));
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Rejects orders that break the exchange's published rules, so they fail
+/// locally instead of at the sequencer. Markets without rules pass.
+impl OrderHook for ExchangeInfo {
+    fn before_order(&self, market: &MarketInfo, order: &mut PerpOrder) -> Result<()> {
+        let Some(rules) = self.market(&market.uuid) else {
+            return Ok(());
+        };
+        let reject = |reason: String| Err(SdkError::OrderRejected(reason));
+
+        let notional = order.price_f64() * order.quantity_f64();
+        let tier_leverage = rules
+            .margin_tier(notional)
+            .map_or(rules.max_leverage, |t| t.max_leverage);
+        let max_leverage = rules.max_leverage.min(tier_leverage);
+        if order.leverage > max_leverage {
+            return reject(format!(
+                "leverage {}x exceeds {}x allowed in {}",
+                order.leverage, max_leverage, market.name
+            ));
+        }
+        if !is_multiple(order.price, rules.tick_size)? {
+            return reject(format!(
+                "price {} is not a multiple of tick size {}",
+                order.price, rules.tick_size
+            ));
+        }
+        if !is_multiple(order.quantity, rules.step_size)? {
+            return reject(format!(
+                "quantity {} is not a multiple of step size {}",
+                order.quantity, rules.step_size
+            ));
+        }
+        let quantity = order.quantity_f64();
+        if quantity < rules.min_quantity || rules.max_quantity.is_some_and(|max| quantity > max) {
+            return reject(format!(
+                "quantity {} is outside the allowed range in {}",
+                order.quantity, market.name
+            ));
+        }
+        if notional < rules.min_notional && !order.reduce_only {
+            return reject(format!(
+                "notional {:.2} is below minimum {:.2}",
+                notional, rules.min_notional
+            ));
+        }
+
+        Ok(())
+    }
+}
+
+fn is_multiple(value: Decimal, step: f64) -> Result<bool> {
+    let step = lossy_decimal(step)?;
+    Ok(step <= Decimal::ZERO || (value % step).is_zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_market;
+
+    #[test]
+    fn test_order_limits() {
+        let market = test_market(0, 0);
+        let limits = OrderLimits {
+            max_notional: Some(1000.0),
+            max_leverage: Some(10),
+            ..Default::default()
+        };
+
+        let mut order = PerpOrder {
+            price: Decimal::from(100),
+            quantity: Decimal::from(5),
+            leverage: 5,
+            ..Default::default()
+        };
+        assert!(limits.before_order(&market, &mut order).is_ok());
+
+        order.quantity = Decimal::from(20);
+        assert!(matches!(
+            limits.before_order(&market, &mut order),
+            Err(SdkError::OrderRejected(_))
+        ));
+    }
+
+    #[test]
+    fn test_exchange_rules() {
+        let market = test_market(0, 0);
+        let info: ExchangeInfo = serde_json::from_value(serde_json::json!({
+            "fees": { "maker_bps": -1.0, "taker_bps": 5.0 },
+            "markets": [{
+                "market_id": "m", "max_leverage": 20, "tick_size": 0.01, "step_size": 0.1,
+                "margin_tiers": [
+                    { "max_notional": 10000.0, "max_leverage": 20,
+                      "initial_margin_rate": 0.05, "maintenance_margin_rate": 0.025 },
+                    { "max_leverage": 5,
+                      "initial_margin_rate": 0.2, "maintenance_margin_rate": 0.1 }
+                ]
+            }]
+        }))
+        .unwrap();
+
+        let mut order = PerpOrder {
+            price: Decimal::new(10025, 2),
+            quantity: Decimal::new(25, 1),
+            leverage: 10,
+            ..Default::default()
+        };
+        assert!(info.before_order(&market, &mut order).is_ok());
+
+        // Off-tick price
+        order.price = Decimal::new(100255, 3);
+        assert!(info.before_order(&market, &mut order).is_err());
+
+        // Large notional falls in the 5x tier
+        order.price = Decimal::from(100);
+        order.quantity = Decimal::from(200);
+        assert!(info.before_order(&market, &mut order).is_err());
+        order.leverage = 5;
+        assert!(info.before_order(&market, &mut order).is_ok());
+    }
+}
@@ -4,16 +4,56 @@
 //!
 //! ## Features
 //!
-//! - Place and cancel perpetual orders via Continuum
-//! - Query market data, orderbooks, and positions
-//! - Multi-format keypair support (file, bytes, base58)
-//! - Human-readable price/quantity inputs with automatic decimal conversion
+//! - Place and cancel perpetual orders via Continuum, with GTC/GTT/IOC/FOK time in force,
+//!   concurrently from tasks sharing one client, or simulate them without submitting
+//! - Query market data, orderbooks, and positions, with or without a key, naming
+//!   markets by UUID or symbol
+//! - Multi-format keypair support (file, bytes, base58), or any hardware or
+//!   remote signer through the `OrderSigner` trait
+//! - Exact decimal price/quantity inputs with configurable rounding to market decimals
+//!   and lot sizes, checked against each market's minimum notional
+//! - Collateral deposits and withdrawals, with withdrawal settlement status
 //! - Testnet airdrop functionality
+//! - Margin health monitoring (`risk` module)
+//! - Funding analytics (`funding` module)
+//! - Lot-based realized PnL attribution (`pnl` module)
+//! - Managed orders such as pegs (`execution` module)
+//! - Client-side stop-loss and take-profit orders (`conditional` module)
+//! - Order lifecycle events with inferred fill detection (`tracker` module)
+//! - Local price-level books kept current from depth snapshots and updates
+//!   (`orderbook` module)
+//! - Price-level, move, and spread alerts (`alerts` module)
+//! - Two-market spread trading with leg rollback (`spread` module)
+//! - WebSocket orderbook, trade, and funding streams with automatic
+//!   reconnection (`stream` module)
+//! - Optional submission rate limiting with cancel-first priority, and
+//!   per-endpoint request limits that honour the node's Retry-After
+//! - Retries with exponential backoff for transient RPC and Continuum failures
+//! - Sequencer congestion monitoring and stall watchdog (`sequencer` module)
+//! - SQLite order/position persistence and trade-history sync (`store` module, `store` feature)
+//! - Startup reconciliation of the store against the server (`reconcile` module, `store` feature)
+//! - Event-logged strategy runs with deterministic replay (`replay` module)
+//! - Session snapshots for redeploying strategies, and trading sessions that
+//!   resubscribe after reconnects (`session` module)
+//! - On-disk market metadata cache for offline signing (`cache` module)
+//! - Hash-chained audit journal of signed submissions (`audit` module)
+//! - Scoped session keys with expiry and size limits (`delegation` module)
+//! - Place → verify → cancel smoke test for CI and onboarding (`diagnostics` module)
+//! - Fault injection for resilience testing (`chaos` module, `chaos` feature)
+//! - `FermiApi` trait over the client, with a programmable mock for strategy
+//!   unit tests (`testing` module, `testing` feature)
+//! - Backtesting over recorded depth, trades, and funding with simulated fills,
+//!   margin, and a PnL report (`backtest` module, `backtest` feature)
+//! - Scheduled recording of depth, trades, and funding to rotating JSONL, CSV,
+//!   or Parquet files (`recorder` module, Parquet behind the `parquet` feature)
 //!
 //! ## Quick Start
 //!
 //! ```rust,no_run
-//! use fermi_trade_sdk::{FermiClient, TradingKeypair, PerpOrder, Side, PositionEffect, MarginMode, ClientConfig};
+//! use fermi_trade_sdk::{
+//!     ClientConfig, Decimal, FermiClient, MarginMode, PerpOrder, PositionEffect, Side,
+//!     TradingKeypair,
+//! };
 //!
 //! #[tokio::main]
 //! async fn main() -> anyhow::Result<()> {
@@ -21,24 +61,24 @@
 //!     let keypair = TradingKeypair::from_file("./my_keypair.json")?;
 //!
 //!     // Initialize client (uses FERMI_CONTINUUM_ENDPOINT and FERMI_RPC_ENDPOINT env vars, or defaults)
-//!     let mut client = FermiClient::new(keypair, ClientConfig::default()).await?;
+//!     let client = FermiClient::new(keypair, ClientConfig::default()).await?;
 //!
 //!     // Airdrop testnet USDC
 //!     client.airdrop(1000.0).await?;
 //!
-//!     // Get markets
-//!     let markets = client.get_markets().await?;
-//!     let sol_perp = markets.iter().find(|m| m.name == "SOL-PERP").unwrap();
+//!     // Look up a market by symbol
+//!     let sol_perp = client.get_market_by_name("SOL-PERP").await?;
 //!
 //!     // Place a long position
 //!     let order = PerpOrder {
 //!         side: Side::Buy,
-//!         price: 185.50,
-//!         quantity: 1.0,
+//!         price: Decimal::new(18550, 2), // 185.50
+//!         quantity: Decimal::ONE,
 //!         leverage: 10,
 //!         position_effect: PositionEffect::Open,
 //!         margin_mode: MarginMode::Cross,
 //!         reduce_only: false,
+//!         ..Default::default()
 //!     };
 //!
 //!     let result = client.place_perp_order(&sol_perp.uuid, order).await?;
@@ -52,45 +92,101 @@
 mod client;
 mod continuum;
 mod error;
+mod hooks;
 mod keypair;
+mod nonce;
+mod retry;
 mod rpc;
+mod signer;
 mod signing;
+mod throttle;
 mod types;
 
+// Public utility modules
+pub mod alerts;
+pub mod api;
+pub mod audit;
+#[cfg(feature = "backtest")]
+pub mod backtest;
+pub mod cache;
+#[cfg(feature = "chaos")]
+pub mod chaos;
+pub mod conditional;
+pub mod delegation;
+pub mod diagnostics;
+pub mod execution;
+pub mod funding;
+#[cfg(any(test, feature = "fuzzing"))]
+#[doc(hidden)]
+pub mod fuzzing;
+pub mod orderbook;
+pub mod pnl;
+#[cfg(feature = "store")]
+pub mod reconcile;
+pub mod recorder;
+pub mod replay;
+pub mod risk;
+pub mod sequencer;
+pub mod session;
+pub mod spread;
+#[cfg(feature = "store")]
+pub mod store;
+pub mod stream;
+#[cfg(feature = "testing")]
+pub mod testing;
+pub mod tracker;
+
 // Re-export public API
-pub use client::{ClientConfig, FermiClient};
+pub use api::FermiApi;
+#[cfg(feature = "store")]
+pub use client::PendingResolution;
+pub use client::{
+    shutdown_signal, AccountGuard, AccountInit, ClientConfig, Confirmation, FermiClient,
+    KeyRotationEvent, OrderSimulation, RecoveredState, ShutdownPolicy, ShutdownReport,
+};
 pub use error::{Result, SdkError};
+pub use hooks::{OrderHook, OrderLimits};
 pub use keypair::TradingKeypair;
+pub use signer::OrderSigner;
+// Implement `OrderSigner` without depending on async-trait directly
+pub use async_trait::async_trait;
+pub use retry::RetryPolicy;
+pub use rpc::{HttpOptions, NodeStatus, RpcAuth};
+pub use throttle::{RateLimit, RequestLimiter, SubmissionPriority, SubmissionThrottle};
+// Enums
+pub use types::{
+    CollateralAction, MarginMode, MarketRef, OrderExpiry, OrderType, PositionEffect, Rounding,
+    Side, TimeInForce, WithdrawalState,
+};
+// Order types
+pub use types::{
+    CancelResult, CollateralResult, ImmediateFill, OrderResult, PerpOrder, PerpOrderBuilder,
+    ReplaceResult, TransferResult,
+};
+// Market types
 pub use types::{
-    // Enums
-    MarginMode,
-    PositionEffect,
-    Side,
-    // Order types
-    CancelResult,
-    OrderResult,
-    PerpOrder,
-    // Market types
-    Depth,
-    FundingEvent,
-    MarketInfo,
-    OpenOrder,
-    Orderbook,
-    OrderbookEntry,
-    Trade,
-    // Account types
-    AccountSummary,
-    Balances,
-    Position,
-    TokenBalance,
-    // Pubkey
-    Pubkey,
-    // Constants
-    SOL_MINT,
-    TESTNET_SOL,
-    TESTNET_USDC,
-    USDC_MINT,
+    AggTrade, Depth, DepthUpdate, ExchangeInfo, FeeEstimate, FeeSchedule, FeeTier, FundingEvent,
+    FundingQuery, HumanBookEntry, HumanOrderbook, IndexComposition, IndexSource, Kline,
+    LiquidationEvent, Liquidity, LiquidityAssumption, MarginTier, MarketInfo, MarketPrices,
+    MarketRules, OpenOrder, Orderbook, OrderbookEntry, OwnerBookStats, Page, Trade, TradesQuery,
+};
+// Account types
+pub use types::{
+    AccountSummary, AdlIndicator, Balances, FundingPayment, HumanPosition, InsuranceFund,
+    InsuranceFundSnapshot, Position, TokenBalance, Withdrawal,
+};
+// Pubkey
+pub use types::Pubkey;
+// Constants
+pub use types::{
+    known_token_decimals, lossy_decimal, DEFAULT_MAX_SLIPPAGE_BPS, SOL_MINT, TESTNET_SOL,
+    TESTNET_USDC, USDC_MINT,
 };
 
 // Re-export Continuum status for advanced users
-pub use continuum::SequencerStatus;
+pub use continuum::{
+    ConnectionState, GrpcCompression, GrpcOptions, SequencerStatus, TransactionStatus,
+};
+
+// Order prices and quantities
+pub use rust_decimal::Decimal;
@@ -0,0 +1,62 @@
+//! Order-ID / nonce generation.
+//!
+//! Continuum uses the order ID as the transaction nonce, so IDs must never
+//! repeat or go backwards for an account, including across restarts.
+
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// Monotonic order-ID generator seeded from the wall clock.
+///
+/// IDs track the current time in microseconds but always exceed every ID
+/// handed out or observed so far, so a clock step backwards or a restart
+/// that recovers a higher ID never produces a collision.
+#[derive(Debug, Default)]
+pub(crate) struct NonceManager {
+    last: AtomicU64,
+}
+
+impl NonceManager {
+    /// Next unused order ID.
+    pub(crate) fn next(&self) -> u64 {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_micros() as u64)
+            .unwrap_or(0);
+        let previous = self
+            .last
+            .fetch_update(Ordering::SeqCst, Ordering::SeqCst, |last| {
+                Some(now.max(last + 1))
+            })
+            .unwrap_or_else(|last| last);
+        now.max(previous + 1)
+    }
+
+    /// Record an ID already in use so later IDs are issued above it.
+    pub(crate) fn observe(&self, nonce: u64) {
+        self.last.fetch_max(nonce, Ordering::SeqCst);
+    }
+
+    /// Highest ID issued or observed.
+    pub(crate) fn last(&self) -> u64 {
+        self.last.load(Ordering::SeqCst)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_nonces_stay_above_observed() {
+        let nonces = NonceManager::default();
+        let first = nonces.next();
+        assert!(nonces.next() > first);
+
+        let future = first + 60_000_000_000;
+        nonces.observe(future);
+        assert_eq!(nonces.next(), future + 1);
+        assert_eq!(nonces.next(), future + 2);
+        assert_eq!(nonces.last(), future + 2);
+    }
+}
@@ -0,0 +1,248 @@
+//! Locally maintained price-level books.
+//!
+//! [`LocalBook`] starts from a [`Depth`] snapshot and folds in
+//! [`DepthUpdate`]s, or fresh snapshots, keeping both sides sorted by price
+//! with exact decimal levels. Updates must arrive in order: one that skips
+//! past the book's last update ID is refused, and the caller resyncs from a
+//! new snapshot.
+//!
+//! ```rust,ignore
+//! let mut book = LocalBook::from_snapshot(&client.get_depth(&market_id).await?)?;
+//! if book.apply_update(&update).is_err() {
+//!     book.apply_snapshot(&client.get_depth(&market_id).await?)?;
+//! }
+//! println!("mid {:?}, spread {:?} bps", book.mid_price(), book.spread_bps());
+//! ```
+
+use std::collections::BTreeMap;
+use std::str::FromStr;
+
+use rust_decimal::prelude::ToPrimitive;
+use rust_decimal::Decimal;
+
+use crate::error::{Result, SdkError};
+use crate::types::{lossy_decimal, Depth, DepthUpdate};
+
+/// One aggregated price level.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct DepthLevel {
+    pub price: Decimal,
+    pub quantity: Decimal,
+}
+
+/// Resting quantity on each side of the book within a band around the mid.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct BookLiquidity {
+    /// Base quantity bid at or above the band's lower edge
+    pub bids: Decimal,
+    /// Base quantity offered at or below the band's upper edge
+    pub asks: Decimal,
+}
+
+/// A price-level book kept current from depth snapshots and updates.
+#[derive(Debug, Clone, Default)]
+pub struct LocalBook {
+    bids: BTreeMap<Decimal, Decimal>,
+    asks: BTreeMap<Decimal, Decimal>,
+    last_update_id: u64,
+}
+
+impl LocalBook {
+    /// A book initialised from a depth snapshot.
+    pub fn from_snapshot(depth: &Depth) -> Result<Self> {
+        let mut book = Self::default();
+        book.apply_snapshot(depth)?;
+        Ok(book)
+    }
+
+    /// Replace the whole book with a snapshot.
+    pub fn apply_snapshot(&mut self, depth: &Depth) -> Result<()> {
+        let bids = parse_levels(&depth.bids)?;
+        let asks = parse_levels(&depth.asks)?;
+        self.bids.clear();
+        self.asks.clear();
+        set_levels(&mut self.bids, bids);
+        set_levels(&mut self.asks, asks);
+        self.last_update_id = depth.last_update_id;
+        Ok(())
+    }
+
+    /// Apply an incremental update. A level with zero quantity is removed.
+    ///
+    /// Updates already covered by the book are ignored. One that starts
+    /// after the next expected ID is rejected, leaving the book unchanged.
+    pub fn apply_update(&mut self, update: &DepthUpdate) -> Result<()> {
+        if update.last_update_id <= self.last_update_id {
+            return Ok(());
+        }
+        if update.first_update_id > self.last_update_id + 1 {
+            return Err(SdkError::InvalidOrderbook(format!(
+                "depth update {}..={} skips past {}",
+                update.first_update_id, update.last_update_id, self.last_update_id
+            )));
+        }
+        let bids = parse_levels(&update.bids)?;
+        let asks = parse_levels(&update.asks)?;
+        set_levels(&mut self.bids, bids);
+        set_levels(&mut self.asks, asks);
+        self.last_update_id = update.last_update_id;
+        Ok(())
+    }
+
+    /// ID of the last snapshot or update folded in.
+    pub fn last_update_id(&self) -> u64 {
+        self.last_update_id
+    }
+
+    /// Bid levels, best (highest) first.
+    pub fn bids(&self) -> impl Iterator<Item = DepthLevel> + '_ {
+        self.bids.iter().rev().map(level)
+    }
+
+    /// Ask levels, best (lowest) first.
+    pub fn asks(&self) -> impl Iterator<Item = DepthLevel> + '_ {
+        self.asks.iter().map(level)
+    }
+
+    pub fn best_bid(&self) -> Option<DepthLevel> {
+        self.bids().next()
+    }
+
+    pub fn best_ask(&self) -> Option<DepthLevel> {
+        self.asks().next()
+    }
+
+    /// Midpoint of the best bid and ask, if both sides are quoted.
+    pub fn mid_price(&self) -> Option<Decimal> {
+        let (bid, ask) = (self.best_bid()?, self.best_ask()?);
+        Some((bid.price + ask.price) / Decimal::TWO)
+    }
+
+    /// Best ask minus best bid, in basis points of the mid.
+    pub fn spread_bps(&self) -> Option<f64> {
+        let (bid, ask) = (self.best_bid()?, self.best_ask()?);
+        let mid = self.mid_price()?;
+        if mid.is_zero() {
+            return None;
+        }
+        ((ask.price - bid.price) / mid * Decimal::from(10_000)).to_f64()
+    }
+
+    /// Quantity resting within `bps` basis points of the mid on each side.
+    /// Empty if either side of the book is, or if `bps` is not finite.
+    pub fn liquidity_within_bps(&self, bps: f64) -> BookLiquidity {
+        let (Some(mid), Ok(fraction)) = (self.mid_price(), lossy_decimal(bps / 10_000.0)) else {
+            return BookLiquidity::default();
+        };
+        let band = mid * fraction;
+        let (low, high) = (mid - band, mid + band);
+        BookLiquidity {
+            bids: self.bids.range(low..).map(|(_, qty)| *qty).sum(),
+            asks: self.asks.range(..=high).map(|(_, qty)| *qty).sum(),
+        }
+    }
+}
+
+fn level((price, quantity): (&Decimal, &Decimal)) -> DepthLevel {
+    DepthLevel {
+        price: *price,
+        quantity: *quantity,
+    }
+}
+
+fn parse_levels(levels: &[[String; 2]]) -> Result<Vec<(Decimal, Decimal)>> {
+    levels
+        .iter()
+        .map(|[price, quantity]| {
+            let parse = |s: &str| {
+                Decimal::from_str(s)
+                    .map_err(|e| SdkError::InvalidOrderbook(format!("depth level {:?}: {}", s, e)))
+            };
+            Ok((parse(price)?, parse(quantity)?))
+        })
+        .collect()
+}
+
+fn set_levels(side: &mut BTreeMap<Decimal, Decimal>, levels: Vec<(Decimal, Decimal)>) {
+    for (price, quantity) in levels {
+        if quantity.is_zero() {
+            side.remove(&price);
+        } else {
+            side.insert(price, quantity);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn levels(levels: &[(&str, &str)]) -> Vec<[String; 2]> {
+        levels
+            .iter()
+            .map(|(p, q)| [p.to_string(), q.to_string()])
+            .collect()
+    }
+
+    #[test]
+    fn test_snapshot_then_updates() {
+        let mut book = LocalBook::from_snapshot(&Depth {
+            last_update_id: 10,
+            bids: levels(&[("99.5", "2"), ("100", "1")]),
+            asks: levels(&[("100.5", "3"), ("101", "4")]),
+        })
+        .unwrap();
+        assert_eq!(book.best_bid().unwrap().price, Decimal::new(100, 0));
+        assert_eq!(book.best_ask().unwrap().price, Decimal::new(1005, 1));
+        assert_eq!(book.mid_price(), Some(Decimal::new(10025, 2)));
+        assert!((book.spread_bps().unwrap() - 49.875).abs() < 1e-3);
+
+        // Already covered by the snapshot
+        let stale = DepthUpdate {
+            first_update_id: 5,
+            last_update_id: 10,
+            bids: levels(&[("100", "0")]),
+            asks: vec![],
+        };
+        book.apply_update(&stale).unwrap();
+        assert_eq!(book.best_bid().unwrap().price, Decimal::new(100, 0));
+
+        // Best bid pulled, a new ask level inside the old best
+        book.apply_update(&DepthUpdate {
+            first_update_id: 11,
+            last_update_id: 12,
+            bids: levels(&[("100", "0")]),
+            asks: levels(&[("100.25", "1.5")]),
+        })
+        .unwrap();
+        assert_eq!(book.last_update_id(), 12);
+        assert_eq!(book.best_bid().unwrap().price, Decimal::new(995, 1));
+        assert_eq!(
+            book.asks().map(|l| l.price).collect::<Vec<_>>(),
+            vec![
+                Decimal::new(10025, 2),
+                Decimal::new(1005, 1),
+                Decimal::new(101, 0)
+            ]
+        );
+
+        // Mid is 99.875; 100 bps is about 1.0 either side
+        let liquidity = book.liquidity_within_bps(100.0);
+        assert_eq!(liquidity.bids, Decimal::new(2, 0));
+        assert_eq!(liquidity.asks, Decimal::new(45, 1));
+
+        // A gap is refused and leaves the book as it was
+        let gap = DepthUpdate {
+            first_update_id: 14,
+            last_update_id: 15,
+            bids: levels(&[("99.9", "1")]),
+            asks: vec![],
+        };
+        assert!(matches!(
+            book.apply_update(&gap),
+            Err(SdkError::InvalidOrderbook(_))
+        ));
+        assert_eq!(book.last_update_id(), 12);
+        assert_eq!(book.best_bid().unwrap().price, Decimal::new(995, 1));
+    }
+}
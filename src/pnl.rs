@@ -0,0 +1,301 @@
+//! Fill-level PnL attribution.
+//!
+//! [`PnlEngine`] replays fills, fees and funding payments into per-market
+//! lots and attributes realized PnL to each closing fill. Lots are matched
+//! first in, first out or merged at average cost, and the totals can be
+//! checked against the server's `realized_pnl`.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::types::{FundingEvent, FundingPayment, MarketInfo, Position, Side, Trade};
+
+/// How closing fills are matched against open lots.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CostBasis {
+    /// Close the oldest lots first
+    #[default]
+    Fifo,
+    /// Keep one lot at the volume-weighted entry price
+    AverageCost,
+}
+
+/// Open quantity at one entry price.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Lot {
+    /// Signed size in human base units (positive = long)
+    pub quantity: f64,
+    pub price: f64,
+    pub opened_at: u64,
+}
+
+/// Realized PnL from one fill closing (part of) one or more lots.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RealizedFill {
+    pub market_id: String,
+    pub timestamp: u64,
+    /// Size closed, always positive
+    pub quantity: f64,
+    /// Weighted entry price of the closed lots
+    pub entry_price: f64,
+    pub exit_price: f64,
+    pub pnl: f64,
+}
+
+/// Engine PnL for one market compared with the server's figure.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PnlReconciliation {
+    pub market_id: String,
+    /// Trading PnL minus funding and fees paid
+    pub engine: f64,
+    /// Fees included in `engine`
+    pub fees: f64,
+    /// Server `realized_pnl` in human quote units
+    pub server: f64,
+    pub difference: f64,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Book {
+    lots: VecDeque<Lot>,
+    trading_pnl: f64,
+    funding_paid: f64,
+    fees_paid: f64,
+}
+
+impl Book {
+    fn size(&self) -> f64 {
+        self.lots.iter().map(|l| l.quantity).sum()
+    }
+}
+
+/// Lot-tracking PnL engine over any number of markets.
+#[derive(Debug, Clone, Default)]
+pub struct PnlEngine {
+    basis: CostBasis,
+    books: HashMap<String, Book>,
+    realized: Vec<RealizedFill>,
+}
+
+impl PnlEngine {
+    pub fn new(basis: CostBasis) -> Self {
+        Self {
+            basis,
+            ..Default::default()
+        }
+    }
+
+    /// Apply a fill in human units, returning the PnL it realized.
+    pub fn apply_fill(
+        &mut self,
+        market_id: &str,
+        side: Side,
+        price: f64,
+        quantity: f64,
+        timestamp: u64,
+    ) -> f64 {
+        let book = self.books.entry(market_id.to_string()).or_default();
+        let mut remaining = match side {
+            Side::Buy => quantity,
+            Side::Sell => -quantity,
+        };
+
+        let mut closed = 0.0;
+        let mut cost = 0.0;
+        let mut pnl = 0.0;
+        while remaining != 0.0 {
+            let Some(lot) = book.lots.front_mut() else {
+                break;
+            };
+            if lot.quantity.signum() == remaining.signum() {
+                break;
+            }
+            let take = remaining.abs().min(lot.quantity.abs());
+            let direction = lot.quantity.signum();
+            pnl += take * (price - lot.price) * direction;
+            closed += take;
+            cost += take * lot.price;
+            lot.quantity -= take * direction;
+            remaining += take * direction;
+            if lot.quantity.abs() <= f64::EPSILON {
+                book.lots.pop_front();
+            }
+        }
+
+        if remaining.abs() > f64::EPSILON {
+            match (self.basis, book.lots.front_mut()) {
+                (CostBasis::AverageCost, Some(lot)) => {
+                    let total = lot.quantity + remaining;
+                    lot.price = (lot.price * lot.quantity + price * remaining) / total;
+                    lot.quantity = total;
+                }
+                _ => book.lots.push_back(Lot {
+                    quantity: remaining,
+                    price,
+                    opened_at: timestamp,
+                }),
+            }
+        }
+
+        if closed > 0.0 {
+            book.trading_pnl += pnl;
+            self.realized.push(RealizedFill {
+                market_id: market_id.to_string(),
+                timestamp,
+                quantity: closed,
+                entry_price: cost / closed,
+                exit_price: price,
+                pnl,
+            });
+        }
+        pnl
+    }
+
+    /// Apply `owner`'s side of a trade in canonical units, including its fee
+    /// when the trade reports one. Trades `owner` was not part of are ignored.
+    pub fn apply_trade(&mut self, owner: &str, market: &MarketInfo, trade: &Trade) -> f64 {
+        let side = if trade.buyer_owner == owner {
+            Side::Buy
+        } else if trade.seller_owner == owner {
+            Side::Sell
+        } else {
+            return 0.0;
+        };
+        let quote_scale = 10f64.powi(market.quote_decimals as i32);
+        let price = trade.price as f64 / quote_scale;
+        let quantity = trade.quantity as f64 / 10f64.powi(market.base_decimals as i32);
+        if let Some(fee) = trade.fee_for(owner) {
+            self.apply_fee(&market.uuid, fee as f64 / quote_scale);
+        }
+        self.apply_fill(&market.uuid, side, price, quantity, trade.timestamp)
+    }
+
+    /// Record a trading fee in human quote units (negative = rebate).
+    pub fn apply_fee(&mut self, market_id: &str, fee: f64) {
+        self.books
+            .entry(market_id.to_string())
+            .or_default()
+            .fees_paid += fee;
+    }
+
+    /// Record a funding payment in human quote units (positive = paid).
+    pub fn apply_funding(&mut self, market_id: &str, payment: f64) {
+        self.books
+            .entry(market_id.to_string())
+            .or_default()
+            .funding_paid += payment;
+    }
+
+    /// Charge a market funding event against the current position, returning
+    /// the payment. Longs pay positive rates and shorts receive them.
+    pub fn apply_funding_event(&mut self, market: &MarketInfo, event: &FundingEvent) -> f64 {
+        let mark = event.mark_price as f64 / 10f64.powi(market.quote_decimals as i32);
+        let payment = self.position(&market.uuid) * mark * event.funding_rate_bps as f64 / 10_000.0;
+        self.apply_funding(&market.uuid, payment);
+        payment
+    }
+
+    /// Record an account funding payment as reported by the node, returning
+    /// it in human quote units.
+    pub fn apply_funding_payment(&mut self, market: &MarketInfo, payment: &FundingPayment) -> f64 {
+        let amount = payment.amount_f64(market);
+        self.apply_funding(&market.uuid, amount);
+        amount
+    }
+
+    /// Signed open size in `market_id`.
+    pub fn position(&self, market_id: &str) -> f64 {
+        self.books.get(market_id).map_or(0.0, Book::size)
+    }
+
+    /// Open lots in `market_id`, oldest first.
+    pub fn lots(&self, market_id: &str) -> Vec<Lot> {
+        self.books
+            .get(market_id)
+            .map(|b| b.lots.iter().cloned().collect())
+            .unwrap_or_default()
+    }
+
+    /// Realized trading PnL in `market_id`, before funding.
+    pub fn trading_pnl(&self, market_id: &str) -> f64 {
+        self.books.get(market_id).map_or(0.0, |b| b.trading_pnl)
+    }
+
+    /// Funding paid in `market_id` (negative = received).
+    pub fn funding_paid(&self, market_id: &str) -> f64 {
+        self.books.get(market_id).map_or(0.0, |b| b.funding_paid)
+    }
+
+    /// Trading fees paid in `market_id` (negative = net rebate).
+    pub fn fees_paid(&self, market_id: &str) -> f64 {
+        self.books.get(market_id).map_or(0.0, |b| b.fees_paid)
+    }
+
+    /// Realized trading PnL net of funding and fees in `market_id`.
+    pub fn realized_pnl(&self, market_id: &str) -> f64 {
+        self.trading_pnl(market_id) - self.funding_paid(market_id) - self.fees_paid(market_id)
+    }
+
+    /// Every closing fill in the order applied.
+    pub fn realized_fills(&self) -> &[RealizedFill] {
+        &self.realized
+    }
+
+    /// Compare engine PnL for `position`'s market with its `realized_pnl`.
+    ///
+    /// Returns `None` if the server figure cannot be parsed.
+    pub fn reconcile(&self, market: &MarketInfo, position: &Position) -> Option<PnlReconciliation> {
+        let server = position.realized_pnl.trim().parse::<f64>().ok()?
+            / 10f64.powi(market.quote_decimals as i32);
+        let engine = self.realized_pnl(&market.uuid);
+        Some(PnlReconciliation {
+            market_id: market.uuid.clone(),
+            engine,
+            fees: self.fees_paid(&market.uuid),
+            server,
+            difference: engine - server,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_fifo_and_average_cost_attribution() {
+        let fills = [
+            (Side::Buy, 100.0, 1.0),
+            (Side::Buy, 110.0, 1.0),
+            (Side::Sell, 120.0, 1.0),
+            // Flips the position short by 1
+            (Side::Sell, 130.0, 2.0),
+        ];
+        let run = |basis| {
+            let mut engine = PnlEngine::new(basis);
+            let realized: Vec<f64> = fills
+                .iter()
+                .enumerate()
+                .map(|(t, (side, price, qty))| {
+                    engine.apply_fill("m", *side, *price, *qty, t as u64)
+                })
+                .collect();
+            (engine, realized)
+        };
+
+        let (fifo, realized) = run(CostBasis::Fifo);
+        assert_eq!(realized, vec![0.0, 0.0, 20.0, 20.0]);
+        assert_eq!(fifo.position("m"), -1.0);
+        assert_eq!(fifo.lots("m")[0].price, 130.0);
+
+        let (mut avg, realized) = run(CostBasis::AverageCost);
+        assert_eq!(realized, vec![0.0, 0.0, 15.0, 25.0]);
+        assert_eq!(avg.realized_fills().len(), 2);
+        assert_eq!(avg.realized_fills()[1].entry_price, 105.0);
+
+        // Short position receives positive funding
+        avg.apply_funding("m", -0.5);
+        assert_eq!(avg.realized_pnl("m"), 40.5);
+        avg.apply_fee("m", 1.5);
+        assert_eq!(avg.realized_pnl("m"), 39.0);
+    }
+}
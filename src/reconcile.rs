@@ -0,0 +1,349 @@
+//! Reconciliation of the local order store against server state.
+//!
+//! Enabled with the `store` feature. After a restart or a dropped
+//! connection, the orders and positions the store believes in can drift from
+//! what the node reports. [`compare`] classifies the differences and
+//! `FermiClient::reconcile` can repair them.
+//!
+//! [`positions`] goes further and rebuilds each position from the synced
+//! trade and funding history, flagging breaks against the server's view.
+
+use std::collections::{HashMap, HashSet};
+
+use crate::error::Result;
+use crate::store::{OrderStore, StoredOrder};
+use crate::types::{CancelResult, FundingEvent, MarketInfo, OpenOrder, Position, Trade};
+
+/// A market whose stored position differs from the server's.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionMismatch {
+    pub market_id: String,
+    /// Stored `base_position`, if the store had one
+    pub local: Option<String>,
+    /// Server `base_position`, if the server reported one
+    pub server: Option<String>,
+}
+
+/// Differences between the local store and the server.
+#[derive(Debug, Clone, Default)]
+pub struct ReconciliationReport {
+    /// Orders open on both sides
+    pub matched_orders: usize,
+    /// Open on the server but not outstanding in the store
+    pub unknown_orders: Vec<OpenOrder>,
+    /// Outstanding in the store but no longer open on the server
+    pub missing_orders: Vec<StoredOrder>,
+    pub position_mismatches: Vec<PositionMismatch>,
+    /// Cancels sent for unknown orders during repair
+    pub cancelled: Vec<CancelResult>,
+}
+
+impl ReconciliationReport {
+    /// Whether the store and the server agreed before any repair.
+    pub fn is_clean(&self) -> bool {
+        self.unknown_orders.is_empty()
+            && self.missing_orders.is_empty()
+            && self.position_mismatches.is_empty()
+    }
+}
+
+/// Classify differences between stored and server orders and positions.
+///
+/// Positions absent on one side are treated as flat, so a closed position
+/// the server no longer lists is not a mismatch.
+pub fn compare(
+    local_orders: &[StoredOrder],
+    server_orders: &[OpenOrder],
+    local_positions: &[Position],
+    server_positions: &[Position],
+) -> ReconciliationReport {
+    let local_ids: HashSet<u64> = local_orders
+        .iter()
+        .filter(|o| o.status.is_outstanding())
+        .map(|o| o.order_id)
+        .collect();
+    let server_ids: HashSet<u64> = server_orders.iter().map(|o| o.order_id).collect();
+
+    let mut report = ReconciliationReport {
+        matched_orders: local_ids.intersection(&server_ids).count(),
+        unknown_orders: server_orders
+            .iter()
+            .filter(|o| !local_ids.contains(&o.order_id))
+            .cloned()
+            .collect(),
+        missing_orders: local_orders
+            .iter()
+            .filter(|o| o.status.is_outstanding() && !server_ids.contains(&o.order_id))
+            .cloned()
+            .collect(),
+        ..Default::default()
+    };
+
+    let local: HashMap<&str, &str> = local_positions
+        .iter()
+        .map(|p| (p.market_id.as_str(), p.base_position.as_str()))
+        .collect();
+    let server: HashMap<&str, &str> = server_positions
+        .iter()
+        .map(|p| (p.market_id.as_str(), p.base_position.as_str()))
+        .collect();
+
+    let mut markets: Vec<&str> = local.keys().chain(server.keys()).copied().collect();
+    markets.sort_unstable();
+    markets.dedup();
+    for market_id in markets {
+        let (l, s) = (local.get(market_id), server.get(market_id));
+        if size_of(l) != size_of(s) {
+            report.position_mismatches.push(PositionMismatch {
+                market_id: market_id.to_string(),
+                local: l.map(|v| v.to_string()),
+                server: s.map(|v| v.to_string()),
+            });
+        }
+    }
+
+    report
+}
+
+/// Position rebuilt from trade and funding history.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct ExpectedPosition {
+    /// Signed size in canonical base units (positive = long)
+    pub base_position: i128,
+    /// Funding paid by the position in human quote units (negative = received)
+    pub funding_paid: f64,
+}
+
+/// One market's rebuilt position checked against the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PositionCheck {
+    pub market_id: String,
+    pub expected: ExpectedPosition,
+    /// Server `base_position`, 0 if the server lists no position
+    pub server_base_position: i128,
+    /// Server `cumulative_funding` in human quote units, if reported
+    pub server_funding: Option<f64>,
+    /// Rebuilt and server sizes differ
+    pub size_break: bool,
+    /// Rebuilt and server funding differ by more than the tolerance
+    pub funding_break: bool,
+}
+
+impl PositionCheck {
+    pub fn is_break(&self) -> bool {
+        self.size_break || self.funding_break
+    }
+}
+
+/// Rebuild `owner`'s position in `market` from its trades and the market's
+/// funding events.
+///
+/// Each funding event charges the size held just before it at
+/// `size * mark * rate`, so longs pay positive rates and shorts receive them.
+pub fn expected_position(
+    owner: &str,
+    market: &MarketInfo,
+    trades: &[Trade],
+    funding: &[FundingEvent],
+) -> ExpectedPosition {
+    let mut fills: Vec<(u64, i128)> = trades
+        .iter()
+        .filter_map(|t| {
+            let quantity = t.quantity as i128;
+            if t.buyer_owner == owner {
+                Some((t.timestamp, quantity))
+            } else if t.seller_owner == owner {
+                Some((t.timestamp, -quantity))
+            } else {
+                None
+            }
+        })
+        .collect();
+    fills.sort_by_key(|(timestamp, _)| *timestamp);
+    let mut events: Vec<&FundingEvent> = funding.iter().collect();
+    events.sort_by_key(|e| e.timestamp);
+
+    let base_scale = 10f64.powi(market.base_decimals as i32);
+    let quote_scale = 10f64.powi(market.quote_decimals as i32);
+    let mut expected = ExpectedPosition::default();
+    let mut fills = fills.into_iter().peekable();
+    for event in events {
+        while let Some((_, delta)) = fills.next_if(|(t, _)| *t < event.timestamp) {
+            expected.base_position += delta;
+        }
+        let size = expected.base_position as f64 / base_scale;
+        let mark = event.mark_price as f64 / quote_scale;
+        expected.funding_paid += size * mark * event.funding_rate_bps as f64 / 10_000.0;
+    }
+    expected.base_position += fills.map(|(_, delta)| delta).sum::<i128>();
+    expected
+}
+
+/// Rebuild every position in `markets` from the store's synced history and
+/// flag breaks against `server_positions`.
+///
+/// The rebuilt figures only cover history synced into the store, so start
+/// syncing while flat or expect breaks for positions opened earlier.
+pub fn positions(
+    store: &OrderStore,
+    owner: &str,
+    markets: &[MarketInfo],
+    server_positions: &[Position],
+    funding_tolerance: f64,
+) -> Result<Vec<PositionCheck>> {
+    let mut checks = Vec::with_capacity(markets.len());
+    for market in markets {
+        let trades = store.trades(&market.uuid)?;
+        let funding = store.funding_events(&market.uuid)?;
+        let expected = expected_position(owner, market, &trades, &funding);
+
+        let server = server_positions.iter().find(|p| p.market_id == market.uuid);
+        let server_base_position = server
+            .and_then(|p| p.base_position.trim().parse().ok())
+            .unwrap_or(0);
+        let server_funding = server
+            .and_then(|p| p.cumulative_funding.as_deref())
+            .and_then(|f| f.trim().parse::<f64>().ok())
+            .map(|f| f / 10f64.powi(market.quote_decimals as i32));
+
+        checks.push(PositionCheck {
+            market_id: market.uuid.clone(),
+            expected,
+            server_base_position,
+            server_funding,
+            size_break: expected.base_position != server_base_position,
+            funding_break: server_funding
+                .is_some_and(|f| (f - expected.funding_paid).abs() > funding_tolerance),
+        });
+    }
+    Ok(checks)
+}
+
+fn size_of(base_position: Option<&&str>) -> f64 {
+    base_position
+        .and_then(|v| v.trim().parse().ok())
+        .unwrap_or(0.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::store::StoredOrderStatus;
+    use crate::types::{test_market, Side};
+
+    fn stored(order_id: u64, status: StoredOrderStatus) -> StoredOrder {
+        StoredOrder {
+            order_id,
+            market_id: "m".to_string(),
+            side: Side::Buy,
+            price: 1.0,
+            quantity: 1.0,
+            filled_quantity: 0.0,
+            leverage: 1,
+            reduce_only: false,
+            status,
+            tx_hash: None,
+            created_at: 0,
+            updated_at: 0,
+        }
+    }
+
+    fn open(order_id: u64) -> OpenOrder {
+        OpenOrder {
+            order_id,
+            market_id: "m".to_string(),
+            market_name: None,
+            owner: "me".to_string(),
+            side: "buy".to_string(),
+            price: 1,
+            quantity: 1,
+            expiry: 0,
+            timestamp: None,
+        }
+    }
+
+    fn position(market_id: &str, base_position: &str) -> Position {
+        Position {
+            owner: "me".to_string(),
+            market_id: market_id.to_string(),
+            market_name: None,
+            base_position: base_position.to_string(),
+            average_entry_price: "0".to_string(),
+            mark_price: "0".to_string(),
+            realized_pnl: "0".to_string(),
+            unrealized_pnl: "0".to_string(),
+            cumulative_funding: None,
+            margin_mode: None,
+            leverage: None,
+        }
+    }
+
+    #[test]
+    fn test_compare_classifies_discrepancies() {
+        let local_orders = [
+            stored(1, StoredOrderStatus::Acked),
+            stored(2, StoredOrderStatus::Acked),
+            stored(3, StoredOrderStatus::Cancelled),
+        ];
+        let server_orders = [open(1), open(3), open(4)];
+        let local_positions = [position("a", "100"), position("b", "0")];
+        let server_positions = [position("a", "150"), position("c", "0")];
+
+        let report = compare(
+            &local_orders,
+            &server_orders,
+            &local_positions,
+            &server_positions,
+        );
+
+        assert_eq!(report.matched_orders, 1);
+        let unknown: Vec<u64> = report.unknown_orders.iter().map(|o| o.order_id).collect();
+        assert_eq!(unknown, vec![3, 4]);
+        assert_eq!(report.missing_orders.len(), 1);
+        assert_eq!(report.missing_orders[0].order_id, 2);
+        // Flat positions on either side are not mismatches
+        assert_eq!(report.position_mismatches.len(), 1);
+        assert_eq!(report.position_mismatches[0].market_id, "a");
+        assert!(!report.is_clean());
+    }
+
+    #[test]
+    fn test_expected_position_applies_funding() {
+        let market = test_market(0, 2);
+        let trade = |buyer: &str, seller: &str, quantity, timestamp| Trade {
+            buyer_owner: buyer.to_string(),
+            seller_owner: seller.to_string(),
+            price: 10_000,
+            quantity,
+            timestamp,
+            base_mint: String::new(),
+            quote_mint: String::new(),
+            liquidated_owner: None,
+            buyer_fee: None,
+            seller_fee: None,
+        };
+        let funding = |timestamp, funding_rate_bps| FundingEvent {
+            market_id: "m".to_string(),
+            timestamp,
+            interval_seconds: 3600,
+            mark_price: 10_000,
+            index_price: 10_000,
+            premium_rate_bps: 0,
+            funding_rate_bps,
+            total_payment: "0".to_string(),
+        };
+
+        // Long 3 through a 10 bps event, then long 1 through a -20 bps event
+        let trades = [
+            trade("me", "x", 3, 10),
+            trade("x", "me", 2, 30),
+            trade("y", "x", 5, 30),
+        ];
+        let events = [funding(40, -20), funding(20, 10)];
+        let expected = expected_position("me", &market, &trades, &events);
+
+        assert_eq!(expected.base_position, 1);
+        // 3 * 100 * 0.001 - 1 * 100 * 0.002
+        assert!((expected.funding_paid - 0.1).abs() < 1e-9);
+    }
+}
@@ -0,0 +1,927 @@
+//! Periodic capture of market data to files.
+//!
+//! [`MarketRecorder`] polls orderbook depth, trades, and funding for a set
+//! of markets on a [`RecordSchedule`] and appends them to JSON Lines, CSV,
+//! or (with the `parquet` feature) Parquet files: one file per kind of data
+//! per rotation period, named like `trades-1760000400.csv`. Trades and
+//! funding events are written once each, however often they are polled.
+//!
+//! JSON Lines files hold one [`MarketEvent`] per line, which
+//! [`read_events`] loads back for the `backtest` module.
+//!
+//! ```rust,ignore
+//! let recorder = MarketRecorder::new(RecorderConfig {
+//!     markets: vec!["SOL-PERP".into()],
+//!     directory: "./market-data".into(),
+//!     ..Default::default()
+//! })?;
+//! let (mut stats, handle) = recorder.spawn(client.clone());
+//! while let Some(tick) = stats.recv().await {
+//!     println!("{:?}", tick);
+//! }
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::types::{Depth, FundingEvent, Trade};
+
+/// One recorded market data point.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    /// Full book snapshot, stamped with the time it was taken
+    Depth {
+        market_id: String,
+        timestamp: u64,
+        depth: Depth,
+    },
+    Trade {
+        market_id: String,
+        trade: Trade,
+    },
+    Funding(FundingEvent),
+}
+
+impl MarketEvent {
+    /// Unix seconds.
+    pub fn timestamp(&self) -> u64 {
+        match self {
+            MarketEvent::Depth { timestamp, .. } => *timestamp,
+            MarketEvent::Trade { trade, .. } => trade.timestamp,
+            MarketEvent::Funding(event) => event.timestamp,
+        }
+    }
+
+    pub fn market_id(&self) -> &str {
+        match self {
+            MarketEvent::Depth { market_id, .. } | MarketEvent::Trade { market_id, .. } => {
+                market_id
+            }
+            MarketEvent::Funding(event) => &event.market_id,
+        }
+    }
+
+    fn kind(&self) -> DataKind {
+        match self {
+            MarketEvent::Depth { .. } => DataKind::Depth,
+            MarketEvent::Trade { .. } => DataKind::Trades,
+            MarketEvent::Funding(_) => DataKind::Funding,
+        }
+    }
+}
+
+/// File format for recorded data.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum RecordFormat {
+    /// One JSON [`MarketEvent`] per line
+    #[default]
+    Jsonl,
+    /// One row per trade, funding event, or depth level, with a header
+    Csv,
+    /// The CSV columns in a Parquet file, one row group per poll
+    #[cfg(feature = "parquet")]
+    Parquet,
+}
+
+impl RecordFormat {
+    fn extension(self) -> &'static str {
+        match self {
+            RecordFormat::Jsonl => "jsonl",
+            RecordFormat::Csv => "csv",
+            #[cfg(feature = "parquet")]
+            RecordFormat::Parquet => "parquet",
+        }
+    }
+}
+
+/// When a new set of files is started.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rotation {
+    /// Append to one file per kind of data
+    Never,
+    /// New files at the start of each UTC hour
+    #[default]
+    Hourly,
+    /// New files at the start of each UTC day
+    Daily,
+}
+
+impl Rotation {
+    /// Start of the period containing `now` (unix seconds), if rotating.
+    fn period(self, now: u64) -> Option<u64> {
+        let length = match self {
+            Rotation::Never => return None,
+            Rotation::Hourly => 3_600,
+            Rotation::Daily => 86_400,
+        };
+        Some(now - now % length)
+    }
+}
+
+/// How often each kind of data is polled; `None` skips it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecordSchedule {
+    pub depth: Option<Duration>,
+    pub trades: Option<Duration>,
+    pub funding: Option<Duration>,
+}
+
+impl Default for RecordSchedule {
+    fn default() -> Self {
+        Self {
+            depth: Some(Duration::from_secs(5)),
+            trades: Some(Duration::from_secs(5)),
+            funding: Some(Duration::from_secs(60)),
+        }
+    }
+}
+
+impl RecordSchedule {
+    fn interval(&self, kind: DataKind) -> Option<Duration> {
+        match kind {
+            DataKind::Depth => self.depth,
+            DataKind::Trades => self.trades,
+            DataKind::Funding => self.funding,
+        }
+    }
+
+    /// Shortest interval scheduled, the rate `spawn` polls at.
+    fn tick(&self) -> Duration {
+        [self.depth, self.trades, self.funding]
+            .into_iter()
+            .flatten()
+            .min()
+            .unwrap_or(Duration::from_secs(60))
+    }
+}
+
+/// Configuration for a [`MarketRecorder`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecorderConfig {
+    /// Markets to record, by UUID or symbol
+    pub markets: Vec<String>,
+    /// Directory the files are written to, created if missing
+    pub directory: PathBuf,
+    pub format: RecordFormat,
+    pub schedule: RecordSchedule,
+    pub rotation: Rotation,
+}
+
+impl Default for RecorderConfig {
+    fn default() -> Self {
+        Self {
+            markets: vec![],
+            directory: PathBuf::from("market-data"),
+            format: RecordFormat::default(),
+            schedule: RecordSchedule::default(),
+            rotation: Rotation::default(),
+        }
+    }
+}
+
+/// What one call to [`MarketRecorder::record`] wrote.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RecordStats {
+    pub depth_snapshots: usize,
+    pub trades: usize,
+    pub funding_events: usize,
+    /// Fetches that failed and were skipped until the next poll
+    pub failures: usize,
+}
+
+impl RecordStats {
+    pub fn is_empty(&self) -> bool {
+        *self == Self::default()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum DataKind {
+    Depth,
+    Trades,
+    Funding,
+}
+
+impl DataKind {
+    const ALL: [DataKind; 3] = [DataKind::Depth, DataKind::Trades, DataKind::Funding];
+
+    fn name(self) -> &'static str {
+        match self {
+            DataKind::Depth => "depth",
+            DataKind::Trades => "trades",
+            DataKind::Funding => "funding",
+        }
+    }
+
+    fn columns(self) -> &'static [(&'static str, ColumnType)] {
+        use ColumnType::{Int, Text};
+        match self {
+            DataKind::Depth => &[
+                ("timestamp", Int),
+                ("market_id", Text),
+                ("side", Text),
+                ("level", Int),
+                ("price", Text),
+                ("quantity", Text),
+            ],
+            DataKind::Trades => &[
+                ("timestamp", Int),
+                ("market_id", Text),
+                ("price", Int),
+                ("quantity", Int),
+                ("buyer_owner", Text),
+                ("seller_owner", Text),
+                ("liquidated_owner", Text),
+                ("buyer_fee", Int),
+                ("seller_fee", Int),
+            ],
+            DataKind::Funding => &[
+                ("timestamp", Int),
+                ("market_id", Text),
+                ("interval_seconds", Int),
+                ("mark_price", Int),
+                ("index_price", Int),
+                ("premium_rate_bps", Int),
+                ("funding_rate_bps", Int),
+                ("total_payment", Text),
+            ],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ColumnType {
+    Int,
+    Text,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Int(i64),
+    Text(String),
+    Null,
+}
+
+impl From<u64> for Cell {
+    fn from(value: u64) -> Self {
+        Cell::Int(value as i64)
+    }
+}
+
+impl From<i64> for Cell {
+    fn from(value: i64) -> Self {
+        Cell::Int(value)
+    }
+}
+
+impl From<&str> for Cell {
+    fn from(value: &str) -> Self {
+        Cell::Text(value.to_string())
+    }
+}
+
+impl<T: Into<Cell>> From<Option<T>> for Cell {
+    fn from(value: Option<T>) -> Self {
+        value.map_or(Cell::Null, Into::into)
+    }
+}
+
+/// Table rows for `event`, in the columns of its kind.
+fn rows(event: &MarketEvent) -> Vec<Vec<Cell>> {
+    match event {
+        MarketEvent::Depth {
+            market_id,
+            timestamp,
+            depth,
+        } => [("bid", &depth.bids), ("ask", &depth.asks)]
+            .into_iter()
+            .flat_map(|(side, levels)| {
+                levels.iter().enumerate().map(move |(level, [price, qty])| {
+                    vec![
+                        (*timestamp).into(),
+                        market_id.as_str().into(),
+                        side.into(),
+                        (level as u64).into(),
+                        price.as_str().into(),
+                        qty.as_str().into(),
+                    ]
+                })
+            })
+            .collect(),
+        MarketEvent::Trade { market_id, trade } => vec![vec![
+            trade.timestamp.into(),
+            market_id.as_str().into(),
+            trade.price.into(),
+            trade.quantity.into(),
+            trade.buyer_owner.as_str().into(),
+            trade.seller_owner.as_str().into(),
+            trade.liquidated_owner.as_deref().into(),
+            trade.buyer_fee.into(),
+            trade.seller_fee.into(),
+        ]],
+        MarketEvent::Funding(event) => vec![vec![
+            event.timestamp.into(),
+            event.market_id.as_str().into(),
+            event.interval_seconds.into(),
+            event.mark_price.into(),
+            event.index_price.into(),
+            event.premium_rate_bps.into(),
+            event.funding_rate_bps.into(),
+            event.total_payment.as_str().into(),
+        ]],
+    }
+}
+
+fn csv_field(cell: &Cell) -> String {
+    match cell {
+        Cell::Int(value) => value.to_string(),
+        Cell::Null => String::new(),
+        Cell::Text(text) if text.contains([',', '"', '\n', '\r']) => {
+            format!("\"{}\"", text.replace('"', "\"\""))
+        }
+        Cell::Text(text) => text.clone(),
+    }
+}
+
+enum Sink {
+    Jsonl(BufWriter<File>),
+    Csv(BufWriter<File>),
+    #[cfg(feature = "parquet")]
+    Parquet(parquet::file::writer::SerializedFileWriter<File>),
+}
+
+impl Sink {
+    fn open(format: RecordFormat, kind: DataKind, path: &Path) -> Result<Self> {
+        let append = |path: &Path| {
+            OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(path)
+                .map_err(io_error)
+        };
+        match format {
+            RecordFormat::Jsonl => Ok(Sink::Jsonl(BufWriter::new(append(path)?))),
+            RecordFormat::Csv => {
+                let file = append(path)?;
+                let fresh = file.metadata().map_err(io_error)?.len() == 0;
+                let mut writer = BufWriter::new(file);
+                if fresh {
+                    let header: Vec<&str> = kind.columns().iter().map(|(name, _)| *name).collect();
+                    writeln!(writer, "{}", header.join(",")).map_err(io_error)?;
+                }
+                Ok(Sink::Csv(writer))
+            }
+            #[cfg(feature = "parquet")]
+            RecordFormat::Parquet => parquet_sink::open(kind, path),
+        }
+    }
+
+    #[cfg_attr(not(feature = "parquet"), allow(unused_variables))]
+    fn write(&mut self, kind: DataKind, events: &[&MarketEvent]) -> Result<()> {
+        match self {
+            Sink::Jsonl(writer) => {
+                for event in events {
+                    serde_json::to_writer(&mut *writer, event)?;
+                    writeln!(writer).map_err(io_error)?;
+                }
+                writer.flush().map_err(io_error)
+            }
+            Sink::Csv(writer) => {
+                for row in events.iter().flat_map(|event| rows(event)) {
+                    let fields: Vec<String> = row.iter().map(csv_field).collect();
+                    writeln!(writer, "{}", fields.join(",")).map_err(io_error)?;
+                }
+                writer.flush().map_err(io_error)
+            }
+            #[cfg(feature = "parquet")]
+            Sink::Parquet(writer) => {
+                let rows: Vec<Vec<Cell>> = events.iter().flat_map(|event| rows(event)).collect();
+                parquet_sink::write(writer, kind, &rows)
+            }
+        }
+    }
+
+    fn close(self) -> Result<()> {
+        match self {
+            Sink::Jsonl(mut writer) | Sink::Csv(mut writer) => writer.flush().map_err(io_error),
+            #[cfg(feature = "parquet")]
+            Sink::Parquet(writer) => writer.close().map(|_| ()).map_err(parquet_sink::error),
+        }
+    }
+}
+
+#[cfg(feature = "parquet")]
+mod parquet_sink {
+    use std::fs::File;
+    use std::path::Path;
+    use std::sync::Arc;
+
+    use parquet::data_type::{ByteArray, ByteArrayType, Int64Type};
+    use parquet::file::properties::WriterProperties;
+    use parquet::file::writer::SerializedFileWriter;
+    use parquet::schema::parser::parse_message_type;
+
+    use super::{io_error, Cell, ColumnType, DataKind, Sink};
+    use crate::error::{Result, SdkError};
+
+    pub(super) fn error(err: parquet::errors::ParquetError) -> SdkError {
+        SdkError::Recorder(err.to_string())
+    }
+
+    /// Parquet files cannot be appended to, so an existing file gets a
+    /// numbered sibling.
+    pub(super) fn open(kind: DataKind, path: &Path) -> Result<Sink> {
+        let mut path = path.to_path_buf();
+        let stem = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let mut n = 1;
+        while path.exists() {
+            path.set_file_name(format!("{}.{}.parquet", stem, n));
+            n += 1;
+        }
+
+        let fields: Vec<String> = kind
+            .columns()
+            .iter()
+            .map(|(name, column)| match column {
+                ColumnType::Int => format!("OPTIONAL INT64 {};", name),
+                ColumnType::Text => format!("OPTIONAL BYTE_ARRAY {} (UTF8);", name),
+            })
+            .collect();
+        let schema = parse_message_type(&format!(
+            "message {} {{ {} }}",
+            kind.name(),
+            fields.join(" ")
+        ))
+        .map_err(error)?;
+        let file = File::create(&path).map_err(io_error)?;
+        let writer = SerializedFileWriter::new(
+            file,
+            Arc::new(schema),
+            Arc::new(WriterProperties::builder().build()),
+        )
+        .map_err(error)?;
+        Ok(Sink::Parquet(writer))
+    }
+
+    /// Write `rows` as one row group.
+    pub(super) fn write(
+        writer: &mut SerializedFileWriter<File>,
+        kind: DataKind,
+        rows: &[Vec<Cell>],
+    ) -> Result<()> {
+        let mut row_group = writer.next_row_group().map_err(error)?;
+        for (index, (name, column)) in kind.columns().iter().enumerate() {
+            let mut column_writer = row_group
+                .next_column()
+                .map_err(error)?
+                .ok_or_else(|| SdkError::Recorder(format!("no parquet column {}", name)))?;
+            let cells = rows.iter().map(|row| &row[index]);
+            let levels: Vec<i16> = cells.clone().map(|c| (*c != Cell::Null) as i16).collect();
+            match column {
+                ColumnType::Int => {
+                    let values: Vec<i64> = cells
+                        .filter_map(|c| match c {
+                            Cell::Int(value) => Some(*value),
+                            _ => None,
+                        })
+                        .collect();
+                    column_writer
+                        .typed::<Int64Type>()
+                        .write_batch(&values, Some(&levels), None)
+                        .map_err(error)?;
+                }
+                ColumnType::Text => {
+                    let values: Vec<ByteArray> = cells
+                        .filter_map(|c| match c {
+                            Cell::Text(text) => Some(ByteArray::from(text.as_str())),
+                            _ => None,
+                        })
+                        .collect();
+                    column_writer
+                        .typed::<ByteArrayType>()
+                        .write_batch(&values, Some(&levels), None)
+                        .map_err(error)?;
+                }
+            }
+            column_writer.close().map_err(error)?;
+        }
+        row_group.close().map_err(error)?;
+        Ok(())
+    }
+}
+
+/// Trades and funding already written for one market.
+#[derive(Debug, Default)]
+struct Cursor {
+    /// Latest trade timestamp written, with the trades written at it
+    last_trade: Option<(u64, HashSet<TradeKey>)>,
+    last_funding: Option<u64>,
+}
+
+type TradeKey = (u64, u64, String, String);
+
+fn trade_key(trade: &Trade) -> TradeKey {
+    (
+        trade.price,
+        trade.quantity,
+        trade.buyer_owner.clone(),
+        trade.seller_owner.clone(),
+    )
+}
+
+impl Cursor {
+    /// The trades in `trades` not written before, oldest first.
+    fn new_trades(&mut self, mut trades: Vec<Trade>) -> Vec<Trade> {
+        trades.sort_by_key(|t| t.timestamp);
+        let fresh: Vec<Trade> = trades
+            .into_iter()
+            .filter(|t| match &self.last_trade {
+                Some((last, seen)) => {
+                    t.timestamp > *last || (t.timestamp == *last && !seen.contains(&trade_key(t)))
+                }
+                None => true,
+            })
+            .collect();
+        for trade in &fresh {
+            match &mut self.last_trade {
+                Some((last, seen)) if *last == trade.timestamp => {
+                    seen.insert(trade_key(trade));
+                }
+                _ => {
+                    self.last_trade = Some((trade.timestamp, HashSet::from([trade_key(trade)])));
+                }
+            }
+        }
+        fresh
+    }
+
+    fn new_funding(&mut self, funding: Vec<FundingEvent>) -> Vec<FundingEvent> {
+        let since = self.last_funding;
+        let mut fresh: Vec<FundingEvent> = funding
+            .into_iter()
+            .filter(|e| since.is_none_or(|since| e.timestamp > since))
+            .collect();
+        fresh.sort_by_key(|e| e.timestamp);
+        if let Some(last) = fresh.last() {
+            self.last_funding = Some(last.timestamp);
+        }
+        fresh
+    }
+}
+
+/// Polls market data on a schedule and writes it to rotating files.
+pub struct MarketRecorder {
+    config: RecorderConfig,
+    /// Configured market name or UUID to UUID
+    market_ids: HashMap<String, String>,
+    cursors: HashMap<String, Cursor>,
+    last_polled: HashMap<DataKind, Instant>,
+    sinks: HashMap<DataKind, (Option<u64>, Sink)>,
+}
+
+impl MarketRecorder {
+    /// A recorder writing under `config.directory`, which is created if missing.
+    pub fn new(config: RecorderConfig) -> Result<Self> {
+        std::fs::create_dir_all(&config.directory).map_err(io_error)?;
+        Ok(Self {
+            config,
+            market_ids: HashMap::new(),
+            cursors: HashMap::new(),
+            last_polled: HashMap::new(),
+            sinks: HashMap::new(),
+        })
+    }
+
+    /// Poll every kind of data that is due and append what is new.
+    ///
+    /// Failed fetches are logged and counted in the stats; only errors
+    /// writing the files are returned.
+    pub async fn record(&mut self, client: &FermiClient) -> Result<RecordStats> {
+        let mut stats = RecordStats::default();
+        let now = Instant::now();
+        let due: Vec<DataKind> = DataKind::ALL
+            .into_iter()
+            .filter(|kind| {
+                let Some(interval) = self.config.schedule.interval(*kind) else {
+                    return false;
+                };
+                // Allow for ticks landing slightly early
+                self.last_polled
+                    .get(kind)
+                    .is_none_or(|last| now.duration_since(*last) >= interval.mul_f64(0.9))
+            })
+            .collect();
+        if due.is_empty() {
+            return Ok(stats);
+        }
+        for kind in &due {
+            self.last_polled.insert(*kind, now);
+        }
+
+        let mut events = Vec::new();
+        for market in self.config.markets.clone() {
+            let market_id = match self.market_id(client, &market).await {
+                Ok(id) => id,
+                Err(e) => {
+                    warn!("Recorder could not resolve market {}: {}", market, e);
+                    stats.failures += 1;
+                    continue;
+                }
+            };
+            for kind in &due {
+                match self.fetch(client, *kind, &market_id).await {
+                    Ok(fetched) => events.extend(fetched),
+                    Err(e) => {
+                        warn!(
+                            "Recorder failed to fetch {} for {}: {}",
+                            kind.name(),
+                            market,
+                            e
+                        );
+                        stats.failures += 1;
+                    }
+                }
+            }
+        }
+
+        for event in &events {
+            match event {
+                MarketEvent::Depth { .. } => stats.depth_snapshots += 1,
+                MarketEvent::Trade { .. } => stats.trades += 1,
+                MarketEvent::Funding(_) => stats.funding_events += 1,
+            }
+        }
+        self.write(&events, unix_secs())?;
+        Ok(stats)
+    }
+
+    /// Close the open files, completing any Parquet footers.
+    pub fn finish(&mut self) -> Result<()> {
+        for (_, (_, sink)) in self.sinks.drain() {
+            sink.close()?;
+        }
+        Ok(())
+    }
+
+    /// Spawn a task recording at the schedule's shortest interval.
+    ///
+    /// The stats of each poll that wrote anything or hit a failure are sent
+    /// on the returned channel. The task stops, closing the files, when the
+    /// returned receiver is dropped.
+    pub fn spawn(
+        mut self,
+        client: Arc<FermiClient>,
+    ) -> (mpsc::UnboundedReceiver<RecordStats>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(self.config.schedule.tick());
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                let stats = match self.record(&client).await {
+                    Ok(stats) => stats,
+                    Err(e) => {
+                        warn!("Recorder failed to write: {}", e);
+                        continue;
+                    }
+                };
+                if !stats.is_empty() && tx.send(stats).is_err() {
+                    break;
+                }
+            }
+            if let Err(e) = self.finish() {
+                warn!("Recorder failed to close its files: {}", e);
+            }
+        });
+        (rx, handle)
+    }
+
+    async fn market_id(&mut self, client: &FermiClient, market: &str) -> Result<String> {
+        if let Some(id) = self.market_ids.get(market) {
+            return Ok(id.clone());
+        }
+        let id = client.get_market(market).await?.uuid;
+        self.market_ids.insert(market.to_string(), id.clone());
+        Ok(id)
+    }
+
+    async fn fetch(
+        &mut self,
+        client: &FermiClient,
+        kind: DataKind,
+        market_id: &str,
+    ) -> Result<Vec<MarketEvent>> {
+        let cursor = self.cursors.entry(market_id.to_string()).or_default();
+        Ok(match kind {
+            DataKind::Depth => vec![MarketEvent::Depth {
+                market_id: market_id.to_string(),
+                timestamp: unix_secs(),
+                depth: client.get_depth(market_id).await?,
+            }],
+            DataKind::Trades => cursor
+                .new_trades(client.get_trades(market_id).await?)
+                .into_iter()
+                .map(|trade| MarketEvent::Trade {
+                    market_id: market_id.to_string(),
+                    trade,
+                })
+                .collect(),
+            DataKind::Funding => cursor
+                .new_funding(client.get_funding(market_id).await?)
+                .into_iter()
+                .map(MarketEvent::Funding)
+                .collect(),
+        })
+    }
+
+    /// Append `events` to the files for the rotation period containing `now`.
+    fn write(&mut self, events: &[MarketEvent], now: u64) -> Result<()> {
+        let period = self.config.rotation.period(now);
+        for kind in DataKind::ALL {
+            let batch: Vec<&MarketEvent> = events.iter().filter(|e| e.kind() == kind).collect();
+            if batch.is_empty() {
+                continue;
+            }
+            if self.sinks.get(&kind).is_some_and(|(p, _)| *p != period) {
+                if let Some((_, sink)) = self.sinks.remove(&kind) {
+                    sink.close()?;
+                }
+            }
+            if !self.sinks.contains_key(&kind) {
+                let sink = Sink::open(self.config.format, kind, &self.path(kind, period))?;
+                self.sinks.insert(kind, (period, sink));
+            }
+            if let Some((_, sink)) = self.sinks.get_mut(&kind) {
+                sink.write(kind, &batch)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn path(&self, kind: DataKind, period: Option<u64>) -> PathBuf {
+        let name = match period {
+            Some(start) => format!("{}-{}", kind.name(), start),
+            None => kind.name().to_string(),
+        };
+        self.config
+            .directory
+            .join(format!("{}.{}", name, self.config.format.extension()))
+    }
+}
+
+impl Drop for MarketRecorder {
+    fn drop(&mut self) {
+        if let Err(e) = self.finish() {
+            warn!("Recorder failed to close its files: {}", e);
+        }
+    }
+}
+
+/// Read the events from a JSON Lines file written by a [`MarketRecorder`].
+pub fn read_events(path: impl AsRef<Path>) -> Result<Vec<MarketEvent>> {
+    let file = File::open(path.as_ref()).map_err(io_error)?;
+    let mut events = Vec::new();
+    for (index, line) in BufReader::new(file).lines().enumerate() {
+        let line = line.map_err(io_error)?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        events.push(
+            serde_json::from_str(&line)
+                .map_err(|e| SdkError::Recorder(format!("line {}: {}", index + 1, e)))?,
+        );
+    }
+    Ok(events)
+}
+
+// std::io::Error converts to a keypair error by default
+fn io_error(err: std::io::Error) -> SdkError {
+    SdkError::Recorder(err.to_string())
+}
+
+fn unix_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trade(timestamp: u64, price: u64) -> Trade {
+        Trade {
+            buyer_owner: "a".into(),
+            seller_owner: "b, c".into(),
+            price,
+            quantity: 1_000,
+            timestamp,
+            base_mint: String::new(),
+            quote_mint: String::new(),
+            liquidated_owner: None,
+            buyer_fee: Some(-1),
+            seller_fee: None,
+        }
+    }
+
+    #[test]
+    fn test_writes_rotates_and_reads_back() {
+        let directory = std::env::temp_dir().join(format!(
+            "fermi-recorder-{}-{}",
+            std::process::id(),
+            SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .unwrap()
+                .as_nanos()
+        ));
+
+        // Trades seen on one poll are not written again on the next
+        let mut cursor = Cursor::default();
+        assert_eq!(cursor.new_trades(vec![trade(10, 1), trade(11, 2)]).len(), 2);
+        let again = cursor.new_trades(vec![trade(11, 2), trade(11, 3), trade(12, 4)]);
+        assert_eq!(
+            again.iter().map(|t| t.price).collect::<Vec<_>>(),
+            vec![3, 4]
+        );
+
+        let depth = MarketEvent::Depth {
+            market_id: "m1".into(),
+            timestamp: 3_599,
+            depth: Depth {
+                last_update_id: 7,
+                bids: vec![["99.5".into(), "2".into()]],
+                asks: vec![["100.5".into(), "1".into()], ["101".into(), "3".into()]],
+            },
+        };
+        let trade = MarketEvent::Trade {
+            market_id: "m1".into(),
+            trade: trade(3_599, 10_000),
+        };
+
+        #[cfg_attr(not(feature = "parquet"), allow(unused_mut))]
+        let mut formats = vec![RecordFormat::Jsonl, RecordFormat::Csv];
+        #[cfg(feature = "parquet")]
+        formats.push(RecordFormat::Parquet);
+        for format in formats {
+            let mut recorder = MarketRecorder::new(RecorderConfig {
+                directory: directory.join(format.extension()),
+                format,
+                ..Default::default()
+            })
+            .unwrap();
+            recorder
+                .write(&[depth.clone(), trade.clone()], 3_599)
+                .unwrap();
+            recorder.write(std::slice::from_ref(&trade), 3_600).unwrap();
+            recorder.finish().unwrap();
+
+            let path = |kind, period| recorder.path(kind, Some(period));
+            assert!(path(DataKind::Trades, 0).exists());
+            assert!(path(DataKind::Trades, 3_600).exists());
+            match format {
+                RecordFormat::Csv => {
+                    let csv = std::fs::read_to_string(path(DataKind::Depth, 0)).unwrap();
+                    let lines: Vec<&str> = csv.lines().collect();
+                    assert_eq!(lines[0], "timestamp,market_id,side,level,price,quantity");
+                    assert_eq!(lines[3], "3599,m1,ask,1,101,3");
+                    let trades = std::fs::read_to_string(path(DataKind::Trades, 0)).unwrap();
+                    assert!(trades.ends_with("3599,m1,10000,1000,a,\"b, c\",,-1,\n"));
+                }
+                RecordFormat::Jsonl => {
+                    let events = read_events(path(DataKind::Depth, 0)).unwrap();
+                    assert!(matches!(
+                        &events[..],
+                        [MarketEvent::Depth { depth, .. }] if depth.asks.len() == 2
+                    ));
+                    let events = read_events(path(DataKind::Trades, 3_600)).unwrap();
+                    assert_eq!(events[0].timestamp(), 3_599);
+                }
+                #[cfg(feature = "parquet")]
+                RecordFormat::Parquet => {
+                    use parquet::file::reader::{FileReader, SerializedFileReader};
+                    let file = File::open(path(DataKind::Depth, 0)).unwrap();
+                    let reader = SerializedFileReader::new(file).unwrap();
+                    assert_eq!(reader.metadata().file_metadata().num_rows(), 3);
+                }
+            }
+        }
+        std::fs::remove_dir_all(&directory).unwrap();
+    }
+}
@@ -0,0 +1,331 @@
+//! Event-sourced strategy runs with deterministic replay.
+//!
+//! A [`StrategyRunner`] feeds market data to a [`Strategy`], executes the
+//! actions it returns, and appends both to an [`EventLog`]. Because a
+//! strategy only sees logged events and their timestamps, [`replay`] can feed
+//! the same log through a fresh strategy offline and show, event by event,
+//! whether it makes the same decisions.
+
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::warn;
+
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::types::{CancelResult, OrderResult, Orderbook, PerpOrder, Position, Trade};
+
+/// Inbound data a strategy reacts to.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum MarketEvent {
+    Orderbook {
+        market_id: String,
+        book: Orderbook,
+    },
+    Trades {
+        market_id: String,
+        trades: Vec<Trade>,
+    },
+    Positions {
+        positions: Vec<Position>,
+    },
+}
+
+/// Outbound request decided by a strategy.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    Place { market_id: String, order: PerpOrder },
+    Cancel { market_id: String, order_id: u64 },
+}
+
+/// One logged event.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "kind", content = "event", rename_all = "snake_case")]
+pub enum LogEntry {
+    Market(MarketEvent),
+    Action(Action),
+}
+
+/// One line of an event log.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EventRecord {
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    pub entry: LogEntry,
+}
+
+/// Decision logic driven by market events.
+///
+/// For replay to be deterministic, `on_event` must depend only on the
+/// events and timestamps it is given, not on wall-clock time, randomness,
+/// or direct client queries.
+pub trait Strategy {
+    fn on_event(&mut self, timestamp_ms: u64, event: &MarketEvent) -> Vec<Action>;
+}
+
+/// Append-only JSONL log of market events and actions.
+pub struct EventLog {
+    file: File,
+    next_seq: u64,
+}
+
+impl EventLog {
+    /// Open a log at `path`, continuing its sequence if the file exists.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let next_seq = if path.exists() {
+            Self::read(path)?.last().map_or(0, |r| r.seq + 1)
+        } else {
+            0
+        };
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        Ok(Self { file, next_seq })
+    }
+
+    /// Every record in the log at `path`, in order.
+    pub fn read(path: impl AsRef<Path>) -> Result<Vec<EventRecord>> {
+        let reader = BufReader::new(File::open(path).map_err(io_error)?);
+        let mut records = Vec::new();
+        for line in reader.lines() {
+            let line = line.map_err(io_error)?;
+            if !line.trim().is_empty() {
+                records.push(serde_json::from_str(&line)?);
+            }
+        }
+        Ok(records)
+    }
+
+    /// Append an entry stamped with the current time.
+    pub fn append(&mut self, entry: LogEntry) -> Result<EventRecord> {
+        let record = EventRecord {
+            seq: self.next_seq,
+            timestamp_ms: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_millis() as u64)
+                .unwrap_or(0),
+            entry,
+        };
+        let mut line = serde_json::to_string(&record)?;
+        line.push('\n');
+        self.file.write_all(line.as_bytes()).map_err(io_error)?;
+        self.next_seq += 1;
+        Ok(record)
+    }
+}
+
+/// Result of executing one action.
+#[derive(Debug, Clone)]
+pub enum ActionOutcome {
+    Placed(OrderResult),
+    Cancelled(CancelResult),
+}
+
+/// Runs a strategy against a live client, logging everything it sees and does.
+pub struct StrategyRunner<S: Strategy> {
+    strategy: S,
+    log: EventLog,
+}
+
+impl<S: Strategy> StrategyRunner<S> {
+    pub fn new(strategy: S, log: EventLog) -> Self {
+        Self { strategy, log }
+    }
+
+    pub fn strategy(&self) -> &S {
+        &self.strategy
+    }
+
+    /// Log `event`, run the strategy on it, then log and execute each action.
+    ///
+    /// Only log writes fail the call; each action's own result is returned
+    /// in order.
+    pub async fn handle(
+        &mut self,
+        client: &FermiClient,
+        event: MarketEvent,
+    ) -> Result<Vec<Result<ActionOutcome>>> {
+        let record = self.log.append(LogEntry::Market(event.clone()))?;
+        let actions = self.strategy.on_event(record.timestamp_ms, &event);
+
+        let mut outcomes = Vec::with_capacity(actions.len());
+        for action in actions {
+            self.log.append(LogEntry::Action(action.clone()))?;
+            let outcome = match action {
+                Action::Place { market_id, order } => client
+                    .place_perp_order(&market_id, order)
+                    .await
+                    .map(ActionOutcome::Placed),
+                Action::Cancel {
+                    market_id,
+                    order_id,
+                } => client
+                    .cancel_order(&market_id, order_id)
+                    .await
+                    .map(ActionOutcome::Cancelled),
+            };
+            if let Err(e) = &outcome {
+                warn!("Strategy action failed: {}", e);
+            }
+            outcomes.push(outcome);
+        }
+        Ok(outcomes)
+    }
+
+    /// Fetch `market_id`'s order book and handle it as an event.
+    pub async fn poll_orderbook(
+        &mut self,
+        client: &FermiClient,
+        market_id: &str,
+    ) -> Result<Vec<Result<ActionOutcome>>> {
+        let book = client.get_orderbook(market_id).await?;
+        let event = MarketEvent::Orderbook {
+            market_id: market_id.to_string(),
+            book,
+        };
+        self.handle(client, event).await
+    }
+}
+
+/// What a replayed strategy decided for one logged market event.
+#[derive(Debug, Clone)]
+pub struct ReplayStep {
+    /// Sequence number of the market event
+    pub seq: u64,
+    pub timestamp_ms: u64,
+    /// Actions logged during the original run
+    pub recorded: Vec<Action>,
+    /// Actions the replayed strategy returned
+    pub replayed: Vec<Action>,
+}
+
+impl ReplayStep {
+    pub fn diverged(&self) -> bool {
+        self.recorded != self.replayed
+    }
+}
+
+/// Feed every market event in `records` through `strategy`, pairing its
+/// decisions with the actions originally logged after that event.
+pub fn replay<S: Strategy>(strategy: &mut S, records: &[EventRecord]) -> Vec<ReplayStep> {
+    let mut steps: Vec<ReplayStep> = Vec::new();
+    for record in records {
+        match &record.entry {
+            LogEntry::Market(event) => steps.push(ReplayStep {
+                seq: record.seq,
+                timestamp_ms: record.timestamp_ms,
+                recorded: Vec::new(),
+                replayed: strategy.on_event(record.timestamp_ms, event),
+            }),
+            LogEntry::Action(action) => {
+                if let Some(step) = steps.last_mut() {
+                    step.recorded.push(action.clone());
+                }
+            }
+        }
+    }
+    steps
+}
+
+// std::io::Error converts to a keypair error by default
+fn io_error(err: std::io::Error) -> SdkError {
+    SdkError::Config(format!("event log: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::Side;
+    use rust_decimal::Decimal;
+
+    /// Buys on book updates before `threshold_ms` and cancels after it.
+    struct Toggle {
+        threshold_ms: u64,
+    }
+
+    impl Strategy for Toggle {
+        fn on_event(&mut self, timestamp_ms: u64, event: &MarketEvent) -> Vec<Action> {
+            let MarketEvent::Orderbook { market_id, .. } = event else {
+                return Vec::new();
+            };
+            if timestamp_ms < self.threshold_ms {
+                vec![Action::Place {
+                    market_id: market_id.clone(),
+                    order: PerpOrder {
+                        side: Side::Buy,
+                        price: Decimal::ONE,
+                        quantity: Decimal::ONE,
+                        ..Default::default()
+                    },
+                }]
+            } else {
+                vec![Action::Cancel {
+                    market_id: market_id.clone(),
+                    order_id: 7,
+                }]
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_detects_divergence() {
+        let book = |seq, timestamp_ms| EventRecord {
+            seq,
+            timestamp_ms,
+            entry: LogEntry::Market(MarketEvent::Orderbook {
+                market_id: "m".to_string(),
+                book: Orderbook {
+                    buys: Vec::new(),
+                    sells: Vec::new(),
+                },
+            }),
+        };
+        let mut original = Toggle { threshold_ms: 150 };
+        let mut records = Vec::new();
+        let mut seq = 0;
+        for timestamp_ms in [100, 200] {
+            let record = book(seq, timestamp_ms);
+            seq += 1;
+            let LogEntry::Market(event) = &record.entry else {
+                unreachable!()
+            };
+            let actions = original.on_event(timestamp_ms, event);
+            records.push(record);
+            for action in actions {
+                records.push(EventRecord {
+                    seq,
+                    timestamp_ms,
+                    entry: LogEntry::Action(action),
+                });
+                seq += 1;
+            }
+        }
+
+        // Log survives a JSON round trip
+        let json: Vec<String> = records
+            .iter()
+            .map(|r| serde_json::to_string(r).unwrap())
+            .collect();
+        let records: Vec<EventRecord> = json
+            .iter()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+
+        let same = replay(&mut Toggle { threshold_ms: 150 }, &records);
+        assert_eq!(same.len(), 2);
+        assert!(same.iter().all(|s| !s.diverged()));
+
+        let changed = replay(&mut Toggle { threshold_ms: 250 }, &records);
+        assert!(!changed[0].diverged());
+        assert!(changed[1].diverged());
+        assert_eq!(changed[1].seq, 2);
+    }
+}
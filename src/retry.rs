@@ -0,0 +1,136 @@
+//! Retries with exponential backoff for transient RPC and Continuum failures.
+
+use std::fmt::Display;
+use std::time::Duration;
+
+use rand::Rng;
+use reqwest::StatusCode;
+use tracing::warn;
+
+/// How transient failures of RPC reads and Continuum calls are retried.
+///
+/// RPC GETs are retried on connection errors, timeouts, and the listed HTTP
+/// statuses; account-changing POSTs are never retried. Continuum calls are
+/// retried on the listed gRPC codes. HTTP 429 and gRPC `RESOURCE_EXHAUSTED`
+/// are always retried, after the server's Retry-After when it sends one. A
+/// submission is resent as the identical signed transaction, so the
+/// sequencer accepts at most one copy.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RetryPolicy {
+    /// Attempts per call, including the first (1 disables retries)
+    pub max_attempts: u32,
+    /// Delay before the first retry, doubled for each one after
+    pub base_delay: Duration,
+    /// Cap on the delay between attempts
+    pub max_delay: Duration,
+    /// Random spread applied to each delay, as a fraction of it (0.0..=1.0)
+    pub jitter: f64,
+    /// HTTP statuses from the rollup node worth retrying
+    pub retry_statuses: Vec<u16>,
+    /// gRPC codes from the sequencer worth retrying
+    pub retry_grpc_codes: Vec<tonic::Code>,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_secs(2),
+            jitter: 0.2,
+            retry_statuses: vec![502, 503, 504],
+            retry_grpc_codes: vec![tonic::Code::Unavailable],
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// A policy that makes every call once.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Whether another attempt is allowed after `attempt` (1-based) failed.
+    pub(crate) fn allows_retry(&self, attempt: u32) -> bool {
+        attempt < self.max_attempts
+    }
+
+    pub(crate) fn retries_status(&self, status: StatusCode) -> bool {
+        self.retry_statuses.contains(&status.as_u16())
+    }
+
+    pub(crate) fn retries_code(&self, code: tonic::Code) -> bool {
+        self.retry_grpc_codes.contains(&code)
+    }
+
+    /// Delay before retrying after `attempt` (1-based) failed.
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let jitter = self.jitter.clamp(0.0, 1.0);
+        if jitter == 0.0 {
+            return exponential;
+        }
+        exponential.mul_f64(1.0 + rand::thread_rng().gen_range(-jitter, jitter))
+    }
+
+    /// Log a failed attempt and wait out its backoff.
+    pub(crate) async fn backoff(&self, attempt: u32, what: &str, reason: impl Display) {
+        self.backoff_for(self.delay(attempt), attempt, what, reason)
+            .await;
+    }
+
+    /// Log a failed attempt and wait `delay`, as asked by the server.
+    pub(crate) async fn backoff_for(
+        &self,
+        delay: Duration,
+        attempt: u32,
+        what: &str,
+        reason: impl Display,
+    ) {
+        warn!(
+            "{} failed ({}), retrying in {:?} ({}/{})",
+            what,
+            reason,
+            delay,
+            attempt + 1,
+            self.max_attempts
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_delay_doubles_within_jitter_and_cap() {
+        let policy = RetryPolicy {
+            base_delay: Duration::from_millis(100),
+            max_delay: Duration::from_millis(1_000),
+            jitter: 0.0,
+            ..RetryPolicy::default()
+        };
+        let delays: Vec<u128> = (1..=5).map(|n| policy.delay(n).as_millis()).collect();
+        assert_eq!(delays, vec![100, 200, 400, 800, 1_000]);
+
+        let jittered = RetryPolicy {
+            jitter: 0.2,
+            ..policy
+        };
+        for _ in 0..100 {
+            let delay = jittered.delay(2).as_millis();
+            assert!((160..=240).contains(&delay), "{}", delay);
+        }
+
+        assert!(!RetryPolicy::none().allows_retry(1));
+        assert!(RetryPolicy::default().retries_status(StatusCode::BAD_GATEWAY));
+        assert!(!RetryPolicy::default().retries_code(tonic::Code::InvalidArgument));
+    }
+}
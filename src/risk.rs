@@ -0,0 +1,1663 @@
+//! Margin health monitoring and risk utilities.
+//!
+//! Turns `AccountSummary` snapshots into a margin ratio (maintenance margin
+//! over equity) and classifies it against user-defined thresholds.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::types::{
+    lossy_decimal, AccountSummary, AdlIndicator, Kline, MarginMode, MarginTier, MarketInfo,
+    OpenOrder, OrderResult, OrderType, PerpOrder, Position, PositionEffect, Side,
+};
+
+/// Margin health level, ordered from safest to most severe.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RiskLevel {
+    Healthy,
+    Warning,
+    Danger,
+    Critical,
+}
+
+/// Margin ratio thresholds for the risk monitor.
+///
+/// Ratios are maintenance margin divided by equity: 1.0 means the account
+/// is at its liquidation point.
+#[derive(Debug, Clone)]
+pub struct RiskThresholds {
+    pub warning: f64,
+    pub danger: f64,
+    pub critical: f64,
+    /// How often the account summary is polled
+    pub poll_interval: Duration,
+    /// Fraction of every open position to close when Critical is reached (None disables)
+    pub auto_reduce_fraction: Option<f64>,
+    /// Slippage bound of auto-reduce market orders, in basis points
+    pub auto_reduce_slippage_bps: u32,
+}
+
+impl Default for RiskThresholds {
+    fn default() -> Self {
+        Self {
+            warning: 0.5,
+            danger: 0.75,
+            critical: 0.9,
+            poll_interval: Duration::from_secs(5),
+            auto_reduce_fraction: None,
+            auto_reduce_slippage_bps: 100,
+        }
+    }
+}
+
+impl RiskThresholds {
+    /// Classify a margin ratio against these thresholds.
+    pub fn classify(&self, margin_ratio: f64) -> RiskLevel {
+        if margin_ratio >= self.critical {
+            RiskLevel::Critical
+        } else if margin_ratio >= self.danger {
+            RiskLevel::Danger
+        } else if margin_ratio >= self.warning {
+            RiskLevel::Warning
+        } else {
+            RiskLevel::Healthy
+        }
+    }
+}
+
+/// Emitted by the risk monitor whenever the margin level changes.
+#[derive(Debug, Clone)]
+pub struct RiskEvent {
+    pub level: RiskLevel,
+    pub previous: RiskLevel,
+    pub margin_ratio: f64,
+    pub account: AccountSummary,
+}
+
+/// A position resolved against its market, in human units.
+#[derive(Debug, Clone)]
+pub struct PortfolioPosition {
+    pub market: MarketInfo,
+    pub position: Position,
+    /// Signed size in base units (positive = long)
+    pub size: f64,
+    pub entry_price: f64,
+    pub mark_price: f64,
+    /// Auto-deleveraging ranking, if the node publishes one for this market
+    pub adl: Option<AdlIndicator>,
+}
+
+impl PortfolioPosition {
+    /// Resolve a raw position against its market metadata.
+    pub fn new(market: MarketInfo, position: Position) -> Result<Self> {
+        Ok(Self {
+            size: position.size(&market)?,
+            entry_price: position.entry_price(&market)?,
+            mark_price: position.mark(&market)?,
+            market,
+            position,
+            adl: None,
+        })
+    }
+
+    /// Absolute position value at the mark price, in USDC.
+    pub fn notional(&self) -> f64 {
+        self.size.abs() * self.mark_price
+    }
+
+    /// Unrealized PnL at the mark price, in USDC.
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.size * (self.mark_price - self.entry_price)
+    }
+
+    /// True when the position is profitable and ranked in the top ADL bucket.
+    pub fn adl_at_risk(&self) -> bool {
+        self.unrealized_pnl() > 0.0 && self.adl.as_ref().is_some_and(|adl| adl.quantile >= 4)
+    }
+}
+
+/// Account summary together with every open position.
+#[derive(Debug, Clone)]
+pub struct Portfolio {
+    pub account: AccountSummary,
+    pub positions: Vec<PortfolioPosition>,
+}
+
+impl Portfolio {
+    /// Sum of absolute position notionals, in USDC.
+    pub fn gross_notional(&self) -> f64 {
+        self.positions.iter().map(PortfolioPosition::notional).sum()
+    }
+
+    /// Positions sitting in the top auto-deleveraging bucket.
+    pub fn adl_at_risk(&self) -> impl Iterator<Item = &PortfolioPosition> {
+        self.positions.iter().filter(|p| p.adl_at_risk())
+    }
+}
+
+/// Account equity in USDC, falling back to collateral plus unrealized PnL
+/// when the node has not published an equity snapshot.
+pub fn equity(account: &AccountSummary) -> f64 {
+    account
+        .equity_snapshot
+        .unwrap_or_else(|| account.usdc_collateral + account.unrealized_pnl_snapshot.unwrap_or(0.0))
+}
+
+/// Maintenance margin over equity.
+///
+/// Returns 0.0 when no maintenance margin is required and infinity when
+/// margin is required but equity is exhausted.
+pub fn margin_ratio(account: &AccountSummary) -> f64 {
+    let maintenance = account.maintenance_margin_snapshot.unwrap_or(0.0);
+    if maintenance <= 0.0 {
+        return 0.0;
+    }
+    let equity = equity(account);
+    if equity <= 0.0 {
+        return f64::INFINITY;
+    }
+    maintenance / equity
+}
+
+/// Hedge offset applied to the account, or `None` without portfolio margining.
+pub fn portfolio_margin_offset(account: &AccountSummary) -> Option<f64> {
+    (account.portfolio_margin == Some(true)).then(|| {
+        account
+            .portfolio_margin_offset
+            .unwrap_or(0.0)
+            .clamp(0.0, 1.0)
+    })
+}
+
+/// Notional that margin rates apply to under portfolio margining.
+///
+/// Takes signed notionals (positive = long). Net exposure is charged in
+/// full; the hedged remainder, where longs and shorts offset, is charged
+/// after waiving `offset` of it.
+pub fn portfolio_margin_base(signed_notionals: impl IntoIterator<Item = f64>, offset: f64) -> f64 {
+    let (gross, net) = signed_notionals
+        .into_iter()
+        .fold((0.0, 0.0), |(gross, net), n: f64| {
+            (gross + n.abs(), net + n)
+        });
+    gross - offset.clamp(0.0, 1.0) * (gross - net.abs())
+}
+
+/// Largest notional a new order can add under portfolio margining.
+///
+/// `hedgeable` is the part of the portfolio's net exposure the order's
+/// side offsets (0 when it adds to the net). Offset notional consumes
+/// `1 - offset` of the usual initial margin.
+pub fn portfolio_max_notional(free: f64, leverage: u64, hedgeable: f64, offset: f64) -> f64 {
+    let unhedged = free.max(0.0) * leverage.max(1) as f64;
+    let offset = offset.clamp(0.0, 1.0);
+    if offset >= 1.0 || unhedged / (1.0 - offset) > hedgeable {
+        unhedged + offset * hedgeable
+    } else {
+        unhedged / (1.0 - offset)
+    }
+}
+
+/// Initial margin required to open `quantity` at `price` with `leverage`, in USDC.
+///
+/// A lossy `f64` estimate for analytics and simulation. The margin attached
+/// to an order by `FermiClient::place_perp_order` is computed exactly from
+/// the order's `Decimal` price and quantity and rounded up to micro-USDC, so
+/// it can differ from this figure in the last places.
+pub fn initial_margin(price: f64, quantity: f64, leverage: u64) -> f64 {
+    price * quantity / leverage.max(1) as f64
+}
+
+/// Collateral available for new exposure, in USDC.
+///
+/// Uses the node's free collateral snapshot when published, otherwise
+/// equity minus the initial margin of existing positions.
+pub fn free_collateral(account: &AccountSummary) -> f64 {
+    account
+        .free_collateral_snapshot
+        .unwrap_or_else(|| equity(account) - account.initial_margin_snapshot.unwrap_or(0.0))
+}
+
+/// Margin reserved by the open remainder of resting orders.
+///
+/// Each order is charged at its own leverage from `leverages`, keyed by
+/// order ID; orders missing from it, such as those placed by another
+/// process, are charged at `default_leverage`. Orders in markets missing
+/// from `markets` are ignored.
+pub fn reserved_margin(
+    orders: &[OpenOrder],
+    markets: &[MarketInfo],
+    leverages: &HashMap<u64, u64>,
+    default_leverage: u64,
+) -> f64 {
+    orders
+        .iter()
+        .filter_map(|order| {
+            let market = markets.iter().find(|m| m.uuid == order.market_id)?;
+            let price = order.price as f64 / 10f64.powi(market.quote_decimals as i32);
+            let quantity = order.quantity as f64 / 10f64.powi(market.base_decimals as i32);
+            let leverage = leverages
+                .get(&order.order_id)
+                .copied()
+                .unwrap_or(default_leverage);
+            Some(initial_margin(price, quantity, leverage))
+        })
+        .sum()
+}
+
+/// Collateral available for a new order, in USDC, given the margin
+/// `reserved` by resting orders.
+///
+/// The node's free collateral snapshot already nets out resting orders, so
+/// it is used as is; `reserved` only comes off free collateral derived
+/// from equity.
+pub fn order_collateral(account: &AccountSummary, reserved: f64) -> f64 {
+    match account.free_collateral_snapshot {
+        Some(free) => free,
+        None => free_collateral(account) - reserved,
+    }
+}
+
+/// Largest quantity (in base units) that free collateral can support at
+/// `price` and `leverage`, rounded down to the market's lot size.
+pub fn max_position(
+    account: &AccountSummary,
+    market: &MarketInfo,
+    leverage: u64,
+    price: f64,
+) -> f64 {
+    if price <= 0.0 {
+        return 0.0;
+    }
+    let free = free_collateral(account).max(0.0);
+    let quantity = free * leverage.max(1) as f64 / price;
+    round_down_to_lot(quantity, market)
+}
+
+/// Round a base quantity down to a whole number of base lots.
+pub(crate) fn round_down_to_lot(quantity: f64, market: &MarketInfo) -> f64 {
+    let multiplier = 10f64.powi(market.base_decimals as i32);
+    let lot = market.base_lot_size.max(1) as f64;
+    ((quantity * multiplier / lot).floor() * lot) / multiplier
+}
+
+/// Collateral and positions the pre-trade margin check runs against.
+///
+/// Fetched once per submission and drawn down as each order is accepted,
+/// so the orders of a batch are checked against what the earlier ones
+/// leave free rather than each against all of it.
+#[derive(Debug, Clone)]
+pub(crate) struct MarginBudget {
+    /// Account with `free_collateral_snapshot` set to what new orders can use
+    account: AccountSummary,
+    /// Signed notional of the portfolio (positive = long), used under
+    /// portfolio margining
+    net_notional: f64,
+    /// Signed position size in base units by market ID
+    positions: HashMap<String, f64>,
+}
+
+impl MarginBudget {
+    /// Budget for `account` after the margin `reserved` by resting orders
+    /// (see [`order_collateral`]).
+    pub(crate) fn new(
+        mut account: AccountSummary,
+        reserved: f64,
+        positions: HashMap<String, f64>,
+        net_notional: f64,
+    ) -> Self {
+        account.free_collateral_snapshot = Some(order_collateral(&account, reserved));
+        Self {
+            account,
+            net_notional,
+            positions,
+        }
+    }
+
+    /// Collateral left for new orders, in USDC.
+    pub(crate) fn free_collateral(&self) -> f64 {
+        free_collateral(&self.account)
+    }
+
+    /// Largest order (in base units) the budget supports on `side` at
+    /// `price` and `leverage`.
+    ///
+    /// Any opposing position in the market is added back, since closing it
+    /// frees margin rather than consuming it. Under portfolio margining, the
+    /// part of the order that offsets the net exposure is charged at the
+    /// reduced hedge rate.
+    pub(crate) fn max_order_size(
+        &self,
+        market: &MarketInfo,
+        side: Side,
+        leverage: u64,
+        price: f64,
+    ) -> f64 {
+        let mut max = max_position(&self.account, market, leverage, price);
+        if let Some(offset) = portfolio_margin_offset(&self.account) {
+            if price > 0.0 {
+                let notional = portfolio_max_notional(
+                    self.free_collateral(),
+                    leverage,
+                    self.hedgeable(side),
+                    offset,
+                );
+                max = round_down_to_lot(notional / price, market);
+            }
+        }
+        max + self.opposing(&market.uuid, side)
+    }
+
+    /// Check `order` against the budget and, if it fits, charge its margin.
+    pub(crate) fn check(&mut self, market: &MarketInfo, order: &PerpOrder) -> Result<()> {
+        let price = order.price_f64();
+        let quantity = order.quantity_f64();
+        let max = self.max_order_size(market, order.side, order.leverage, price);
+        if quantity > max * (1.0 + 1e-9) {
+            return Err(SdkError::InsufficientMargin(format!(
+                "{} {} at {}x exceeds the {} free collateral supports",
+                order.quantity, market.name, order.leverage, max
+            )));
+        }
+        self.reserve(&market.uuid, order.side, quantity, price, order.leverage);
+        Ok(())
+    }
+
+    /// Charge an accepted order.
+    ///
+    /// The part closing an opposing position uses that position up, and
+    /// the rest consumes initial margin. Resting orders are not counted as
+    /// exposure, so a bid and an ask in one batch never offset each other.
+    fn reserve(&mut self, market_id: &str, side: Side, quantity: f64, price: f64, leverage: u64) {
+        let sign = match side {
+            Side::Buy => 1.0,
+            Side::Sell => -1.0,
+        };
+        let closing = quantity.min(self.opposing(market_id, side));
+        *self.positions.entry(market_id.to_string()).or_default() += sign * closing;
+
+        let notional = (quantity - closing) * price;
+        let leverage = leverage.max(1) as f64;
+        let margin = match portfolio_margin_offset(&self.account) {
+            Some(offset) => {
+                let hedged = notional.min(self.hedgeable(side));
+                self.net_notional += sign * hedged;
+                (notional - offset * hedged) / leverage
+            }
+            None => notional / leverage,
+        };
+        self.account.free_collateral_snapshot = Some(self.free_collateral() - margin);
+    }
+
+    /// Size of the position in `market_id` that an order on `side` closes.
+    fn opposing(&self, market_id: &str, side: Side) -> f64 {
+        let size = self.positions.get(market_id).copied().unwrap_or(0.0);
+        match side {
+            Side::Buy => (-size).max(0.0),
+            Side::Sell => size.max(0.0),
+        }
+    }
+
+    /// Net exposure an order on `side` offsets.
+    fn hedgeable(&self, side: Side) -> f64 {
+        match side {
+            Side::Buy => (-self.net_notional).max(0.0),
+            Side::Sell => self.net_notional.max(0.0),
+        }
+    }
+}
+
+/// Margin requirements as a fraction of position notional.
+#[derive(Debug, Clone, Copy)]
+pub struct MarginRates {
+    pub initial: f64,
+    pub maintenance: f64,
+}
+
+impl Default for MarginRates {
+    fn default() -> Self {
+        Self {
+            initial: 0.10,
+            maintenance: 0.05,
+        }
+    }
+}
+
+impl MarginRates {
+    /// Rates published for a margin tier.
+    pub fn from_tier(tier: &MarginTier) -> Self {
+        Self {
+            initial: tier.initial_margin_rate,
+            maintenance: tier.maintenance_margin_rate,
+        }
+    }
+
+    /// Back out the rates the node is applying from the portfolio's margin
+    /// snapshots, falling back to the defaults when there is no exposure.
+    ///
+    /// Under portfolio margining the snapshots are divided by the offset
+    /// margin base rather than gross notional.
+    pub fn from_portfolio(portfolio: &Portfolio) -> Self {
+        let gross = match portfolio_margin_offset(&portfolio.account) {
+            Some(offset) => portfolio_margin_base(
+                portfolio.positions.iter().map(|p| p.size * p.mark_price),
+                offset,
+            ),
+            None => portfolio.gross_notional(),
+        };
+        let defaults = Self::default();
+        if gross <= 0.0 {
+            return defaults;
+        }
+        let account = &portfolio.account;
+        Self {
+            initial: account
+                .initial_margin_snapshot
+                .map_or(defaults.initial, |m| m / gross),
+            maintenance: account
+                .maintenance_margin_snapshot
+                .map_or(defaults.maintenance, |m| m / gross),
+        }
+    }
+}
+
+/// A hypothetical change applied by the what-if simulator.
+#[derive(Debug, Clone)]
+pub enum PortfolioChange {
+    /// Fill an order in full at its limit price.
+    Order { market_id: String, order: PerpOrder },
+    /// Close the whole position in a market at its mark price.
+    Close { market_id: String },
+    /// Switch a position's margin mode. Isolated positions are allocated
+    /// `margin` USDC, or their initial margin when `None`.
+    SetMarginMode {
+        market_id: String,
+        mode: MarginMode,
+        margin: Option<f64>,
+    },
+    /// Add (positive) or withdraw (negative) USDC collateral.
+    Collateral(f64),
+}
+
+/// A position after simulation.
+#[derive(Debug, Clone)]
+pub struct SimulatedPosition {
+    pub market_id: String,
+    /// Signed size in base units (positive = long)
+    pub size: f64,
+    pub entry_price: f64,
+    pub mark_price: f64,
+    pub margin_mode: MarginMode,
+    /// USDC allocated to an isolated position (0 for cross)
+    pub isolated_margin: f64,
+    /// Mark price at which the position would be liquidated, if reachable
+    pub liquidation_price: Option<f64>,
+}
+
+impl SimulatedPosition {
+    pub fn notional(&self) -> f64 {
+        self.size.abs() * self.mark_price
+    }
+
+    pub fn unrealized_pnl(&self) -> f64 {
+        self.size * (self.mark_price - self.entry_price)
+    }
+
+    /// Relative mark price move that would trigger liquidation
+    /// (0.05 = liquidated after a 5% adverse move).
+    pub fn distance_to_liquidation(&self) -> Option<f64> {
+        let liquidation = self.liquidation_price?;
+        if self.mark_price <= 0.0 {
+            return None;
+        }
+        Some(((self.mark_price - liquidation) / self.mark_price * self.size.signum()).max(0.0))
+    }
+}
+
+/// Outcome of a what-if simulation.
+#[derive(Debug, Clone)]
+pub struct Simulation {
+    /// Account metrics recomputed with every `*_snapshot` field populated
+    pub account: AccountSummary,
+    pub positions: Vec<SimulatedPosition>,
+}
+
+impl Simulation {
+    pub fn margin_ratio(&self) -> f64 {
+        margin_ratio(&self.account)
+    }
+}
+
+/// Apply hypothetical changes to a portfolio and recompute equity, margin
+/// requirements, and liquidation prices locally.
+///
+/// With portfolio margining enabled on the account, cross positions are
+/// charged on their offset base (see [`portfolio_margin_base`]) and their
+/// liquidation prices use the resulting effective maintenance rate.
+///
+/// Existing positions keep the margin mode the node reports, defaulting to
+/// cross. The node does not report an isolated position's margin, so it is
+/// taken as the initial margin of opening it at its reported leverage, or
+/// at the initial rate without one.
+pub fn simulate(
+    portfolio: &Portfolio,
+    changes: &[PortfolioChange],
+    rates: MarginRates,
+) -> Simulation {
+    let mut collateral = portfolio.account.usdc_collateral;
+    let mut realized = 0.0;
+    let mut positions: Vec<SimulatedPosition> = portfolio
+        .positions
+        .iter()
+        .map(|p| {
+            let margin_mode = p.position.margin_mode.unwrap_or(MarginMode::Cross);
+            let isolated_margin = match (margin_mode, p.position.leverage) {
+                (MarginMode::Cross, _) => 0.0,
+                (MarginMode::Isolated, Some(leverage)) => {
+                    initial_margin(p.entry_price, p.size.abs(), leverage)
+                }
+                (MarginMode::Isolated, None) => p.notional() * rates.initial,
+            };
+            SimulatedPosition {
+                market_id: p.market.uuid.clone(),
+                size: p.size,
+                entry_price: p.entry_price,
+                mark_price: p.mark_price,
+                margin_mode,
+                isolated_margin,
+                liquidation_price: None,
+            }
+        })
+        .collect();
+
+    for change in changes {
+        match change {
+            PortfolioChange::Order { market_id, order } => {
+                let (price, quantity) = (order.price_f64(), order.quantity_f64());
+                let delta = match order.side {
+                    Side::Buy => quantity,
+                    Side::Sell => -quantity,
+                };
+                let index = match positions.iter().position(|p| &p.market_id == market_id) {
+                    Some(index) => index,
+                    None => {
+                        positions.push(SimulatedPosition {
+                            market_id: market_id.clone(),
+                            size: 0.0,
+                            entry_price: price,
+                            mark_price: price,
+                            margin_mode: order.margin_mode,
+                            isolated_margin: 0.0,
+                            liquidation_price: None,
+                        });
+                        positions.len() - 1
+                    }
+                };
+                let position = &mut positions[index];
+                realized += apply_fill(position, delta, price);
+                if position.margin_mode == MarginMode::Isolated {
+                    position.isolated_margin += initial_margin(price, quantity, order.leverage);
+                }
+            }
+            PortfolioChange::Close { market_id } => {
+                if let Some(position) = positions.iter_mut().find(|p| &p.market_id == market_id) {
+                    let mark = position.mark_price;
+                    realized += apply_fill(position, -position.size, mark);
+                }
+            }
+            PortfolioChange::SetMarginMode {
+                market_id,
+                mode,
+                margin,
+            } => {
+                if let Some(position) = positions.iter_mut().find(|p| &p.market_id == market_id) {
+                    position.margin_mode = *mode;
+                    position.isolated_margin = match mode {
+                        MarginMode::Cross => 0.0,
+                        MarginMode::Isolated => {
+                            margin.unwrap_or(position.notional() * rates.initial)
+                        }
+                    };
+                }
+            }
+            PortfolioChange::Collateral(amount) => collateral += amount,
+        }
+    }
+
+    collateral += realized;
+    positions.retain(|p| p.size != 0.0);
+
+    let unrealized: f64 = positions
+        .iter()
+        .map(SimulatedPosition::unrealized_pnl)
+        .sum();
+    let gross: f64 = positions.iter().map(SimulatedPosition::notional).sum();
+    let equity = collateral + unrealized;
+
+    // Cross positions share whatever collateral is not allocated to isolated ones
+    let isolated_total: f64 = positions.iter().map(|p| p.isolated_margin).sum();
+    let cross: Vec<&SimulatedPosition> = positions
+        .iter()
+        .filter(|p| p.margin_mode == MarginMode::Cross)
+        .collect();
+    let cross_equity =
+        collateral - isolated_total + cross.iter().map(|p| p.unrealized_pnl()).sum::<f64>();
+    let cross_gross: f64 = cross.iter().map(|p| p.notional()).sum();
+    let cross_base = match portfolio_margin_offset(&portfolio.account) {
+        Some(offset) => portfolio_margin_base(cross.iter().map(|p| p.size * p.mark_price), offset),
+        None => cross_gross,
+    };
+    let margin_base = gross - cross_gross + cross_base;
+    let initial = margin_base * rates.initial;
+    let maintenance = margin_base * rates.maintenance;
+    let cross_maintenance = cross_base * rates.maintenance;
+    let cross_rate = if cross_gross > 0.0 {
+        rates.maintenance * cross_base / cross_gross
+    } else {
+        rates.maintenance
+    };
+
+    let liquidation_prices: Vec<Option<f64>> = positions
+        .iter()
+        .map(|p| {
+            let rate = match p.margin_mode {
+                MarginMode::Cross => cross_rate,
+                MarginMode::Isolated => rates.maintenance,
+            };
+            liquidation_price(p, cross_equity, cross_maintenance, rate)
+        })
+        .collect();
+    for (position, price) in positions.iter_mut().zip(liquidation_prices) {
+        position.liquidation_price = price;
+    }
+
+    let free = equity - initial;
+    let account = AccountSummary {
+        owner: portfolio.account.owner.clone(),
+        usdc_collateral: collateral,
+        equity_snapshot: Some(equity),
+        realized_pnl_snapshot: Some(
+            portfolio.account.realized_pnl_snapshot.unwrap_or(0.0) + realized,
+        ),
+        unrealized_pnl_snapshot: Some(unrealized),
+        initial_margin_snapshot: Some(initial),
+        maintenance_margin_snapshot: Some(maintenance),
+        free_collateral_snapshot: Some(free),
+        available_withdrawal_snapshot: Some(free.max(0.0)),
+        portfolio_margin: portfolio.account.portfolio_margin,
+        portfolio_margin_offset: portfolio.account.portfolio_margin_offset,
+    };
+
+    Simulation { account, positions }
+}
+
+/// Fill `delta` base units at `price`, returning the realized PnL.
+fn apply_fill(position: &mut SimulatedPosition, delta: f64, price: f64) -> f64 {
+    let old = position.size;
+    let new = old + delta;
+    let mut realized = 0.0;
+
+    if old == 0.0 || old.signum() == delta.signum() {
+        // Opening or increasing: average the entry price
+        position.entry_price = (old.abs() * position.entry_price + delta.abs() * price) / new.abs();
+    } else {
+        // Reducing: realize PnL on the closed portion
+        let closed = delta.abs().min(old.abs());
+        realized = closed * old.signum() * (price - position.entry_price);
+        if new != 0.0 && new.signum() != old.signum() {
+            // Flipped through zero: the remainder opens at the fill price
+            position.entry_price = price;
+        }
+    }
+
+    position.size = new;
+    realized
+}
+
+/// Mark price at which a position's margin pool falls to maintenance,
+/// holding every other position at its current mark.
+fn liquidation_price(
+    position: &SimulatedPosition,
+    cross_equity: f64,
+    cross_maintenance: f64,
+    maintenance_rate: f64,
+) -> Option<f64> {
+    let size = position.size;
+    let denominator = size - maintenance_rate * size.abs();
+    if denominator.abs() < f64::EPSILON {
+        return None;
+    }
+
+    let price = match position.margin_mode {
+        // margin + size * (p - entry) = rate * |size| * p
+        MarginMode::Isolated => {
+            (size * position.entry_price - position.isolated_margin) / denominator
+        }
+        // cross_equity + size * (p - mark) = cross_mm - rate*|size|*mark + rate*|size|*p
+        MarginMode::Cross => {
+            let other_maintenance = cross_maintenance - maintenance_rate * position.notional();
+            (other_maintenance - cross_equity + size * position.mark_price) / denominator
+        }
+    };
+
+    (price > 0.0).then_some(price)
+}
+
+/// A set of relative price shocks applied to mark prices.
+#[derive(Debug, Clone)]
+pub struct StressScenario {
+    pub name: String,
+    /// Shock per market UUID, e.g. -0.1 for a 10% drop
+    pub shocks: HashMap<String, f64>,
+    /// Shock for markets not listed in `shocks`
+    pub default_shock: f64,
+}
+
+impl StressScenario {
+    /// Move every market by the same fraction.
+    pub fn uniform(shock: f64) -> Self {
+        Self {
+            name: format!("{:+}%", shock * 100.0),
+            shocks: HashMap::new(),
+            default_shock: shock,
+        }
+    }
+
+    /// Uniform moves of ±5%, ±10%, and ±20%.
+    pub fn standard() -> Vec<Self> {
+        [-0.20, -0.10, -0.05, 0.05, 0.10, 0.20]
+            .into_iter()
+            .map(Self::uniform)
+            .collect()
+    }
+
+    pub fn shock_for(&self, market_id: &str) -> f64 {
+        self.shocks
+            .get(market_id)
+            .copied()
+            .unwrap_or(self.default_shock)
+    }
+}
+
+/// Portfolio state under a stress scenario.
+#[derive(Debug, Clone)]
+pub struct StressResult {
+    pub scenario: String,
+    pub simulation: Simulation,
+    /// Markets whose positions would be liquidated at the shocked prices
+    pub liquidated: Vec<String>,
+}
+
+impl StressResult {
+    pub fn equity(&self) -> f64 {
+        equity(&self.simulation.account)
+    }
+
+    pub fn margin_ratio(&self) -> f64 {
+        self.simulation.margin_ratio()
+    }
+}
+
+/// Apply each scenario's price shocks to the portfolio and recompute
+/// equity, margin, and liquidations with the simulator's margin math.
+///
+/// Margin rates are calibrated once from the unshocked portfolio.
+pub fn stress(portfolio: &Portfolio, scenarios: &[StressScenario]) -> Vec<StressResult> {
+    let rates = MarginRates::from_portfolio(portfolio);
+
+    scenarios
+        .iter()
+        .map(|scenario| {
+            let mut shocked = portfolio.clone();
+            for position in &mut shocked.positions {
+                position.mark_price *= 1.0 + scenario.shock_for(&position.market.uuid);
+            }
+
+            let simulation = simulate(&shocked, &[], rates);
+            let liquidated = simulation
+                .positions
+                .iter()
+                .filter(|p| match p.liquidation_price {
+                    Some(liq) if p.size > 0.0 => p.mark_price <= liq,
+                    Some(liq) => p.mark_price >= liq,
+                    None => false,
+                })
+                .map(|p| p.market_id.clone())
+                .collect();
+
+            StressResult {
+                scenario: scenario.name.clone(),
+                simulation,
+                liquidated,
+            }
+        })
+        .collect()
+}
+
+/// Historical-simulation Value-at-Risk estimate.
+#[derive(Debug, Clone)]
+pub struct VarEstimate {
+    /// Loss in USDC not expected to be exceeded at `confidence`
+    pub value: f64,
+    /// Average loss in USDC across scenarios beyond the VaR
+    pub expected_shortfall: f64,
+    pub confidence: f64,
+    /// Holding period in candles
+    pub horizon: usize,
+    /// Number of historical scenarios used
+    pub scenarios: usize,
+}
+
+/// Estimate portfolio Value-at-Risk by replaying historical `horizon`-candle
+/// returns against current position notionals.
+///
+/// `candles` maps market UUID to that market's candles at a common
+/// interval. Only timestamps present for every held market are used.
+/// Returns `None` when there is not enough history to form a scenario.
+pub fn var(
+    portfolio: &Portfolio,
+    candles: &HashMap<String, Vec<Kline>>,
+    confidence: f64,
+    horizon: usize,
+) -> Option<VarEstimate> {
+    let horizon = horizon.max(1);
+    if portfolio.positions.is_empty() {
+        return Some(VarEstimate {
+            value: 0.0,
+            expected_shortfall: 0.0,
+            confidence,
+            horizon,
+            scenarios: 0,
+        });
+    }
+
+    // Close prices per held market, keyed by timestamp
+    let mut closes: Vec<(f64, HashMap<u64, f64>)> = Vec::new();
+    for position in &portfolio.positions {
+        let series = candles.get(&position.market.uuid)?;
+        let by_time = series.iter().map(|k| (k.timestamp, k.close)).collect();
+        closes.push((position.size * position.mark_price, by_time));
+    }
+
+    let mut timestamps: Vec<u64> = closes[0].1.keys().copied().collect();
+    timestamps.retain(|t| closes.iter().all(|(_, series)| series.contains_key(t)));
+    timestamps.sort_unstable();
+    if timestamps.len() <= horizon {
+        return None;
+    }
+
+    let mut pnls: Vec<f64> = timestamps
+        .windows(horizon + 1)
+        .map(|window| {
+            let (start, end) = (window[0], window[horizon]);
+            closes
+                .iter()
+                .map(|(exposure, series)| exposure * (series[&end] / series[&start] - 1.0))
+                .sum()
+        })
+        .collect();
+    pnls.sort_by(|a, b| a.total_cmp(b));
+
+    let tail = (((1.0 - confidence) * pnls.len() as f64).ceil() as usize).clamp(1, pnls.len()) - 1;
+    let value = (-pnls[tail]).max(0.0);
+    let beyond = &pnls[..=tail];
+    let expected_shortfall = (-beyond.iter().sum::<f64>() / beyond.len() as f64).max(0.0);
+
+    Some(VarEstimate {
+        value,
+        expected_shortfall,
+        confidence,
+        horizon,
+        scenarios: pnls.len(),
+    })
+}
+
+/// Tracks margin health across successive account snapshots.
+#[derive(Debug, Clone)]
+pub struct RiskMonitor {
+    thresholds: RiskThresholds,
+    level: RiskLevel,
+}
+
+impl RiskMonitor {
+    pub fn new(thresholds: RiskThresholds) -> Self {
+        Self {
+            thresholds,
+            level: RiskLevel::Healthy,
+        }
+    }
+
+    /// The level observed in the most recent snapshot.
+    pub fn level(&self) -> RiskLevel {
+        self.level
+    }
+
+    /// Feed a snapshot; returns an event if the level changed.
+    pub fn evaluate(&mut self, account: &AccountSummary) -> Option<RiskEvent> {
+        let ratio = margin_ratio(account);
+        let level = self.thresholds.classify(ratio);
+        if level == self.level {
+            return None;
+        }
+
+        let previous = std::mem::replace(&mut self.level, level);
+        Some(RiskEvent {
+            level,
+            previous,
+            margin_ratio: ratio,
+            account: account.clone(),
+        })
+    }
+
+    /// Spawn a background task that polls the account and emits events on
+    /// every level change. When the level first reaches Critical and
+    /// `auto_reduce_fraction` is set, reduce-only orders are submitted for
+    /// every open position.
+    ///
+    /// The task stops when the returned receiver is dropped.
+    pub fn watch(
+        client: Arc<FermiClient>,
+        thresholds: RiskThresholds,
+    ) -> (mpsc::UnboundedReceiver<RiskEvent>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut monitor = RiskMonitor::new(thresholds);
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(monitor.thresholds.poll_interval);
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+
+                let account = match client.get_account().await {
+                    Ok(account) => account,
+                    Err(e) => {
+                        warn!("Risk monitor failed to fetch account: {}", e);
+                        continue;
+                    }
+                };
+
+                let Some(event) = monitor.evaluate(&account) else {
+                    continue;
+                };
+
+                info!(
+                    "Margin level {:?} -> {:?} (ratio {:.3})",
+                    event.previous, event.level, event.margin_ratio
+                );
+
+                if event.level == RiskLevel::Critical {
+                    if let Some(fraction) = monitor.thresholds.auto_reduce_fraction {
+                        let slippage = monitor.thresholds.auto_reduce_slippage_bps;
+                        if let Err(e) = reduce_all_positions(&client, fraction, slippage).await {
+                            warn!("Risk monitor auto-reduce failed: {}", e);
+                        }
+                    }
+                }
+
+                if tx.send(event).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+/// What to do when a margin-call tier is breached.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum MarginCallAction {
+    /// Emit an alert only
+    Alert,
+    /// Close this fraction of every open position
+    Reduce { fraction: f64 },
+}
+
+/// A margin-call tier, expressed as equity coverage of maintenance margin.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginCallTier {
+    pub name: String,
+    /// Equity divided by maintenance margin that breaches the tier (1.5 = 150%)
+    pub coverage: f64,
+    pub action: MarginCallAction,
+}
+
+impl MarginCallTier {
+    pub fn alert(name: &str, coverage: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            coverage,
+            action: MarginCallAction::Alert,
+        }
+    }
+
+    pub fn reduce(name: &str, coverage: f64, fraction: f64) -> Self {
+        Self {
+            name: name.to_string(),
+            coverage,
+            action: MarginCallAction::Reduce { fraction },
+        }
+    }
+}
+
+/// Default tiers: alert at 150% and 120% of maintenance margin.
+pub fn default_margin_call_tiers() -> Vec<MarginCallTier> {
+    vec![
+        MarginCallTier::alert("margin_call", 1.5),
+        MarginCallTier::alert("pre_liquidation", 1.2),
+    ]
+}
+
+/// Account margin coverage evaluated against the configured tiers.
+#[derive(Debug, Clone)]
+pub struct MarginStatus {
+    /// Equity divided by maintenance margin (infinite with no requirement)
+    pub coverage: f64,
+    pub account: AccountSummary,
+    /// The most severe breached tier, if any
+    pub tier: Option<MarginCallTier>,
+}
+
+/// Evaluate an account against margin-call tiers.
+pub fn margin_status(account: &AccountSummary, tiers: &[MarginCallTier]) -> MarginStatus {
+    let ratio = margin_ratio(account);
+    let coverage = if ratio > 0.0 {
+        1.0 / ratio
+    } else {
+        f64::INFINITY
+    };
+    let tier = tiers
+        .iter()
+        .filter(|t| coverage <= t.coverage)
+        .min_by(|a, b| a.coverage.total_cmp(&b.coverage))
+        .cloned();
+
+    MarginStatus {
+        coverage,
+        account: account.clone(),
+        tier,
+    }
+}
+
+/// Watches the account against the margin-call tiers in the client's config.
+pub struct MarginCallMonitor;
+
+impl MarginCallMonitor {
+    /// Spawn a background task that emits a status whenever the breached
+    /// tier changes, running the tier's action on entry.
+    ///
+    /// The task stops when the returned receiver is dropped.
+    pub fn watch(
+        client: Arc<FermiClient>,
+        poll_interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<MarginStatus>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(poll_interval);
+            let mut current: Option<String> = None;
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+
+                let status = match client.margin_status().await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        warn!("Margin call monitor failed to fetch account: {}", e);
+                        continue;
+                    }
+                };
+
+                let name = status.tier.as_ref().map(|t| t.name.clone());
+                if name == current {
+                    continue;
+                }
+                current = name;
+
+                if let Some(tier) = &status.tier {
+                    warn!(
+                        "Margin call tier '{}' breached (coverage {:.0}%)",
+                        tier.name,
+                        status.coverage * 100.0
+                    );
+                    if let MarginCallAction::Reduce { fraction } = tier.action {
+                        if let Err(e) = reduce_all_positions(&client, fraction, 100).await {
+                            warn!("Margin call reduction failed: {}", e);
+                        }
+                    }
+                }
+
+                if tx.send(status).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+/// Configuration for the liquidation monitor.
+#[derive(Debug, Clone)]
+pub struct LiquidationGuard {
+    /// Alert when a position is within this relative distance of liquidation
+    pub buffer: f64,
+    pub poll_interval: Duration,
+    /// Submit reduce-only orders for positions inside the buffer
+    pub auto_flatten: bool,
+    /// Margin ratio that auto-flatten reduces the account back to
+    pub target_margin_ratio: f64,
+    /// Slippage bound of flatten market orders, in basis points
+    pub slippage_bps: u32,
+}
+
+impl Default for LiquidationGuard {
+    fn default() -> Self {
+        Self {
+            buffer: 0.05,
+            poll_interval: Duration::from_secs(5),
+            auto_flatten: false,
+            target_margin_ratio: 0.5,
+            slippage_bps: 100,
+        }
+    }
+}
+
+/// A position found inside the liquidation buffer.
+#[derive(Debug, Clone)]
+pub struct LiquidationAlert {
+    pub market_id: String,
+    pub size: f64,
+    pub mark_price: f64,
+    pub liquidation_price: f64,
+    pub distance: f64,
+    /// Base quantity submitted for reduction when auto-flatten is enabled
+    pub reduced: Option<f64>,
+}
+
+/// Check every position against the guard's buffer.
+pub fn positions_near_liquidation(
+    simulation: &Simulation,
+    guard: &LiquidationGuard,
+) -> Vec<LiquidationAlert> {
+    simulation
+        .positions
+        .iter()
+        .filter_map(|p| {
+            let distance = p.distance_to_liquidation()?;
+            (distance <= guard.buffer).then(|| LiquidationAlert {
+                market_id: p.market_id.clone(),
+                size: p.size,
+                mark_price: p.mark_price,
+                liquidation_price: p.liquidation_price.unwrap_or_default(),
+                distance,
+                reduced: None,
+            })
+        })
+        .collect()
+}
+
+/// Base quantity of `position` to close at its mark so the account's
+/// margin ratio falls to `target`, clamped to the position size.
+pub fn reduction_to_target(
+    simulation: &Simulation,
+    position: &SimulatedPosition,
+    rates: MarginRates,
+    target: f64,
+) -> f64 {
+    let equity = equity(&simulation.account);
+    let maintenance = simulation
+        .account
+        .maintenance_margin_snapshot
+        .unwrap_or(0.0);
+    let per_unit = rates.maintenance * position.mark_price;
+    if per_unit <= 0.0 {
+        return 0.0;
+    }
+    ((maintenance - target * equity) / per_unit).clamp(0.0, position.size.abs())
+}
+
+/// Watches distance-to-liquidation for every open position.
+pub struct LiquidationMonitor;
+
+impl LiquidationMonitor {
+    /// Spawn a background task that emits an alert for every position inside
+    /// the buffer on each poll, optionally flattening it back to the target
+    /// margin ratio.
+    ///
+    /// The task stops when the returned receiver is dropped.
+    pub fn watch(
+        client: Arc<FermiClient>,
+        guard: LiquidationGuard,
+    ) -> (mpsc::UnboundedReceiver<LiquidationAlert>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut interval = tokio::time::interval(guard.poll_interval);
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+
+                let portfolio = match client.get_portfolio().await {
+                    Ok(portfolio) => portfolio,
+                    Err(e) => {
+                        warn!("Liquidation monitor failed to fetch portfolio: {}", e);
+                        continue;
+                    }
+                };
+                let rates = MarginRates::from_portfolio(&portfolio);
+                let simulation = simulate(&portfolio, &[], rates);
+
+                for mut alert in positions_near_liquidation(&simulation, &guard) {
+                    warn!(
+                        "Position in {} is {:.2}% from liquidation (mark {:.4}, liq {:.4})",
+                        alert.market_id,
+                        alert.distance * 100.0,
+                        alert.mark_price,
+                        alert.liquidation_price
+                    );
+
+                    if guard.auto_flatten {
+                        let simulated = simulation
+                            .positions
+                            .iter()
+                            .find(|p| p.market_id == alert.market_id);
+                        let held = portfolio
+                            .positions
+                            .iter()
+                            .find(|p| p.market.uuid == alert.market_id);
+                        if let (Some(simulated), Some(held)) = (simulated, held) {
+                            let quantity = reduction_to_target(
+                                &simulation,
+                                simulated,
+                                rates,
+                                guard.target_margin_ratio,
+                            );
+                            let fraction = quantity / simulated.size.abs();
+                            match reduce_position(
+                                &client,
+                                &held.position,
+                                fraction,
+                                guard.slippage_bps,
+                            )
+                            .await
+                            {
+                                Ok(_) => alert.reduced = Some(quantity),
+                                Err(e) => warn!("Liquidation monitor auto-flatten failed: {}", e),
+                            }
+                        }
+                    }
+
+                    if tx.send(alert).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+/// Submit reduce-only orders closing `fraction` of every open position.
+async fn reduce_all_positions(
+    client: &FermiClient,
+    fraction: f64,
+    slippage_bps: u32,
+) -> Result<()> {
+    let positions = client.get_positions().await?;
+    for position in positions {
+        reduce_position(client, &position, fraction, slippage_bps).await?;
+    }
+    Ok(())
+}
+
+/// Submit a reduce-only market order closing `fraction` of a position,
+/// priced from the book within `slippage_bps` of the best opposing price
+/// and in the position's margin mode. Returns `None` for a flat position.
+pub(crate) async fn reduce_position(
+    client: &FermiClient,
+    position: &Position,
+    fraction: f64,
+    slippage_bps: u32,
+) -> Result<Option<OrderResult>> {
+    let market = client.get_market(&position.market_id).await?;
+    let size = position.size(&market)?;
+    if size == 0.0 {
+        return Ok(None);
+    }
+
+    let side = if size > 0.0 { Side::Sell } else { Side::Buy };
+    let order = PerpOrder {
+        side,
+        quantity: lossy_decimal(size.abs() * fraction.clamp(0.0, 1.0))?,
+        leverage: position.leverage.unwrap_or(1),
+        position_effect: PositionEffect::Close,
+        margin_mode: position.margin_mode.unwrap_or(MarginMode::Cross),
+        reduce_only: true,
+        order_type: OrderType::Market,
+        max_slippage_bps: Some(slippage_bps),
+        ..Default::default()
+    };
+
+    warn!(
+        "Reducing position in {}: {} {} at market",
+        market.name, order.side, order.quantity
+    );
+    client.place_perp_order(&market.uuid, order).await.map(Some)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_market;
+    use rust_decimal::Decimal;
+
+    fn account(collateral: f64, maintenance: f64) -> AccountSummary {
+        AccountSummary {
+            owner: None,
+            usdc_collateral: collateral,
+            equity_snapshot: None,
+            realized_pnl_snapshot: None,
+            unrealized_pnl_snapshot: None,
+            initial_margin_snapshot: None,
+            maintenance_margin_snapshot: Some(maintenance),
+            free_collateral_snapshot: None,
+            available_withdrawal_snapshot: None,
+            portfolio_margin: None,
+            portfolio_margin_offset: None,
+        }
+    }
+
+    fn market() -> MarketInfo {
+        MarketInfo {
+            base_lot_size: 1_000_000,
+            ..test_market(9, 6)
+        }
+    }
+
+    fn long_position(size: f64, price: f64) -> PortfolioPosition {
+        PortfolioPosition {
+            market: market(),
+            position: serde_json::from_value(serde_json::json!({
+                "owner": "o", "market_id": "m", "base_position": "0",
+                "average_entry_price": "0", "mark_price": "0",
+                "realized_pnl": "0", "unrealized_pnl": "0",
+            }))
+            .unwrap(),
+            size,
+            entry_price: price,
+            mark_price: price,
+            adl: None,
+        }
+    }
+
+    #[test]
+    fn test_margin_ratio() {
+        assert_eq!(margin_ratio(&account(1000.0, 0.0)), 0.0);
+        assert_eq!(margin_ratio(&account(1000.0, 250.0)), 0.25);
+        assert!(margin_ratio(&account(0.0, 10.0)).is_infinite());
+    }
+
+    #[test]
+    fn test_max_position() {
+        let mut account = account(1000.0, 0.0);
+        account.initial_margin_snapshot = Some(400.0);
+
+        let market = market();
+
+        // 600 USDC free * 10x / 150 = 40 SOL
+        assert!((max_position(&account, &market, 10, 150.0) - 40.0).abs() < 1e-9);
+        // Rounded down to 0.001 SOL lots
+        assert!((max_position(&account, &market, 1, 7.0) - 85.714).abs() < 1e-9);
+
+        // A 2 SOL bid at 100 placed at 5x reserves 40, even when the new
+        // order uses 10x; an unknown order falls back to the default
+        let bid = |order_id| OpenOrder {
+            order_id,
+            market_id: "m".into(),
+            market_name: None,
+            owner: "me".into(),
+            side: "buy".into(),
+            price: 100_000_000,
+            quantity: 2_000_000_000,
+            expiry: 0,
+            timestamp: None,
+        };
+        let leverages = HashMap::from([(1, 5)]);
+        let reserved = reserved_margin(&[bid(1), bid(2)], &[market], &leverages, 10);
+        assert!((reserved - 60.0).abs() < 1e-9);
+        assert!((order_collateral(&account, reserved) - 540.0).abs() < 1e-9);
+
+        // A published snapshot already nets resting orders
+        account.free_collateral_snapshot = Some(500.0);
+        assert_eq!(order_collateral(&account, reserved), 500.0);
+    }
+
+    #[test]
+    fn test_margin_budget_rejects_batch_over_free_collateral() {
+        let market = market();
+        let order = |side, quantity| PerpOrder {
+            leverage: 10,
+            ..PerpOrder::from_f64(side, 100.0, quantity).unwrap()
+        };
+
+        // 1000 USDC free at 10x supports 100 SOL at 100 in total
+        let mut budget = MarginBudget::new(account(1000.0, 0.0), 0.0, HashMap::new(), 0.0);
+        assert!(budget.check(&market, &order(Side::Buy, 60.0)).is_ok());
+        assert!((budget.free_collateral() - 400.0).abs() < 1e-9);
+        assert!(matches!(
+            budget.check(&market, &order(Side::Sell, 60.0)),
+            Err(SdkError::InsufficientMargin(_))
+        ));
+        assert!(budget.check(&market, &order(Side::Sell, 40.0)).is_ok());
+
+        // Closing a 30 SOL long is free, but only once per batch
+        let positions = HashMap::from([("m".to_string(), 30.0)]);
+        let mut budget = MarginBudget::new(account(1000.0, 0.0), 900.0, positions, 0.0);
+        assert!((budget.max_order_size(&market, Side::Sell, 10, 100.0) - 40.0).abs() < 1e-9);
+        assert!(budget.check(&market, &order(Side::Sell, 30.0)).is_ok());
+        assert!((budget.free_collateral() - 100.0).abs() < 1e-9);
+        assert!((budget.max_order_size(&market, Side::Sell, 10, 100.0) - 10.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_simulate_keeps_isolated_positions_isolated() {
+        let mut isolated = long_position(10.0, 100.0);
+        isolated.position.margin_mode = Some(MarginMode::Isolated);
+        isolated.position.leverage = Some(10);
+        let portfolio = Portfolio {
+            account: account(1000.0, 0.0),
+            positions: vec![isolated],
+        };
+
+        let sim = simulate(&portfolio, &[], MarginRates::default());
+        let position = &sim.positions[0];
+        assert_eq!(position.margin_mode, MarginMode::Isolated);
+        assert!((position.isolated_margin - 100.0).abs() < 1e-9);
+        // 100 + 10 * (p - 100) = 0.05 * 10 * p  =>  p = 900 / 9.5, rather
+        // than drawing on the whole 1000 of collateral as cross would
+        let liq = position.liquidation_price.unwrap();
+        assert!((liq - 900.0 / 9.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_simulate_new_position() {
+        let portfolio = Portfolio {
+            account: account(1000.0, 0.0),
+            positions: vec![],
+        };
+        let order = PerpOrder {
+            side: Side::Buy,
+            price: Decimal::from(100),
+            quantity: Decimal::from(50),
+            leverage: 10,
+            ..Default::default()
+        };
+        let changes = [PortfolioChange::Order {
+            market_id: "m".to_string(),
+            order,
+        }];
+
+        let sim = simulate(&portfolio, &changes, MarginRates::default());
+        assert_eq!(sim.account.initial_margin_snapshot, Some(500.0));
+        assert_eq!(sim.account.maintenance_margin_snapshot, Some(250.0));
+        assert_eq!(sim.account.free_collateral_snapshot, Some(500.0));
+
+        // 1000 + 50 * (p - 100) = 0.05 * 50 * p  =>  p = 4000 / 47.5
+        let liq = sim.positions[0].liquidation_price.unwrap();
+        assert!((liq - 4000.0 / 47.5).abs() < 1e-9);
+
+        let closed = simulate(
+            &portfolio,
+            &[
+                changes[0].clone(),
+                PortfolioChange::Close {
+                    market_id: "m".to_string(),
+                },
+            ],
+            MarginRates::default(),
+        );
+        assert!(closed.positions.is_empty());
+        assert_eq!(closed.account.equity_snapshot, Some(1000.0));
+    }
+
+    #[test]
+    fn test_portfolio_margin_offsets_hedges() {
+        // 1000 long, 600 short: 400 net in full plus 1200 hedged at half
+        assert_eq!(portfolio_margin_base([1000.0, -600.0], 0.5), 1000.0);
+        assert_eq!(portfolio_margin_base([1000.0, -600.0], 0.0), 1600.0);
+        // 100 free at 10x is 1000 unhedged, or 2000 if fully hedged at half
+        assert_eq!(portfolio_max_notional(100.0, 10, 0.0, 0.5), 1000.0);
+        assert_eq!(portfolio_max_notional(100.0, 10, 5000.0, 0.5), 2000.0);
+        // Only 600 of the order can be offset
+        assert_eq!(portfolio_max_notional(100.0, 10, 600.0, 0.5), 1300.0);
+
+        let mut account = account(1000.0, 0.0);
+        account.portfolio_margin = Some(true);
+        account.portfolio_margin_offset = Some(0.5);
+        let mut short = long_position(-6.0, 100.0);
+        short.market.uuid = "e".to_string();
+        let portfolio = Portfolio {
+            account,
+            positions: vec![long_position(10.0, 100.0), short],
+        };
+        let sim = simulate(&portfolio, &[], MarginRates::default());
+        assert_eq!(sim.account.initial_margin_snapshot, Some(100.0));
+        assert_eq!(sim.account.maintenance_margin_snapshot, Some(50.0));
+        assert_eq!(sim.account.portfolio_margin, Some(true));
+    }
+
+    #[test]
+    fn test_stress_liquidates_long() {
+        let portfolio = Portfolio {
+            account: account(1000.0, 0.0),
+            positions: vec![long_position(10.0, 100.0)],
+        };
+
+        let results = stress(&portfolio, &StressScenario::standard());
+        assert_eq!(results.len(), 6);
+
+        // -20%: equity 800 on 800 notional, fine at 5% maintenance
+        assert!(results[0].liquidated.is_empty());
+        assert!((results[0].equity() - 800.0).abs() < 1e-9);
+
+        let leveraged = Portfolio {
+            account: account(100.0, 0.0),
+            positions: vec![long_position(10.0, 100.0)],
+        };
+        let results = stress(&leveraged, &[StressScenario::uniform(-0.10)]);
+        assert_eq!(results[0].liquidated, vec!["m".to_string()]);
+    }
+
+    #[test]
+    fn test_liquidation_buffer_and_reduction() {
+        let portfolio = Portfolio {
+            account: account(60.0, 0.0),
+            positions: vec![long_position(10.0, 100.0)],
+        };
+        let rates = MarginRates::default();
+        let simulation = simulate(&portfolio, &[], rates);
+
+        // 60 + 10 * (p - 100) = 0.5 * p  =>  p = 940 / 9.5
+        let guard = LiquidationGuard::default();
+        let alerts = positions_near_liquidation(&simulation, &guard);
+        assert_eq!(alerts.len(), 1);
+        assert!((alerts[0].liquidation_price - 940.0 / 9.5).abs() < 1e-9);
+
+        // Maintenance 50 on equity 60; reach 0.5 by closing 4 units (5 USDC each)
+        let quantity = reduction_to_target(&simulation, &simulation.positions[0], rates, 0.5);
+        assert!((quantity - 4.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_margin_status_picks_most_severe_tier() {
+        let tiers = default_margin_call_tiers();
+
+        let status = margin_status(&account(1000.0, 500.0), &tiers);
+        assert_eq!(status.coverage, 2.0);
+        assert!(status.tier.is_none());
+
+        let status = margin_status(&account(1000.0, 800.0), &tiers);
+        assert_eq!(status.tier.unwrap().name, "margin_call");
+
+        let status = margin_status(&account(1000.0, 900.0), &tiers);
+        assert_eq!(status.tier.unwrap().name, "pre_liquidation");
+    }
+
+    #[test]
+    fn test_var_single_position() {
+        let portfolio = Portfolio {
+            account: account(1000.0, 0.0),
+            positions: vec![long_position(10.0, 100.0)],
+        };
+
+        let closes = [100.0, 90.0, 99.0, 99.0, 108.9];
+        let klines = closes
+            .iter()
+            .enumerate()
+            .map(|(i, &close)| Kline {
+                timestamp: i as u64 * 60,
+                open: close,
+                high: close,
+                low: close,
+                close,
+                volume: 1.0,
+            })
+            .collect();
+        let candles = HashMap::from([("m".to_string(), klines)]);
+
+        // Returns: -10%, +10%, 0%, +10% on 1000 USDC notional
+        let estimate = var(&portfolio, &candles, 0.75, 1).unwrap();
+        assert_eq!(estimate.scenarios, 4);
+        assert!((estimate.value - 100.0).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_monitor_emits_on_level_change() {
+        let mut monitor = RiskMonitor::new(RiskThresholds::default());
+
+        assert!(monitor.evaluate(&account(1000.0, 100.0)).is_none());
+
+        let event = monitor.evaluate(&account(1000.0, 800.0)).unwrap();
+        assert_eq!(event.previous, RiskLevel::Healthy);
+        assert_eq!(event.level, RiskLevel::Danger);
+
+        assert!(monitor.evaluate(&account(1000.0, 850.0)).is_none());
+        let event = monitor.evaluate(&account(1000.0, 950.0)).unwrap();
+        assert_eq!(event.level, RiskLevel::Critical);
+    }
+}
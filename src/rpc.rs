@@ -1,36 +1,302 @@
 //! REST API client for reading market data, positions, and account information.
 
-use reqwest::Client;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Proxy, RequestBuilder, Response, StatusCode};
 use serde::{Deserialize, Serialize};
 
+#[cfg(feature = "chaos")]
+use crate::chaos::FaultInjector;
+use crate::delegation::Delegation;
 use crate::error::{Result, SdkError};
+use crate::retry::RetryPolicy;
+use crate::signer::{self, OrderSigner};
+use crate::throttle::RequestLimiter;
 use crate::types::{
-    AccountSummary, Balances, Depth, FundingEvent, MarketInfo, OpenOrder, Orderbook, Position,
-    Trade,
+    AccountSummary, AdlIndicator, Balances, Depth, ExchangeInfo, FeeTier, FundingEvent,
+    FundingPayment, FundingQuery, IndexComposition, InsuranceFund, Kline, LiquidationEvent,
+    MarketInfo, MarketPrices, OpenOrder, Orderbook, Page, Position, Trade, TradesQuery, Withdrawal,
 };
 
+/// Credentials sent with account-scoped requests (`/accounts`, `/balances`,
+/// `/positions`, `/orders/user`)
+#[derive(Clone, PartialEq, Eq)]
+pub enum RpcAuth {
+    /// Static API token sent as `Authorization: Bearer <token>`
+    Bearer(String),
+    /// Sign each request with the trading keypair. The signature covers
+    /// `GET\n<path and query>\n<unix ms>` and is sent with the public key and
+    /// timestamp in `X-Fermi-Pubkey`, `X-Fermi-Timestamp` and
+    /// `X-Fermi-Signature`.
+    Signed,
+}
+
+impl std::fmt::Debug for RpcAuth {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RpcAuth::Bearer(_) => f.write_str("Bearer(<redacted>)"),
+            RpcAuth::Signed => f.write_str("Signed"),
+        }
+    }
+}
+
+/// HTTP transport options for the rollup node's REST API
+#[derive(Clone, PartialEq)]
+pub struct HttpOptions {
+    /// Limit on a whole request, from connecting to reading the body
+    /// (`None` waits indefinitely)
+    pub request_timeout: Option<Duration>,
+    /// Limit on establishing a connection
+    pub connect_timeout: Option<Duration>,
+    /// Proxy URL for every request, e.g. "http://proxy:3128". Without one
+    /// the `HTTP_PROXY`/`HTTPS_PROXY` environment variables apply.
+    pub proxy: Option<String>,
+    /// Headers sent with every request, e.g. an API key for a gated node
+    pub headers: HashMap<String, String>,
+}
+
+impl Default for HttpOptions {
+    fn default() -> Self {
+        Self {
+            request_timeout: Some(Duration::from_secs(30)),
+            connect_timeout: Some(Duration::from_secs(10)),
+            proxy: None,
+            headers: HashMap::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for HttpOptions {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        // Header values often carry credentials
+        let mut headers: Vec<&String> = self.headers.keys().collect();
+        headers.sort();
+        f.debug_struct("HttpOptions")
+            .field("request_timeout", &self.request_timeout)
+            .field("connect_timeout", &self.connect_timeout)
+            .field("proxy", &self.proxy)
+            .field("headers", &headers)
+            .finish()
+    }
+}
+
+impl HttpOptions {
+    /// A reqwest client configured with these options
+    fn client(&self) -> Result<Client> {
+        let mut headers = HeaderMap::new();
+        for (name, value) in &self.headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|e| SdkError::Config(format!("header name {:?}: {}", name, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| SdkError::Config(format!("header {}: {}", name, e)))?;
+            headers.insert(name, value);
+        }
+
+        let mut builder = Client::builder().default_headers(headers);
+        if let Some(timeout) = self.request_timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(timeout) = self.connect_timeout {
+            builder = builder.connect_timeout(timeout);
+        }
+        if let Some(proxy) = &self.proxy {
+            let proxy = Proxy::all(proxy)
+                .map_err(|e| SdkError::Config(format!("proxy {:?}: {}", proxy, e)))?;
+            builder = builder.proxy(proxy);
+        }
+        builder
+            .build()
+            .map_err(|e| SdkError::Config(format!("HTTP client: {}", e)))
+    }
+}
+
+enum Credentials {
+    Bearer(String),
+    Signed(Arc<dyn OrderSigner>),
+}
+
 /// REST API client for the Fermi rollup node
+#[derive(Clone)]
 pub struct RpcClient {
-    client: Client,
+    /// Connection pool, shared with clones so any of them can release it
+    client: Arc<RwLock<Client>>,
+    http: HttpOptions,
     base_url: String,
+    credentials: Option<Arc<Credentials>>,
+    retry: RetryPolicy,
+    limiter: Option<RequestLimiter>,
+    #[cfg(feature = "chaos")]
+    chaos: Option<Arc<FaultInjector>>,
 }
 
 impl RpcClient {
-    /// Create a new RPC client
-    pub fn new(base_url: &str) -> Self {
-        Self {
-            client: Client::new(),
+    /// Create a new RPC client whose requests follow `http`
+    pub fn new(base_url: &str, http: &HttpOptions) -> Result<Self> {
+        Ok(Self {
+            client: Arc::new(RwLock::new(http.client()?)),
+            http: http.clone(),
             base_url: base_url.trim_end_matches('/').to_string(),
-        }
+            credentials: None,
+            retry: RetryPolicy::default(),
+            limiter: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        })
+    }
+
+    /// Authenticate account-scoped requests. `signer` signs requests
+    /// under `RpcAuth::Signed` and is otherwise unused.
+    pub fn with_auth(mut self, auth: &RpcAuth, signer: &Arc<dyn OrderSigner>) -> Self {
+        self.credentials = Some(Arc::new(match auth {
+            RpcAuth::Bearer(token) => Credentials::Bearer(token.clone()),
+            RpcAuth::Signed => Credentials::Signed(signer.clone()),
+        }));
+        self
+    }
+
+    /// Retry transient read failures under `policy`.
+    pub fn with_retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Send every request through `limiter`'s token bucket.
+    pub fn with_request_limiter(mut self, limiter: RequestLimiter) -> Self {
+        self.limiter = Some(limiter);
+        self
+    }
+
+    /// Release the connection pool shared with this client's clones. Later
+    /// requests from any of them open new connections.
+    pub fn close_connections(&self) {
+        *self.client.write().unwrap() = self
+            .http
+            .client()
+            .expect("HTTP options were accepted when the client was created");
+    }
+
+    /// Handle on the current connection pool
+    fn client(&self) -> Client {
+        self.client.read().unwrap().clone()
     }
 
     /// Create an RPC client with a custom reqwest client
     #[allow(dead_code)]
     pub fn with_client(base_url: &str, client: Client) -> Self {
         Self {
-            client,
+            client: Arc::new(RwLock::new(client)),
+            http: HttpOptions::default(),
             base_url: base_url.trim_end_matches('/').to_string(),
+            credentials: None,
+            retry: RetryPolicy::default(),
+            limiter: None,
+            #[cfg(feature = "chaos")]
+            chaos: None,
+        }
+    }
+
+    /// Route market-data reads through `injector`.
+    #[cfg(feature = "chaos")]
+    pub fn set_fault_injector(&mut self, injector: Arc<FaultInjector>) {
+        self.chaos = Some(injector);
+    }
+
+    /// `fresh`, or a stale earlier response to `url` if one is injected
+    #[cfg(feature = "chaos")]
+    fn market_data<T: Clone + Send + 'static>(&self, url: &str, fresh: T) -> T {
+        match &self.chaos {
+            Some(chaos) => chaos.market_data(url, fresh),
+            None => fresh,
+        }
+    }
+
+    #[cfg(not(feature = "chaos"))]
+    fn market_data<T>(&self, _url: &str, fresh: T) -> T {
+        fresh
+    }
+
+    /// GET `url`, retrying transient failures under the retry policy
+    async fn get(&self, url: &str) -> Result<Response> {
+        self.send_retrying(url, || async { Ok(self.client().get(url)) })
+            .await
+    }
+
+    /// GET an account-scoped `path`, signing each attempt afresh
+    async fn private_get(&self, path: &str) -> Result<Response> {
+        self.send_retrying(path, || self.private_request(path))
+            .await
+    }
+
+    /// Send the request built by `request`, rebuilding and resending it after
+    /// connection errors, timeouts, and retryable statuses
+    async fn send_retrying<F, Fut>(&self, what: &str, request: F) -> Result<Response>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<RequestBuilder>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let sent = self.send_limited(request().await?).await;
+            let (reason, delay) = match &sent {
+                Ok(response) if response.status() == StatusCode::TOO_MANY_REQUESTS => {
+                    (response.status().to_string(), retry_after(response))
+                }
+                Ok(response) if self.retry.retries_status(response.status()) => {
+                    (response.status().to_string(), None)
+                }
+                Err(e) if e.is_connect() || e.is_timeout() => (e.to_string(), None),
+                _ => return Ok(sent?),
+            };
+            if !self.retry.allows_retry(attempt) {
+                return Ok(sent?);
+            }
+            let what = format!("GET {}", what);
+            match delay {
+                Some(delay) => self.retry.backoff_for(delay, attempt, &what, reason).await,
+                None => self.retry.backoff(attempt, &what, reason).await,
+            }
+            attempt += 1;
+        }
+    }
+
+    /// Send `request` once it clears the request limiter. A 429 holds back
+    /// every request sharing the limiter until its Retry-After has passed.
+    async fn send_limited(&self, request: RequestBuilder) -> reqwest::Result<Response> {
+        if let Some(limiter) = &self.limiter {
+            limiter.acquire().await;
         }
+        let response = request.send().await?;
+        if response.status() == StatusCode::TOO_MANY_REQUESTS {
+            if let Some(limiter) = &self.limiter {
+                limiter.pause_for(retry_after(&response).unwrap_or_else(|| self.retry.delay(1)));
+            }
+        }
+        Ok(response)
+    }
+
+    /// GET request to an account-scoped `path`, with credentials attached
+    async fn private_request(&self, path: &str) -> Result<RequestBuilder> {
+        let request = self.client().get(format!("{}{}", self.base_url, path));
+        Ok(match self.credentials.as_deref() {
+            None => request,
+            Some(Credentials::Bearer(token)) => request.bearer_auth(token),
+            Some(Credentials::Signed(signer)) => {
+                let timestamp = SystemTime::now()
+                    .duration_since(UNIX_EPOCH)
+                    .map_err(|e| SdkError::Signing(e.to_string()))?
+                    .as_millis();
+                let message = format!("GET\n{}\n{}", path, timestamp);
+                let signature = signer::sign_hex(signer.as_ref(), message.as_bytes()).await?;
+                request
+                    .header("X-Fermi-Pubkey", signer.pubkey_string())
+                    .header("X-Fermi-Timestamp", timestamp.to_string())
+                    .header("X-Fermi-Signature", signature)
+            }
+        })
     }
 
     // =========================================================================
@@ -40,13 +306,10 @@ impl RpcClient {
     /// List all available markets
     pub async fn list_markets(&self) -> Result<Vec<MarketInfo>> {
         let url = format!("{}/markets", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let response = self.get(&url).await?;
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch markets: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch markets").await);
         }
 
         let markets: Vec<MarketInfo> = response.json().await?;
@@ -65,95 +328,270 @@ impl RpcClient {
     /// Get the orderbook for a market
     pub async fn get_orderbook(&self, market_id: &str) -> Result<Orderbook> {
         let url = format!("{}/markets/{}/orderbook", self.base_url, market_id);
-        let response = self.client.get(&url).send().await?;
+        let response = self.get(&url).await?;
 
         if response.status().is_client_error() {
             return Err(SdkError::MarketNotFound(market_id.to_string()));
         }
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch orderbook: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch orderbook").await);
         }
 
         let orderbook: Orderbook = response.json().await?;
-        Ok(orderbook)
+        Ok(self.market_data(&url, orderbook))
     }
 
     /// Get depth data (Binance-style format)
     pub async fn get_depth(&self, market_id: &str) -> Result<Depth> {
         let url = format!("{}/markets/{}/depth", self.base_url, market_id);
-        let response = self.client.get(&url).send().await?;
+        let response = self.get(&url).await?;
 
         if response.status().is_client_error() {
             return Err(SdkError::MarketNotFound(market_id.to_string()));
         }
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch depth: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch depth").await);
         }
 
         let depth: Depth = response.json().await?;
-        Ok(depth)
+        Ok(self.market_data(&url, depth))
     }
 
     /// Get recent trades for a market
     pub async fn get_trades(&self, market_id: &str) -> Result<Vec<Trade>> {
-        let url = format!("{}/markets/{}/trades", self.base_url, market_id);
-        let response = self.client.get(&url).send().await?;
+        Ok(self
+            .get_trades_page(market_id, &TradesQuery::default())
+            .await?
+            .items)
+    }
+
+    /// Get one page of trades for a market, filtered by `query`
+    pub async fn get_trades_page(
+        &self,
+        market_id: &str,
+        query: &TradesQuery,
+    ) -> Result<Page<Trade>> {
+        let url = format!(
+            "{}/markets/{}/trades{}",
+            self.base_url,
+            market_id,
+            query.to_query_string()
+        );
+        let response = self.get(&url).await?;
 
         if response.status().is_client_error() {
             return Err(SdkError::MarketNotFound(market_id.to_string()));
         }
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch trades: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch trades").await);
         }
 
+        let next_cursor = next_cursor(&response);
         let trades: Vec<Trade> = response.json().await?;
-        Ok(trades)
+        Ok(Page {
+            items: self.market_data(&url, trades),
+            next_cursor,
+        })
+    }
+
+    /// Get OHLCV candles of `interval` seconds for a market, optionally
+    /// bounded by unix-second timestamps.
+    ///
+    /// Returns `None` if the node does not serve klines.
+    pub async fn get_klines(
+        &self,
+        market_id: &str,
+        interval: u64,
+        start: Option<u64>,
+        end: Option<u64>,
+    ) -> Result<Option<Vec<Kline>>> {
+        let mut url = format!(
+            "{}/markets/{}/klines?interval={}",
+            self.base_url, market_id, interval
+        );
+        if let Some(start) = start {
+            url = format!("{}&start={}", url, start);
+        }
+        if let Some(end) = end {
+            url = format!("{}&end={}", url, end);
+        }
+        let response = self.get(&url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch klines").await);
+        }
+
+        let klines: Vec<Kline> = response.json().await?;
+        Ok(Some(self.market_data(&url, klines)))
     }
 
     /// Get funding events for a market
     pub async fn get_funding(&self, market_id: &str) -> Result<Vec<FundingEvent>> {
-        let url = format!("{}/markets/{}/funding", self.base_url, market_id);
-        let response = self.client.get(&url).send().await?;
+        Ok(self
+            .get_funding_page(market_id, &FundingQuery::default())
+            .await?
+            .items)
+    }
+
+    /// Get one page of funding events for a market, filtered by `query`
+    pub async fn get_funding_page(
+        &self,
+        market_id: &str,
+        query: &FundingQuery,
+    ) -> Result<Page<FundingEvent>> {
+        let url = format!(
+            "{}/markets/{}/funding{}",
+            self.base_url,
+            market_id,
+            query.to_query_string()
+        );
+        let response = self.get(&url).await?;
 
         if response.status().is_client_error() {
             return Err(SdkError::MarketNotFound(market_id.to_string()));
         }
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch funding: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch funding").await);
         }
 
+        let next_cursor = next_cursor(&response);
         let events: Vec<FundingEvent> = response.json().await?;
+        Ok(Page {
+            items: events,
+            next_cursor,
+        })
+    }
+
+    /// Get funding paid and received by an owner, optionally bounded by
+    /// unix-second timestamps
+    pub async fn get_account_funding(
+        &self,
+        owner: &str,
+        since: Option<u64>,
+        until: Option<u64>,
+    ) -> Result<Vec<FundingPayment>> {
+        let mut path = format!("/funding/{}", owner);
+        let bounds: Vec<String> = [("since", since), ("until", until)]
+            .into_iter()
+            .filter_map(|(name, value)| value.map(|v| format!("{}={}", name, v)))
+            .collect();
+        if !bounds.is_empty() {
+            path = format!("{}?{}", path, bounds.join("&"));
+        }
+        let response = self.private_get(&path).await?;
+        check_authorized(&response)?;
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch funding payments").await);
+        }
+
+        let payments: Vec<FundingPayment> = response.json().await?;
+        Ok(payments)
+    }
+
+    /// Get recent liquidations in a market.
+    ///
+    /// Nodes without a liquidations endpoint fall back to recent trades
+    /// flagged as liquidations.
+    pub async fn get_liquidations(&self, market_id: &str) -> Result<Vec<LiquidationEvent>> {
+        let url = format!("{}/markets/{}/liquidations", self.base_url, market_id);
+        let response = self.get(&url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            let trades = self.get_trades(market_id).await?;
+            return Ok(trades
+                .iter()
+                .filter_map(|t| LiquidationEvent::from_trade(market_id, t))
+                .collect());
+        }
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch liquidations").await);
+        }
+
+        let mut events: Vec<LiquidationEvent> = response.json().await?;
+        for event in &mut events {
+            if event.market_id.is_empty() {
+                event.market_id = market_id.to_string();
+            }
+        }
         Ok(events)
     }
 
+    /// Get the sources behind a market's index price.
+    ///
+    /// Returns `None` if the node does not publish index composition.
+    pub async fn get_index_composition(&self, market_id: &str) -> Result<Option<IndexComposition>> {
+        let url = format!("{}/markets/{}/index", self.base_url, market_id);
+        let response = self.get(&url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch index composition").await);
+        }
+
+        let composition: IndexComposition = response.json().await?;
+        Ok(Some(composition))
+    }
+
+    /// Get the current mark and index price of a market, or `None` if the
+    /// node does not publish them
+    pub async fn get_prices(&self, market_id: &str) -> Result<Option<MarketPrices>> {
+        let url = format!("{}/markets/{}/prices", self.base_url, market_id);
+        let response = self.get(&url).await?;
+
+        if response.status() == reqwest::StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch prices").await);
+        }
+
+        let prices: MarketPrices = response.json().await?;
+        Ok(Some(self.market_data(&url, prices)))
+    }
+
+    /// Get the auto-deleveraging ranking for every position in a market
+    pub async fn get_adl_ranking(&self, market_id: &str) -> Result<Vec<AdlIndicator>> {
+        let url = format!("{}/markets/{}/adl", self.base_url, market_id);
+        let response = self.get(&url).await?;
+
+        // Also what nodes without ADL support answer
+        if response.status() == StatusCode::NOT_FOUND {
+            return Err(SdkError::MarketNotFound(market_id.to_string()));
+        }
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch ADL ranking").await);
+        }
+
+        let ranking: Vec<AdlIndicator> = response.json().await?;
+        Ok(ranking)
+    }
+
     // =========================================================================
     // Account queries
     // =========================================================================
 
     /// Get account summary for an owner
     pub async fn get_account(&self, owner: &str) -> Result<AccountSummary> {
-        let url = format!("{}/accounts/{}", self.base_url, owner);
-        let response = self.client.get(&url).send().await?;
-
-        if response.status().is_client_error() {
-            // Account might not exist yet, return empty account
-            return Ok(AccountSummary {
+        // Account might not exist yet, return empty account
+        Ok(self
+            .find_account(owner)
+            .await?
+            .unwrap_or_else(|| AccountSummary {
                 owner: Some(owner.to_string()),
                 usdc_collateral: 0.0,
                 equity_snapshot: None,
@@ -163,24 +601,85 @@ impl RpcClient {
                 maintenance_margin_snapshot: None,
                 free_collateral_snapshot: None,
                 available_withdrawal_snapshot: None,
-            });
+                portfolio_margin: None,
+                portfolio_margin_offset: None,
+            }))
+    }
+
+    /// Get account summary for an owner, or `None` if the account does not exist
+    pub async fn find_account(&self, owner: &str) -> Result<Option<AccountSummary>> {
+        let response = self.private_get(&format!("/accounts/{}", owner)).await?;
+        check_authorized(&response)?;
+
+        if response.status().is_client_error() {
+            return Ok(None);
         }
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch account: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch account").await);
         }
 
         let account: AccountSummary = response.json().await?;
-        Ok(account)
+        Ok(Some(account))
+    }
+
+    /// Get an owner's fee tier, or `None` if the node does not publish tiers
+    pub async fn get_fee_tier(&self, owner: &str) -> Result<Option<FeeTier>> {
+        let response = self.private_get(&format!("/fees/{}", owner)).await?;
+        check_authorized(&response)?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(None);
+        }
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch fee tier").await);
+        }
+
+        let tier: FeeTier = response.json().await?;
+        Ok(Some(tier))
+    }
+
+    /// Submit a signed account registration.
+    ///
+    /// Returns `false` if the node has no registration endpoint.
+    pub async fn register_account(
+        &self,
+        owner: &str,
+        timestamp: u64,
+        signature: &str,
+    ) -> Result<bool> {
+        #[derive(Serialize)]
+        struct RegisterRequest<'a> {
+            owner: &'a str,
+            timestamp: u64,
+            signature: &'a str,
+        }
+
+        let url = format!("{}/accounts/register", self.base_url);
+        let request = RegisterRequest {
+            owner,
+            timestamp,
+            signature,
+        };
+        let response = self
+            .send_limited(self.client().post(&url).json(&request))
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to register account").await);
+        }
+
+        Ok(true)
     }
 
     /// Get token balances for an owner
     pub async fn get_balances(&self, owner: &str) -> Result<Balances> {
-        let url = format!("{}/balances/{}", self.base_url, owner);
-        let response = self.client.get(&url).send().await?;
+        let response = self.private_get(&format!("/balances/{}", owner)).await?;
+        check_authorized(&response)?;
 
         if response.status().is_client_error() {
             // No balances yet
@@ -190,10 +689,7 @@ impl RpcClient {
         }
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch balances: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch balances").await);
         }
 
         let balances: Balances = response.json().await?;
@@ -202,18 +698,16 @@ impl RpcClient {
 
     /// Get positions, optionally filtered by owner
     pub async fn get_positions(&self, owner: Option<&str>) -> Result<Vec<Position>> {
-        let url = match owner {
-            Some(o) => format!("{}/positions?owner={}", self.base_url, o),
-            None => format!("{}/positions", self.base_url),
+        let path = match owner {
+            Some(o) => format!("/positions?owner={}", o),
+            None => "/positions".to_string(),
         };
 
-        let response = self.client.get(&url).send().await?;
+        let response = self.private_get(&path).await?;
+        check_authorized(&response)?;
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch positions: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch positions").await);
         }
 
         let positions: Vec<Position> = response.json().await?;
@@ -222,20 +716,119 @@ impl RpcClient {
 
     /// Get open orders for an owner
     pub async fn get_user_orders(&self, owner: &str) -> Result<Vec<OpenOrder>> {
-        let url = format!("{}/orders/user/{}", self.base_url, owner);
-        let response = self.client.get(&url).send().await?;
+        let response = self.private_get(&format!("/orders/user/{}", owner)).await?;
+        check_authorized(&response)?;
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch user orders: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch user orders").await);
         }
 
         let orders: Vec<OpenOrder> = response.json().await?;
         Ok(orders)
     }
 
+    /// Get withdrawals requested by an owner, with their settlement state
+    pub async fn get_withdrawals(&self, owner: &str) -> Result<Vec<Withdrawal>> {
+        let response = self.private_get(&format!("/withdrawals/{}", owner)).await?;
+        check_authorized(&response)?;
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch withdrawals").await);
+        }
+
+        let withdrawals: Vec<Withdrawal> = response.json().await?;
+        Ok(withdrawals)
+    }
+
+    /// Register a session-key delegation with the node
+    pub async fn register_delegation(&self, delegation: &Delegation) -> Result<()> {
+        let url = format!("{}/delegations", self.base_url);
+        let response = self
+            .send_limited(self.client().post(&url).json(delegation))
+            .await?;
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to register delegation").await);
+        }
+
+        Ok(())
+    }
+
+    /// Register a signing-key rotation with the node.
+    ///
+    /// Returns `false` if the node does not support key rotation.
+    pub async fn register_key_rotation(
+        &self,
+        old_key: &str,
+        new_key: &str,
+        old_signature: &str,
+        new_signature: &str,
+    ) -> Result<bool> {
+        #[derive(Serialize)]
+        struct RotationRequest<'a> {
+            old_key: &'a str,
+            new_key: &'a str,
+            old_signature: &'a str,
+            new_signature: &'a str,
+        }
+
+        let url = format!("{}/keys/rotate", self.base_url);
+        let request = RotationRequest {
+            old_key,
+            new_key,
+            old_signature,
+            new_signature,
+        };
+        let response = self
+            .send_limited(self.client().post(&url).json(&request))
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to register key rotation").await);
+        }
+
+        Ok(true)
+    }
+
+    /// Enable or disable portfolio margining for `owner`.
+    ///
+    /// Returns `false` when the node does not support portfolio margining.
+    pub async fn set_portfolio_margin(
+        &self,
+        owner: &str,
+        enabled: bool,
+        signature: &str,
+    ) -> Result<bool> {
+        #[derive(Serialize)]
+        struct PortfolioMarginRequest<'a> {
+            owner: &'a str,
+            enabled: bool,
+            signature: &'a str,
+        }
+
+        let url = format!("{}/account/portfolio_margin", self.base_url);
+        let request = PortfolioMarginRequest {
+            owner,
+            enabled,
+            signature,
+        };
+        let response = self
+            .send_limited(self.client().post(&url).json(&request))
+            .await?;
+
+        if response.status() == StatusCode::NOT_FOUND {
+            return Ok(false);
+        }
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to set portfolio margin").await);
+        }
+
+        Ok(true)
+    }
+
     // =========================================================================
     // Airdrop (testnet only)
     // =========================================================================
@@ -263,7 +856,9 @@ impl RpcClient {
             amount,
         };
 
-        let response = self.client.post(&url).json(&request).send().await?;
+        let response = self
+            .send_limited(self.client().post(&url).json(&request))
+            .await?;
 
         let status = response.status();
         let body: AirdropResponse = response.json().await.unwrap_or(AirdropResponse {
@@ -284,31 +879,115 @@ impl RpcClient {
         Ok(())
     }
 
+    // =========================================================================
+    // Exchange-wide queries
+    // =========================================================================
+
+    /// Get the insurance fund balance and history
+    pub async fn get_insurance_fund(&self) -> Result<InsuranceFund> {
+        let url = format!("{}/insurance-fund", self.base_url);
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch insurance fund").await);
+        }
+
+        let fund: InsuranceFund = response.json().await?;
+        Ok(fund)
+    }
+
+    /// Get global trading rules: fees, leverage tiers and order filters
+    pub async fn get_exchange_info(&self) -> Result<ExchangeInfo> {
+        let url = format!("{}/exchange-info", self.base_url);
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch exchange info").await);
+        }
+
+        let info: ExchangeInfo = response.json().await?;
+        Ok(info)
+    }
+
     // =========================================================================
     // Status
     // =========================================================================
 
     /// Get node status
-    #[allow(dead_code)]
     pub async fn get_status(&self) -> Result<NodeStatus> {
         let url = format!("{}/status", self.base_url);
-        let response = self.client.get(&url).send().await?;
+        let response = self.get(&url).await?;
 
         if !response.status().is_success() {
-            return Err(SdkError::Rpc(format!(
-                "Failed to fetch status: {}",
-                response.status()
-            )));
+            return Err(rpc_error(response, "Failed to fetch status").await);
         }
 
         let status: NodeStatus = response.json().await?;
         Ok(status)
     }
+
+    /// Get the node's wall-clock time in Unix milliseconds
+    pub async fn get_server_time(&self) -> Result<u64> {
+        let url = format!("{}/time", self.base_url);
+        let response = self.get(&url).await?;
+
+        if !response.status().is_success() {
+            return Err(rpc_error(response, "Failed to fetch server time").await);
+        }
+
+        let time: ServerTime = response.json().await?;
+        Ok(time.server_time_ms)
+    }
+}
+
+/// Fail on rejected credentials, which some endpoints would otherwise read
+/// as an empty account
+/// The typed error for a failed `response`, parsed from its body
+async fn rpc_error(response: Response, context: &str) -> SdkError {
+    let status = response.status();
+    let retry_after = retry_after(&response);
+    let body = response.text().await.unwrap_or_default();
+    SdkError::from_rpc_response(context, status, &body, retry_after)
+}
+
+/// Delay asked for by a `Retry-After` header in seconds
+fn retry_after(response: &Response) -> Option<Duration> {
+    response
+        .headers()
+        .get(reqwest::header::RETRY_AFTER)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.trim().parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Cursor for the next page of a paginated history endpoint, if any
+fn next_cursor(response: &Response) -> Option<String> {
+    response
+        .headers()
+        .get("x-next-cursor")
+        .and_then(|v| v.to_str().ok())
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+}
+
+fn check_authorized(response: &Response) -> Result<()> {
+    match response.status() {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN => Err(SdkError::Rpc(format!(
+            "Not authorized for {}: {}",
+            response.url().path(),
+            response.status()
+        ))),
+        _ => Ok(()),
+    }
+}
+
+#[derive(Deserialize)]
+struct ServerTime {
+    server_time_ms: u64,
 }
 
 /// Node status information
 #[derive(Debug, Clone, Deserialize)]
-#[allow(dead_code)]
 pub struct NodeStatus {
     pub block_height: u64,
     pub applied_batches: u64,
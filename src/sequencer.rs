@@ -0,0 +1,1005 @@
+//! Sequencer health monitoring, tick timing, and inclusion checks.
+//!
+//! Status snapshots from Continuum are turned into rates (ticks per second,
+//! backlog growth per second) and edge-triggered threshold events, so a bot
+//! can back off while the sequencer is congested. [`SequencerWatchdog`]
+//! goes further, watching for a stalled tick or node block height and
+//! pausing quoting or cancelling orders until health returns.
+
+use std::collections::VecDeque;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::client::FermiClient;
+use crate::continuum::{ContinuumClient, SequencerStatus};
+use crate::error::{Result, SdkError};
+use crate::throttle::SubmissionThrottle;
+use crate::types::{OrderResult, Pubkey};
+
+/// Limits that mark the sequencer as congested. `None` disables a check.
+#[derive(Debug, Clone)]
+pub struct SequencerThresholds {
+    /// Maximum pending transactions in the queue
+    pub max_pending: Option<u64>,
+    /// Maximum growth of the pending queue, in transactions per second
+    pub max_backlog_growth: Option<f64>,
+    /// Minimum tick production rate, in ticks per second
+    pub min_tick_rate: Option<f64>,
+    /// Maximum time to drain the pending queue at the reported TPS
+    pub max_drain_time: Option<Duration>,
+}
+
+impl Default for SequencerThresholds {
+    fn default() -> Self {
+        Self {
+            max_pending: Some(1_000),
+            max_backlog_growth: Some(50.0),
+            min_tick_rate: None,
+            max_drain_time: Some(Duration::from_secs(5)),
+        }
+    }
+}
+
+/// A congestion condition tracked by the monitor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequencerCondition {
+    /// Pending queue above `max_pending`
+    Backlog,
+    /// Pending queue growing faster than `max_backlog_growth`
+    BacklogGrowth,
+    /// Ticks produced slower than `min_tick_rate`
+    SlowTicks,
+    /// Pending queue would take longer than `max_drain_time` to clear
+    DrainTime,
+}
+
+const CONDITIONS: [SequencerCondition; 4] = [
+    SequencerCondition::Backlog,
+    SequencerCondition::BacklogGrowth,
+    SequencerCondition::SlowTicks,
+    SequencerCondition::DrainTime,
+];
+
+/// A threshold crossing in either direction.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SequencerEvent {
+    Breached(SequencerCondition),
+    Cleared(SequencerCondition),
+}
+
+/// Rates derived from status snapshots.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct SequencerMetrics {
+    /// Ticks produced per second since the previous snapshot
+    pub tick_rate: Option<f64>,
+    /// Change in pending transactions per second since the previous snapshot
+    pub backlog_growth: Option<f64>,
+    /// Time to clear the pending queue at the reported TPS
+    pub drain_time: Option<Duration>,
+}
+
+/// One monitor observation.
+#[derive(Debug, Clone)]
+pub struct SequencerUpdate {
+    pub status: SequencerStatus,
+    pub metrics: SequencerMetrics,
+    /// Threshold crossings caused by this snapshot
+    pub events: Vec<SequencerEvent>,
+    /// Whether any condition is in breach after this snapshot
+    pub congested: bool,
+}
+
+/// Derives metrics and threshold events from successive status snapshots.
+#[derive(Debug, Clone)]
+pub struct SequencerMonitor {
+    thresholds: SequencerThresholds,
+    previous: Option<(Instant, SequencerStatus)>,
+    breached: Vec<SequencerCondition>,
+}
+
+impl SequencerMonitor {
+    pub fn new(thresholds: SequencerThresholds) -> Self {
+        Self {
+            thresholds,
+            previous: None,
+            breached: Vec::new(),
+        }
+    }
+
+    /// Conditions currently in breach.
+    pub fn breached(&self) -> &[SequencerCondition] {
+        &self.breached
+    }
+
+    /// Whether any congestion condition is in breach.
+    pub fn is_congested(&self) -> bool {
+        !self.breached.is_empty()
+    }
+
+    /// Feed a snapshot taken now.
+    pub fn evaluate(&mut self, status: SequencerStatus) -> SequencerUpdate {
+        self.evaluate_at(status, Instant::now())
+    }
+
+    /// Feed a snapshot taken at `at`.
+    pub fn evaluate_at(&mut self, status: SequencerStatus, at: Instant) -> SequencerUpdate {
+        let drain_time = (status.transactions_per_second > 0.0).then(|| {
+            Duration::from_secs_f64(
+                status.pending_transactions as f64 / status.transactions_per_second,
+            )
+        });
+        let mut metrics = match &self.previous {
+            Some((then, prev)) if at > *then => {
+                let secs = at.duration_since(*then).as_secs_f64();
+                SequencerMetrics {
+                    tick_rate: Some(
+                        status.current_tick.saturating_sub(prev.current_tick) as f64 / secs,
+                    ),
+                    backlog_growth: Some(
+                        (status.pending_transactions as f64 - prev.pending_transactions as f64)
+                            / secs,
+                    ),
+                    drain_time: None,
+                }
+            }
+            _ => SequencerMetrics::default(),
+        };
+        metrics.drain_time = drain_time;
+
+        let mut events = Vec::new();
+        for condition in CONDITIONS {
+            let Some(breached) = self.check(condition, &status, &metrics) else {
+                continue;
+            };
+            let was_breached = self.breached.contains(&condition);
+            if breached && !was_breached {
+                self.breached.push(condition);
+                events.push(SequencerEvent::Breached(condition));
+            } else if !breached && was_breached {
+                self.breached.retain(|c| *c != condition);
+                events.push(SequencerEvent::Cleared(condition));
+            }
+        }
+
+        self.previous = Some((at, status.clone()));
+        SequencerUpdate {
+            status,
+            metrics,
+            events,
+            congested: self.is_congested(),
+        }
+    }
+
+    /// Whether `condition` is breached, or `None` if it cannot be judged yet.
+    fn check(
+        &self,
+        condition: SequencerCondition,
+        status: &SequencerStatus,
+        metrics: &SequencerMetrics,
+    ) -> Option<bool> {
+        match condition {
+            SequencerCondition::Backlog => {
+                let max = self.thresholds.max_pending?;
+                Some(status.pending_transactions > max)
+            }
+            SequencerCondition::BacklogGrowth => {
+                let max = self.thresholds.max_backlog_growth?;
+                Some(metrics.backlog_growth? > max)
+            }
+            SequencerCondition::SlowTicks => {
+                let min = self.thresholds.min_tick_rate?;
+                Some(metrics.tick_rate? < min)
+            }
+            SequencerCondition::DrainTime => {
+                let max = self.thresholds.max_drain_time?;
+                Some(metrics.drain_time? > max)
+            }
+        }
+    }
+
+    /// Spawn a task polling sequencer status every `poll_interval`.
+    ///
+    /// With `backpressure` set, quotes on the throttle are slowed by the given
+    /// factor while the sequencer is congested, and restored to the full rate
+    /// when the task ends. The task stops at the first poll after the
+    /// returned receiver is dropped.
+    pub(crate) fn watch(
+        continuum: ContinuumClient,
+        poll_interval: Duration,
+        thresholds: SequencerThresholds,
+        backpressure: Option<(SubmissionThrottle, f64)>,
+    ) -> (mpsc::UnboundedReceiver<SequencerUpdate>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut monitor = SequencerMonitor::new(thresholds);
+
+        let handle = tokio::spawn(async move {
+            let mut slowdown = backpressure.map(|(throttle, factor)| SlowdownGuard {
+                throttle,
+                factor,
+                slowed: false,
+            });
+            let mut interval = tokio::time::interval(poll_interval);
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+
+                let status = match continuum.get_status().await {
+                    Ok(status) => status,
+                    Err(e) => {
+                        warn!("Sequencer monitor failed to fetch status: {}", e);
+                        continue;
+                    }
+                };
+
+                let update = monitor.evaluate(status);
+                for event in &update.events {
+                    info!("Sequencer {:?}", event);
+                }
+                if let Some(slowdown) = &mut slowdown {
+                    if !update.events.is_empty() {
+                        slowdown.set(update.congested);
+                    }
+                }
+                if tx.send(update).is_err() {
+                    break;
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+/// Quote slowdown applied by a monitor task, lifted when the task ends
+/// however it ends.
+struct SlowdownGuard {
+    throttle: SubmissionThrottle,
+    factor: f64,
+    slowed: bool,
+}
+
+impl SlowdownGuard {
+    fn set(&mut self, slowed: bool) {
+        self.slowed = slowed;
+        self.throttle
+            .set_quote_slowdown(if slowed { self.factor } else { 1.0 });
+    }
+}
+
+impl Drop for SlowdownGuard {
+    fn drop(&mut self) {
+        if self.slowed {
+            self.throttle.set_quote_slowdown(1.0);
+        }
+    }
+}
+
+/// Quoting paused by the watchdog task, resumed when the task ends however
+/// it ends.
+struct PauseGuard {
+    client: Arc<FermiClient>,
+    paused: bool,
+}
+
+impl PauseGuard {
+    fn set(&mut self, paused: bool) {
+        self.paused = paused;
+        self.client.set_quoting_paused(paused);
+    }
+}
+
+impl Drop for PauseGuard {
+    fn drop(&mut self) {
+        if self.paused {
+            self.client.set_quoting_paused(false);
+        }
+    }
+}
+
+/// A sequencer or node health problem tracked by [`SequencerWatchdog`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HealthIssue {
+    /// Sequencer tick has not advanced for longer than `max_tick_stall`
+    TickStalled,
+    /// Pending queue above `max_pending` or growing faster than `max_backlog_growth`
+    BacklogExploding,
+    /// Node block height has not advanced for longer than `max_height_stall`
+    HeightFrozen,
+    /// `max_failed_polls` consecutive status requests failed
+    Unreachable,
+}
+
+const HEALTH_ISSUES: [HealthIssue; 4] = [
+    HealthIssue::TickStalled,
+    HealthIssue::BacklogExploding,
+    HealthIssue::HeightFrozen,
+    HealthIssue::Unreachable,
+];
+
+/// A health issue starting or clearing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogEvent {
+    Degraded(HealthIssue),
+    Recovered(HealthIssue),
+}
+
+/// Safety action the watchdog takes when health first degrades.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WatchdogAction {
+    /// Reject new quotes until every issue clears (`FermiClient::set_quoting_paused`)
+    PauseQuoting,
+    /// Cancel every open order
+    CancelAll,
+}
+
+/// Called with every watchdog event as it is observed.
+pub type WatchdogCallback = Arc<dyn Fn(&WatchdogEvent) + Send + Sync>;
+
+/// Stall limits and responses for [`SequencerWatchdog`]. `None` disables a
+/// check.
+#[derive(Clone)]
+pub struct WatchdogPolicy {
+    pub poll_interval: Duration,
+    /// Longest the sequencer tick may stay unchanged
+    pub max_tick_stall: Option<Duration>,
+    /// Longest the node block height may stay unchanged
+    pub max_height_stall: Option<Duration>,
+    /// Maximum pending transactions in the queue
+    pub max_pending: Option<u64>,
+    /// Maximum growth of the pending queue, in transactions per second
+    pub max_backlog_growth: Option<f64>,
+    /// Consecutive failed sequencer polls before it counts as unreachable
+    pub max_failed_polls: Option<u32>,
+    /// Run in order when the first issue appears
+    pub actions: Vec<WatchdogAction>,
+    pub callbacks: Vec<WatchdogCallback>,
+}
+
+impl Default for WatchdogPolicy {
+    fn default() -> Self {
+        Self {
+            poll_interval: Duration::from_secs(1),
+            max_tick_stall: Some(Duration::from_secs(5)),
+            max_height_stall: Some(Duration::from_secs(30)),
+            max_pending: Some(10_000),
+            max_backlog_growth: Some(500.0),
+            max_failed_polls: Some(3),
+            actions: vec![WatchdogAction::PauseQuoting],
+            callbacks: Vec::new(),
+        }
+    }
+}
+
+impl std::fmt::Debug for WatchdogPolicy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("WatchdogPolicy")
+            .field("poll_interval", &self.poll_interval)
+            .field("max_tick_stall", &self.max_tick_stall)
+            .field("max_height_stall", &self.max_height_stall)
+            .field("max_pending", &self.max_pending)
+            .field("max_backlog_growth", &self.max_backlog_growth)
+            .field("max_failed_polls", &self.max_failed_polls)
+            .field("actions", &self.actions)
+            .field("callbacks", &self.callbacks.len())
+            .finish()
+    }
+}
+
+impl WatchdogPolicy {
+    /// Add a callback run on every event.
+    pub fn on_event(mut self, callback: impl Fn(&WatchdogEvent) + Send + Sync + 'static) -> Self {
+        self.callbacks.push(Arc::new(callback));
+        self
+    }
+}
+
+/// Detects a stalled sequencer or node from successive polls.
+#[derive(Debug, Clone)]
+pub struct SequencerWatchdog {
+    policy: WatchdogPolicy,
+    backlog: SequencerMonitor,
+    /// Last tick seen and when it last changed
+    tick: Option<(u64, Instant)>,
+    /// Last block height seen and when it last changed
+    height: Option<(u64, Instant)>,
+    failed_polls: u32,
+    degraded: Vec<HealthIssue>,
+}
+
+impl SequencerWatchdog {
+    pub fn new(policy: WatchdogPolicy) -> Self {
+        let backlog = SequencerMonitor::new(SequencerThresholds {
+            max_pending: policy.max_pending,
+            max_backlog_growth: policy.max_backlog_growth,
+            min_tick_rate: None,
+            max_drain_time: None,
+        });
+        Self {
+            policy,
+            backlog,
+            tick: None,
+            height: None,
+            failed_polls: 0,
+            degraded: Vec::new(),
+        }
+    }
+
+    pub fn policy(&self) -> &WatchdogPolicy {
+        &self.policy
+    }
+
+    /// Issues currently present.
+    pub fn degraded(&self) -> &[HealthIssue] {
+        &self.degraded
+    }
+
+    pub fn is_healthy(&self) -> bool {
+        self.degraded.is_empty()
+    }
+
+    /// Feed one poll taken at `at`: the sequencer status, or `None` if the
+    /// request failed, and the node block height if known. Returns the
+    /// issues that appeared or cleared, after passing each to the policy's
+    /// callbacks.
+    pub fn observe_at(
+        &mut self,
+        status: Option<&SequencerStatus>,
+        block_height: Option<u64>,
+        at: Instant,
+    ) -> Vec<WatchdogEvent> {
+        match status {
+            Some(status) => {
+                self.failed_polls = 0;
+                advance(&mut self.tick, status.current_tick, at);
+                self.backlog.evaluate_at(status.clone(), at);
+            }
+            None => self.failed_polls += 1,
+        }
+        if let Some(height) = block_height {
+            advance(&mut self.height, height, at);
+        }
+
+        let mut events = Vec::new();
+        for issue in HEALTH_ISSUES {
+            let Some(present) = self.check(issue, status.is_some(), at) else {
+                continue;
+            };
+            let was_present = self.degraded.contains(&issue);
+            if present && !was_present {
+                self.degraded.push(issue);
+                events.push(WatchdogEvent::Degraded(issue));
+            } else if !present && was_present {
+                self.degraded.retain(|i| *i != issue);
+                events.push(WatchdogEvent::Recovered(issue));
+            }
+        }
+        for event in &events {
+            for callback in &self.policy.callbacks {
+                callback(event);
+            }
+        }
+        events
+    }
+
+    /// Whether `issue` is present, or `None` if this poll cannot tell.
+    fn check(&self, issue: HealthIssue, polled: bool, at: Instant) -> Option<bool> {
+        match issue {
+            HealthIssue::TickStalled => {
+                let (_, since) = self.tick?;
+                Some(at.duration_since(since) > self.policy.max_tick_stall?)
+            }
+            HealthIssue::BacklogExploding => polled.then(|| {
+                self.backlog.breached().iter().any(|c| {
+                    matches!(
+                        c,
+                        SequencerCondition::Backlog | SequencerCondition::BacklogGrowth
+                    )
+                })
+            }),
+            HealthIssue::HeightFrozen => {
+                let (_, since) = self.height?;
+                Some(at.duration_since(since) > self.policy.max_height_stall?)
+            }
+            HealthIssue::Unreachable => {
+                Some(self.failed_polls >= self.policy.max_failed_polls?.max(1))
+            }
+        }
+    }
+
+    /// Spawn a task polling the sequencer status and node block height every
+    /// `policy.poll_interval`.
+    ///
+    /// Each event is passed to the policy's callbacks and sent on the
+    /// returned channel. When the first issue appears the policy's actions
+    /// run; quoting paused by the watchdog resumes once every issue has
+    /// cleared, or when the task ends. The task stops at the first poll
+    /// after the returned receiver is dropped.
+    pub fn spawn(
+        client: Arc<FermiClient>,
+        policy: WatchdogPolicy,
+    ) -> (mpsc::UnboundedReceiver<WatchdogEvent>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let mut watchdog = SequencerWatchdog::new(policy);
+
+        let handle = tokio::spawn(async move {
+            let mut pause = PauseGuard {
+                client: client.clone(),
+                paused: false,
+            };
+            let mut interval = tokio::time::interval(watchdog.policy.poll_interval);
+            loop {
+                interval.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+
+                let status = match client.get_sequencer_status().await {
+                    Ok(status) => Some(status),
+                    Err(e) => {
+                        warn!("Sequencer watchdog failed to fetch status: {}", e);
+                        None
+                    }
+                };
+                let height = match client.get_node_status().await {
+                    Ok(node) => Some(node.block_height),
+                    Err(e) => {
+                        warn!("Sequencer watchdog failed to fetch node status: {}", e);
+                        None
+                    }
+                };
+
+                let was_healthy = watchdog.is_healthy();
+                let events = watchdog.observe_at(status.as_ref(), height, Instant::now());
+                if events.is_empty() {
+                    continue;
+                }
+                for event in &events {
+                    warn!("Sequencer watchdog {:?}", event);
+                }
+
+                let pauses = watchdog
+                    .policy
+                    .actions
+                    .contains(&WatchdogAction::PauseQuoting);
+                if was_healthy && !watchdog.is_healthy() {
+                    for action in &watchdog.policy.actions {
+                        match action {
+                            WatchdogAction::PauseQuoting => pause.set(true),
+                            WatchdogAction::CancelAll => {
+                                let mut failures = Vec::new();
+                                let cancelled = client.cancel_open_orders(&mut failures).await;
+                                info!("Sequencer watchdog cancelled {} orders", cancelled.len());
+                                for failure in failures {
+                                    warn!("Sequencer watchdog cancel-all: {}", failure);
+                                }
+                            }
+                        }
+                    }
+                } else if !was_healthy && watchdog.is_healthy() && pauses {
+                    pause.set(false);
+                }
+
+                for event in events {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+/// Record `value` at `at`, keeping the time it last changed.
+fn advance(last: &mut Option<(u64, Instant)>, value: u64, at: Instant) {
+    match last {
+        Some((seen, _)) if *seen == value => {}
+        _ => *last = Some((value, at)),
+    }
+}
+
+/// Maps sequencer ticks to wall-clock time from observed tick numbers.
+///
+/// Feed it `current_tick` samples (or call [`TickClock::sample`]); the tick
+/// duration is the average over the retained window.
+#[derive(Debug, Clone)]
+pub struct TickClock {
+    samples: VecDeque<(Instant, u64)>,
+    capacity: usize,
+}
+
+impl Default for TickClock {
+    fn default() -> Self {
+        Self::new(32)
+    }
+}
+
+impl TickClock {
+    /// A clock that keeps the latest `capacity` samples (at least two).
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            samples: VecDeque::new(),
+            capacity: capacity.max(2),
+        }
+    }
+
+    /// Record the sequencer's current tick as observed now.
+    pub fn record(&mut self, tick: u64) {
+        self.record_at(tick, Instant::now());
+    }
+
+    /// Record the sequencer's current tick as observed at `at`.
+    pub fn record_at(&mut self, tick: u64, at: Instant) {
+        if self.samples.len() == self.capacity {
+            self.samples.pop_front();
+        }
+        self.samples.push_back((at, tick));
+    }
+
+    /// Fetch sequencer status and record its tick.
+    pub async fn sample(&mut self, client: &FermiClient) -> Result<SequencerStatus> {
+        let status = client.get_sequencer_status().await?;
+        self.record(status.current_tick);
+        Ok(status)
+    }
+
+    /// Average time per tick over the sample window.
+    pub fn tick_duration(&self) -> Option<Duration> {
+        let (first_at, first_tick) = self.samples.front()?;
+        let (last_at, last_tick) = self.samples.back()?;
+        let ticks = last_tick.checked_sub(*first_tick).filter(|t| *t > 0)?;
+        Some(last_at.duration_since(*first_at) / ticks as u32)
+    }
+
+    /// Estimated tick number at `at`, extrapolated from the latest sample.
+    pub fn tick_at(&self, at: Instant) -> Option<f64> {
+        let tick = self.tick_duration()?.as_secs_f64();
+        let (last_at, last_tick) = self.samples.back()?;
+        let elapsed = if at >= *last_at {
+            at.duration_since(*last_at).as_secs_f64()
+        } else {
+            -last_at.duration_since(at).as_secs_f64()
+        };
+        Some(*last_tick as f64 + elapsed / tick)
+    }
+
+    /// When `tick` is expected to start.
+    pub fn instant_of(&self, tick: u64) -> Option<Instant> {
+        let duration = self.tick_duration()?;
+        let (last_at, last_tick) = self.samples.back()?;
+        if tick >= *last_tick {
+            Some(*last_at + duration * (tick - last_tick) as u32)
+        } else {
+            last_at.checked_sub(duration * (last_tick - tick) as u32)
+        }
+    }
+
+    /// Time from `now` until `tick` starts (zero if it should already have).
+    pub fn eta_at(&self, tick: u64, now: Instant) -> Option<Duration> {
+        Some(self.instant_of(tick)?.saturating_duration_since(now))
+    }
+
+    /// Time until `tick` starts, e.g. an order's `expected_tick`.
+    pub fn eta(&self, tick: u64) -> Option<Duration> {
+        self.eta_at(tick, Instant::now())
+    }
+}
+
+/// Offset of the local wall clock from the server's, estimated from the
+/// timing of a server-time request.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ClockSkew {
+    /// Server time minus local time in milliseconds (positive = local clock behind)
+    pub offset_ms: i64,
+    pub round_trip: Duration,
+    /// Half the round trip; the offset is accurate to within this
+    pub one_way_latency: Duration,
+}
+
+impl ClockSkew {
+    /// Estimate from one request: local send and receive times and the
+    /// server's timestamp, all in Unix milliseconds. The server is assumed to
+    /// have read its clock halfway through the round trip.
+    pub fn from_exchange(sent_ms: u64, server_ms: u64, received_ms: u64) -> Self {
+        let round_trip = received_ms.saturating_sub(sent_ms);
+        let midpoint = sent_ms + round_trip / 2;
+        Self {
+            offset_ms: server_ms as i64 - midpoint as i64,
+            round_trip: Duration::from_millis(round_trip),
+            one_way_latency: Duration::from_millis(round_trip / 2),
+        }
+    }
+
+    /// The estimate with the shortest round trip, which has the tightest bound.
+    pub fn best(samples: &[ClockSkew]) -> Option<Self> {
+        samples.iter().min_by_key(|s| s.round_trip).copied()
+    }
+
+    /// Convert a local Unix time in milliseconds to server time.
+    pub fn to_server_ms(&self, local_ms: u64) -> u64 {
+        local_ms.saturating_add_signed(self.offset_ms)
+    }
+}
+
+/// A transaction as recorded in a sequenced tick.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ProofEntry {
+    pub tx_hash: String,
+    /// Position within the tick
+    pub sequence_number: u64,
+    pub public_key: Vec<u8>,
+    pub nonce: u64,
+}
+
+/// The sequenced tick that claims to contain a transaction.
+#[derive(Debug, Clone)]
+pub struct InclusionProof {
+    pub tick_number: u64,
+    pub timestamp: u64,
+    pub transaction_batch_hash: String,
+    /// VDF output of the previous tick, chaining this tick to it
+    pub previous_output: String,
+    pub vdf_input: String,
+    pub vdf_output: String,
+    /// Every transaction in the tick, in sequence order
+    pub entries: Vec<ProofEntry>,
+}
+
+/// Where a checked transaction was sequenced, according to the sequencer.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InclusionReceipt {
+    pub tick_number: u64,
+    /// Position within the tick
+    pub sequence_number: u64,
+    /// Whether the tick matches the `expected_tick` returned at submission
+    pub at_expected_tick: bool,
+}
+
+/// Check that `proof` records `result` as submitted by `owner`.
+///
+/// The transaction must appear in the tick with the signer's public key and
+/// the order ID as nonce, and the tick's entries must be in strictly
+/// increasing sequence order.
+///
+/// This is a consistency check of the sequencer's own tick data, not a
+/// cryptographic proof. The batch hash, the transaction signature and the
+/// chain to the previous tick are not verified, as their construction is
+/// sequencer-defined, so a sequencer serving false data can pass it.
+pub fn check_inclusion(
+    result: &OrderResult,
+    owner: &Pubkey,
+    proof: &InclusionProof,
+) -> Result<InclusionReceipt> {
+    let entry = proof
+        .entries
+        .iter()
+        .find(|e| e.tx_hash == result.tx_hash)
+        .ok_or_else(|| {
+            SdkError::InclusionCheck(format!(
+                "{} not in tick {}",
+                result.tx_hash, proof.tick_number
+            ))
+        })?;
+
+    if entry.public_key.as_slice() != owner.as_ref() {
+        return Err(SdkError::InclusionCheck(format!(
+            "{} was signed by a different key",
+            result.tx_hash
+        )));
+    }
+    if entry.nonce != result.order_id {
+        return Err(SdkError::InclusionCheck(format!(
+            "{} carries nonce {}, expected order {}",
+            result.tx_hash, entry.nonce, result.order_id
+        )));
+    }
+    if proof
+        .entries
+        .windows(2)
+        .any(|w| w[0].sequence_number >= w[1].sequence_number)
+    {
+        return Err(SdkError::InclusionCheck(format!(
+            "tick {} is not in sequence order",
+            proof.tick_number
+        )));
+    }
+
+    Ok(InclusionReceipt {
+        tick_number: proof.tick_number,
+        sequence_number: entry.sequence_number,
+        at_expected_tick: proof.tick_number == result.expected_tick,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn status(tick: u64, pending: u64) -> SequencerStatus {
+        SequencerStatus {
+            current_tick: tick,
+            total_transactions: 0,
+            pending_transactions: pending,
+            uptime_seconds: 0,
+            transactions_per_second: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_drain_time_uses_reported_tps() {
+        let mut monitor = SequencerMonitor::new(SequencerThresholds::default());
+        let update = monitor.evaluate(SequencerStatus {
+            transactions_per_second: 100.0,
+            ..status(1, 800)
+        });
+        assert_eq!(update.metrics.drain_time, Some(Duration::from_secs(8)));
+        assert!(update.congested);
+        assert_eq!(
+            update.events,
+            vec![SequencerEvent::Breached(SequencerCondition::DrainTime)]
+        );
+    }
+
+    #[test]
+    fn test_tick_clock_eta() {
+        let mut clock = TickClock::new(3);
+        let start = Instant::now();
+        assert_eq!(clock.tick_duration(), None);
+
+        for i in 0..4u32 {
+            clock.record_at(100 + 10 * i as u64, start + Duration::from_millis(500) * i);
+        }
+        assert_eq!(clock.tick_duration(), Some(Duration::from_millis(50)));
+
+        let last = start + Duration::from_millis(1500);
+        assert_eq!(
+            clock.tick_at(last + Duration::from_millis(100)),
+            Some(132.0)
+        );
+        assert_eq!(clock.eta_at(140, last), Some(Duration::from_millis(500)));
+        assert_eq!(clock.eta_at(120, last), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn test_clock_skew_prefers_shortest_round_trip() {
+        // Local clock 250ms behind, 40ms and 200ms round trips
+        let fast = ClockSkew::from_exchange(1_000, 1_270, 1_040);
+        let slow = ClockSkew::from_exchange(2_000, 2_350, 2_200);
+        assert_eq!(fast.offset_ms, 250);
+        assert_eq!(fast.one_way_latency, Duration::from_millis(20));
+        assert_eq!(slow.offset_ms, 250);
+
+        let best = ClockSkew::best(&[slow, fast]).unwrap();
+        assert_eq!(best, fast);
+        assert_eq!(best.to_server_ms(5_000), 5_250);
+        assert_eq!(ClockSkew::best(&[]), None);
+    }
+
+    #[test]
+    fn test_check_inclusion() {
+        let owner = Pubkey([7; 32]);
+        let entry = |hash: &str, seq: u64, nonce: u64| ProofEntry {
+            tx_hash: hash.to_string(),
+            sequence_number: seq,
+            public_key: owner.0.to_vec(),
+            nonce,
+        };
+        let mut proof = InclusionProof {
+            tick_number: 42,
+            timestamp: 0,
+            transaction_batch_hash: String::new(),
+            previous_output: String::new(),
+            vdf_input: String::new(),
+            vdf_output: String::new(),
+            entries: vec![entry("a", 0, 1), entry("mine", 1, 99)],
+        };
+        let result = OrderResult {
+            order_id: 99,
+            sequence_number: 1,
+            expected_tick: 41,
+            tx_hash: "mine".to_string(),
+            immediate: None,
+        };
+
+        let receipt = check_inclusion(&result, &owner, &proof).unwrap();
+        assert_eq!(receipt.sequence_number, 1);
+        assert!(!receipt.at_expected_tick);
+
+        proof.entries[1].nonce = 98;
+        assert!(check_inclusion(&result, &owner, &proof).is_err());
+        assert!(check_inclusion(&result, &Pubkey([8; 32]), &proof).is_err());
+    }
+
+    #[test]
+    fn test_monitor_rates_and_events() {
+        let mut monitor = SequencerMonitor::new(SequencerThresholds {
+            max_pending: Some(500),
+            max_backlog_growth: Some(100.0),
+            min_tick_rate: Some(5.0),
+            max_drain_time: None,
+        });
+        let start = Instant::now();
+
+        let first = monitor.evaluate_at(status(100, 100), start);
+        assert_eq!(first.metrics, SequencerMetrics::default());
+        assert!(first.events.is_empty());
+
+        let second = monitor.evaluate_at(status(110, 400), start + Duration::from_secs(2));
+        assert_eq!(second.metrics.tick_rate, Some(5.0));
+        assert_eq!(second.metrics.backlog_growth, Some(150.0));
+        assert_eq!(
+            second.events,
+            vec![SequencerEvent::Breached(SequencerCondition::BacklogGrowth)]
+        );
+
+        let third = monitor.evaluate_at(status(112, 600), start + Duration::from_secs(4));
+        assert_eq!(
+            third.events,
+            vec![
+                SequencerEvent::Breached(SequencerCondition::Backlog),
+                SequencerEvent::Cleared(SequencerCondition::BacklogGrowth),
+                SequencerEvent::Breached(SequencerCondition::SlowTicks),
+            ]
+        );
+        assert!(monitor.is_congested());
+    }
+
+    #[test]
+    fn test_watchdog_detects_stalls() {
+        let fired = Arc::new(std::sync::Mutex::new(Vec::new()));
+        let seen = fired.clone();
+        let mut watchdog = SequencerWatchdog::new(
+            WatchdogPolicy {
+                max_tick_stall: Some(Duration::from_secs(5)),
+                max_height_stall: Some(Duration::from_secs(10)),
+                max_pending: Some(1_000),
+                max_backlog_growth: None,
+                max_failed_polls: Some(2),
+                ..WatchdogPolicy::default()
+            }
+            .on_event(move |event| seen.lock().unwrap().push(*event)),
+        );
+        let start = Instant::now();
+        let after = |secs: u64| start + Duration::from_secs(secs);
+
+        assert!(watchdog
+            .observe_at(Some(&status(1, 0)), Some(50), after(0))
+            .is_empty());
+        // Tick frozen past the limit; height still moving
+        assert_eq!(
+            watchdog.observe_at(Some(&status(1, 0)), Some(51), after(6)),
+            vec![WatchdogEvent::Degraded(HealthIssue::TickStalled)]
+        );
+        // One failed poll is tolerated, the second is not
+        assert_eq!(watchdog.observe_at(None, None, after(7)), vec![]);
+        assert_eq!(
+            watchdog.observe_at(None, None, after(8)),
+            vec![WatchdogEvent::Degraded(HealthIssue::Unreachable)]
+        );
+        assert_eq!(
+            watchdog.observe_at(Some(&status(2, 5_000)), Some(51), after(12)),
+            vec![
+                WatchdogEvent::Recovered(HealthIssue::TickStalled),
+                WatchdogEvent::Degraded(HealthIssue::BacklogExploding),
+                WatchdogEvent::Recovered(HealthIssue::Unreachable),
+            ]
+        );
+        assert_eq!(
+            watchdog.observe_at(Some(&status(3, 0)), Some(51), after(17)),
+            vec![
+                WatchdogEvent::Recovered(HealthIssue::BacklogExploding),
+                WatchdogEvent::Degraded(HealthIssue::HeightFrozen),
+            ]
+        );
+        assert_eq!(watchdog.degraded(), &[HealthIssue::HeightFrozen]);
+        assert!(!watchdog.is_healthy());
+        assert_eq!(fired.lock().unwrap().len(), 7);
+    }
+}
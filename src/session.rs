@@ -0,0 +1,417 @@
+//! Client sessions for long-running strategies.
+//!
+//! A [`SessionSnapshot`] captures what a long-running strategy needs to pick
+//! up where it left off after a redeploy: the nonce floor, open orders,
+//! tracked ladders, and risk settings. Keys and endpoints are deliberately
+//! left out; `FermiClient::resume` takes them fresh.
+//!
+//! A [`TradingSession`] keeps a strategy running across connection drops
+//! within one process. It owns the client, its streams, and its managed
+//! orders; [`TradingSession::maintain`] checks the connection and, after a
+//! reconnect, restarts every stream and drops managed orders that no longer
+//! rest on the server.
+
+use std::collections::HashSet;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
+use tracing::{info, warn};
+
+use crate::client::{FermiClient, ShutdownPolicy, ShutdownReport};
+use crate::error::{Result, SdkError};
+use crate::execution::{LadderHandle, PeggedOrder, TriggerOrder};
+use crate::hooks::OrderLimits;
+use crate::risk::MarginCallTier;
+use crate::throttle::RateLimit;
+use crate::types::{OpenOrder, OrderResult};
+
+/// Snapshot format written by this version of the SDK
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Serializable client session state.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub version: u32,
+    /// Unix seconds
+    pub taken_at: u64,
+    /// Base58 pubkey of the account the snapshot belongs to
+    pub owner: String,
+    /// Last order ID issued; resumed clients issue strictly greater IDs
+    pub nonce_floor: u64,
+    /// Server open orders at export time
+    pub open_orders: Vec<OpenOrder>,
+    /// Ladders the strategy is managing, added with [`SessionSnapshot::add_ladder`]
+    pub ladders: Vec<LadderHandle>,
+    pub order_limits: Option<OrderLimits>,
+    pub margin_call_tiers: Vec<MarginCallTier>,
+    pub rate_limit: Option<RateLimit>,
+    pub max_resubmits: u32,
+}
+
+impl SessionSnapshot {
+    pub(crate) fn new(owner: String, nonce_floor: u64, open_orders: Vec<OpenOrder>) -> Self {
+        Self {
+            version: SNAPSHOT_VERSION,
+            taken_at: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map(|d| d.as_secs())
+                .unwrap_or(0),
+            owner,
+            nonce_floor,
+            open_orders,
+            ladders: Vec::new(),
+            order_limits: None,
+            margin_call_tiers: Vec::new(),
+            rate_limit: None,
+            max_resubmits: 0,
+        }
+    }
+
+    /// Include a managed ladder in the snapshot.
+    pub fn add_ladder(&mut self, ladder: &LadderHandle) {
+        self.ladders.push(ladder.clone());
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Parse a snapshot, rejecting formats newer than this SDK understands.
+    pub fn from_json(json: &str) -> Result<Self> {
+        let snapshot: SessionSnapshot = serde_json::from_str(json)?;
+        if snapshot.version > SNAPSHOT_VERSION {
+            return Err(SdkError::Config(format!(
+                "session snapshot version {} is newer than supported version {}",
+                snapshot.version, SNAPSHOT_VERSION
+            )));
+        }
+        Ok(snapshot)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<()> {
+        std::fs::write(path, self.to_json()?)
+            .map_err(|e| SdkError::Config(format!("failed to write session snapshot: {}", e)))
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let json = std::fs::read_to_string(path)
+            .map_err(|e| SdkError::Config(format!("failed to read session snapshot: {}", e)))?;
+        Self::from_json(&json)
+    }
+}
+
+/// Starts a stream's tasks against the current connection.
+type Subscribe = Box<dyn FnMut(&FermiClient) -> Vec<JoinHandle<()>> + Send>;
+
+struct SessionStream {
+    subscribe: Subscribe,
+    tasks: Vec<JoinHandle<()>>,
+    /// Whether the strategy dropped its receiver
+    closed: Box<dyn Fn() -> bool + Send>,
+}
+
+impl SessionStream {
+    fn restart(&mut self, client: &FermiClient) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+        self.tasks = (self.subscribe)(client);
+    }
+
+    fn stop(&mut self) {
+        for task in self.tasks.drain(..) {
+            task.abort();
+        }
+    }
+}
+
+/// Outcome of one [`TradingSession::maintain`] pass.
+#[derive(Debug, Clone, Default)]
+pub struct MaintenanceReport {
+    pub connected: bool,
+    /// The connection came back since the previous pass
+    pub reconnected: bool,
+    /// Streams started again, after a reconnect or because they ended
+    pub resubscribed: usize,
+    /// Managed orders found missing from the server's open orders
+    pub orders_dropped: usize,
+    /// Orders placed by pegs and triggers
+    pub placements: Vec<OrderResult>,
+    /// Steps that failed, in the order they ran
+    pub failures: Vec<String>,
+}
+
+/// A client together with the streams, managed orders, and risk limits a
+/// strategy runs on.
+///
+/// Streams are registered as factories so they can be started again after
+/// a reconnect; the receiver returned by [`TradingSession::subscribe`]
+/// stays valid throughout. Call [`TradingSession::maintain`] periodically.
+pub struct TradingSession {
+    client: FermiClient,
+    streams: Vec<SessionStream>,
+    pegs: Vec<PeggedOrder>,
+    ladders: Vec<LadderHandle>,
+    triggers: Vec<TriggerOrder>,
+    connected: bool,
+}
+
+impl TradingSession {
+    pub fn new(client: FermiClient) -> Self {
+        Self {
+            client,
+            streams: Vec::new(),
+            pegs: Vec::new(),
+            ladders: Vec::new(),
+            triggers: Vec::new(),
+            connected: true,
+        }
+    }
+
+    pub fn client(&self) -> &FermiClient {
+        &self.client
+    }
+
+    pub fn client_mut(&mut self) -> &mut FermiClient {
+        &mut self.client
+    }
+
+    /// Apply pre-trade limits to every order the session places.
+    pub fn set_order_limits(&mut self, limits: OrderLimits) {
+        self.client.set_order_limits(limits);
+    }
+
+    /// Start a stream and keep it running across reconnects.
+    ///
+    /// `watch` is one of the client's `watch_*` calls, e.g.
+    /// `|c| c.watch_liquidations("SOL-PERP", interval)`. It is called again
+    /// whenever the stream has to be restarted, and its output is forwarded
+    /// to the returned receiver. Dropping the receiver stops the stream.
+    pub fn subscribe<T, F>(&mut self, watch: F) -> mpsc::UnboundedReceiver<T>
+    where
+        T: Send + 'static,
+        F: Fn(&FermiClient) -> (mpsc::UnboundedReceiver<T>, JoinHandle<()>) + Send + 'static,
+    {
+        let (tx, rx) = mpsc::unbounded_channel();
+        let probe = tx.clone();
+        let mut subscribe: Subscribe = Box::new(move |client| {
+            let (mut inner, task) = watch(client);
+            let tx = tx.clone();
+            let forward = tokio::spawn(async move {
+                while let Some(item) = inner.recv().await {
+                    if tx.send(item).is_err() {
+                        return;
+                    }
+                }
+            });
+            vec![task, forward]
+        });
+        let tasks = subscribe(&self.client);
+        self.streams.push(SessionStream {
+            subscribe,
+            tasks,
+            closed: Box::new(move || probe.is_closed()),
+        });
+        rx
+    }
+
+    pub fn add_peg(&mut self, peg: PeggedOrder) {
+        self.pegs.push(peg);
+    }
+
+    pub fn add_ladder(&mut self, ladder: LadderHandle) {
+        self.ladders.push(ladder);
+    }
+
+    pub fn add_trigger(&mut self, trigger: TriggerOrder) {
+        self.triggers.push(trigger);
+    }
+
+    pub fn pegs(&self) -> &[PeggedOrder] {
+        &self.pegs
+    }
+
+    pub fn ladders(&self) -> &[LadderHandle] {
+        &self.ladders
+    }
+
+    pub fn triggers(&self) -> &[TriggerOrder] {
+        &self.triggers
+    }
+
+    /// Check the connection, recover from a reconnect, and advance managed
+    /// orders.
+    ///
+    /// While the node is unreachable nothing else is attempted. On the first
+    /// pass after it comes back, every stream is restarted and pegs and
+    /// ladders forget orders that are no longer open, so they are re-placed
+    /// or left alone instead of being cancelled twice. Streams whose task
+    /// ended on its own are restarted on any pass. Pegs are then refreshed
+    /// and unfired triggers checked. Failures are recorded in the report.
+    pub async fn maintain(&mut self) -> MaintenanceReport {
+        let mut report = MaintenanceReport::default();
+        self.streams.retain_mut(|stream| {
+            let closed = (stream.closed)();
+            if closed {
+                stream.stop();
+            }
+            !closed
+        });
+
+        if let Err(e) = self.check_connection().await {
+            if self.connected {
+                warn!("Session lost its connection: {}", e);
+            }
+            self.connected = false;
+            report.failures.push(format!("connection check: {}", e));
+            return report;
+        }
+        report.connected = true;
+
+        if !self.connected {
+            info!(
+                "Session reconnected; restarting {} streams",
+                self.streams.len()
+            );
+            self.connected = true;
+            report.reconnected = true;
+            for stream in &mut self.streams {
+                stream.restart(&self.client);
+            }
+            report.resubscribed = self.streams.len();
+            match self.revalidate().await {
+                Ok(dropped) => report.orders_dropped = dropped,
+                Err(e) => report.failures.push(format!("revalidation: {}", e)),
+            }
+        } else {
+            for stream in &mut self.streams {
+                if stream.tasks.iter().any(|t| t.is_finished()) {
+                    stream.restart(&self.client);
+                    report.resubscribed += 1;
+                }
+            }
+        }
+
+        for peg in &mut self.pegs {
+            match peg.refresh(&self.client).await {
+                Ok(Some(placed)) => report.placements.push(placed),
+                Ok(None) => {}
+                Err(e) => report.failures.push(format!("peg refresh: {}", e)),
+            }
+        }
+        for trigger in &mut self.triggers {
+            match trigger.check(&self.client).await {
+                Ok(Some(placed)) => report.placements.push(placed),
+                Ok(None) => {}
+                Err(e) => report
+                    .failures
+                    .push(format!("trigger {:?}: {}", trigger.condition(), e)),
+            }
+        }
+        report
+    }
+
+    async fn check_connection(&self) -> Result<()> {
+        self.client.get_sequencer_status().await?;
+        self.client.get_server_time().await?;
+        Ok(())
+    }
+
+    /// Match managed orders against the server's open orders.
+    async fn revalidate(&mut self) -> Result<usize> {
+        let open: HashSet<u64> = self
+            .client
+            .get_my_orders()
+            .await?
+            .iter()
+            .map(|o| o.order_id)
+            .collect();
+        let mut dropped = 0;
+        for peg in &mut self.pegs {
+            dropped += peg.retain_open(&open) as usize;
+        }
+        for ladder in &mut self.ladders {
+            dropped += ladder.retain_open(&open);
+        }
+        if dropped > 0 {
+            warn!("{} managed orders were gone after reconnecting", dropped);
+        }
+        Ok(dropped)
+    }
+
+    /// Client state plus the session's ladders, for a later
+    /// `FermiClient::resume`.
+    pub async fn snapshot(&self) -> Result<SessionSnapshot> {
+        let mut snapshot = self.client.export_state().await?;
+        for ladder in &self.ladders {
+            snapshot.add_ladder(ladder);
+        }
+        Ok(snapshot)
+    }
+
+    /// Stop every stream and shut the client down under `policy`.
+    pub async fn shutdown(mut self, policy: ShutdownPolicy) -> Result<ShutdownReport> {
+        for stream in &mut self.streams {
+            stream.stop();
+        }
+        self.client.shutdown(policy).await
+    }
+}
+
+impl Drop for TradingSession {
+    fn drop(&mut self) {
+        for stream in &mut self.streams {
+            stream.stop();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::execution::{LadderDistribution, LadderSpec};
+    use crate::types::{test_market, Side};
+
+    #[test]
+    fn test_snapshot_round_trip() {
+        let mut snapshot = SessionSnapshot::new("owner".to_string(), 42, Vec::new());
+        snapshot.order_limits = Some(OrderLimits {
+            max_leverage: Some(5),
+            ..Default::default()
+        });
+        snapshot.rate_limit = Some(RateLimit {
+            per_second: 10.0,
+            burst: 5,
+        });
+        snapshot.margin_call_tiers = vec![MarginCallTier::reduce("cut", 1.2, 0.5)];
+        let spec = LadderSpec {
+            side: Side::Sell,
+            total_quantity: 3.0,
+            price_from: 100.0,
+            price_to: 102.0,
+            num_orders: 3,
+            distribution: LadderDistribution::Geometric(2.0),
+            leverage: 2,
+        };
+        snapshot.add_ladder(&LadderHandle {
+            market: test_market(0, 0),
+            rungs: crate::execution::plan_ladder(&spec).unwrap(),
+            spec,
+        });
+
+        let restored = SessionSnapshot::from_json(&snapshot.to_json().unwrap()).unwrap();
+        assert_eq!(restored.nonce_floor, 42);
+        assert_eq!(restored.order_limits.unwrap().max_leverage, Some(5));
+        assert_eq!(restored.rate_limit, snapshot.rate_limit);
+        assert_eq!(restored.margin_call_tiers, snapshot.margin_call_tiers);
+        assert_eq!(restored.ladders[0].rungs(), snapshot.ladders[0].rungs());
+        assert_eq!(restored.ladders[0].market_id(), "m");
+
+        let mut future = snapshot;
+        future.version = SNAPSHOT_VERSION + 1;
+        assert!(SessionSnapshot::from_json(&future.to_json().unwrap()).is_err());
+    }
+}
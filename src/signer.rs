@@ -0,0 +1,142 @@
+//! Pluggable signing backends.
+//!
+//! Everything the SDK signs goes through [`OrderSigner`], so the key can
+//! live outside the process: on a hardware wallet, in a KMS, or behind a
+//! remote signing service. [`TradingKeypair`] is the in-process
+//! implementation.
+
+use std::sync::Arc;
+
+use async_trait::async_trait;
+
+use crate::error::{Result, SdkError};
+use crate::keypair::TradingKeypair;
+use crate::types::Pubkey;
+
+/// An ed25519 signer for orders, cancels, transfers, and signed requests.
+///
+/// Implementations must produce standard ed25519 signatures over the exact
+/// bytes given; the SDK hashes and prefixes messages itself.
+///
+/// ```rust,ignore
+/// struct KmsSigner { key_id: String, pubkey: Pubkey }
+///
+/// #[async_trait]
+/// impl OrderSigner for KmsSigner {
+///     async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+///         kms_sign_ed25519(&self.key_id, message).await
+///     }
+///
+///     fn pubkey(&self) -> Pubkey {
+///         self.pubkey
+///     }
+/// }
+/// ```
+#[async_trait]
+pub trait OrderSigner: Send + Sync {
+    /// Sign `message`, returning the 64-byte signature.
+    async fn sign(&self, message: &[u8]) -> Result<[u8; 64]>;
+
+    /// Public key the signatures verify against.
+    fn pubkey(&self) -> Pubkey;
+
+    /// The public key as a base58 string.
+    fn pubkey_string(&self) -> String {
+        self.pubkey().to_string()
+    }
+}
+
+#[async_trait]
+impl OrderSigner for TradingKeypair {
+    async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+        Ok(TradingKeypair::sign(self, message))
+    }
+
+    fn pubkey(&self) -> Pubkey {
+        TradingKeypair::pubkey(self)
+    }
+}
+
+#[async_trait]
+impl<S: OrderSigner + ?Sized> OrderSigner for Arc<S> {
+    async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+        (**self).sign(message).await
+    }
+
+    fn pubkey(&self) -> Pubkey {
+        (**self).pubkey()
+    }
+}
+
+/// Signer of a read-only client: holds no key and refuses to sign.
+pub(crate) struct NoSigner;
+
+#[async_trait]
+impl OrderSigner for NoSigner {
+    async fn sign(&self, _message: &[u8]) -> Result<[u8; 64]> {
+        Err(SdkError::Signing(
+            "read-only client has no signing key".into(),
+        ))
+    }
+
+    fn pubkey(&self) -> Pubkey {
+        Pubkey::default()
+    }
+}
+
+/// Sign `message` and hex-encode the signature.
+pub(crate) async fn sign_hex<S: OrderSigner + ?Sized>(
+    signer: &S,
+    message: &[u8],
+) -> Result<String> {
+    Ok(hex::encode(signer.sign(message).await?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::signing::{sign_perp_order, verify_order};
+    use crate::types::{MarginMode, PositionEffect, Side};
+
+    /// Stands in for a remote signer: only the signing call sees the key.
+    struct RemoteSigner(TradingKeypair);
+
+    #[async_trait]
+    impl OrderSigner for RemoteSigner {
+        async fn sign(&self, message: &[u8]) -> Result<[u8; 64]> {
+            tokio::task::yield_now().await;
+            Ok(self.0.sign(message))
+        }
+
+        fn pubkey(&self) -> Pubkey {
+            self.0.pubkey()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_signer_signs_verifiable_orders() {
+        let signer: Arc<dyn OrderSigner> = Arc::new(RemoteSigner(TradingKeypair::generate()));
+        let mint = Pubkey::new_from_array([1; 32]);
+        let signed = sign_perp_order(
+            signer.as_ref(),
+            7,
+            Side::Sell,
+            100,
+            1,
+            0,
+            &mint,
+            &mint,
+            1,
+            PositionEffect::Open,
+            MarginMode::Cross,
+            None,
+            false,
+        )
+        .await
+        .unwrap();
+
+        verify_order(&signed.request).unwrap();
+        assert_eq!(signed.request.intent.owner, signer.pubkey_string());
+        assert_eq!(signed.owner_bytes, signer.pubkey().to_bytes());
+    }
+}
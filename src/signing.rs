@@ -4,13 +4,15 @@
 //! `sequencer_client/scripts/place_perp_order_fixed.rs` which is the
 //! canonical perps order structure WITHOUT the `order_type` field.
 
-use borsh::BorshSerialize;
+use borsh::{BorshDeserialize, BorshSerialize};
 use sha2::{Digest, Sha256};
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 
 use crate::error::{Result, SdkError};
-use crate::keypair::TradingKeypair;
-use crate::types::{MarginMode, MarketKind, OrderSide, PositionEffect, Pubkey, Side};
+use crate::signer::OrderSigner;
+use crate::types::{
+    CollateralAction, MarginMode, MarketKind, OrderSide, PositionEffect, Pubkey, Side,
+};
 
 // =============================================================================
 // Signing prefixes (must match server)
@@ -18,6 +20,9 @@ use crate::types::{MarginMode, MarketKind, OrderSide, PositionEffect, Pubkey, Si
 
 const SIGNED_ORDER_PREFIX: &[u8] = b"FRM_DEX_ORDER:";
 const CANCEL_ORDER_PREFIX: &[u8] = b"FRM_DEX_CANCEL:";
+const TRANSFER_PREFIX: &[u8] = b"FRM_DEX_TRANSFER:";
+const DEPOSIT_PREFIX: &[u8] = b"FRM_DEX_DEPOSIT:";
+const WITHDRAW_PREFIX: &[u8] = b"FRM_DEX_WITHDRAW:";
 
 // =============================================================================
 // Borsh structures for signing (MUST match server exactly)
@@ -26,7 +31,7 @@ const CANCEL_ORDER_PREFIX: &[u8] = b"FRM_DEX_CANCEL:";
 /// PerpOrderIntentBorsh - EXACTLY matching server structure
 /// Reference: sequencer_client/scripts/place_perp_order_fixed.rs:43-59
 /// NOTE: NO order_type field (spot has it, perps don't)
-#[derive(Debug, Clone, BorshSerialize)]
+#[derive(Debug, Clone, PartialEq, BorshSerialize, BorshDeserialize)]
 struct PerpOrderIntentBorsh {
     order_id: u64,
     owner: Pubkey,
@@ -54,11 +59,30 @@ struct CancelOrderData {
     quote_mint: Pubkey,
 }
 
+/// TransferData for signing collateral transfers between accounts
+#[derive(Debug, Clone, BorshSerialize)]
+struct TransferData {
+    nonce: u64,
+    from: Pubkey,
+    to: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+}
+
+/// CollateralData for signing deposits into and withdrawals from margin
+#[derive(Debug, Clone, BorshSerialize)]
+struct CollateralData {
+    nonce: u64,
+    owner: Pubkey,
+    mint: Pubkey,
+    amount: u64,
+}
+
 // =============================================================================
 // JSON DTOs for API submission
 // =============================================================================
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct OrderIntentDto {
     pub order_id: u64,
     pub owner: String,
@@ -81,10 +105,14 @@ pub struct OrderIntentDto {
     pub liquidation: bool,
 }
 
-#[derive(Debug, Clone, Serialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SignedOrderRequest {
     pub intent: OrderIntentDto,
     pub signature: String,
+    /// Referral or builder code for attribution. Sent as an FRM field
+    /// alongside the intent; not covered by the signature.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub builder_code: Option<String>,
 }
 
 #[derive(Debug, Clone, Serialize)]
@@ -96,6 +124,25 @@ pub struct CancelOrderRequest {
     pub signature: String,
 }
 
+#[derive(Debug, Clone, Serialize)]
+pub struct TransferRequest {
+    pub nonce: u64,
+    pub from: String,
+    pub to: String,
+    pub mint: String,
+    pub amount: u64,
+    pub signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CollateralRequest {
+    pub nonce: u64,
+    pub owner: String,
+    pub mint: String,
+    pub amount: u64,
+    pub signature: String,
+}
+
 // =============================================================================
 // Signed order/cancel results
 // =============================================================================
@@ -116,6 +163,23 @@ pub struct SignedCancel {
     pub owner_bytes: [u8; 32],
 }
 
+/// A signed transfer ready for submission
+#[derive(Debug, Clone)]
+pub struct SignedTransfer {
+    pub nonce: u64,
+    pub request: TransferRequest,
+    pub owner_bytes: [u8; 32],
+}
+
+/// A signed deposit or withdrawal ready for submission
+#[derive(Debug, Clone)]
+pub struct SignedCollateral {
+    pub action: CollateralAction,
+    pub nonce: u64,
+    pub request: CollateralRequest,
+    pub owner_bytes: [u8; 32],
+}
+
 // =============================================================================
 // Signing functions
 // =============================================================================
@@ -123,8 +187,8 @@ pub struct SignedCancel {
 /// Sign a perp order using the exact server structure.
 /// Reference: sequencer_client/scripts/place_perp_order_fixed.rs:87-110
 #[allow(clippy::too_many_arguments)]
-pub fn sign_perp_order(
-    keypair: &TradingKeypair,
+pub async fn sign_perp_order<S: OrderSigner + ?Sized>(
+    signer: &S,
     order_id: u64,
     side: Side,
     price: u64,
@@ -141,7 +205,7 @@ pub fn sign_perp_order(
     // 1. Build PerpOrderIntentBorsh for signing
     let perp_intent = PerpOrderIntentBorsh {
         order_id,
-        owner: keypair.pubkey(),
+        owner: signer.pubkey(),
         side: side.into(),
         price,
         quantity,
@@ -174,48 +238,30 @@ pub fn sign_perp_order(
     tracing::debug!("Order SHA256 hash: {}", hex_string);
 
     // 4. Sign the message bytes
-    let signature = keypair.sign(message);
+    let signature = signer.sign(message).await?;
     let signature_hex = hex::encode(signature);
     tracing::debug!("Order signature: {}", signature_hex);
 
     // 5. Build the JSON request DTO
-    let dto = OrderIntentDto {
-        order_id,
-        owner: keypair.pubkey_string(),
-        side: match side {
-            Side::Buy => "Buy".to_string(),
-            Side::Sell => "Sell".to_string(),
-        },
-        price,
-        quantity,
-        expiry,
-        base_mint: base_mint.to_string(),
-        quote_mint: quote_mint.to_string(),
-        market_kind: "perp".to_string(),
-        leverage: Some(leverage),
-        position_effect: Some(position_effect.to_string()),
-        reduce_only,
-        margin_mode: Some(margin_mode.to_string()),
-        margin_amount,
-        liquidation: false,
-    };
+    let dto = dto_from_intent(&perp_intent);
 
     let request = SignedOrderRequest {
         intent: dto,
         signature: signature_hex,
+        builder_code: None,
     };
 
     Ok(SignedOrder {
         order_id,
         request,
-        owner_bytes: keypair.pubkey_bytes(),
+        owner_bytes: signer.pubkey().to_bytes(),
     })
 }
 
 /// Sign a cancel request.
 /// Reference: sequencer_client/src/order_cancel.rs
-pub fn sign_cancel(
-    keypair: &TradingKeypair,
+pub async fn sign_cancel<S: OrderSigner + ?Sized>(
+    signer: &S,
     order_id: u64,
     base_mint: &Pubkey,
     quote_mint: &Pubkey,
@@ -223,7 +269,7 @@ pub fn sign_cancel(
     // 1. Build CancelOrderData for signing
     let cancel_data = CancelOrderData {
         order_id,
-        owner: keypair.pubkey(),
+        owner: signer.pubkey(),
         base_mint: *base_mint,
         quote_mint: *quote_mint,
     };
@@ -242,13 +288,13 @@ pub fn sign_cancel(
     let message = hex_string.as_bytes();
 
     // 4. Sign the message bytes
-    let signature = keypair.sign(message);
+    let signature = signer.sign(message).await?;
     let signature_hex = hex::encode(signature);
 
     // 5. Build the JSON request
     let request = CancelOrderRequest {
         order_id,
-        owner: keypair.pubkey_string(),
+        owner: signer.pubkey_string(),
         base_mint: base_mint.to_string(),
         quote_mint: quote_mint.to_string(),
         signature: signature_hex,
@@ -257,7 +303,222 @@ pub fn sign_cancel(
     Ok(SignedCancel {
         order_id,
         request,
-        owner_bytes: keypair.pubkey_bytes(),
+        owner_bytes: signer.pubkey().to_bytes(),
+    })
+}
+
+/// Sign a transfer of `amount` canonical units of `mint` to `to`.
+pub async fn sign_transfer<S: OrderSigner + ?Sized>(
+    signer: &S,
+    nonce: u64,
+    to: &Pubkey,
+    mint: &Pubkey,
+    amount: u64,
+) -> Result<SignedTransfer> {
+    // 1. Build TransferData for signing
+    let transfer_data = TransferData {
+        nonce,
+        from: signer.pubkey(),
+        to: *to,
+        mint: *mint,
+        amount,
+    };
+
+    // 2. Create signing message: PREFIX + Borsh(transfer_data)
+    let mut data = TRANSFER_PREFIX.to_vec();
+    data.extend(
+        transfer_data
+            .try_to_vec()
+            .map_err(|e| SdkError::Serialization(format!("Borsh serialization failed: {}", e)))?,
+    );
+
+    // 3. Hash: SHA256(data) -> hex string -> UTF-8 bytes
+    let hash = Sha256::digest(&data);
+    let hex_string = hex::encode(hash);
+    let message = hex_string.as_bytes();
+
+    // 4. Sign the message bytes
+    let signature = signer.sign(message).await?;
+    let signature_hex = hex::encode(signature);
+
+    // 5. Build the JSON request
+    let request = TransferRequest {
+        nonce,
+        from: signer.pubkey_string(),
+        to: to.to_string(),
+        mint: mint.to_string(),
+        amount,
+        signature: signature_hex,
+    };
+
+    Ok(SignedTransfer {
+        nonce,
+        request,
+        owner_bytes: signer.pubkey().to_bytes(),
+    })
+}
+
+/// Sign a deposit of `amount` canonical units of `mint` into the signer's
+/// margin account, or a withdrawal out of it.
+pub async fn sign_collateral<S: OrderSigner + ?Sized>(
+    signer: &S,
+    action: CollateralAction,
+    nonce: u64,
+    mint: &Pubkey,
+    amount: u64,
+) -> Result<SignedCollateral> {
+    // 1. Build CollateralData for signing
+    let collateral_data = CollateralData {
+        nonce,
+        owner: signer.pubkey(),
+        mint: *mint,
+        amount,
+    };
+
+    // 2. Create signing message: PREFIX + Borsh(collateral_data)
+    let mut data = match action {
+        CollateralAction::Deposit => DEPOSIT_PREFIX.to_vec(),
+        CollateralAction::Withdraw => WITHDRAW_PREFIX.to_vec(),
+    };
+    data.extend(
+        collateral_data
+            .try_to_vec()
+            .map_err(|e| SdkError::Serialization(format!("Borsh serialization failed: {}", e)))?,
+    );
+
+    // 3. Hash: SHA256(data) -> hex string -> UTF-8 bytes
+    let hash = Sha256::digest(&data);
+    let hex_string = hex::encode(hash);
+    let message = hex_string.as_bytes();
+
+    // 4. Sign the message bytes
+    let signature = signer.sign(message).await?;
+    let signature_hex = hex::encode(signature);
+
+    // 5. Build the JSON request
+    let request = CollateralRequest {
+        nonce,
+        owner: signer.pubkey_string(),
+        mint: mint.to_string(),
+        amount,
+        signature: signature_hex,
+    };
+
+    Ok(SignedCollateral {
+        action,
+        nonce,
+        request,
+        owner_bytes: signer.pubkey().to_bytes(),
+    })
+}
+
+/// Check that `request.signature` is the intent owner's signature over the
+/// intent, rebuilding the signed bytes from the JSON fields as the server
+/// does.
+#[cfg(any(test, feature = "fuzzing"))]
+pub fn verify_order(request: &SignedOrderRequest) -> Result<()> {
+    use ed25519_dalek::{PublicKey, Signature, Verifier};
+
+    let intent = intent_from_dto(&request.intent)?;
+    let borsh_bytes = intent
+        .try_to_vec()
+        .map_err(|e| SdkError::Serialization(format!("Borsh serialization failed: {}", e)))?;
+    let message = hex::encode(Sha256::digest([SIGNED_ORDER_PREFIX, &borsh_bytes].concat()));
+
+    let public = PublicKey::from_bytes(intent.owner.as_ref())
+        .map_err(|e| SdkError::Signing(format!("order owner: {}", e)))?;
+    let signature = hex::decode(&request.signature)
+        .ok()
+        .and_then(|bytes| Signature::from_bytes(&bytes).ok())
+        .ok_or_else(|| SdkError::Signing("malformed order signature".into()))?;
+    public
+        .verify(message.as_bytes(), &signature)
+        .map_err(|_| SdkError::Signing("order signature does not match intent".into()))
+}
+
+/// Borsh bytes the server signs for `dto`.
+#[cfg(any(test, feature = "fuzzing"))]
+pub(crate) fn encode_intent(dto: &OrderIntentDto) -> Result<Vec<u8>> {
+    intent_from_dto(dto)?
+        .try_to_vec()
+        .map_err(|e| SdkError::Serialization(format!("Borsh serialization failed: {}", e)))
+}
+
+/// JSON fields for Borsh intent bytes, rejecting trailing data.
+#[cfg(any(test, feature = "fuzzing"))]
+pub(crate) fn decode_intent(bytes: &[u8]) -> Result<OrderIntentDto> {
+    let intent = PerpOrderIntentBorsh::try_from_slice(bytes)
+        .map_err(|e| SdkError::Serialization(format!("Borsh deserialization failed: {}", e)))?;
+    Ok(dto_from_intent(&intent))
+}
+
+fn dto_from_intent(intent: &PerpOrderIntentBorsh) -> OrderIntentDto {
+    OrderIntentDto {
+        order_id: intent.order_id,
+        owner: intent.owner.to_string(),
+        side: intent.side.to_string(),
+        price: intent.price,
+        quantity: intent.quantity,
+        expiry: intent.expiry,
+        base_mint: intent.base_mint.to_string(),
+        quote_mint: intent.quote_mint.to_string(),
+        market_kind: "perp".to_string(),
+        leverage: intent.leverage,
+        position_effect: intent.position_effect.map(|e| e.to_string()),
+        reduce_only: intent.reduce_only,
+        margin_mode: intent.margin_mode.map(|m| m.to_string()),
+        margin_amount: intent.margin_amount,
+        liquidation: intent.liquidation,
+    }
+}
+
+#[cfg(any(test, feature = "fuzzing"))]
+fn intent_from_dto(dto: &OrderIntentDto) -> Result<PerpOrderIntentBorsh> {
+    let pubkey = |field: &str, value: &str| {
+        value
+            .parse::<Pubkey>()
+            .map_err(|e| SdkError::InvalidPubkey(format!("{}: {}", field, e)))
+    };
+    let unknown = |field: &str, value: &str| {
+        SdkError::Serialization(format!("unknown {} {:?}", field, value))
+    };
+    if dto.market_kind != "perp" {
+        return Err(unknown("market_kind", &dto.market_kind));
+    }
+    let side = match dto.side.as_str() {
+        "Buy" => OrderSide::Buy,
+        "Sell" => OrderSide::Sell,
+        other => return Err(unknown("side", other)),
+    };
+    let position_effect = match dto.position_effect.as_deref() {
+        None => None,
+        Some("open") => Some(PositionEffect::Open),
+        Some("close") => Some(PositionEffect::Close),
+        Some(other) => return Err(unknown("position_effect", other)),
+    };
+    let margin_mode = match dto.margin_mode.as_deref() {
+        None => None,
+        Some("cross") => Some(MarginMode::Cross),
+        Some("isolated") => Some(MarginMode::Isolated),
+        Some(other) => return Err(unknown("margin_mode", other)),
+    };
+
+    Ok(PerpOrderIntentBorsh {
+        order_id: dto.order_id,
+        owner: pubkey("owner", &dto.owner)?,
+        side,
+        price: dto.price,
+        quantity: dto.quantity,
+        expiry: dto.expiry,
+        base_mint: pubkey("base_mint", &dto.base_mint)?,
+        quote_mint: pubkey("quote_mint", &dto.quote_mint)?,
+        market_kind: MarketKind::Perp,
+        leverage: dto.leverage,
+        position_effect,
+        reduce_only: dto.reduce_only,
+        margin_mode,
+        margin_amount: dto.margin_amount,
+        liquidation: dto.liquidation,
     })
 }
 
@@ -275,13 +536,28 @@ impl SignedCancel {
     }
 }
 
+impl SignedTransfer {
+    /// Convert the signed transfer request to JSON string
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(&self.request).map_err(|e| SdkError::Serialization(e.to_string()))
+    }
+}
+
+impl SignedCollateral {
+    /// Convert the signed deposit or withdrawal request to JSON string
+    pub fn to_json(&self) -> Result<String> {
+        serde_json::to_string(&self.request).map_err(|e| SdkError::Serialization(e.to_string()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::keypair::TradingKeypair;
     use std::str::FromStr;
 
-    #[test]
-    fn test_sign_perp_order() {
+    #[tokio::test]
+    async fn test_sign_perp_order() {
         let keypair = TradingKeypair::generate();
         let base_mint = Pubkey::from_str("11111111111111111111111111111112").unwrap();
         let quote_mint = Pubkey::from_str("11111111111111111111111111111113").unwrap();
@@ -301,6 +577,7 @@ mod tests {
             Some(18_550_000), // margin amount
             false,
         )
+        .await
         .unwrap();
 
         assert_eq!(signed.order_id, 12345);
@@ -308,15 +585,51 @@ mod tests {
         assert_eq!(signed.request.intent.market_kind, "perp");
     }
 
-    #[test]
-    fn test_sign_cancel() {
+    #[tokio::test]
+    async fn test_sign_cancel() {
         let keypair = TradingKeypair::generate();
         let base_mint = Pubkey::from_str("11111111111111111111111111111112").unwrap();
         let quote_mint = Pubkey::from_str("11111111111111111111111111111113").unwrap();
 
-        let signed = sign_cancel(&keypair, 12345, &base_mint, &quote_mint).unwrap();
+        let signed = sign_cancel(&keypair, 12345, &base_mint, &quote_mint)
+            .await
+            .unwrap();
 
         assert_eq!(signed.order_id, 12345);
         assert!(!signed.request.signature.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_sign_transfer() {
+        let keypair = TradingKeypair::generate();
+        let to = TradingKeypair::generate().pubkey();
+        let mint = Pubkey::from_str("11111111111111111111111111111113").unwrap();
+
+        let signed = sign_transfer(&keypair, 7, &to, &mint, 1_000_000)
+            .await
+            .unwrap();
+
+        assert_eq!(signed.nonce, 7);
+        assert_eq!(signed.request.to, to.to_string());
+        assert_eq!(signed.request.from, keypair.pubkey_string());
+        assert_eq!(signed.request.signature.len(), 128);
+    }
+
+    #[tokio::test]
+    async fn test_sign_collateral() {
+        let keypair = TradingKeypair::generate();
+        let mint = Pubkey::from_str("11111111111111111111111111111113").unwrap();
+
+        let deposit = sign_collateral(&keypair, CollateralAction::Deposit, 9, &mint, 5_000_000)
+            .await
+            .unwrap();
+        let withdraw = sign_collateral(&keypair, CollateralAction::Withdraw, 9, &mint, 5_000_000)
+            .await
+            .unwrap();
+
+        assert_eq!(deposit.request.owner, keypair.pubkey_string());
+        assert_eq!(deposit.request.amount, 5_000_000);
+        // The action is bound into the signature by its prefix
+        assert_ne!(deposit.request.signature, withdraw.request.signature);
+    }
 }
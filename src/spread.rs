@@ -0,0 +1,396 @@
+//! Cross-market spread trading.
+//!
+//! A [`SpreadTrader`] trades the spread `first - ratio * second` between two
+//! perp markets. Buying the spread buys `quantity` of the first market and
+//! sells `ratio * quantity` of the second; selling it does the reverse. Both
+//! legs go out together through `FermiClient::place_legs` and are rolled
+//! back if either fails. Executions are tracked in a [`PnlEngine`] for
+//! combined PnL, and the account's positions are checked for leg imbalance.
+
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+
+use crate::client::FermiClient;
+use crate::error::{Result, SdkError};
+use crate::execution::LegPlacement;
+use crate::pnl::{CostBasis, PnlEngine};
+use crate::types::{
+    lossy_decimal, MarginMode, MarketInfo, Orderbook, PerpOrder, PositionEffect, Side,
+};
+
+/// The spread to trade and when.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SpreadSpec {
+    pub first_market: String,
+    pub second_market: String,
+    /// Units of the second market per unit of the first
+    pub ratio: f64,
+    /// First-leg size per entry, in base units
+    pub quantity: f64,
+    /// Sell the spread when its bid reaches this level
+    pub sell_above: f64,
+    /// Buy the spread when its ask falls to this level
+    pub buy_below: f64,
+    /// Close an open spread once it trades back through this level
+    pub exit_at: f64,
+    pub leverage: u64,
+    /// How far through the touch leg orders are priced, in basis points
+    pub slippage_bps: u32,
+}
+
+/// Which way the spread is held.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SpreadDirection {
+    /// Long the first market, short the second
+    Long,
+    /// Short the first market, long the second
+    Short,
+}
+
+/// What the thresholds call for at the current quote.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpreadAction {
+    Enter(SpreadDirection),
+    Exit,
+}
+
+/// Executable spread prices from the two books.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SpreadQuote {
+    /// Selling the spread: first bid against second ask
+    pub bid: f64,
+    /// Buying the spread: first ask against second bid
+    pub ask: f64,
+    /// Top of book in the first market (bid, ask)
+    pub first: (f64, f64),
+    /// Top of book in the second market (bid, ask)
+    pub second: (f64, f64),
+}
+
+impl SpreadQuote {
+    /// Quote the spread, if both sides of both books exist.
+    pub fn from_books(
+        first: (&Orderbook, &MarketInfo),
+        second: (&Orderbook, &MarketInfo),
+        ratio: f64,
+    ) -> Option<Self> {
+        let touch = |(book, market): (&Orderbook, &MarketInfo)| {
+            let scale = 10f64.powi(market.quote_decimals as i32);
+            Some((
+                book.best_bid()?.price as f64 / scale,
+                book.best_ask()?.price as f64 / scale,
+            ))
+        };
+        let first = touch(first)?;
+        let second = touch(second)?;
+        Some(Self {
+            bid: first.0 - ratio * second.1,
+            ask: first.1 - ratio * second.0,
+            first,
+            second,
+        })
+    }
+
+    pub fn mid(&self) -> f64 {
+        (self.bid + self.ask) / 2.0
+    }
+}
+
+/// Decide what to do at `quote` given the spread currently held.
+pub fn spread_action(
+    spec: &SpreadSpec,
+    quote: &SpreadQuote,
+    held: Option<SpreadDirection>,
+) -> Option<SpreadAction> {
+    match held {
+        None if quote.bid >= spec.sell_above => Some(SpreadAction::Enter(SpreadDirection::Short)),
+        None if quote.ask <= spec.buy_below => Some(SpreadAction::Enter(SpreadDirection::Long)),
+        // Closing a long sells the spread at its bid, closing a short buys at its ask
+        Some(SpreadDirection::Long) if quote.bid >= spec.exit_at => Some(SpreadAction::Exit),
+        Some(SpreadDirection::Short) if quote.ask <= spec.exit_at => Some(SpreadAction::Exit),
+        _ => None,
+    }
+}
+
+/// Account exposure in the two markets and the strategy's PnL.
+#[derive(Debug, Clone)]
+pub struct SpreadStatus {
+    pub held: Option<SpreadDirection>,
+    /// Account position in the first market, in base units (positive = long)
+    pub first_size: f64,
+    /// Account position in the second market, in base units
+    pub second_size: f64,
+    /// Second-market units out of line with the ratio (0 when balanced)
+    pub imbalance: f64,
+    /// Realized PnL of the spread's executions across both legs
+    pub realized_pnl: f64,
+    /// Open executions marked at the book mids
+    pub unrealized_pnl: f64,
+    pub quote: Option<SpreadQuote>,
+}
+
+/// Trades one spread between two markets.
+#[derive(Debug, Clone)]
+pub struct SpreadTrader {
+    spec: SpreadSpec,
+    first: MarketInfo,
+    second: MarketInfo,
+    held: Option<SpreadDirection>,
+    pnl: PnlEngine,
+}
+
+impl SpreadTrader {
+    /// Look up both markets and start flat.
+    pub async fn new(client: &FermiClient, spec: SpreadSpec) -> Result<Self> {
+        if spec.ratio <= 0.0 || spec.quantity <= 0.0 {
+            return Err(SdkError::Config(
+                "spread ratio and quantity must be positive".into(),
+            ));
+        }
+        let first = client.get_market(&spec.first_market).await?;
+        let second = client.get_market(&spec.second_market).await?;
+        Ok(Self {
+            spec,
+            first,
+            second,
+            held: None,
+            pnl: PnlEngine::new(CostBasis::AverageCost),
+        })
+    }
+
+    pub fn spec(&self) -> &SpreadSpec {
+        &self.spec
+    }
+
+    pub fn held(&self) -> Option<SpreadDirection> {
+        self.held
+    }
+
+    /// Engine holding every execution of both legs.
+    pub fn pnl(&self) -> &PnlEngine {
+        &self.pnl
+    }
+
+    /// Fetch both books and quote the spread.
+    pub async fn quote(&self, client: &FermiClient) -> Result<Option<SpreadQuote>> {
+        let first = client.get_orderbook(&self.first.uuid).await?;
+        let second = client.get_orderbook(&self.second.uuid).await?;
+        Ok(SpreadQuote::from_books(
+            (&first, &self.first),
+            (&second, &self.second),
+            self.spec.ratio,
+        ))
+    }
+
+    /// Quote the spread and enter or exit if a threshold is crossed.
+    ///
+    /// Returns the placement when legs were sent. If a leg fails, the legs
+    /// that went through are rolled back and the spread is left as it was.
+    pub async fn step(&mut self, client: &FermiClient) -> Result<Option<LegPlacement>> {
+        let Some(quote) = self.quote(client).await? else {
+            return Ok(None);
+        };
+        let Some(action) = spread_action(&self.spec, &quote, self.held) else {
+            return Ok(None);
+        };
+        let (direction, closing) = match action {
+            SpreadAction::Enter(direction) => (direction, false),
+            // Closing trades the opposite way
+            SpreadAction::Exit => match self.held {
+                Some(SpreadDirection::Long) => (SpreadDirection::Short, true),
+                _ => (SpreadDirection::Long, true),
+            },
+        };
+        info!(
+            "Spread {}/{} at {:.4}/{:.4}: {:?}",
+            self.first.name, self.second.name, quote.bid, quote.ask, action
+        );
+        self.execute(client, &quote, direction, closing).await
+    }
+
+    /// Send both legs for one spread trade in `direction`.
+    async fn execute(
+        &mut self,
+        client: &FermiClient,
+        quote: &SpreadQuote,
+        direction: SpreadDirection,
+        closing: bool,
+    ) -> Result<Option<LegPlacement>> {
+        let (first_side, second_side) = match direction {
+            SpreadDirection::Long => (Side::Buy, Side::Sell),
+            SpreadDirection::Short => (Side::Sell, Side::Buy),
+        };
+        let first = self.leg(first_side, quote.first, self.spec.quantity, closing)?;
+        let second = self.leg(
+            second_side,
+            quote.second,
+            self.spec.quantity * self.spec.ratio,
+            closing,
+        )?;
+        let placement = client
+            .place_legs(&[
+                (self.first.uuid.as_str(), first.clone()),
+                (self.second.uuid.as_str(), second.clone()),
+            ])
+            .await?;
+
+        if !placement.all_placed() {
+            warn!(
+                "Spread {}/{} leg failed; rolling back",
+                self.first.name, self.second.name
+            );
+            let rollback = client
+                .rollback_legs(&placement, self.spec.slippage_bps)
+                .await?;
+            for failure in &rollback.failures {
+                warn!("Spread rollback: {}", failure);
+            }
+            return Ok(Some(placement));
+        }
+
+        // Legs are recorded at their limit prices, the worst they can fill at
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or(0);
+        for (market, order) in [(&self.first, &first), (&self.second, &second)] {
+            self.pnl.apply_fill(
+                &market.uuid,
+                order.side,
+                order.price_f64(),
+                order.quantity_f64(),
+                now,
+            );
+        }
+        self.held = if closing { None } else { Some(direction) };
+        Ok(Some(placement))
+    }
+
+    /// A marketable leg order priced through the touch.
+    fn leg(
+        &self,
+        side: Side,
+        touch: (f64, f64),
+        quantity: f64,
+        closing: bool,
+    ) -> Result<PerpOrder> {
+        let slippage = self.spec.slippage_bps as f64 / 10_000.0;
+        let price = match side {
+            Side::Buy => touch.1 * (1.0 + slippage),
+            Side::Sell => touch.0 * (1.0 - slippage),
+        };
+        Ok(PerpOrder {
+            side,
+            price: lossy_decimal(price)?,
+            quantity: lossy_decimal(quantity)?,
+            leverage: self.spec.leverage,
+            position_effect: if closing {
+                PositionEffect::Close
+            } else {
+                PositionEffect::Open
+            },
+            margin_mode: MarginMode::Cross,
+            reduce_only: closing,
+            ..Default::default()
+        })
+    }
+
+    /// Current positions, imbalance, and PnL.
+    pub async fn status(&self, client: &FermiClient) -> Result<SpreadStatus> {
+        let quote = self.quote(client).await?;
+        let positions = client.get_positions().await?;
+        let size = |market: &MarketInfo| -> Result<f64> {
+            match positions.iter().find(|p| p.market_id == market.uuid) {
+                Some(position) => position.size(market),
+                None => Ok(0.0),
+            }
+        };
+        let first_size = size(&self.first)?;
+        let second_size = size(&self.second)?;
+
+        let realized_pnl =
+            self.pnl.realized_pnl(&self.first.uuid) + self.pnl.realized_pnl(&self.second.uuid);
+        let unrealized_pnl = quote.map_or(0.0, |q| {
+            let mid = |(bid, ask): (f64, f64)| (bid + ask) / 2.0;
+            [(&self.first, mid(q.first)), (&self.second, mid(q.second))]
+                .into_iter()
+                .flat_map(|(market, mark)| {
+                    self.pnl
+                        .lots(&market.uuid)
+                        .into_iter()
+                        .map(move |lot| lot.quantity * (mark - lot.price))
+                })
+                .sum()
+        });
+
+        Ok(SpreadStatus {
+            held: self.held,
+            first_size,
+            second_size,
+            imbalance: second_size + self.spec.ratio * first_size,
+            realized_pnl,
+            unrealized_pnl,
+            quote,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{test_market, OrderbookEntry};
+
+    fn book(bid: u64, ask: u64) -> Orderbook {
+        let entry = |price| OrderbookEntry {
+            order_id: price,
+            owner: String::new(),
+            price,
+            quantity: 1,
+            side: String::new(),
+            expiry: 0,
+        };
+        Orderbook {
+            buys: vec![entry(bid)],
+            sells: vec![entry(ask)],
+        }
+    }
+
+    #[test]
+    fn test_spread_quote_and_thresholds() {
+        let market = test_market(0, 2);
+        let spec = SpreadSpec {
+            first_market: "a".into(),
+            second_market: "b".into(),
+            ratio: 2.0,
+            quantity: 1.0,
+            sell_above: 10.0,
+            buy_below: -10.0,
+            exit_at: 0.0,
+            leverage: 5,
+            slippage_bps: 10,
+        };
+
+        // 120/121 against 2 x 50/51
+        let quote = SpreadQuote::from_books(
+            (&book(12_000, 12_100), &market),
+            (&book(5_000, 5_100), &market),
+            spec.ratio,
+        )
+        .unwrap();
+        assert_eq!((quote.bid, quote.ask), (18.0, 21.0));
+        assert_eq!(
+            spread_action(&spec, &quote, None),
+            Some(SpreadAction::Enter(SpreadDirection::Short))
+        );
+        assert_eq!(
+            spread_action(&spec, &quote, Some(SpreadDirection::Short)),
+            None
+        );
+        assert_eq!(
+            spread_action(&spec, &quote, Some(SpreadDirection::Long)),
+            Some(SpreadAction::Exit)
+        );
+    }
+}
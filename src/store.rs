@@ -0,0 +1,810 @@
+//! SQLite-backed local record of orders, fills, and positions.
+//!
+//! Enabled with the `store` feature. Attach a store with
+//! `FermiClient::set_store` and every order intent, acknowledgement,
+//! rejection, and cancel is written before the call returns, so a restarted
+//! bot can see what it had outstanding without relying on server queries.
+//! Write failures are logged and never block trading.
+//!
+//! The store also keeps a local copy of your trades and market funding
+//! events. `FermiClient::sync_history` pulls new rows from the node using
+//! per-market cursors, so analytics can run against history the node has
+//! since pruned.
+
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use rusqlite::{params, Connection, OptionalExtension, Row};
+
+use crate::error::{Result, SdkError};
+use crate::rpc::RpcClient;
+use crate::types::{CancelResult, FundingEvent, OrderResult, PerpOrder, Position, Side, Trade};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS orders (
+    order_id        INTEGER PRIMARY KEY,
+    market_id       TEXT NOT NULL,
+    side            TEXT NOT NULL,
+    price           REAL NOT NULL,
+    quantity        REAL NOT NULL,
+    filled_quantity REAL NOT NULL DEFAULT 0,
+    leverage        INTEGER NOT NULL,
+    reduce_only     INTEGER NOT NULL,
+    status          TEXT NOT NULL,
+    tx_hash         TEXT,
+    created_at      INTEGER NOT NULL,
+    updated_at      INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS pending_submissions (
+    order_id   INTEGER PRIMARY KEY,
+    request    TEXT NOT NULL,
+    expiry     INTEGER NOT NULL,
+    created_at INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS fills (
+    id        INTEGER PRIMARY KEY AUTOINCREMENT,
+    order_id  INTEGER NOT NULL,
+    price     REAL NOT NULL,
+    quantity  REAL NOT NULL,
+    timestamp INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS positions (
+    market_id           TEXT PRIMARY KEY,
+    base_position       TEXT NOT NULL,
+    average_entry_price TEXT NOT NULL,
+    mark_price          TEXT NOT NULL,
+    realized_pnl        TEXT NOT NULL,
+    unrealized_pnl      TEXT NOT NULL,
+    updated_at          INTEGER NOT NULL
+);
+CREATE TABLE IF NOT EXISTS trades (
+    market_id    TEXT NOT NULL,
+    side         TEXT NOT NULL,
+    buyer_owner  TEXT NOT NULL,
+    seller_owner TEXT NOT NULL,
+    price        INTEGER NOT NULL,
+    quantity     INTEGER NOT NULL,
+    timestamp    INTEGER NOT NULL,
+    base_mint    TEXT NOT NULL,
+    quote_mint   TEXT NOT NULL,
+    buyer_fee    INTEGER,
+    seller_fee   INTEGER,
+    UNIQUE (market_id, timestamp, buyer_owner, seller_owner, price, quantity)
+);
+CREATE TABLE IF NOT EXISTS funding_events (
+    market_id        TEXT NOT NULL,
+    timestamp        INTEGER NOT NULL,
+    interval_seconds INTEGER NOT NULL,
+    mark_price       INTEGER NOT NULL,
+    index_price      INTEGER NOT NULL,
+    premium_rate_bps INTEGER NOT NULL,
+    funding_rate_bps INTEGER NOT NULL,
+    total_payment    TEXT NOT NULL,
+    PRIMARY KEY (market_id, timestamp)
+);
+CREATE TABLE IF NOT EXISTS sync_cursors (
+    stream     TEXT NOT NULL,
+    market_id  TEXT NOT NULL,
+    cursor     INTEGER NOT NULL,
+    updated_at INTEGER NOT NULL,
+    PRIMARY KEY (stream, market_id)
+);
+";
+
+/// Lifecycle state of a stored order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StoredOrderStatus {
+    /// Signed and about to be submitted
+    Pending,
+    /// Acknowledged by the sequencer
+    Acked,
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    /// Submission failed
+    Rejected,
+}
+
+impl StoredOrderStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            StoredOrderStatus::Pending => "pending",
+            StoredOrderStatus::Acked => "acked",
+            StoredOrderStatus::PartiallyFilled => "partially_filled",
+            StoredOrderStatus::Filled => "filled",
+            StoredOrderStatus::Cancelled => "cancelled",
+            StoredOrderStatus::Rejected => "rejected",
+        }
+    }
+
+    fn parse(s: &str) -> Self {
+        match s {
+            "acked" => StoredOrderStatus::Acked,
+            "partially_filled" => StoredOrderStatus::PartiallyFilled,
+            "filled" => StoredOrderStatus::Filled,
+            "cancelled" => StoredOrderStatus::Cancelled,
+            "rejected" => StoredOrderStatus::Rejected,
+            _ => StoredOrderStatus::Pending,
+        }
+    }
+
+    /// Whether the order may still be resting on the book.
+    pub fn is_outstanding(self) -> bool {
+        matches!(
+            self,
+            StoredOrderStatus::Pending
+                | StoredOrderStatus::Acked
+                | StoredOrderStatus::PartiallyFilled
+        )
+    }
+}
+
+/// An order as recorded in the store.
+#[derive(Debug, Clone, PartialEq)]
+pub struct StoredOrder {
+    pub order_id: u64,
+    pub market_id: String,
+    pub side: Side,
+    pub price: f64,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    pub leverage: u64,
+    pub reduce_only: bool,
+    pub status: StoredOrderStatus,
+    pub tx_hash: Option<String>,
+    /// Unix seconds
+    pub created_at: u64,
+    pub updated_at: u64,
+}
+
+impl StoredOrder {
+    fn from_row(row: &Row<'_>) -> rusqlite::Result<Self> {
+        let side: String = row.get("side")?;
+        let status: String = row.get("status")?;
+        Ok(StoredOrder {
+            order_id: row.get::<_, i64>("order_id")? as u64,
+            market_id: row.get("market_id")?,
+            side: if side == "sell" {
+                Side::Sell
+            } else {
+                Side::Buy
+            },
+            price: row.get("price")?,
+            quantity: row.get("quantity")?,
+            filled_quantity: row.get("filled_quantity")?,
+            leverage: row.get::<_, i64>("leverage")? as u64,
+            reduce_only: row.get("reduce_only")?,
+            status: StoredOrderStatus::parse(&status),
+            tx_hash: row.get("tx_hash")?,
+            created_at: row.get::<_, i64>("created_at")? as u64,
+            updated_at: row.get::<_, i64>("updated_at")? as u64,
+        })
+    }
+}
+
+/// A signed order persisted before submission whose outcome is not yet known.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PendingSubmission {
+    pub order_id: u64,
+    /// Signed request JSON, resubmittable as-is
+    pub request: String,
+    /// Unix seconds after which the order can no longer execute
+    pub expiry: u64,
+    pub created_at: u64,
+}
+
+/// History stream tracked by a sync cursor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SyncStream {
+    Trades,
+    Funding,
+}
+
+impl SyncStream {
+    fn as_str(self) -> &'static str {
+        match self {
+            SyncStream::Trades => "trades",
+            SyncStream::Funding => "funding",
+        }
+    }
+}
+
+/// Rows added by one history sync pass.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SyncReport {
+    pub markets: usize,
+    pub trades: usize,
+    pub funding_events: usize,
+    /// Markets whose oldest fetched trade is newer than the stored cursor,
+    /// so trades in between may have been pruned before they were synced
+    pub possible_gaps: Vec<String>,
+}
+
+/// Local SQLite order and position store.
+pub struct OrderStore {
+    conn: Mutex<Connection>,
+}
+
+impl OrderStore {
+    /// Open (or create) a store at `path`.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self> {
+        Self::init(Connection::open(path)?)
+    }
+
+    /// A store that lives only as long as this value.
+    pub fn open_in_memory() -> Result<Self> {
+        Self::init(Connection::open_in_memory()?)
+    }
+
+    fn init(conn: Connection) -> Result<Self> {
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    fn with_conn<T>(&self, f: impl FnOnce(&Connection) -> rusqlite::Result<T>) -> Result<T> {
+        let conn = self
+            .conn
+            .lock()
+            .map_err(|_| SdkError::Store("connection lock poisoned".to_string()))?;
+        Ok(f(&conn)?)
+    }
+
+    /// Record a signed order about to be submitted.
+    pub fn record_intent(&self, market_id: &str, order_id: u64, order: &PerpOrder) -> Result<()> {
+        let now = now_secs();
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO orders (order_id, market_id, side, price, quantity,
+                    leverage, reduce_only, status, created_at, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?9)",
+                params![
+                    order_id as i64,
+                    market_id,
+                    order.side.to_string(),
+                    order.price_f64(),
+                    order.quantity_f64(),
+                    order.leverage as i64,
+                    order.reduce_only,
+                    StoredOrderStatus::Pending.as_str(),
+                    now as i64,
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Persist a signed order just before it is submitted. The row stays
+    /// until the order is acknowledged or definitively rejected, so
+    /// [`FermiClient::resolve_pending`](crate::FermiClient::resolve_pending)
+    /// can settle it after a crash mid-submit.
+    pub fn record_pending(&self, order_id: u64, request: &str, expiry: u64) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT OR REPLACE INTO pending_submissions (order_id, request, expiry, created_at)
+                 VALUES (?1, ?2, ?3, ?4)",
+                params![order_id as i64, request, expiry as i64, now_secs() as i64],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Signed orders submitted without a known outcome, oldest first.
+    pub fn pending_submissions(&self) -> Result<Vec<PendingSubmission>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT order_id, request, expiry, created_at FROM pending_submissions
+                 ORDER BY created_at, order_id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(PendingSubmission {
+                    order_id: row.get::<_, i64>(0)? as u64,
+                    request: row.get(1)?,
+                    expiry: row.get::<_, i64>(2)? as u64,
+                    created_at: row.get::<_, i64>(3)? as u64,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Record that an order was found sequenced without seeing its ack.
+    pub fn record_sequenced(&self, order_id: u64) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM pending_submissions WHERE order_id = ?1",
+                params![order_id as i64],
+            )?;
+            conn.execute(
+                "UPDATE orders SET status = ?2, updated_at = ?3 WHERE order_id = ?1 AND status = ?4",
+                params![
+                    order_id as i64,
+                    StoredOrderStatus::Acked.as_str(),
+                    now_secs() as i64,
+                    StoredOrderStatus::Pending.as_str(),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Record the sequencer's acknowledgement of an order.
+    pub fn record_ack(&self, result: &OrderResult) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM pending_submissions WHERE order_id = ?1",
+                params![result.order_id as i64],
+            )?;
+            conn.execute(
+                "UPDATE orders SET status = ?2, tx_hash = ?3, updated_at = ?4
+                 WHERE order_id = ?1 AND status = ?5",
+                params![
+                    result.order_id as i64,
+                    StoredOrderStatus::Acked.as_str(),
+                    result.tx_hash,
+                    now_secs() as i64,
+                    StoredOrderStatus::Pending.as_str(),
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Record that submitting an order failed.
+    pub fn record_rejection(&self, order_id: u64) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "DELETE FROM pending_submissions WHERE order_id = ?1",
+                params![order_id as i64],
+            )
+        })?;
+        self.set_status(order_id, StoredOrderStatus::Rejected)
+    }
+
+    /// Record a fill against an order, updating its filled quantity and status.
+    pub fn record_fill(
+        &self,
+        order_id: u64,
+        price: f64,
+        quantity: f64,
+        timestamp: u64,
+    ) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "INSERT INTO fills (order_id, price, quantity, timestamp) VALUES (?1, ?2, ?3, ?4)",
+                params![order_id as i64, price, quantity, timestamp as i64],
+            )?;
+            conn.execute(
+                "UPDATE orders SET filled_quantity = filled_quantity + ?2,
+                    status = CASE WHEN filled_quantity + ?2 >= quantity THEN ?3 ELSE ?4 END,
+                    updated_at = ?5
+                 WHERE order_id = ?1",
+                params![
+                    order_id as i64,
+                    quantity,
+                    StoredOrderStatus::Filled.as_str(),
+                    StoredOrderStatus::PartiallyFilled.as_str(),
+                    now_secs() as i64,
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Record an acknowledged cancel.
+    pub fn record_cancel(&self, result: &CancelResult) -> Result<()> {
+        self.set_status(result.order_id, StoredOrderStatus::Cancelled)
+    }
+
+    /// Close an order the server no longer lists as open. Without fill
+    /// history the store cannot tell a fill from a cancel, so it is
+    /// recorded as cancelled unless already fully filled.
+    pub fn record_closed(&self, order_id: u64) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "UPDATE orders SET status = CASE WHEN filled_quantity >= quantity
+                    THEN ?2 ELSE ?3 END, updated_at = ?4
+                 WHERE order_id = ?1",
+                params![
+                    order_id as i64,
+                    StoredOrderStatus::Filled.as_str(),
+                    StoredOrderStatus::Cancelled.as_str(),
+                    now_secs() as i64,
+                ],
+            )
+        })?;
+        Ok(())
+    }
+
+    fn set_status(&self, order_id: u64, status: StoredOrderStatus) -> Result<()> {
+        self.with_conn(|conn| {
+            conn.execute(
+                "UPDATE orders SET status = ?2, updated_at = ?3 WHERE order_id = ?1",
+                params![order_id as i64, status.as_str(), now_secs() as i64],
+            )
+        })?;
+        Ok(())
+    }
+
+    /// Replace the stored position snapshot.
+    pub fn save_positions(&self, positions: &[Position]) -> Result<()> {
+        let now = now_secs() as i64;
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|_| SdkError::Store("connection lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+        tx.execute("DELETE FROM positions", [])?;
+        for p in positions {
+            tx.execute(
+                "INSERT INTO positions (market_id, base_position, average_entry_price,
+                    mark_price, realized_pnl, unrealized_pnl, updated_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+                params![
+                    p.market_id,
+                    p.base_position,
+                    p.average_entry_price,
+                    p.mark_price,
+                    p.realized_pnl,
+                    p.unrealized_pnl,
+                    now,
+                ],
+            )?;
+        }
+        tx.commit()?;
+        Ok(())
+    }
+
+    /// Store your trades from `trades` at or after the trade cursor for
+    /// `market_id`, then advance the cursor. Returns the number of new rows.
+    ///
+    /// Trades already stored are ignored, so overlapping fetches are safe.
+    pub fn record_trades(&self, market_id: &str, owner: &str, trades: &[Trade]) -> Result<usize> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|_| SdkError::Store("connection lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+        let cursor = read_cursor(&tx, SyncStream::Trades, market_id)?.unwrap_or(0);
+
+        let mut inserted = 0;
+        let mut latest = cursor;
+        for trade in trades {
+            if trade.timestamp < cursor
+                || (trade.buyer_owner != owner && trade.seller_owner != owner)
+            {
+                continue;
+            }
+            let side = if trade.buyer_owner == owner {
+                Side::Buy
+            } else {
+                Side::Sell
+            };
+            inserted += tx.execute(
+                "INSERT OR IGNORE INTO trades (market_id, side, buyer_owner, seller_owner,
+                    price, quantity, timestamp, base_mint, quote_mint, buyer_fee, seller_fee)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11)",
+                params![
+                    market_id,
+                    side.to_string(),
+                    trade.buyer_owner,
+                    trade.seller_owner,
+                    trade.price as i64,
+                    trade.quantity as i64,
+                    trade.timestamp as i64,
+                    trade.base_mint,
+                    trade.quote_mint,
+                    trade.buyer_fee,
+                    trade.seller_fee,
+                ],
+            )?;
+            latest = latest.max(trade.timestamp);
+        }
+
+        if latest > cursor {
+            write_cursor(&tx, SyncStream::Trades, market_id, latest)?;
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// Store funding events at or after the funding cursor for `market_id`,
+    /// then advance the cursor. Returns the number of new rows.
+    pub fn record_funding(&self, market_id: &str, events: &[FundingEvent]) -> Result<usize> {
+        let mut conn = self
+            .conn
+            .lock()
+            .map_err(|_| SdkError::Store("connection lock poisoned".to_string()))?;
+        let tx = conn.transaction()?;
+        let cursor = read_cursor(&tx, SyncStream::Funding, market_id)?.unwrap_or(0);
+
+        let mut inserted = 0;
+        let mut latest = cursor;
+        for event in events.iter().filter(|e| e.timestamp >= cursor) {
+            inserted += tx.execute(
+                "INSERT OR IGNORE INTO funding_events (market_id, timestamp, interval_seconds,
+                    mark_price, index_price, premium_rate_bps, funding_rate_bps, total_payment)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+                params![
+                    market_id,
+                    event.timestamp as i64,
+                    event.interval_seconds as i64,
+                    event.mark_price as i64,
+                    event.index_price as i64,
+                    event.premium_rate_bps,
+                    event.funding_rate_bps,
+                    event.total_payment,
+                ],
+            )?;
+            latest = latest.max(event.timestamp);
+        }
+
+        if latest > cursor {
+            write_cursor(&tx, SyncStream::Funding, market_id, latest)?;
+        }
+        tx.commit()?;
+        Ok(inserted)
+    }
+
+    /// Timestamp of the newest synced row in `stream` for `market_id`.
+    pub fn sync_cursor(&self, stream: SyncStream, market_id: &str) -> Result<Option<u64>> {
+        self.with_conn(|conn| read_cursor(conn, stream, market_id))
+    }
+
+    /// Your synced trades in `market_id`, oldest first.
+    pub fn trades(&self, market_id: &str) -> Result<Vec<Trade>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT buyer_owner, seller_owner, price, quantity, timestamp, base_mint,
+                    quote_mint, buyer_fee, seller_fee FROM trades
+                 WHERE market_id = ?1 ORDER BY timestamp",
+            )?;
+            let rows = stmt.query_map(params![market_id], |row| {
+                Ok(Trade {
+                    buyer_owner: row.get(0)?,
+                    seller_owner: row.get(1)?,
+                    price: row.get::<_, i64>(2)? as u64,
+                    quantity: row.get::<_, i64>(3)? as u64,
+                    timestamp: row.get::<_, i64>(4)? as u64,
+                    base_mint: row.get(5)?,
+                    quote_mint: row.get(6)?,
+                    liquidated_owner: None,
+                    buyer_fee: row.get(7)?,
+                    seller_fee: row.get(8)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Synced funding events for `market_id`, oldest first.
+    pub fn funding_events(&self, market_id: &str) -> Result<Vec<FundingEvent>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT timestamp, interval_seconds, mark_price, index_price, premium_rate_bps,
+                    funding_rate_bps, total_payment FROM funding_events
+                 WHERE market_id = ?1 ORDER BY timestamp",
+            )?;
+            let rows = stmt.query_map(params![market_id], |row| {
+                Ok(FundingEvent {
+                    market_id: market_id.to_string(),
+                    timestamp: row.get::<_, i64>(0)? as u64,
+                    interval_seconds: row.get::<_, i64>(1)? as u64,
+                    mark_price: row.get::<_, i64>(2)? as u64,
+                    index_price: row.get::<_, i64>(3)? as u64,
+                    premium_rate_bps: row.get(4)?,
+                    funding_rate_bps: row.get(5)?,
+                    total_payment: row.get(6)?,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+
+    /// Look up one order.
+    pub fn order(&self, order_id: u64) -> Result<Option<StoredOrder>> {
+        self.with_conn(|conn| {
+            conn.query_row(
+                "SELECT * FROM orders WHERE order_id = ?1",
+                params![order_id as i64],
+                StoredOrder::from_row,
+            )
+            .optional()
+        })
+    }
+
+    /// Orders that may still be resting, oldest first.
+    pub fn outstanding_orders(&self) -> Result<Vec<StoredOrder>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT * FROM orders WHERE status IN (?1, ?2, ?3) ORDER BY created_at, order_id",
+            )?;
+            let rows = stmt.query_map(
+                params![
+                    StoredOrderStatus::Pending.as_str(),
+                    StoredOrderStatus::Acked.as_str(),
+                    StoredOrderStatus::PartiallyFilled.as_str(),
+                ],
+                StoredOrder::from_row,
+            )?;
+            rows.collect()
+        })
+    }
+
+    /// The last saved position snapshot.
+    pub fn positions(&self) -> Result<Vec<Position>> {
+        self.with_conn(|conn| {
+            let mut stmt = conn.prepare(
+                "SELECT market_id, base_position, average_entry_price, mark_price,
+                    realized_pnl, unrealized_pnl FROM positions ORDER BY market_id",
+            )?;
+            let rows = stmt.query_map([], |row| {
+                Ok(Position {
+                    owner: String::new(),
+                    market_id: row.get(0)?,
+                    market_name: None,
+                    base_position: row.get(1)?,
+                    average_entry_price: row.get(2)?,
+                    mark_price: row.get(3)?,
+                    realized_pnl: row.get(4)?,
+                    unrealized_pnl: row.get(5)?,
+                    cumulative_funding: None,
+                    margin_mode: None,
+                    leverage: None,
+                })
+            })?;
+            rows.collect()
+        })
+    }
+}
+
+/// Pull new trades and funding events for each market into `store`.
+pub(crate) async fn sync_history(
+    rpc: &RpcClient,
+    store: &OrderStore,
+    owner: &str,
+    market_ids: &[String],
+) -> Result<SyncReport> {
+    let mut report = SyncReport::default();
+    for market_id in market_ids {
+        let trades = rpc.get_trades(market_id).await?;
+        let cursor = store.sync_cursor(SyncStream::Trades, market_id)?;
+        let oldest = trades.iter().map(|t| t.timestamp).min();
+        if let (Some(cursor), Some(oldest)) = (cursor, oldest) {
+            if oldest > cursor {
+                report.possible_gaps.push(market_id.clone());
+            }
+        }
+        report.trades += store.record_trades(market_id, owner, &trades)?;
+
+        let events = rpc.get_funding(market_id).await?;
+        report.funding_events += store.record_funding(market_id, &events)?;
+        report.markets += 1;
+    }
+    Ok(report)
+}
+
+fn read_cursor(
+    conn: &Connection,
+    stream: SyncStream,
+    market_id: &str,
+) -> rusqlite::Result<Option<u64>> {
+    conn.query_row(
+        "SELECT cursor FROM sync_cursors WHERE stream = ?1 AND market_id = ?2",
+        params![stream.as_str(), market_id],
+        |row| row.get::<_, i64>(0),
+    )
+    .optional()
+    .map(|cursor| cursor.map(|c| c as u64))
+}
+
+fn write_cursor(
+    conn: &Connection,
+    stream: SyncStream,
+    market_id: &str,
+    cursor: u64,
+) -> rusqlite::Result<()> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_cursors (stream, market_id, cursor, updated_at)
+         VALUES (?1, ?2, ?3, ?4)",
+        params![stream.as_str(), market_id, cursor as i64, now_secs() as i64],
+    )?;
+    Ok(())
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rust_decimal::Decimal;
+
+    #[test]
+    fn test_order_lifecycle() {
+        let store = OrderStore::open_in_memory().unwrap();
+        let order = PerpOrder {
+            side: Side::Sell,
+            price: Decimal::from(100),
+            quantity: Decimal::from(2),
+            ..Default::default()
+        };
+
+        store.record_intent("m", 1, &order).unwrap();
+        store.record_intent("m", 2, &order).unwrap();
+        store
+            .record_ack(&OrderResult {
+                order_id: 1,
+                sequence_number: 0,
+                expected_tick: 0,
+                tx_hash: "h".to_string(),
+                immediate: None,
+            })
+            .unwrap();
+        store.record_pending(3, "{}", 0).unwrap();
+        store.record_rejection(2).unwrap();
+        store.record_fill(1, 100.0, 0.5, 0).unwrap();
+        assert_eq!(store.pending_submissions().unwrap()[0].order_id, 3);
+        store.record_sequenced(3).unwrap();
+        assert!(store.pending_submissions().unwrap().is_empty());
+
+        let outstanding = store.outstanding_orders().unwrap();
+        assert_eq!(outstanding.len(), 1);
+        assert_eq!(outstanding[0].side, Side::Sell);
+        assert_eq!(outstanding[0].status, StoredOrderStatus::PartiallyFilled);
+        assert_eq!(outstanding[0].tx_hash.as_deref(), Some("h"));
+
+        store.record_fill(1, 100.0, 1.5, 0).unwrap();
+        let filled = store.order(1).unwrap().unwrap();
+        assert_eq!(filled.status, StoredOrderStatus::Filled);
+        assert!(store.outstanding_orders().unwrap().is_empty());
+    }
+
+    #[test]
+    fn test_trade_sync_cursor() {
+        let store = OrderStore::open_in_memory().unwrap();
+        let trade = |buyer: &str, seller: &str, timestamp| Trade {
+            buyer_owner: buyer.to_string(),
+            seller_owner: seller.to_string(),
+            price: 100,
+            quantity: 1,
+            timestamp,
+            base_mint: String::new(),
+            quote_mint: String::new(),
+            liquidated_owner: None,
+            buyer_fee: Some(5),
+            seller_fee: Some(-1),
+        };
+
+        let first = [
+            trade("me", "a", 10),
+            trade("a", "b", 11),
+            trade("b", "me", 12),
+        ];
+        assert_eq!(store.record_trades("m", "me", &first).unwrap(), 2);
+        assert_eq!(
+            store.sync_cursor(SyncStream::Trades, "m").unwrap(),
+            Some(12)
+        );
+
+        // Overlapping fetch: only the new trade is added, older ones are skipped
+        let second = [
+            trade("me", "a", 5),
+            trade("b", "me", 12),
+            trade("me", "c", 12),
+        ];
+        assert_eq!(store.record_trades("m", "me", &second).unwrap(), 1);
+
+        let stored = store.trades("m").unwrap();
+        assert_eq!(stored.len(), 3);
+        assert_eq!(stored[0].fee_for("me"), Some(5));
+        assert!(stored.iter().all(|t| t.timestamp >= 10));
+        assert_eq!(store.sync_cursor(SyncStream::Funding, "m").unwrap(), None);
+    }
+}
@@ -0,0 +1,524 @@
+//! Push-based market data over the node's WebSocket endpoint.
+//!
+//! A [`FermiStream`] holds one connection and fans messages out on
+//! `tokio::sync::broadcast` channels, one per kind: orderbook deltas, trades,
+//! and funding events. Subscriptions are kept by the stream rather than the
+//! connection, so after a drop it reconnects with backoff and resubscribes
+//! to everything on its own. Each channel can also be read as a `Stream`.
+//!
+//! Orderbook subscriptions start with a snapshot and continue with
+//! sequenced deltas. A delta that skips a sequence number is dropped and the
+//! market is resubscribed, so consumers only ever see a gap-free sequence
+//! that [`OrderbookDelta::apply`] can fold into a local [`Orderbook`].
+
+use std::collections::{BTreeSet, HashMap};
+use std::time::Duration;
+
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use tokio::sync::{broadcast, mpsc};
+use tokio::task::JoinHandle;
+use tokio_stream::wrappers::BroadcastStream;
+use tokio_stream::Stream;
+use tokio_tungstenite::tungstenite::Message;
+use tracing::{debug, info, warn};
+
+use crate::error::{Result, SdkError};
+use crate::types::{FundingEvent, Orderbook, OrderbookEntry, Trade};
+
+/// Connection settings for a [`FermiStream`].
+#[derive(Debug, Clone)]
+pub struct StreamConfig {
+    /// WebSocket endpoint (e.g., "ws://localhost:8080/ws")
+    pub url: String,
+    /// Wait before the first reconnect attempt; doubles on each failure
+    pub reconnect_delay: Duration,
+    /// Longest wait between reconnect attempts
+    pub max_reconnect_delay: Duration,
+    /// Messages buffered per channel before slow receivers lag
+    pub capacity: usize,
+}
+
+impl Default for StreamConfig {
+    fn default() -> Self {
+        Self {
+            url: std::env::var("FERMI_WS_ENDPOINT")
+                .unwrap_or_else(|_| "ws://localhost:8080/ws".to_string()),
+            reconnect_delay: Duration::from_millis(500),
+            max_reconnect_delay: Duration::from_secs(30),
+            capacity: 1024,
+        }
+    }
+}
+
+impl StreamConfig {
+    /// The `/ws` endpoint served alongside an RPC endpoint.
+    pub fn for_rpc_endpoint(rpc_endpoint: &str) -> Self {
+        let base = rpc_endpoint.trim_end_matches('/');
+        let url = match base.split_once("://") {
+            Some(("https", rest)) => format!("wss://{}/ws", rest),
+            Some((_, rest)) => format!("ws://{}/ws", rest),
+            None => format!("ws://{}/ws", base),
+        };
+        Self {
+            url,
+            ..Self::default()
+        }
+    }
+}
+
+/// A kind of market data a stream can subscribe to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Channel {
+    Orderbook,
+    Trades,
+    Funding,
+}
+
+/// One change to a market's resting orders.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum BookChange {
+    /// Order added, or its remaining quantity changed
+    Upsert(OrderbookEntry),
+    /// Order filled, cancelled, or expired
+    Remove { order_id: u64 },
+}
+
+/// Orderbook update for one market.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderbookDelta {
+    pub market_id: String,
+    /// Increases by one with every update to this market
+    pub sequence: u64,
+    /// The changes replace the whole book rather than amend it
+    #[serde(default)]
+    pub snapshot: bool,
+    pub changes: Vec<BookChange>,
+}
+
+impl OrderbookDelta {
+    /// Fold this update into `book`.
+    pub fn apply(&self, book: &mut Orderbook) {
+        if self.snapshot {
+            book.buys.clear();
+            book.sells.clear();
+        }
+        for change in &self.changes {
+            match change {
+                BookChange::Upsert(entry) => {
+                    book.buys.retain(|o| o.order_id != entry.order_id);
+                    book.sells.retain(|o| o.order_id != entry.order_id);
+                    if entry.side.eq_ignore_ascii_case("buy") {
+                        book.buys.push(entry.clone());
+                    } else {
+                        book.sells.push(entry.clone());
+                    }
+                }
+                BookChange::Remove { order_id } => {
+                    book.buys.retain(|o| o.order_id != *order_id);
+                    book.sells.retain(|o| o.order_id != *order_id);
+                }
+            }
+        }
+    }
+}
+
+/// A trade and the market it printed in.
+#[derive(Debug, Clone)]
+pub struct MarketTrade {
+    pub market_id: String,
+    pub trade: Trade,
+}
+
+/// Connection state changes.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum StreamStatus {
+    /// Connected and resubscribed to `subscriptions` channels
+    Connected { subscriptions: usize },
+    /// Connection lost or refused; a reconnect follows
+    Disconnected(String),
+}
+
+#[derive(Serialize)]
+struct Request<'a> {
+    op: &'a str,
+    channel: Channel,
+    market_id: &'a str,
+}
+
+#[derive(Deserialize)]
+#[serde(tag = "channel", rename_all = "snake_case")]
+enum WireMessage {
+    Orderbook(OrderbookDelta),
+    Trades {
+        market_id: String,
+        trades: Vec<Trade>,
+    },
+    Funding(FundingEvent),
+    Error {
+        message: String,
+    },
+}
+
+enum Command {
+    Subscribe(Channel, String),
+    Unsubscribe(Channel, String),
+}
+
+/// Whether a delta continues the sequence seen so far for its market.
+#[derive(Debug, Default)]
+struct BookSequences {
+    last: HashMap<String, u64>,
+}
+
+impl BookSequences {
+    /// Accept `delta` if it is a snapshot or the next in sequence after one.
+    /// `Err` carries the expected sequence when updates were missed.
+    fn accept(&mut self, delta: &OrderbookDelta) -> std::result::Result<(), Option<u64>> {
+        if !delta.snapshot {
+            let expected = self.last.get(&delta.market_id).map(|s| s + 1);
+            if expected != Some(delta.sequence) {
+                self.last.remove(&delta.market_id);
+                return Err(expected);
+            }
+        }
+        self.last.insert(delta.market_id.clone(), delta.sequence);
+        Ok(())
+    }
+}
+
+/// Senders shared with the connection task.
+#[derive(Clone)]
+struct Outputs {
+    books: broadcast::Sender<OrderbookDelta>,
+    trades: broadcast::Sender<MarketTrade>,
+    funding: broadcast::Sender<FundingEvent>,
+    status: broadcast::Sender<StreamStatus>,
+}
+
+/// A live, self-reconnecting market-data subscription.
+///
+/// The connection task stops when the stream is dropped.
+pub struct FermiStream {
+    commands: mpsc::UnboundedSender<Command>,
+    outputs: Outputs,
+    handle: JoinHandle<()>,
+}
+
+impl std::fmt::Debug for FermiStream {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("FermiStream")
+            .field("running", &!self.handle.is_finished())
+            .finish()
+    }
+}
+
+impl FermiStream {
+    /// Start the connection task. Connecting happens in the background;
+    /// watch [`FermiStream::status`] to see when it is up.
+    pub fn connect(config: StreamConfig) -> Self {
+        let capacity = config.capacity.max(1);
+        let outputs = Outputs {
+            books: broadcast::channel(capacity).0,
+            trades: broadcast::channel(capacity).0,
+            funding: broadcast::channel(capacity).0,
+            status: broadcast::channel(16).0,
+        };
+        let (commands, rx) = mpsc::unbounded_channel();
+        let handle = tokio::spawn(run(config, rx, outputs.clone()));
+        Self {
+            commands,
+            outputs,
+            handle,
+        }
+    }
+
+    /// Subscribe to `channel` for a market. Kept across reconnects.
+    pub fn subscribe(&self, channel: Channel, market_id: &str) -> Result<()> {
+        self.send(Command::Subscribe(channel, market_id.to_string()))
+    }
+
+    pub fn unsubscribe(&self, channel: Channel, market_id: &str) -> Result<()> {
+        self.send(Command::Unsubscribe(channel, market_id.to_string()))
+    }
+
+    fn send(&self, command: Command) -> Result<()> {
+        self.commands
+            .send(command)
+            .map_err(|_| SdkError::Stream("connection task has stopped".into()))
+    }
+
+    pub fn orderbook(&self) -> broadcast::Receiver<OrderbookDelta> {
+        self.outputs.books.subscribe()
+    }
+
+    pub fn trades(&self) -> broadcast::Receiver<MarketTrade> {
+        self.outputs.trades.subscribe()
+    }
+
+    pub fn funding(&self) -> broadcast::Receiver<FundingEvent> {
+        self.outputs.funding.subscribe()
+    }
+
+    pub fn status(&self) -> broadcast::Receiver<StreamStatus> {
+        self.outputs.status.subscribe()
+    }
+
+    /// Orderbook deltas as a `Stream`. Messages missed by a lagging reader
+    /// are skipped with a warning; resubscribe the market to resync.
+    pub fn orderbook_stream(&self) -> impl Stream<Item = OrderbookDelta> {
+        skip_lagged(self.orderbook())
+    }
+
+    pub fn trade_stream(&self) -> impl Stream<Item = MarketTrade> {
+        skip_lagged(self.trades())
+    }
+
+    pub fn funding_stream(&self) -> impl Stream<Item = FundingEvent> {
+        skip_lagged(self.funding())
+    }
+}
+
+impl Drop for FermiStream {
+    fn drop(&mut self) {
+        self.handle.abort();
+    }
+}
+
+fn skip_lagged<T: Clone + Send + 'static>(rx: broadcast::Receiver<T>) -> impl Stream<Item = T> {
+    BroadcastStream::new(rx).filter_map(|item| {
+        futures_util::future::ready(match item {
+            Ok(item) => Some(item),
+            Err(e) => {
+                warn!("Stream reader lagged: {}", e);
+                None
+            }
+        })
+    })
+}
+
+/// Connection task: connect, resubscribe, pump messages, and back off
+/// between attempts until the stream is dropped.
+async fn run(
+    config: StreamConfig,
+    mut commands: mpsc::UnboundedReceiver<Command>,
+    outputs: Outputs,
+) {
+    let mut subscriptions: BTreeSet<(Channel, String)> = BTreeSet::new();
+    let mut delay = config.reconnect_delay;
+
+    loop {
+        let reason = match tokio_tungstenite::connect_async(config.url.as_str()).await {
+            Ok((socket, _)) => {
+                delay = config.reconnect_delay;
+                let (mut sink, mut source) = socket.split();
+                let mut sequences = BookSequences::default();
+                let mut resubscribed = Ok(());
+                for (channel, market_id) in &subscriptions {
+                    resubscribed = send_request(&mut sink, "subscribe", *channel, market_id).await;
+                    if resubscribed.is_err() {
+                        break;
+                    }
+                }
+                if resubscribed.is_ok() {
+                    info!("Stream connected to {}", config.url);
+                    let _ = outputs.status.send(StreamStatus::Connected {
+                        subscriptions: subscriptions.len(),
+                    });
+                }
+
+                loop {
+                    if let Err(e) = &resubscribed {
+                        break e.to_string();
+                    }
+                    tokio::select! {
+                        command = commands.recv() => {
+                            let Some(command) = command else {
+                                let _ = sink.close().await;
+                                return;
+                            };
+                            let (op, channel, market_id) = match command {
+                                Command::Subscribe(channel, market_id) => {
+                                    subscriptions.insert((channel, market_id.clone()));
+                                    ("subscribe", channel, market_id)
+                                }
+                                Command::Unsubscribe(channel, market_id) => {
+                                    subscriptions.remove(&(channel, market_id.clone()));
+                                    ("unsubscribe", channel, market_id)
+                                }
+                            };
+                            resubscribed = send_request(&mut sink, op, channel, &market_id).await;
+                        }
+                        message = source.next() => {
+                            let text = match message {
+                                Some(Ok(Message::Text(text))) => text,
+                                Some(Ok(Message::Close(frame))) => {
+                                    break format!("closed by server: {:?}", frame);
+                                }
+                                Some(Ok(_)) => continue,
+                                Some(Err(e)) => break e.to_string(),
+                                None => break "connection closed".to_string(),
+                            };
+                            let Some(resync) = dispatch(&text, &mut sequences, &outputs) else {
+                                continue;
+                            };
+                            resubscribed = resubscribe(&mut sink, &resync).await;
+                        }
+                    }
+                }
+            }
+            Err(e) => e.to_string(),
+        };
+
+        warn!(
+            "Stream disconnected ({}), reconnecting in {:?}",
+            reason, delay
+        );
+        let _ = outputs.status.send(StreamStatus::Disconnected(reason));
+
+        // Keep taking subscription changes while waiting to reconnect
+        let sleep = tokio::time::sleep(delay);
+        tokio::pin!(sleep);
+        loop {
+            tokio::select! {
+                _ = &mut sleep => break,
+                command = commands.recv() => match command {
+                    Some(Command::Subscribe(channel, market_id)) => {
+                        subscriptions.insert((channel, market_id));
+                    }
+                    Some(Command::Unsubscribe(channel, market_id)) => {
+                        subscriptions.remove(&(channel, market_id));
+                    }
+                    None => return,
+                },
+            }
+        }
+        delay = (delay * 2).min(config.max_reconnect_delay);
+    }
+}
+
+/// Broadcast one server message. Returns the market to resubscribe when an
+/// orderbook delta arrived out of sequence.
+fn dispatch(text: &str, sequences: &mut BookSequences, outputs: &Outputs) -> Option<String> {
+    let message = match serde_json::from_str::<WireMessage>(text) {
+        Ok(message) => message,
+        Err(e) => {
+            debug!("Ignoring stream message ({}): {}", e, text);
+            return None;
+        }
+    };
+    match message {
+        WireMessage::Orderbook(delta) => {
+            if let Err(expected) = sequences.accept(&delta) {
+                warn!(
+                    "Orderbook {} out of sequence (expected {:?}, got {}), resubscribing",
+                    delta.market_id, expected, delta.sequence
+                );
+                return Some(delta.market_id);
+            }
+            let _ = outputs.books.send(delta);
+        }
+        WireMessage::Trades { market_id, trades } => {
+            for trade in trades {
+                let _ = outputs.trades.send(MarketTrade {
+                    market_id: market_id.clone(),
+                    trade,
+                });
+            }
+        }
+        WireMessage::Funding(event) => {
+            let _ = outputs.funding.send(event);
+        }
+        WireMessage::Error { message } => warn!("Stream error from server: {}", message),
+    }
+    None
+}
+
+type Sink = futures_util::stream::SplitSink<
+    tokio_tungstenite::WebSocketStream<tokio_tungstenite::MaybeTlsStream<tokio::net::TcpStream>>,
+    Message,
+>;
+
+async fn send_request(sink: &mut Sink, op: &str, channel: Channel, market_id: &str) -> Result<()> {
+    let request = serde_json::to_string(&Request {
+        op,
+        channel,
+        market_id,
+    })?;
+    sink.send(Message::Text(request)).await?;
+    Ok(())
+}
+
+/// Resubscribe a market's orderbook to get a fresh snapshot.
+async fn resubscribe(sink: &mut Sink, market_id: &str) -> Result<()> {
+    send_request(sink, "unsubscribe", Channel::Orderbook, market_id).await?;
+    send_request(sink, "subscribe", Channel::Orderbook, market_id).await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(order_id: u64, side: &str, price: u64) -> OrderbookEntry {
+        OrderbookEntry {
+            order_id,
+            owner: "o".to_string(),
+            price,
+            quantity: 1,
+            side: side.to_string(),
+            expiry: 0,
+        }
+    }
+
+    #[test]
+    fn test_orderbook_deltas_apply_in_sequence() {
+        let message = r#"{"channel":"orderbook","market_id":"m","sequence":7,"snapshot":true,
+            "changes":[{"type":"upsert","order_id":1,"owner":"o","price":100,"quantity":1,
+            "side":"Buy","expiry":0}]}"#;
+        let WireMessage::Orderbook(snapshot) = serde_json::from_str(message).unwrap() else {
+            panic!("expected an orderbook message");
+        };
+        let delta = |sequence: u64, changes: Vec<BookChange>| OrderbookDelta {
+            market_id: "m".to_string(),
+            sequence,
+            snapshot: false,
+            changes,
+        };
+
+        let mut sequences = BookSequences::default();
+        // Deltas before the first snapshot cannot be applied
+        assert_eq!(sequences.accept(&delta(6, vec![])), Err(None));
+        assert_eq!(sequences.accept(&snapshot), Ok(()));
+
+        let mut book = Orderbook {
+            buys: vec![entry(9, "Buy", 90)],
+            sells: vec![],
+        };
+        snapshot.apply(&mut book);
+        assert_eq!(book.best_bid().unwrap().order_id, 1);
+        assert_eq!(book.buys.len(), 1);
+
+        let next = delta(
+            8,
+            vec![
+                BookChange::Upsert(entry(2, "Sell", 105)),
+                BookChange::Remove { order_id: 1 },
+            ],
+        );
+        assert_eq!(sequences.accept(&next), Ok(()));
+        next.apply(&mut book);
+        assert!(book.buys.is_empty());
+        assert_eq!(book.best_ask().unwrap().price, 105);
+
+        // A skipped sequence needs a fresh snapshot
+        assert_eq!(sequences.accept(&delta(10, vec![])), Err(Some(9)));
+        assert_eq!(sequences.accept(&delta(11, vec![])), Err(None));
+
+        assert_eq!(
+            StreamConfig::for_rpc_endpoint("https://node.example/").url,
+            "wss://node.example/ws"
+        );
+    }
+}
@@ -0,0 +1,426 @@
+//! Test doubles for code written against [`FermiApi`].
+//!
+//! [`MockFermiClient`] answers queries from state you program, places and
+//! cancels orders against its own open-order list, and records every call so
+//! a test can assert on what a strategy did.
+//!
+//! ```rust,ignore
+//! let mock = MockFermiClient::new("trader");
+//! mock.set_markets(vec![sol_perp]);
+//! mock.push_order_response(Err(SdkError::InsufficientMargin("im".into())));
+//!
+//! run_strategy(&mock).await;
+//! assert_eq!(mock.placed_orders().len(), 1);
+//! ```
+
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Mutex, MutexGuard};
+
+use async_trait::async_trait;
+
+use crate::api::FermiApi;
+use crate::client::to_canonical_units;
+use crate::error::{Result, SdkError};
+use crate::types::{
+    AccountSummary, Balances, CancelResult, FundingEvent, MarketInfo, OpenOrder, OrderResult,
+    Orderbook, PerpOrder, Position, ReplaceResult, Rounding, Trade,
+};
+
+/// One call made on a [`MockFermiClient`], with markets as passed in.
+#[derive(Debug, Clone, PartialEq)]
+pub enum MockCall {
+    GetMarkets,
+    GetMarket(String),
+    GetOrderbook(String),
+    GetTrades(String),
+    GetFunding(String),
+    GetPositions,
+    GetMyOrders,
+    GetAccount,
+    GetBalances,
+    PlacePerpOrder {
+        market: String,
+        order: PerpOrder,
+    },
+    CancelOrder {
+        market: String,
+        order_id: u64,
+    },
+    CancelAllOrders(Option<String>),
+    ReplaceOrder {
+        market: String,
+        old_order_id: u64,
+        order: PerpOrder,
+    },
+}
+
+#[derive(Default)]
+struct MockState {
+    markets: Vec<MarketInfo>,
+    orderbooks: HashMap<String, Orderbook>,
+    trades: HashMap<String, Vec<Trade>>,
+    funding: HashMap<String, Vec<FundingEvent>>,
+    positions: Vec<Position>,
+    open_orders: Vec<OpenOrder>,
+    account: Option<AccountSummary>,
+    balances: Option<Balances>,
+    order_responses: VecDeque<Result<OrderResult>>,
+    cancel_responses: VecDeque<Result<CancelResult>>,
+    failures: VecDeque<SdkError>,
+    calls: Vec<MockCall>,
+    next_order_id: u64,
+    sequence_number: u64,
+}
+
+/// An in-memory [`FermiApi`] with programmable responses.
+///
+/// Markets must be registered with [`set_markets`](Self::set_markets) before
+/// they can be named, by UUID or symbol. Unprogrammed books, trades, and
+/// funding are empty. Placements succeed with increasing order IDs and rest
+/// in [`get_my_orders`](FermiApi::get_my_orders) until cancelled, unless a
+/// response was queued with [`push_order_response`](Self::push_order_response).
+pub struct MockFermiClient {
+    pubkey: String,
+    state: Mutex<MockState>,
+}
+
+impl MockFermiClient {
+    pub fn new(pubkey: impl Into<String>) -> Self {
+        Self {
+            pubkey: pubkey.into(),
+            state: Mutex::new(MockState {
+                next_order_id: 1,
+                ..MockState::default()
+            }),
+        }
+    }
+
+    fn state(&self) -> MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_markets(&self, markets: Vec<MarketInfo>) {
+        self.state().markets = markets;
+    }
+
+    pub fn set_orderbook(&self, market_id: &str, book: Orderbook) {
+        self.state().orderbooks.insert(market_id.to_string(), book);
+    }
+
+    pub fn set_trades(&self, market_id: &str, trades: Vec<Trade>) {
+        self.state().trades.insert(market_id.to_string(), trades);
+    }
+
+    pub fn set_funding(&self, market_id: &str, funding: Vec<FundingEvent>) {
+        self.state().funding.insert(market_id.to_string(), funding);
+    }
+
+    pub fn set_positions(&self, positions: Vec<Position>) {
+        self.state().positions = positions;
+    }
+
+    /// Replace the open-order list placements and cancels work against.
+    pub fn set_open_orders(&self, orders: Vec<OpenOrder>) {
+        self.state().open_orders = orders;
+    }
+
+    pub fn set_account(&self, account: AccountSummary) {
+        self.state().account = Some(account);
+    }
+
+    pub fn set_balances(&self, balances: Balances) {
+        self.state().balances = Some(balances);
+    }
+
+    /// Queue the outcome of the next placement, ahead of the default.
+    pub fn push_order_response(&self, response: Result<OrderResult>) {
+        self.state().order_responses.push_back(response);
+    }
+
+    /// Queue the outcome of the next cancel, ahead of the default.
+    pub fn push_cancel_response(&self, response: Result<CancelResult>) {
+        self.state().cancel_responses.push_back(response);
+    }
+
+    /// Fail the next call of any kind with `error`.
+    pub fn fail_next(&self, error: SdkError) {
+        self.state().failures.push_back(error);
+    }
+
+    /// Every call made so far, oldest first.
+    pub fn calls(&self) -> Vec<MockCall> {
+        self.state().calls.clone()
+    }
+
+    /// Orders passed to `place_perp_order` and `replace_order`, oldest first.
+    pub fn placed_orders(&self) -> Vec<PerpOrder> {
+        self.state()
+            .calls
+            .iter()
+            .filter_map(|call| match call {
+                MockCall::PlacePerpOrder { order, .. } | MockCall::ReplaceOrder { order, .. } => {
+                    Some(order.clone())
+                }
+                _ => None,
+            })
+            .collect()
+    }
+
+    pub fn clear_calls(&self) {
+        self.state().calls.clear();
+    }
+
+    /// Record `call` and take any queued failure.
+    fn begin(&self, call: MockCall) -> Result<MutexGuard<'_, MockState>> {
+        let mut state = self.state();
+        state.calls.push(call);
+        match state.failures.pop_front() {
+            Some(error) => Err(error),
+            None => Ok(state),
+        }
+    }
+}
+
+impl MockState {
+    fn market(&self, market: &str) -> Result<MarketInfo> {
+        self.markets
+            .iter()
+            .find(|m| m.uuid == market || m.name.eq_ignore_ascii_case(market))
+            .cloned()
+            .ok_or_else(|| SdkError::MarketNotFound(market.to_string()))
+    }
+
+    fn place(&mut self, owner: &str, market: &str, order: &PerpOrder) -> Result<OrderResult> {
+        let market = self.market(market)?;
+        let result = match self.order_responses.pop_front() {
+            Some(response) => response?,
+            None => {
+                let order_id = self.next_order_id;
+                self.next_order_id += 1;
+                self.sequence_number += 1;
+                OrderResult {
+                    order_id,
+                    sequence_number: self.sequence_number,
+                    expected_tick: 0,
+                    tx_hash: format!("mock-tx-{}", order_id),
+                    immediate: None,
+                }
+            }
+        };
+        self.open_orders.push(OpenOrder {
+            order_id: result.order_id,
+            market_id: market.uuid.clone(),
+            market_name: Some(market.name.clone()),
+            owner: owner.to_string(),
+            side: order.side.to_string(),
+            price: to_canonical_units(order.price, market.quote_decimals, Rounding::Nearest)?,
+            quantity: to_canonical_units(order.quantity, market.base_decimals, Rounding::Nearest)?,
+            expiry: 0,
+            timestamp: None,
+        });
+        Ok(result)
+    }
+
+    fn cancel(&mut self, order_id: u64) -> Result<CancelResult> {
+        let result = match self.cancel_responses.pop_front() {
+            Some(response) => response?,
+            None => {
+                self.sequence_number += 1;
+                CancelResult {
+                    order_id,
+                    sequence_number: self.sequence_number,
+                    expected_tick: 0,
+                    tx_hash: format!("mock-cancel-{}", order_id),
+                }
+            }
+        };
+        self.open_orders.retain(|o| o.order_id != order_id);
+        Ok(result)
+    }
+}
+
+#[async_trait]
+impl FermiApi for MockFermiClient {
+    fn pubkey(&self) -> String {
+        self.pubkey.clone()
+    }
+
+    async fn get_markets(&self) -> Result<Vec<MarketInfo>> {
+        Ok(self.begin(MockCall::GetMarkets)?.markets.clone())
+    }
+
+    async fn get_market(&self, market: &str) -> Result<MarketInfo> {
+        self.begin(MockCall::GetMarket(market.to_string()))?
+            .market(market)
+    }
+
+    async fn get_orderbook(&self, market: &str) -> Result<Orderbook> {
+        let state = self.begin(MockCall::GetOrderbook(market.to_string()))?;
+        let market = state.market(market)?;
+        Ok(state
+            .orderbooks
+            .get(&market.uuid)
+            .cloned()
+            .unwrap_or(Orderbook {
+                buys: vec![],
+                sells: vec![],
+            }))
+    }
+
+    async fn get_trades(&self, market: &str) -> Result<Vec<Trade>> {
+        let state = self.begin(MockCall::GetTrades(market.to_string()))?;
+        let market = state.market(market)?;
+        Ok(state.trades.get(&market.uuid).cloned().unwrap_or_default())
+    }
+
+    async fn get_funding(&self, market: &str) -> Result<Vec<FundingEvent>> {
+        let state = self.begin(MockCall::GetFunding(market.to_string()))?;
+        let market = state.market(market)?;
+        Ok(state.funding.get(&market.uuid).cloned().unwrap_or_default())
+    }
+
+    async fn get_positions(&self) -> Result<Vec<Position>> {
+        Ok(self.begin(MockCall::GetPositions)?.positions.clone())
+    }
+
+    async fn get_my_orders(&self) -> Result<Vec<OpenOrder>> {
+        Ok(self.begin(MockCall::GetMyOrders)?.open_orders.clone())
+    }
+
+    async fn get_account(&self) -> Result<AccountSummary> {
+        self.begin(MockCall::GetAccount)?
+            .account
+            .clone()
+            .ok_or_else(|| SdkError::AccountNotFound(self.pubkey.clone()))
+    }
+
+    async fn get_balances(&self) -> Result<Balances> {
+        Ok(self
+            .begin(MockCall::GetBalances)?
+            .balances
+            .clone()
+            .unwrap_or(Balances {
+                tokens: HashMap::new(),
+            }))
+    }
+
+    async fn place_perp_order(&self, market: &str, order: PerpOrder) -> Result<OrderResult> {
+        let mut state = self.begin(MockCall::PlacePerpOrder {
+            market: market.to_string(),
+            order: order.clone(),
+        })?;
+        state.place(&self.pubkey, market, &order)
+    }
+
+    async fn cancel_order(&self, market: &str, order_id: u64) -> Result<CancelResult> {
+        let mut state = self.begin(MockCall::CancelOrder {
+            market: market.to_string(),
+            order_id,
+        })?;
+        state.market(market)?;
+        state.cancel(order_id)
+    }
+
+    async fn cancel_all_orders(
+        &self,
+        market: Option<&str>,
+    ) -> Result<Vec<(u64, Result<CancelResult>)>> {
+        let mut state = self.begin(MockCall::CancelAllOrders(market.map(str::to_string)))?;
+        let market_id = market.map(|m| state.market(m)).transpose()?.map(|m| m.uuid);
+        let order_ids: Vec<u64> = state
+            .open_orders
+            .iter()
+            .filter(|o| market_id.as_ref().is_none_or(|id| o.market_id == *id))
+            .map(|o| o.order_id)
+            .collect();
+        Ok(order_ids
+            .into_iter()
+            .map(|order_id| (order_id, state.cancel(order_id)))
+            .collect())
+    }
+
+    async fn replace_order(
+        &self,
+        market: &str,
+        old_order_id: u64,
+        new_order: PerpOrder,
+    ) -> Result<ReplaceResult> {
+        let mut state = self.begin(MockCall::ReplaceOrder {
+            market: market.to_string(),
+            old_order_id,
+            order: new_order.clone(),
+        })?;
+        state.market(market)?;
+        let cancel = state.cancel(old_order_id)?;
+        let order = state.place(&self.pubkey, market, &new_order)?;
+        Ok(ReplaceResult { cancel, order })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::{test_market, Side};
+    use rust_decimal::Decimal;
+
+    fn market() -> MarketInfo {
+        MarketInfo {
+            uuid: "m1".into(),
+            ..test_market(3, 2)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_mock_places_cancels_and_records() {
+        let mock = MockFermiClient::new("trader");
+        mock.set_markets(vec![market()]);
+        let api: &dyn FermiApi = &mock;
+
+        let order = PerpOrder {
+            side: Side::Sell,
+            price: Decimal::new(18550, 2),
+            quantity: Decimal::ONE,
+            ..Default::default()
+        };
+        let placed = api
+            .place_perp_order("SOL-PERP", order.clone())
+            .await
+            .unwrap();
+        assert_eq!(placed.order_id, 1);
+        let open = api.get_my_orders().await.unwrap();
+        assert_eq!(open.len(), 1);
+        assert_eq!((open[0].price, open[0].quantity), (18_550, 1_000));
+        assert_eq!(open[0].side, "sell");
+
+        // Scripted rejection, then an injected failure on the next query
+        mock.push_order_response(Err(SdkError::InsufficientMargin("im".into())));
+        assert!(matches!(
+            api.place_perp_order("m1", order.clone()).await,
+            Err(SdkError::InsufficientMargin(_))
+        ));
+        mock.fail_next(SdkError::Rpc("down".into()));
+        assert!(api.get_positions().await.is_err());
+        assert!(api.get_positions().await.unwrap().is_empty());
+
+        let cancelled = api.cancel_all_orders(Some("m1")).await.unwrap();
+        assert_eq!(cancelled.len(), 1);
+        assert_eq!(cancelled[0].0, 1);
+        assert!(api.get_my_orders().await.unwrap().is_empty());
+        assert!(matches!(
+            api.get_orderbook("ETH-PERP").await,
+            Err(SdkError::MarketNotFound(_))
+        ));
+
+        assert_eq!(mock.placed_orders().len(), 2);
+        assert_eq!(
+            mock.calls()[..2],
+            [
+                MockCall::PlacePerpOrder {
+                    market: "SOL-PERP".into(),
+                    order: order.clone(),
+                },
+                MockCall::GetMyOrders,
+            ]
+        );
+    }
+}
@@ -0,0 +1,364 @@
+//! Rate-limited, priority-ordered submission queue, and per-endpoint
+//! request limits.
+//!
+//! Every order and cancel waits for a token before it is signed. When tokens
+//! are scarce, waiting submissions are released by priority rather than in
+//! arrival order, so cancels and risk-reducing orders are never stuck behind
+//! a backlog of new quotes.
+//!
+//! Below that, every RPC request and Continuum call to an endpoint with a
+//! configured limit waits on that endpoint's [`RequestLimiter`], which also
+//! holds all callers back while the endpoint is signalling overload.
+//!
+//! Submissions in flight are also counted, so shutdown can refuse new ones
+//! and wait for those already past their checks.
+
+use std::collections::BTreeSet;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+use tokio::sync::Notify;
+
+use crate::types::{PerpOrder, PositionEffect};
+
+/// Submission rate allowed by a throttle.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RateLimit {
+    /// Sustained submissions per second
+    pub per_second: f64,
+    /// Submissions that may be sent back-to-back after an idle period
+    pub burst: u32,
+}
+
+/// Queue priority of a submission. Lower variants are released first.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum SubmissionPriority {
+    Cancel,
+    /// Reduce-only or closing orders
+    RiskReducing,
+    /// Orders that open or add to a position
+    Quote,
+}
+
+impl SubmissionPriority {
+    /// Priority for placing `order`.
+    pub fn for_order(order: &PerpOrder) -> Self {
+        if order.reduce_only || order.position_effect == PositionEffect::Close {
+            SubmissionPriority::RiskReducing
+        } else {
+            SubmissionPriority::Quote
+        }
+    }
+}
+
+#[derive(Debug)]
+struct State {
+    tokens: f64,
+    refilled_at: Instant,
+    next_ticket: u64,
+    waiting: BTreeSet<(SubmissionPriority, u64)>,
+    /// Tokens consumed by each `Quote` submission
+    quote_cost: f64,
+}
+
+/// Token-bucket limiter that releases waiters in priority order.
+///
+/// Cloning is cheap and clones share the same bucket, so one throttle can
+/// be handed to several clients trading under the same rate limit.
+#[derive(Debug, Clone)]
+pub struct SubmissionThrottle {
+    limit: RateLimit,
+    state: Arc<Mutex<State>>,
+    notify: Arc<Notify>,
+}
+
+impl SubmissionThrottle {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Arc::new(Mutex::new(State {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+                next_ticket: 0,
+                waiting: BTreeSet::new(),
+                quote_cost: 1.0,
+            })),
+            notify: Arc::new(Notify::new()),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Number of submissions currently waiting for a token.
+    pub fn queued(&self) -> usize {
+        self.state.lock().unwrap().waiting.len()
+    }
+
+    /// Slow `Quote` submissions to `1 / factor` of the configured rate while
+    /// leaving cancels and risk-reducing orders untouched. A factor of 1
+    /// restores the normal rate.
+    pub fn set_quote_slowdown(&self, factor: f64) {
+        self.state.lock().unwrap().quote_cost = factor.max(1.0);
+        self.notify.notify_waiters();
+    }
+
+    /// Current `Quote` slowdown factor.
+    pub fn quote_slowdown(&self) -> f64 {
+        self.state.lock().unwrap().quote_cost
+    }
+
+    /// Wait until a submission at `priority` may be sent.
+    pub async fn acquire(&self, priority: SubmissionPriority) {
+        let ticket = {
+            let mut state = self.state.lock().unwrap();
+            let ticket = (priority, state.next_ticket);
+            state.next_ticket += 1;
+            state.waiting.insert(ticket);
+            ticket
+        };
+        // Remove the ticket if the caller stops waiting
+        let mut guard = TicketGuard {
+            throttle: self,
+            ticket: Some(ticket),
+        };
+
+        loop {
+            let notified = self.notify.notified();
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                self.refill(&mut state);
+                let at_front = state.waiting.first() == Some(&ticket);
+                let cost = match priority {
+                    SubmissionPriority::Quote => state.quote_cost,
+                    _ => 1.0,
+                };
+                if at_front && state.tokens >= cost {
+                    state.tokens -= cost;
+                    state.waiting.remove(&ticket);
+                    guard.ticket = None;
+                    drop(state);
+                    self.notify.notify_waiters();
+                    return;
+                }
+                let missing = (cost - state.tokens).max(0.0);
+                Duration::from_secs_f64(missing / self.limit.per_second.max(f64::EPSILON))
+            };
+
+            tokio::select! {
+                _ = notified => {}
+                _ = tokio::time::sleep(wait) => {}
+            }
+        }
+    }
+
+    fn refill(&self, state: &mut State) {
+        let now = Instant::now();
+        let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+        // The cap must fit one slowed quote or it could never be released
+        let cap = (self.limit.burst.max(1) as f64).max(state.quote_cost);
+        state.tokens = (state.tokens + elapsed * self.limit.per_second).min(cap);
+        state.refilled_at = now;
+    }
+}
+
+#[derive(Debug)]
+struct LimiterState {
+    tokens: f64,
+    refilled_at: Instant,
+    /// Set when the endpoint asked callers to back off
+    paused_until: Option<Instant>,
+}
+
+/// Token bucket for every request to one endpoint.
+///
+/// Clones share the bucket, so RPC and Continuum clients talking to the
+/// same endpoint draw from one budget. After the endpoint answers with HTTP
+/// 429 or gRPC `RESOURCE_EXHAUSTED`, every caller waits out its Retry-After.
+#[derive(Debug, Clone)]
+pub struct RequestLimiter {
+    limit: RateLimit,
+    state: Arc<Mutex<LimiterState>>,
+}
+
+impl RequestLimiter {
+    pub fn new(limit: RateLimit) -> Self {
+        Self {
+            limit,
+            state: Arc::new(Mutex::new(LimiterState {
+                tokens: limit.burst as f64,
+                refilled_at: Instant::now(),
+                paused_until: None,
+            })),
+        }
+    }
+
+    pub fn limit(&self) -> RateLimit {
+        self.limit
+    }
+
+    /// Wait until a request may be sent.
+    pub async fn acquire(&self) {
+        loop {
+            let wait = {
+                let mut state = self.state.lock().unwrap();
+                let now = Instant::now();
+                let elapsed = now.duration_since(state.refilled_at).as_secs_f64();
+                state.tokens = (state.tokens + elapsed * self.limit.per_second)
+                    .min(self.limit.burst.max(1) as f64);
+                state.refilled_at = now;
+                match state.paused_until.filter(|until| *until > now) {
+                    Some(until) => until - now,
+                    None if state.tokens >= 1.0 => {
+                        state.tokens -= 1.0;
+                        return;
+                    }
+                    None => Duration::from_secs_f64(
+                        (1.0 - state.tokens) / self.limit.per_second.max(f64::EPSILON),
+                    ),
+                }
+            };
+            tokio::time::sleep(wait).await;
+        }
+    }
+
+    /// Hold every caller back for `delay`, as asked by the endpoint.
+    pub fn pause_for(&self, delay: Duration) {
+        let until = Instant::now() + delay;
+        let mut state = self.state.lock().unwrap();
+        state.paused_until = Some(state.paused_until.map_or(until, |u| u.max(until)));
+    }
+}
+
+struct TicketGuard<'a> {
+    throttle: &'a SubmissionThrottle,
+    ticket: Option<(SubmissionPriority, u64)>,
+}
+
+impl Drop for TicketGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(ticket) = self.ticket.take() {
+            self.throttle.state.lock().unwrap().waiting.remove(&ticket);
+            self.throttle.notify.notify_waiters();
+        }
+    }
+}
+
+/// Order submissions in flight, and whether shutdown has closed the gate.
+#[derive(Debug, Default)]
+pub(crate) struct SubmissionGate {
+    closed: AtomicBool,
+    in_flight: AtomicUsize,
+    drained: Notify,
+}
+
+impl SubmissionGate {
+    /// Count a submission in flight until the guard drops.
+    ///
+    /// Enter before checking `is_closed`: a submission then either sees the
+    /// gate closed or is waited for by `close`.
+    pub(crate) fn enter(&self) -> InFlightGuard<'_> {
+        self.in_flight.fetch_add(1, Ordering::SeqCst);
+        InFlightGuard(self)
+    }
+
+    pub(crate) fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    /// Close the gate, then wait until no submission is in flight.
+    pub(crate) async fn close(&self) {
+        self.closed.store(true, Ordering::SeqCst);
+        loop {
+            // Registered before the count is read, so no wakeup is missed
+            let drained = self.drained.notified();
+            if self.in_flight.load(Ordering::SeqCst) == 0 {
+                return;
+            }
+            drained.await;
+        }
+    }
+}
+
+pub(crate) struct InFlightGuard<'a>(&'a SubmissionGate);
+
+impl Drop for InFlightGuard<'_> {
+    fn drop(&mut self) {
+        if self.0.in_flight.fetch_sub(1, Ordering::SeqCst) == 1 {
+            self.0.drained.notify_waiters();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_gate_close_waits_for_submissions_in_flight() {
+        let gate = Arc::new(SubmissionGate::default());
+        let submission = gate.enter();
+
+        let closing = tokio::spawn({
+            let gate = gate.clone();
+            async move { gate.close().await }
+        });
+        tokio::task::yield_now().await;
+        assert!(gate.is_closed());
+        assert!(!closing.is_finished());
+
+        // Entered after closing, as shutdown's own reduce-only orders are
+        drop(gate.enter());
+        tokio::task::yield_now().await;
+        assert!(!closing.is_finished());
+
+        drop(submission);
+        tokio::time::timeout(Duration::from_secs(1), closing)
+            .await
+            .unwrap()
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_cancels_jump_queued_quotes() {
+        let throttle = SubmissionThrottle::new(RateLimit {
+            per_second: 20.0,
+            burst: 1,
+        });
+        throttle.acquire(SubmissionPriority::Quote).await;
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for priority in [SubmissionPriority::Quote, SubmissionPriority::Cancel] {
+            let (throttle, tx) = (throttle.clone(), tx.clone());
+            tokio::spawn(async move {
+                throttle.acquire(priority).await;
+                tx.send(priority).unwrap();
+            });
+            tokio::task::yield_now().await;
+        }
+
+        assert_eq!(rx.recv().await, Some(SubmissionPriority::Cancel));
+        assert_eq!(rx.recv().await, Some(SubmissionPriority::Quote));
+        assert_eq!(throttle.queued(), 0);
+    }
+
+    #[tokio::test]
+    async fn test_request_limiter_honours_pause() {
+        let limiter = RequestLimiter::new(RateLimit {
+            per_second: 1_000.0,
+            burst: 5,
+        });
+        let started = Instant::now();
+        for _ in 0..5 {
+            limiter.acquire().await;
+        }
+        assert!(started.elapsed() < Duration::from_millis(20));
+
+        limiter.clone().pause_for(Duration::from_millis(50));
+        limiter.acquire().await;
+        assert!(started.elapsed() >= Duration::from_millis(50));
+    }
+}
@@ -0,0 +1,567 @@
+//! Order lifecycle tracking.
+//!
+//! An [`OrderTracker`] follows orders from submission to a final state and
+//! reports each step as an [`OrderEvent`]. It works from periodic snapshots
+//! of the account's open orders, positions and recent trades;
+//! [`OrderTracker::spawn`] polls them from a client.
+//!
+//! The venue's trades carry no order IDs, so fills are inferred. An open
+//! order whose remaining quantity drops was partially filled. An order that
+//! leaves the open-order list was filled if, since the last snapshot, the
+//! account's position moved its way by the remaining quantity, or new trades
+//! on its side at or inside its limit price cover it. Otherwise it expired
+//! if its expiry has passed, and was cancelled if not. Evidence is shared
+//! out oldest order first, so two orders cannot claim the same fill.
+//!
+//! Start the tracker before placing the orders it follows: positions and
+//! trades seen on the first snapshot are the baseline later changes are
+//! measured against.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::Arc;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use tokio::sync::{mpsc, Mutex};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+use crate::client::{to_canonical_units, FermiClient};
+use crate::error::Result;
+use crate::types::{
+    MarketInfo, OpenOrder, OrderResult, PerpOrder, Position, Rounding, Side, Trade,
+};
+
+/// Where an order is in its lifecycle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OrderState {
+    /// Acknowledged by the sequencer
+    Accepted,
+    /// Seen resting on the book
+    Resting,
+    /// Part of the order traded; the rest is still open
+    PartiallyFilled,
+    Filled,
+    Cancelled,
+    /// Left the book unfilled after its expiry
+    Expired,
+}
+
+impl OrderState {
+    /// Whether the order can change no further.
+    pub fn is_final(self) -> bool {
+        matches!(self, Self::Filled | Self::Cancelled | Self::Expired)
+    }
+}
+
+/// A tracked order reaching a new state.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderEvent {
+    pub order_id: u64,
+    pub market_id: String,
+    pub state: OrderState,
+    /// Base quantity filled so far, in human units
+    pub filled_quantity: f64,
+    /// Base quantity still open, in human units
+    pub remaining_quantity: f64,
+}
+
+/// Account state at one moment, as the tracker compares it.
+#[derive(Debug, Clone, Default)]
+pub struct TrackerSnapshot {
+    pub open_orders: Vec<OpenOrder>,
+    pub positions: Vec<Position>,
+    /// Recent trades per market. A market left out gives no trade evidence.
+    pub trades: HashMap<String, Vec<Trade>>,
+    /// Unix time of the snapshot, in seconds
+    pub now_secs: u64,
+}
+
+impl TrackerSnapshot {
+    /// Fetch the account's open orders and positions, and the recent trades
+    /// of `markets`, from `client`.
+    ///
+    /// A market whose trades cannot be fetched is logged and left out.
+    pub async fn fetch(client: &FermiClient, markets: Vec<String>) -> Result<Self> {
+        let open_orders = client.get_my_orders().await?;
+        let positions = client.get_positions().await?;
+        let mut trades = HashMap::new();
+        for market_id in markets {
+            match client.get_trades(&market_id).await {
+                Ok(recent) => {
+                    trades.insert(market_id, recent);
+                }
+                Err(e) => warn!("Order tracker failed to fetch trades: {}", e),
+            }
+        }
+        Ok(Self {
+            open_orders,
+            positions,
+            trades,
+            now_secs: SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_secs()),
+        })
+    }
+}
+
+struct TrackedOrder {
+    market_id: String,
+    base_decimals: u8,
+    side: Side,
+    /// Limit price in canonical quote units
+    price: u64,
+    /// Quantity in canonical base units
+    quantity: u64,
+    filled: u64,
+    /// Expiry reported by the open-orders endpoint, once seen
+    expiry: Option<u64>,
+    resting: bool,
+}
+
+impl TrackedOrder {
+    fn event(&self, order_id: u64, state: OrderState) -> OrderEvent {
+        let scale = 10f64.powi(self.base_decimals as i32);
+        OrderEvent {
+            order_id,
+            market_id: self.market_id.clone(),
+            state,
+            filled_quantity: self.filled as f64 / scale,
+            remaining_quantity: self.quantity.saturating_sub(self.filled) as f64 / scale,
+        }
+    }
+
+    /// Whether `trade` filled this order's side at an acceptable price.
+    fn matches(&self, owner: &str, trade: &Trade) -> bool {
+        match self.side {
+            Side::Buy => trade.buyer_owner == owner && trade.price <= self.price,
+            Side::Sell => trade.seller_owner == owner && trade.price >= self.price,
+        }
+    }
+}
+
+type TradeKey = (u64, u64, u64, String, String);
+
+fn trade_key(trade: &Trade) -> TradeKey {
+    (
+        trade.timestamp,
+        trade.price,
+        trade.quantity,
+        trade.buyer_owner.clone(),
+        trade.seller_owner.clone(),
+    )
+}
+
+/// Follows submitted orders to a final state.
+pub struct OrderTracker {
+    owner: String,
+    orders: BTreeMap<u64, TrackedOrder>,
+    pending: Vec<OrderEvent>,
+    /// Signed position per market at the last snapshot, in canonical units
+    positions: Option<HashMap<String, i128>>,
+    /// Trades already accounted for, per market
+    seen_trades: HashMap<String, HashSet<TradeKey>>,
+}
+
+impl OrderTracker {
+    /// A tracker for the orders of `owner`, the account's public key.
+    pub fn new(owner: impl Into<String>) -> Self {
+        Self {
+            owner: owner.into(),
+            orders: BTreeMap::new(),
+            pending: Vec::new(),
+            positions: None,
+            seen_trades: HashMap::new(),
+        }
+    }
+
+    /// Start following an order placed in `market`.
+    ///
+    /// Queues an [`OrderState::Accepted`] event, returned by the next
+    /// [`observe`](Self::observe).
+    pub fn track(
+        &mut self,
+        market: &MarketInfo,
+        result: &OrderResult,
+        order: &PerpOrder,
+    ) -> Result<()> {
+        let tracked = TrackedOrder {
+            market_id: market.uuid.clone(),
+            base_decimals: market.base_decimals,
+            side: order.side,
+            price: to_canonical_units(order.price, market.quote_decimals, Rounding::Nearest)?,
+            quantity: to_canonical_units(order.quantity, market.base_decimals, Rounding::Nearest)?,
+            filled: 0,
+            expiry: None,
+            resting: false,
+        };
+        self.pending
+            .push(tracked.event(result.order_id, OrderState::Accepted));
+        self.orders.insert(result.order_id, tracked);
+        Ok(())
+    }
+
+    /// Start following an order already resting in `market`, from its
+    /// open-order entry. Fills before `open` was fetched are not reported.
+    pub fn track_open(&mut self, market: &MarketInfo, open: &OpenOrder) {
+        let side = if open.side.eq_ignore_ascii_case("buy") || open.side.eq_ignore_ascii_case("bid")
+        {
+            Side::Buy
+        } else {
+            Side::Sell
+        };
+        let tracked = TrackedOrder {
+            market_id: market.uuid.clone(),
+            base_decimals: market.base_decimals,
+            side,
+            price: open.price,
+            quantity: open.quantity,
+            filled: 0,
+            expiry: Some(open.expiry),
+            resting: true,
+        };
+        self.orders.insert(open.order_id, tracked);
+    }
+
+    /// Stop following an order without reporting a final state.
+    pub fn untrack(&mut self, order_id: u64) -> bool {
+        self.orders.remove(&order_id).is_some()
+    }
+
+    /// IDs of the orders still followed.
+    pub fn tracked(&self) -> Vec<u64> {
+        self.orders.keys().copied().collect()
+    }
+
+    /// Markets with followed orders, whose trades the next snapshot needs.
+    pub fn markets(&self) -> Vec<String> {
+        let markets: HashSet<&String> = self.orders.values().map(|o| &o.market_id).collect();
+        markets.into_iter().cloned().collect()
+    }
+
+    /// Compare a snapshot with the previous one and report what changed.
+    ///
+    /// Orders that reach a final state are no longer followed.
+    pub fn observe(&mut self, snapshot: &TrackerSnapshot) -> Vec<OrderEvent> {
+        let mut events = std::mem::take(&mut self.pending);
+
+        let positions: HashMap<String, i128> = snapshot
+            .positions
+            .iter()
+            .filter(|p| p.owner == self.owner)
+            .filter_map(|p| Some((p.market_id.clone(), canonical_position(&p.base_position)?)))
+            .collect();
+        // Base quantity each market's position moved since the last snapshot
+        let mut moved: HashMap<String, i128> = HashMap::new();
+        if let Some(previous) = &self.positions {
+            for market_id in self.markets() {
+                let before = previous.get(&market_id).copied().unwrap_or(0);
+                let after = positions.get(&market_id).copied().unwrap_or(0);
+                moved.insert(market_id, after - before);
+            }
+        }
+        self.positions = Some(positions);
+
+        // Trades not seen before, for markets with an earlier trade snapshot
+        let mut new_trades: HashMap<&str, Vec<(&Trade, bool)>> = HashMap::new();
+        for (market_id, trades) in &snapshot.trades {
+            let keys: HashSet<TradeKey> = trades.iter().map(trade_key).collect();
+            if let Some(seen) = self.seen_trades.get(market_id) {
+                let fresh = trades
+                    .iter()
+                    .filter(|t| !seen.contains(&trade_key(t)))
+                    .map(|t| (t, false))
+                    .collect();
+                new_trades.insert(market_id, fresh);
+            }
+            self.seen_trades.insert(market_id.clone(), keys);
+        }
+
+        let open: HashMap<u64, &OpenOrder> = snapshot
+            .open_orders
+            .iter()
+            .map(|o| (o.order_id, o))
+            .collect();
+        let mut finished = Vec::new();
+        for (&order_id, order) in self.orders.iter_mut() {
+            let direction = match order.side {
+                Side::Buy => 1,
+                Side::Sell => -1,
+            };
+            if let Some(open) = open.get(&order_id) {
+                order.expiry = Some(open.expiry);
+                if !order.resting {
+                    order.resting = true;
+                    events.push(order.event(order_id, OrderState::Resting));
+                }
+                let filled = order.quantity.saturating_sub(open.quantity);
+                if filled > order.filled {
+                    // Claim the position move this fill explains
+                    if let Some(delta) = moved.get_mut(&order.market_id) {
+                        *delta -= direction * (filled - order.filled) as i128;
+                    }
+                    order.filled = filled;
+                    events.push(order.event(order_id, OrderState::PartiallyFilled));
+                }
+                continue;
+            }
+
+            let remaining = order.quantity.saturating_sub(order.filled) as i128;
+            let by_position = moved
+                .get(&order.market_id)
+                .is_some_and(|delta| delta * direction >= remaining);
+            let trades = new_trades
+                .get_mut(order.market_id.as_str())
+                .map(Vec::as_mut_slice)
+                .unwrap_or_default();
+            let traded: i128 = trades
+                .iter()
+                .filter(|(t, used)| !used && order.matches(&self.owner, t))
+                .map(|(t, _)| t.quantity as i128)
+                .sum();
+            let state = if by_position || traded >= remaining {
+                if let Some(delta) = moved.get_mut(&order.market_id) {
+                    *delta -= direction * remaining;
+                }
+                let mut claimed = 0;
+                for (trade, used) in trades.iter_mut() {
+                    if claimed >= remaining {
+                        break;
+                    }
+                    if !*used && order.matches(&self.owner, trade) {
+                        *used = true;
+                        claimed += trade.quantity as i128;
+                    }
+                }
+                order.filled = order.quantity;
+                OrderState::Filled
+            } else if order.expiry.is_some_and(|e| snapshot.now_secs >= e) {
+                OrderState::Expired
+            } else {
+                OrderState::Cancelled
+            };
+            events.push(order.event(order_id, state));
+            finished.push(order_id);
+        }
+        for order_id in finished {
+            self.orders.remove(&order_id);
+        }
+        events
+    }
+
+    /// Poll `client` every `interval` and send the events of each snapshot.
+    ///
+    /// Orders can still be tracked through `tracker` while the task runs. A
+    /// failed poll is logged and skipped. The task stops when the returned
+    /// receiver is dropped.
+    pub fn spawn(
+        tracker: Arc<Mutex<OrderTracker>>,
+        client: Arc<FermiClient>,
+        interval: Duration,
+    ) -> (mpsc::UnboundedReceiver<OrderEvent>, JoinHandle<()>) {
+        let (tx, rx) = mpsc::unbounded_channel();
+
+        let handle = tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            loop {
+                ticker.tick().await;
+                if tx.is_closed() {
+                    break;
+                }
+                let markets = tracker.lock().await.markets();
+
+                let snapshot = match TrackerSnapshot::fetch(&client, markets).await {
+                    Ok(snapshot) => snapshot,
+                    Err(e) => {
+                        warn!("Order tracker failed to fetch account state: {}", e);
+                        continue;
+                    }
+                };
+
+                for event in tracker.lock().await.observe(&snapshot) {
+                    if tx.send(event).is_err() {
+                        return;
+                    }
+                }
+            }
+        });
+
+        (rx, handle)
+    }
+}
+
+/// A canonical position string as an integer, tolerating a decimal form.
+pub(crate) fn canonical_position(value: &str) -> Option<i128> {
+    let value = value.trim();
+    value
+        .parse()
+        .ok()
+        .or_else(|| value.parse::<f64>().ok().map(|v| v.round() as i128))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::types::test_market;
+
+    fn market() -> MarketInfo {
+        MarketInfo {
+            uuid: "m1".into(),
+            ..test_market(3, 2)
+        }
+    }
+
+    fn open(order_id: u64, quantity: u64, expiry: u64) -> OpenOrder {
+        OpenOrder {
+            order_id,
+            market_id: "m1".into(),
+            market_name: None,
+            owner: "me".into(),
+            side: "buy".into(),
+            price: 10_000,
+            quantity,
+            expiry,
+            timestamp: None,
+        }
+    }
+
+    fn position(base: i64) -> Position {
+        Position {
+            owner: "me".into(),
+            market_id: "m1".into(),
+            market_name: None,
+            base_position: base.to_string(),
+            average_entry_price: "0".into(),
+            mark_price: "0".into(),
+            realized_pnl: "0".into(),
+            unrealized_pnl: "0".into(),
+            cumulative_funding: None,
+            margin_mode: None,
+            leverage: None,
+        }
+    }
+
+    fn sell_to_me(price: u64, quantity: u64, timestamp: u64) -> Trade {
+        Trade {
+            buyer_owner: "me".into(),
+            seller_owner: "them".into(),
+            price,
+            quantity,
+            timestamp,
+            base_mint: String::new(),
+            quote_mint: String::new(),
+            liquidated_owner: None,
+            buyer_fee: None,
+            seller_fee: None,
+        }
+    }
+
+    fn result(order_id: u64) -> OrderResult {
+        OrderResult {
+            order_id,
+            sequence_number: 0,
+            expected_tick: 0,
+            tx_hash: String::new(),
+            immediate: None,
+        }
+    }
+
+    fn states(events: &[OrderEvent]) -> Vec<(u64, OrderState)> {
+        events.iter().map(|e| (e.order_id, e.state)).collect()
+    }
+
+    #[test]
+    fn test_lifecycle_from_snapshots() {
+        let market = market();
+        let mut tracker = OrderTracker::new("me");
+        let baseline = TrackerSnapshot {
+            positions: vec![position(0)],
+            trades: HashMap::from([("m1".to_string(), vec![sell_to_me(9_000, 500, 1)])]),
+            now_secs: 100,
+            ..TrackerSnapshot::default()
+        };
+        assert!(tracker.observe(&baseline).is_empty());
+
+        // Four 2.0 buys at 100.00
+        for id in 1..=4 {
+            tracker
+                .track(
+                    &market,
+                    &result(id),
+                    &PerpOrder::from_f64(Side::Buy, 100.0, 2.0).unwrap(),
+                )
+                .unwrap();
+        }
+        let resting = TrackerSnapshot {
+            open_orders: (1..=4).map(|id| open(id, 2_000, 200)).collect(),
+            ..baseline.clone()
+        };
+        let events = tracker.observe(&resting);
+        assert_eq!(events.len(), 8);
+        assert!(events[..4].iter().all(|e| e.state == OrderState::Accepted));
+        assert!(events[4..].iter().all(|e| e.state == OrderState::Resting));
+
+        // Order 1 half filled, order 2 filled (the position grew by 1.0 + 2.0),
+        // order 3 pulled before its expiry, order 4 still resting
+        let moved = TrackerSnapshot {
+            open_orders: vec![open(1, 1_000, 200), open(4, 2_000, 200)],
+            positions: vec![position(3_000)],
+            ..baseline.clone()
+        };
+        let events = tracker.observe(&moved);
+        assert_eq!(
+            states(&events),
+            vec![
+                (1, OrderState::PartiallyFilled),
+                (2, OrderState::Filled),
+                (3, OrderState::Cancelled),
+            ]
+        );
+        assert_eq!(events[0].filled_quantity, 1.0);
+        assert_eq!(events[0].remaining_quantity, 1.0);
+        assert_eq!(tracker.tracked(), vec![1, 4]);
+
+        // Order 1's rest fills through a trade; order 4 outlives its expiry
+        let last = TrackerSnapshot {
+            positions: vec![position(3_000)],
+            trades: HashMap::from([(
+                "m1".to_string(),
+                vec![sell_to_me(9_000, 500, 1), sell_to_me(9_950, 1_000, 2)],
+            )]),
+            now_secs: 250,
+            ..TrackerSnapshot::default()
+        };
+        let events = tracker.observe(&last);
+        assert_eq!(
+            states(&events),
+            vec![(1, OrderState::Filled), (4, OrderState::Expired)]
+        );
+        assert_eq!(events[0].filled_quantity, 2.0);
+        assert!(tracker.tracked().is_empty());
+    }
+
+    #[test]
+    fn test_track_open_order_tells_fill_from_cancel() {
+        let market = market();
+        let mut tracker = OrderTracker::new("me");
+        tracker.track_open(&market, &open(1, 2_000, 200));
+        tracker.track_open(&market, &open(2, 1_000, 200));
+        let baseline = TrackerSnapshot {
+            open_orders: vec![open(1, 2_000, 200), open(2, 1_000, 200)],
+            positions: vec![position(0)],
+            now_secs: 100,
+            ..TrackerSnapshot::default()
+        };
+        assert!(tracker.observe(&baseline).is_empty());
+
+        // Both leave the book; the position only explains order 1
+        let gone = TrackerSnapshot {
+            positions: vec![position(2_000)],
+            now_secs: 110,
+            ..TrackerSnapshot::default()
+        };
+        assert_eq!(
+            states(&tracker.observe(&gone)),
+            vec![(1, OrderState::Filled), (2, OrderState::Cancelled)]
+        );
+    }
+}
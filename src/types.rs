@@ -1,13 +1,18 @@
 use borsh::{BorshDeserialize, BorshSerialize};
+use rust_decimal::prelude::{FromPrimitive, ToPrimitive};
+use rust_decimal::{Decimal, RoundingStrategy};
 use serde::{Deserialize, Serialize};
 use std::fmt;
 use std::str::FromStr;
+use std::time::Duration;
+
+use crate::error::{Result as SdkResult, SdkError};
 
 // =============================================================================
 // Pubkey - 32-byte public key
 // =============================================================================
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash, BorshSerialize, BorshDeserialize)]
 pub struct Pubkey(pub [u8; 32]);
 
 impl Pubkey {
@@ -15,6 +20,7 @@ impl Pubkey {
         Pubkey(bytes)
     }
 
+    #[allow(clippy::should_implement_trait)]
     pub fn as_ref(&self) -> &[u8; 32] {
         &self.0
     }
@@ -24,12 +30,6 @@ impl Pubkey {
     }
 }
 
-impl Default for Pubkey {
-    fn default() -> Self {
-        Pubkey([0u8; 32])
-    }
-}
-
 impl fmt::Display for Pubkey {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", bs58::encode(&self.0).into_string())
@@ -102,7 +102,9 @@ impl fmt::Display for PositionEffect {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 pub enum MarginMode {
+    #[serde(alias = "cross")]
     Cross,
+    #[serde(alias = "isolated")]
     Isolated,
 }
 
@@ -120,7 +122,8 @@ impl fmt::Display for MarginMode {
 // =============================================================================
 
 /// Side of an order (user-friendly version)
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
 pub enum Side {
     Buy,
     Sell,
@@ -135,6 +138,66 @@ impl From<Side> for OrderSide {
     }
 }
 
+/// A market named by UUID or by symbol, such as "SOL-PERP".
+///
+/// Client methods that take a market accept either, along with `&str`,
+/// `String`, and `&MarketInfo`. A string shaped like a UUID converts to
+/// [`MarketRef::Uuid`] and anything else to [`MarketRef::Name`]; a name
+/// that matches no symbol is also tried as a UUID, so IDs in other formats
+/// still resolve.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum MarketRef {
+    Uuid(String),
+    Name(String),
+}
+
+impl MarketRef {
+    fn parse(value: String) -> Self {
+        let is_uuid = value.len() == 36
+            && value.char_indices().all(|(i, c)| match i {
+                8 | 13 | 18 | 23 => c == '-',
+                _ => c.is_ascii_hexdigit(),
+            });
+        if is_uuid {
+            MarketRef::Uuid(value)
+        } else {
+            MarketRef::Name(value)
+        }
+    }
+}
+
+impl From<&str> for MarketRef {
+    fn from(value: &str) -> Self {
+        Self::parse(value.to_string())
+    }
+}
+
+impl From<&String> for MarketRef {
+    fn from(value: &String) -> Self {
+        Self::parse(value.clone())
+    }
+}
+
+impl From<String> for MarketRef {
+    fn from(value: String) -> Self {
+        Self::parse(value)
+    }
+}
+
+impl From<&MarketInfo> for MarketRef {
+    fn from(market: &MarketInfo) -> Self {
+        MarketRef::Uuid(market.uuid.clone())
+    }
+}
+
+impl fmt::Display for MarketRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MarketRef::Uuid(value) | MarketRef::Name(value) => write!(f, "{}", value),
+        }
+    }
+}
+
 impl fmt::Display for Side {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
@@ -144,32 +207,313 @@ impl fmt::Display for Side {
     }
 }
 
-/// A perpetual order to be placed
-#[derive(Debug, Clone)]
+/// When a resting `TimeInForce::Gtc` order stops being valid; the other
+/// time-in-force values set their own lifetime
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OrderExpiry {
+    /// One hour after signing
+    #[default]
+    Default,
+    /// Valid for the given number of sequencer ticks; converted to a wall-clock
+    /// expiry using the sequencer's recent tick rate
+    Ticks(u64),
+}
+
+/// How long an order may rest on the book.
+///
+/// This governs the signed lifetime: an order's `expiry` is only used
+/// under `Gtc` and is ignored otherwise.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum TimeInForce {
+    /// Good till cancelled: rests until cancelled or its `expiry` lapses
+    #[default]
+    Gtc,
+    /// Good till time: rests for the given duration; `expiry` is ignored
+    Gtt(Duration),
+    /// Immediate or cancel: whatever does not fill on arrival is cancelled,
+    /// and a remainder the cancel misses lapses shortly after
+    Ioc,
+    /// Fill or kill: rejected before signing unless the book can fill the
+    /// whole quantity, then treated as `Ioc`
+    Fok,
+}
+
+impl TimeInForce {
+    /// Whether any unfilled remainder is cancelled right after placement.
+    pub fn is_immediate(self) -> bool {
+        matches!(self, TimeInForce::Ioc | TimeInForce::Fok)
+    }
+}
+
+/// Slippage bound for market orders that do not set `max_slippage_bps`
+pub const DEFAULT_MAX_SLIPPAGE_BPS: u32 = 100;
+
+/// How an order's price is chosen
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum OrderType {
+    /// Signed at `price` as given
+    #[default]
+    Limit,
+    /// Priced from the book at placement to fill immediately; `price` is
+    /// replaced by the worst level needed to fill the quantity
+    Market,
+}
+
+/// How prices and quantities finer than a market's decimals are converted
+/// to canonical units
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum Rounding {
+    /// Reject the order
+    Exact,
+    /// Round to the nearest unit, halves away from zero
+    #[default]
+    Nearest,
+    /// Round toward zero
+    Down,
+    /// Round away from zero
+    Up,
+}
+
+impl Rounding {
+    pub(crate) fn strategy(self) -> RoundingStrategy {
+        match self {
+            Rounding::Exact | Rounding::Down => RoundingStrategy::ToZero,
+            Rounding::Nearest => RoundingStrategy::MidpointAwayFromZero,
+            Rounding::Up => RoundingStrategy::AwayFromZero,
+        }
+    }
+}
+
+/// `value` as a whole number of `step`s, rounded per `rounding`. `None`
+/// under `Rounding::Exact` if `value` is not already a multiple.
+pub(crate) fn round_to_step(value: Decimal, step: Decimal, rounding: Rounding) -> Option<Decimal> {
+    if step <= Decimal::ZERO {
+        return Some(value);
+    }
+    let rounded = (value / step).round_dp_with_strategy(0, rounding.strategy()) * step;
+    (rounding != Rounding::Exact || rounded == value).then(|| rounded.normalize())
+}
+
+/// A perpetual order to be placed.
+///
+/// Price and quantity are exact decimals in human units; see
+/// [`PerpOrder::from_f64`] and [`lossy_decimal`] for `f64` inputs.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct PerpOrder {
     pub side: Side,
-    pub price: f64,
-    pub quantity: f64,
+    pub price: Decimal,
+    pub quantity: Decimal,
     pub leverage: u64,
     pub position_effect: PositionEffect,
     pub margin_mode: MarginMode,
     pub reduce_only: bool,
+    /// Lifetime under `TimeInForce::Gtc`; other time-in-force values ignore it
+    #[serde(default)]
+    pub expiry: OrderExpiry,
+    #[serde(default)]
+    pub order_type: OrderType,
+    /// Furthest a market order may fill from the best opposing price, in
+    /// basis points (`DEFAULT_MAX_SLIPPAGE_BPS` if unset)
+    #[serde(default)]
+    pub max_slippage_bps: Option<u32>,
+    #[serde(default)]
+    pub time_in_force: TimeInForce,
 }
 
 impl Default for PerpOrder {
     fn default() -> Self {
         Self {
             side: Side::Buy,
-            price: 0.0,
-            quantity: 0.0,
+            price: Decimal::ZERO,
+            quantity: Decimal::ZERO,
             leverage: 1,
             position_effect: PositionEffect::Open,
             margin_mode: MarginMode::Cross,
             reduce_only: false,
+            expiry: OrderExpiry::Default,
+            order_type: OrderType::Limit,
+            max_slippage_bps: None,
+            time_in_force: TimeInForce::Gtc,
         }
     }
 }
 
+impl PerpOrder {
+    /// Start an order from typed setters, checked by
+    /// [`PerpOrderBuilder::build`].
+    ///
+    /// ```rust,ignore
+    /// let order = PerpOrder::builder().buy().limit(185.5).qty(1.0).leverage(10).build()?;
+    /// ```
+    pub fn builder() -> PerpOrderBuilder {
+        PerpOrderBuilder::default()
+    }
+
+    /// An order with `f64` price and quantity, converted with
+    /// [`lossy_decimal`]. Other fields are defaulted.
+    pub fn from_f64(side: Side, price: f64, quantity: f64) -> SdkResult<Self> {
+        Ok(Self {
+            side,
+            price: lossy_decimal(price)?,
+            quantity: lossy_decimal(quantity)?,
+            ..Self::default()
+        })
+    }
+
+    /// Price as `f64`, for analytics and risk estimates.
+    pub fn price_f64(&self) -> f64 {
+        self.price.to_f64().unwrap_or_default()
+    }
+
+    /// Quantity as `f64`, for analytics and risk estimates.
+    pub fn quantity_f64(&self) -> f64 {
+        self.quantity.to_f64().unwrap_or_default()
+    }
+}
+
+/// Builds a [`PerpOrder`], validating it before it reaches the server.
+///
+/// Unset fields take [`PerpOrder::default`] values, except that price and
+/// quantity must be given.
+#[derive(Debug, Clone, Default)]
+pub struct PerpOrderBuilder {
+    order: PerpOrder,
+    /// First `f64` input that did not convert, reported by `build`
+    unconverted: Option<String>,
+}
+
+impl PerpOrderBuilder {
+    pub fn side(mut self, side: Side) -> Self {
+        self.order.side = side;
+        self
+    }
+
+    pub fn buy(self) -> Self {
+        self.side(Side::Buy)
+    }
+
+    pub fn sell(self) -> Self {
+        self.side(Side::Sell)
+    }
+
+    /// Limit price in human quote units.
+    pub fn price(mut self, price: Decimal) -> Self {
+        self.order.price = price;
+        self
+    }
+
+    /// Limit price from an `f64`, converted with [`lossy_decimal`].
+    pub fn limit(self, price: f64) -> Self {
+        self.convert("price", price, Self::price)
+    }
+
+    /// Market order, priced from the book at placement; no price needed.
+    pub fn market(mut self) -> Self {
+        self.order.order_type = OrderType::Market;
+        self
+    }
+
+    /// Quantity in human base units.
+    pub fn quantity(mut self, quantity: Decimal) -> Self {
+        self.order.quantity = quantity;
+        self
+    }
+
+    /// Quantity from an `f64`, converted with [`lossy_decimal`].
+    pub fn qty(self, quantity: f64) -> Self {
+        self.convert("quantity", quantity, Self::quantity)
+    }
+
+    fn convert(mut self, field: &str, value: f64, set: fn(Self, Decimal) -> Self) -> Self {
+        match lossy_decimal(value) {
+            Ok(value) => set(self, value),
+            Err(_) => {
+                self.unconverted
+                    .get_or_insert_with(|| format!("{} {} has no decimal value", field, value));
+                self
+            }
+        }
+    }
+
+    pub fn leverage(mut self, leverage: u64) -> Self {
+        self.order.leverage = leverage;
+        self
+    }
+
+    pub fn position_effect(mut self, effect: PositionEffect) -> Self {
+        self.order.position_effect = effect;
+        self
+    }
+
+    /// Close an existing position rather than open one.
+    pub fn close(self) -> Self {
+        self.position_effect(PositionEffect::Close)
+    }
+
+    /// Only reduce the position; requires [`close`](Self::close).
+    pub fn reduce_only(mut self) -> Self {
+        self.order.reduce_only = true;
+        self
+    }
+
+    pub fn margin_mode(mut self, mode: MarginMode) -> Self {
+        self.order.margin_mode = mode;
+        self
+    }
+
+    pub fn isolated(self) -> Self {
+        self.margin_mode(MarginMode::Isolated)
+    }
+
+    pub fn expiry(mut self, expiry: OrderExpiry) -> Self {
+        self.order.expiry = expiry;
+        self
+    }
+
+    pub fn time_in_force(mut self, time_in_force: TimeInForce) -> Self {
+        self.order.time_in_force = time_in_force;
+        self
+    }
+
+    pub fn max_slippage_bps(mut self, bps: u32) -> Self {
+        self.order.max_slippage_bps = Some(bps);
+        self
+    }
+
+    /// The order, or `SdkError::Validation` naming the first problem found.
+    /// An `f64` price or quantity that did not convert fails with
+    /// `SdkError::DecimalConversion`.
+    pub fn build(self) -> SdkResult<PerpOrder> {
+        if let Some(reason) = self.unconverted {
+            return Err(SdkError::DecimalConversion(reason));
+        }
+        let order = self.order;
+        let invalid = |reason: String| Err(SdkError::Validation(reason));
+        if order.leverage == 0 {
+            return invalid("leverage must be at least 1".into());
+        }
+        if order.quantity <= Decimal::ZERO {
+            return invalid(format!("quantity {} must be positive", order.quantity));
+        }
+        if order.order_type == OrderType::Limit && order.price <= Decimal::ZERO {
+            return invalid(format!("limit price {} must be positive", order.price));
+        }
+        if order.reduce_only && order.position_effect == PositionEffect::Open {
+            return invalid("reduce-only orders must close a position".into());
+        }
+        Ok(order)
+    }
+}
+
+/// `value` as a decimal, at the precision an `f64` reliably carries (about
+/// 15 significant digits): `0.29` becomes exactly 0.29 and `0.1 + 0.2`
+/// becomes 0.3. Digits beyond that are lost. NaN, infinities, and values
+/// outside `Decimal`'s range fail with `SdkError::DecimalConversion`.
+pub fn lossy_decimal(value: f64) -> SdkResult<Decimal> {
+    Decimal::from_f64(value)
+        .ok_or_else(|| SdkError::DecimalConversion(format!("{} has no decimal value", value)))
+}
+
 /// Result of placing an order
 #[derive(Debug, Clone)]
 pub struct OrderResult {
@@ -177,6 +521,18 @@ pub struct OrderResult {
     pub sequence_number: u64,
     pub expected_tick: u64,
     pub tx_hash: String,
+    /// For IOC and FOK orders, what filled before the remainder was
+    /// cancelled, if it could be measured
+    pub immediate: Option<ImmediateFill>,
+}
+
+/// What became of an IOC or FOK order once its remainder was cancelled.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ImmediateFill {
+    /// Base quantity filled, in human units
+    pub filled_quantity: f64,
+    /// Base quantity cancelled unfilled, in human units
+    pub cancelled_quantity: f64,
 }
 
 /// Result of cancelling an order
@@ -188,8 +544,81 @@ pub struct CancelResult {
     pub tx_hash: String,
 }
 
+/// Result of replacing an order with `FermiClient::replace_order`
+#[derive(Debug, Clone)]
+pub struct ReplaceResult {
+    pub cancel: CancelResult,
+    pub order: OrderResult,
+}
+
+/// Result of a transfer between accounts
+#[derive(Debug, Clone)]
+pub struct TransferResult {
+    pub nonce: u64,
+    pub sequence_number: u64,
+    pub expected_tick: u64,
+    pub tx_hash: String,
+}
+
+/// Direction of a collateral movement between wallet and margin account
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum CollateralAction {
+    Deposit,
+    Withdraw,
+}
+
+impl std::fmt::Display for CollateralAction {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            CollateralAction::Deposit => write!(f, "deposit"),
+            CollateralAction::Withdraw => write!(f, "withdraw"),
+        }
+    }
+}
+
+/// Result of a collateral deposit or withdrawal submission
+#[derive(Debug, Clone)]
+pub struct CollateralResult {
+    pub action: CollateralAction,
+    pub nonce: u64,
+    pub sequence_number: u64,
+    pub expected_tick: u64,
+    pub tx_hash: String,
+}
+
+/// Settlement state of a withdrawal
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum WithdrawalState {
+    /// Accepted by the node, waiting to be settled
+    Pending,
+    /// Settlement transaction sent
+    Processing,
+    Completed,
+    Failed,
+    #[serde(other)]
+    Unknown,
+}
+
+/// A withdrawal requested from the margin account
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Withdrawal {
+    pub nonce: u64,
+    pub owner: String,
+    pub mint: String,
+    /// Amount in canonical units of `mint`
+    pub amount: u64,
+    pub status: WithdrawalState,
+    #[serde(default)]
+    pub requested_at: Option<u64>,
+    /// On-chain transaction paying out the withdrawal, once sent
+    #[serde(default)]
+    pub settlement_tx: Option<String>,
+}
+
 /// Market information
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MarketInfo {
     pub uuid: String,
     pub base_mint: String,
@@ -210,10 +639,108 @@ pub struct MarketInfo {
     pub price_decimals: Option<u8>,
     #[serde(default)]
     pub open_interest: Option<i128>,
+    /// Smallest price * quantity accepted for orders that are not reduce-only
+    #[serde(default)]
+    pub min_notional: Option<f64>,
+}
+
+impl MarketInfo {
+    /// Price increment in human quote units: the coarser of one quote lot
+    /// and the market's price decimals.
+    pub fn price_step(&self) -> Decimal {
+        let lot = step(self.quote_lot_size, self.quote_decimals);
+        match self.price_decimals {
+            Some(decimals) => lot.max(step(1, decimals)),
+            None => lot,
+        }
+    }
+
+    /// Quantity increment in human base units: one base lot.
+    pub fn qty_step(&self) -> Decimal {
+        step(self.base_lot_size, self.base_decimals)
+    }
+
+    /// `price` rounded to the nearest [`price_step`](Self::price_step).
+    pub fn round_price(&self, price: Decimal) -> Decimal {
+        round_to_step(price, self.price_step(), Rounding::Nearest).unwrap_or(price)
+    }
+
+    /// `quantity` rounded to the nearest [`qty_step`](Self::qty_step).
+    pub fn round_qty(&self, quantity: Decimal) -> Decimal {
+        round_to_step(quantity, self.qty_step(), Rounding::Nearest).unwrap_or(quantity)
+    }
+
+    /// Round `order` onto the price and quantity steps per `rounding`, and
+    /// check it meets the minimum notional.
+    pub(crate) fn fit_order(&self, order: &mut PerpOrder, rounding: Rounding) -> SdkResult<()> {
+        let invalid = |reason: String| SdkError::InvalidOrder(format!("{}: {}", self.name, reason));
+
+        let price_step = self.price_step();
+        let price = round_to_step(order.price, price_step, rounding).ok_or_else(|| {
+            invalid(format!(
+                "price {} is not a multiple of {}",
+                order.price, price_step
+            ))
+        })?;
+        let qty_step = self.qty_step();
+        let quantity = round_to_step(order.quantity, qty_step, rounding).ok_or_else(|| {
+            invalid(format!(
+                "quantity {} is not a multiple of lot size {}",
+                order.quantity, qty_step
+            ))
+        })?;
+        if price.is_zero() {
+            return Err(invalid(format!("price {} rounds to zero", order.price)));
+        }
+        if quantity.is_zero() {
+            return Err(invalid(format!(
+                "quantity {} rounds to zero at lot size {}",
+                order.quantity, qty_step
+            )));
+        }
+        if let Some(min) = self.min_notional {
+            let notional = price * quantity;
+            if !order.reduce_only && notional < lossy_decimal(min)? {
+                return Err(invalid(format!(
+                    "notional {} is below the minimum {}",
+                    notional, min
+                )));
+            }
+        }
+
+        order.price = price;
+        order.quantity = quantity;
+        Ok(())
+    }
+}
+
+/// A perp market "m" named SOL-PERP with one-unit lots, for tests.
+#[cfg(test)]
+pub(crate) fn test_market(base_decimals: u8, quote_decimals: u8) -> MarketInfo {
+    MarketInfo {
+        uuid: "m".into(),
+        base_mint: "b".into(),
+        quote_mint: "q".into(),
+        name: "SOL-PERP".into(),
+        created_at: 0,
+        kind: "perp".into(),
+        base_decimals,
+        quote_decimals,
+        base_lot_size: 1,
+        quote_lot_size: 1,
+        price_decimals: None,
+        open_interest: None,
+        min_notional: None,
+    }
+}
+
+/// `lots` canonical units (at least one) at `decimals`, in human units.
+fn step(lots: u64, decimals: u8) -> Decimal {
+    (Decimal::from(lots.max(1)) * Decimal::new(1, decimals.min(28) as u32)).normalize()
 }
 
 /// A single order in the orderbook
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderbookEntry {
     pub order_id: u64,
     pub owner: String,
@@ -224,14 +751,203 @@ pub struct OrderbookEntry {
 }
 
 /// Orderbook data
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Orderbook {
     pub buys: Vec<OrderbookEntry>,
     pub sells: Vec<OrderbookEntry>,
 }
 
+impl Orderbook {
+    /// Highest-priced buy order.
+    pub fn best_bid(&self) -> Option<&OrderbookEntry> {
+        self.buys.iter().max_by_key(|o| o.price)
+    }
+
+    /// Lowest-priced sell order.
+    pub fn best_ask(&self) -> Option<&OrderbookEntry> {
+        self.sells.iter().min_by_key(|o| o.price)
+    }
+
+    /// The book with prices and quantities in human units, in the same order.
+    pub fn to_human(&self, market: &MarketInfo) -> HumanOrderbook {
+        let entry = |o: &OrderbookEntry| HumanBookEntry {
+            order_id: o.order_id,
+            owner: o.owner.clone(),
+            price: from_canonical(o.price, market.quote_decimals),
+            quantity: from_canonical(o.quantity, market.base_decimals),
+            expiry: o.expiry,
+        };
+        HumanOrderbook {
+            buys: self.buys.iter().map(entry).collect(),
+            sells: self.sells.iter().map(entry).collect(),
+        }
+    }
+
+    /// Copy of the book with every order from `owner` removed.
+    pub fn without_owner(&self, owner: &str) -> Orderbook {
+        Orderbook {
+            buys: self
+                .buys
+                .iter()
+                .filter(|o| o.owner != owner)
+                .cloned()
+                .collect(),
+            sells: self
+                .sells
+                .iter()
+                .filter(|o| o.owner != owner)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Copy of the book with only `owner`'s orders.
+    pub fn only_owner(&self, owner: &str) -> Orderbook {
+        Orderbook {
+            buys: self
+                .buys
+                .iter()
+                .filter(|o| o.owner == owner)
+                .cloned()
+                .collect(),
+            sells: self
+                .sells
+                .iter()
+                .filter(|o| o.owner == owner)
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Resting orders and size per owner, largest total size first.
+    pub fn owner_stats(&self) -> Vec<OwnerBookStats> {
+        let mut stats: std::collections::HashMap<&str, OwnerBookStats> =
+            std::collections::HashMap::new();
+        for (entries, is_buy) in [(&self.buys, true), (&self.sells, false)] {
+            for entry in entries {
+                let owner = stats.entry(&entry.owner).or_insert_with(|| OwnerBookStats {
+                    owner: entry.owner.clone(),
+                    ..Default::default()
+                });
+                if is_buy {
+                    owner.bid_orders += 1;
+                    owner.bid_size += entry.quantity;
+                } else {
+                    owner.ask_orders += 1;
+                    owner.ask_size += entry.quantity;
+                }
+            }
+        }
+        let mut stats: Vec<OwnerBookStats> = stats.into_values().collect();
+        stats.sort_by(|a, b| {
+            (b.bid_size + b.ask_size)
+                .cmp(&(a.bid_size + a.ask_size))
+                .then_with(|| a.owner.cmp(&b.owner))
+        });
+        stats
+    }
+
+    /// Check the snapshot's invariants: buys in descending and sells in
+    /// ascending price order, a non-zero price and quantity on every entry,
+    /// no order ID listed twice, and no crossed book.
+    ///
+    /// Several orders may rest at one price, so equal neighbouring prices
+    /// are allowed.
+    pub fn validate(&self) -> SdkResult<()> {
+        let invalid = |reason: String| Err(SdkError::InvalidOrderbook(reason));
+
+        if let Some(entry) = self
+            .buys
+            .iter()
+            .chain(&self.sells)
+            .find(|o| o.price == 0 || o.quantity == 0)
+        {
+            return invalid(format!(
+                "order {} has price {} and quantity {}",
+                entry.order_id, entry.price, entry.quantity
+            ));
+        }
+        if let Some(w) = self.buys.windows(2).find(|w| w[0].price < w[1].price) {
+            return invalid(format!(
+                "buy {} at {} sorted above buy {} at {}",
+                w[0].order_id, w[0].price, w[1].order_id, w[1].price
+            ));
+        }
+        if let Some(w) = self.sells.windows(2).find(|w| w[0].price > w[1].price) {
+            return invalid(format!(
+                "sell {} at {} sorted below sell {} at {}",
+                w[0].order_id, w[0].price, w[1].order_id, w[1].price
+            ));
+        }
+
+        let mut seen = std::collections::HashSet::new();
+        if let Some(dup) = self
+            .buys
+            .iter()
+            .chain(&self.sells)
+            .find(|o| !seen.insert(o.order_id))
+        {
+            return invalid(format!("order {} listed twice", dup.order_id));
+        }
+
+        if let (Some(bid), Some(ask)) = (self.best_bid(), self.best_ask()) {
+            if bid.price >= ask.price {
+                return invalid(format!(
+                    "crossed book: bid {} >= ask {}",
+                    bid.price, ask.price
+                ));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// A resting order with price and quantity in human units
+#[derive(Debug, Clone, PartialEq)]
+pub struct HumanBookEntry {
+    pub order_id: u64,
+    pub owner: String,
+    pub price: f64,
+    pub quantity: f64,
+    pub expiry: u64,
+}
+
+/// An [`Orderbook`] in human units, from [`Orderbook::to_human`]
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct HumanOrderbook {
+    pub buys: Vec<HumanBookEntry>,
+    pub sells: Vec<HumanBookEntry>,
+}
+
+impl HumanOrderbook {
+    /// Highest-priced buy order.
+    pub fn best_bid(&self) -> Option<&HumanBookEntry> {
+        self.buys.iter().max_by(|a, b| a.price.total_cmp(&b.price))
+    }
+
+    /// Lowest-priced sell order.
+    pub fn best_ask(&self) -> Option<&HumanBookEntry> {
+        self.sells.iter().min_by(|a, b| a.price.total_cmp(&b.price))
+    }
+
+    /// Midpoint of the best bid and ask, if both sides are quoted.
+    pub fn mid_price(&self) -> Option<f64> {
+        Some((self.best_bid()?.price + self.best_ask()?.price) / 2.0)
+    }
+}
+
+/// One owner's resting orders in a book, sizes in canonical base units
+#[derive(Debug, Clone, PartialEq, Eq, Default)]
+pub struct OwnerBookStats {
+    pub owner: String,
+    pub bid_orders: usize,
+    pub bid_size: u64,
+    pub ask_orders: usize,
+    pub ask_size: u64,
+}
+
 /// Depth data (Binance-style)
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Depth {
     #[serde(rename = "lastUpdateId")]
     pub last_update_id: u64,
@@ -239,8 +955,21 @@ pub struct Depth {
     pub asks: Vec<[String; 2]>,
 }
 
-/// Trade information
+/// Incremental depth change covering update IDs `first_update_id` through
+/// `last_update_id`. Each level carries its new total quantity; `"0"`
+/// removes it.
 #[derive(Debug, Clone, Deserialize)]
+pub struct DepthUpdate {
+    #[serde(rename = "firstUpdateId")]
+    pub first_update_id: u64,
+    #[serde(rename = "lastUpdateId")]
+    pub last_update_id: u64,
+    pub bids: Vec<[String; 2]>,
+    pub asks: Vec<[String; 2]>,
+}
+
+/// Trade information
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Trade {
     pub buyer_owner: String,
     pub seller_owner: String,
@@ -249,10 +978,391 @@ pub struct Trade {
     pub timestamp: u64,
     pub base_mint: String,
     pub quote_mint: String,
+    /// Account force-closed by this trade, if it was a liquidation
+    #[serde(default)]
+    pub liquidated_owner: Option<String>,
+    /// Fees charged to each side in canonical quote units (negative = rebate)
+    #[serde(default)]
+    pub buyer_fee: Option<i64>,
+    #[serde(default)]
+    pub seller_fee: Option<i64>,
 }
 
-/// Funding event
+impl Trade {
+    /// Fee `owner` paid on this trade, if reported and `owner` took part.
+    pub fn fee_for(&self, owner: &str) -> Option<i64> {
+        if self.buyer_owner == owner {
+            self.buyer_fee
+        } else if self.seller_owner == owner {
+            self.seller_fee
+        } else {
+            None
+        }
+    }
+
+    /// Price in human quote units.
+    pub fn price_f64(&self, market: &MarketInfo) -> f64 {
+        from_canonical(self.price, market.quote_decimals)
+    }
+
+    /// Quantity in human base units.
+    pub fn quantity_f64(&self, market: &MarketInfo) -> f64 {
+        from_canonical(self.quantity, market.base_decimals)
+    }
+}
+
+/// Filters and paging for trade history queries.
+///
+/// Unset fields are left to the node's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TradesQuery {
+    /// Maximum trades per page
+    pub limit: Option<u32>,
+    /// Only trades strictly before this timestamp
+    pub before: Option<u64>,
+    /// Only trades at or after this timestamp
+    pub after: Option<u64>,
+    /// Resume from a previous page's `next_cursor`
+    pub cursor: Option<String>,
+}
+
+impl TradesQuery {
+    pub(crate) fn to_query_string(&self) -> String {
+        history_query(self.limit, self.before, self.after, self.cursor.as_deref())
+    }
+
+    /// The query for the page after `page`, or `None` once history is
+    /// exhausted.
+    ///
+    /// Follows the node's cursor when it sends one. Otherwise a full page
+    /// (as long as `limit`) continues before its oldest trade.
+    pub(crate) fn next_page(&self, page: &Page<Trade>) -> Option<TradesQuery> {
+        if page.items.is_empty() {
+            return None;
+        }
+        if let Some(cursor) = &page.next_cursor {
+            return Some(TradesQuery {
+                cursor: Some(cursor.clone()),
+                ..self.clone()
+            });
+        }
+        let full = self.limit.is_some_and(|l| page.items.len() >= l as usize);
+        let oldest = page.items.iter().map(|t| t.timestamp).min()?;
+        (full && self.before != Some(oldest)).then(|| TradesQuery {
+            before: Some(oldest),
+            cursor: None,
+            ..self.clone()
+        })
+    }
+}
+
+/// Filters and paging for funding history queries.
+///
+/// Unset fields are left to the node's defaults.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FundingQuery {
+    /// Maximum events per page
+    pub limit: Option<u32>,
+    /// Only events strictly before this timestamp
+    pub before: Option<u64>,
+    /// Only events at or after this timestamp
+    pub after: Option<u64>,
+    /// Resume from a previous page's `next_cursor`
+    pub cursor: Option<String>,
+}
+
+impl FundingQuery {
+    pub(crate) fn to_query_string(&self) -> String {
+        history_query(self.limit, self.before, self.after, self.cursor.as_deref())
+    }
+}
+
+/// `?limit=..&before=..` for the set parameters, or empty if none are.
+fn history_query(
+    limit: Option<u32>,
+    before: Option<u64>,
+    after: Option<u64>,
+    cursor: Option<&str>,
+) -> String {
+    let params: Vec<String> = [
+        limit.map(|v| format!("limit={}", v)),
+        before.map(|v| format!("before={}", v)),
+        after.map(|v| format!("after={}", v)),
+        cursor.map(|v| format!("cursor={}", v)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if params.is_empty() {
+        String::new()
+    } else {
+        format!("?{}", params.join("&"))
+    }
+}
+
+/// One page of a history query
+#[derive(Debug, Clone)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Cursor for the next page, if the node paginates by cursor
+    pub next_cursor: Option<String>,
+}
+
+/// OHLCV candle in human units
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct Kline {
+    /// Start of the bucket, in the same units as trade timestamps
+    pub timestamp: u64,
+    pub open: f64,
+    pub high: f64,
+    pub low: f64,
+    pub close: f64,
+    /// Traded quantity in base units
+    pub volume: f64,
+}
+
+impl Kline {
+    /// Aggregate trades into candles of `interval` timestamp units.
+    ///
+    /// Trades may be in any order; buckets without trades are skipped.
+    pub fn from_trades(trades: &[Trade], market: &MarketInfo, interval: u64) -> Vec<Kline> {
+        let interval = interval.max(1);
+        let price_scale = 10f64.powi(market.quote_decimals as i32);
+        let qty_scale = 10f64.powi(market.base_decimals as i32);
+
+        let mut sorted: Vec<&Trade> = trades.iter().collect();
+        sorted.sort_by_key(|t| t.timestamp);
+
+        let mut klines: Vec<Kline> = Vec::new();
+        for trade in sorted {
+            let bucket = trade.timestamp - trade.timestamp % interval;
+            let price = trade.price as f64 / price_scale;
+            let volume = trade.quantity as f64 / qty_scale;
+
+            match klines.last_mut() {
+                Some(kline) if kline.timestamp == bucket => {
+                    kline.high = kline.high.max(price);
+                    kline.low = kline.low.min(price);
+                    kline.close = price;
+                    kline.volume += volume;
+                }
+                _ => klines.push(Kline {
+                    timestamp: bucket,
+                    open: price,
+                    high: price,
+                    low: price,
+                    close: price,
+                    volume,
+                }),
+            }
+        }
+        klines
+    }
+}
+
+/// Consecutive trades at one price merged into a single print
+#[derive(Debug, Clone, PartialEq)]
+pub struct AggTrade {
+    /// Timestamp of the first merged trade
+    pub first_timestamp: u64,
+    /// Timestamp of the last merged trade
+    pub last_timestamp: u64,
+    /// Price in human quote units
+    pub price: f64,
+    /// Aggressor side inferred by the tick rule; `None` until the first
+    /// price change is seen
+    pub side: Option<Side>,
+    /// Total quantity in human base units
+    pub quantity: f64,
+    /// Number of trades merged
+    pub count: usize,
+}
+
+impl AggTrade {
+    /// Merge consecutive trades at the same price and side that fall in the
+    /// same `window` of timestamp units.
+    ///
+    /// Trades carry no aggressor flag, so the side follows the tick rule: an
+    /// uptick is a buy, a downtick a sell, and an unchanged price keeps the
+    /// previous side.
+    pub fn from_trades(trades: &[Trade], market: &MarketInfo, window: u64) -> Vec<AggTrade> {
+        let window = window.max(1);
+        let price_scale = 10f64.powi(market.quote_decimals as i32);
+        let qty_scale = 10f64.powi(market.base_decimals as i32);
+
+        let mut sorted: Vec<&Trade> = trades.iter().collect();
+        sorted.sort_by_key(|t| t.timestamp);
+
+        let mut aggs: Vec<AggTrade> = Vec::new();
+        let mut last: Option<(u64, Option<Side>)> = None;
+        for trade in sorted {
+            let side = match last {
+                Some((price, _)) if trade.price > price => Some(Side::Buy),
+                Some((price, _)) if trade.price < price => Some(Side::Sell),
+                Some((_, side)) => side,
+                None => None,
+            };
+            last = Some((trade.price, side));
+            let price = trade.price as f64 / price_scale;
+            let quantity = trade.quantity as f64 / qty_scale;
+
+            match aggs.last_mut() {
+                Some(agg)
+                    if agg.price == price
+                        && agg.side == side
+                        && agg.first_timestamp / window == trade.timestamp / window =>
+                {
+                    agg.last_timestamp = trade.timestamp;
+                    agg.quantity += quantity;
+                    agg.count += 1;
+                }
+                _ => aggs.push(AggTrade {
+                    first_timestamp: trade.timestamp,
+                    last_timestamp: trade.timestamp,
+                    price,
+                    side,
+                    quantity,
+                    count: 1,
+                }),
+            }
+        }
+        aggs
+    }
+}
+
+/// A forced position close
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LiquidationEvent {
+    #[serde(default)]
+    pub market_id: String,
+    /// Liquidated account
+    pub owner: String,
+    /// Side of the closing trade (`Sell` closes a long)
+    pub side: Side,
+    /// Quantity in canonical base units
+    pub quantity: u64,
+    /// Price in canonical quote units
+    pub price: u64,
+    pub timestamp: u64,
+}
+
+impl LiquidationEvent {
+    /// The liquidation recorded by `trade`, if it was flagged as one.
+    pub fn from_trade(market_id: &str, trade: &Trade) -> Option<Self> {
+        let owner = trade.liquidated_owner.clone()?;
+        let side = if owner == trade.seller_owner {
+            Side::Sell
+        } else {
+            Side::Buy
+        };
+        Some(Self {
+            market_id: market_id.to_string(),
+            owner,
+            side,
+            quantity: trade.quantity,
+            price: trade.price,
+            timestamp: trade.timestamp,
+        })
+    }
+}
+
+/// One price source feeding a market's index
 #[derive(Debug, Clone, Deserialize)]
+pub struct IndexSource {
+    pub name: String,
+    /// Source price in canonical quote units
+    pub price: u64,
+    /// Share of the index, normally summing to 1 across sources
+    pub weight: f64,
+    #[serde(default)]
+    pub timestamp: Option<u64>,
+}
+
+/// Constituents of a market's index price
+#[derive(Debug, Clone, Deserialize)]
+pub struct IndexComposition {
+    pub market_id: String,
+    /// Published index price in canonical quote units
+    pub index_price: u64,
+    pub timestamp: u64,
+    pub sources: Vec<IndexSource>,
+}
+
+impl IndexComposition {
+    /// Index recomputed from the weighted sources, for comparison with
+    /// `index_price`. `None` if no source has positive weight.
+    pub fn weighted_price(&self) -> Option<f64> {
+        let total: f64 = self.sources.iter().map(|s| s.weight.max(0.0)).sum();
+        if total <= 0.0 {
+            return None;
+        }
+        let sum: f64 = self
+            .sources
+            .iter()
+            .map(|s| s.price as f64 * s.weight.max(0.0))
+            .sum();
+        Some(sum / total)
+    }
+
+    /// Largest deviation of any source from `index_price`, in basis points.
+    pub fn max_deviation_bps(&self) -> Option<f64> {
+        if self.index_price == 0 {
+            return None;
+        }
+        let index = self.index_price as f64;
+        self.sources
+            .iter()
+            .map(|s| (s.price as f64 - index).abs() / index * 10_000.0)
+            .max_by(|a, b| a.total_cmp(b))
+    }
+}
+
+/// Mark and index price of a market at one time
+#[derive(Debug, Clone, Deserialize)]
+pub struct MarketPrices {
+    pub market_id: String,
+    /// Canonical quote units
+    pub mark_price: u64,
+    /// Canonical quote units
+    pub index_price: u64,
+    pub timestamp: u64,
+}
+
+impl MarketPrices {
+    /// Mark price in human quote units.
+    pub fn mark_f64(&self, market: &MarketInfo) -> f64 {
+        from_canonical(self.mark_price, market.quote_decimals)
+    }
+
+    /// Index price in human quote units.
+    pub fn index_f64(&self, market: &MarketInfo) -> f64 {
+        from_canonical(self.index_price, market.quote_decimals)
+    }
+
+    /// Premium of mark over index in basis points; negative at a discount.
+    /// `None` without an index price.
+    pub fn basis_bps(&self) -> Option<f64> {
+        if self.index_price == 0 {
+            return None;
+        }
+        let index = self.index_price as f64;
+        Some((self.mark_price as f64 - index) / index * 10_000.0)
+    }
+}
+
+impl From<&FundingEvent> for MarketPrices {
+    fn from(event: &FundingEvent) -> Self {
+        Self {
+            market_id: event.market_id.clone(),
+            mark_price: event.mark_price,
+            index_price: event.index_price,
+            timestamp: event.timestamp,
+        }
+    }
+}
+
+/// Funding event
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct FundingEvent {
     pub market_id: String,
     pub timestamp: u64,
@@ -264,8 +1374,28 @@ pub struct FundingEvent {
     pub total_payment: String,
 }
 
-/// Position information
+/// Funding paid or received by one account for one interval
 #[derive(Debug, Clone, Deserialize)]
+pub struct FundingPayment {
+    pub market_id: String,
+    #[serde(default)]
+    pub market_name: Option<String>,
+    pub timestamp: u64,
+    pub interval_seconds: u64,
+    pub funding_rate_bps: i64,
+    /// Canonical quote units; positive when the account paid
+    pub amount: i64,
+}
+
+impl FundingPayment {
+    /// Amount in human quote units (positive = paid).
+    pub fn amount_f64(&self, market: &MarketInfo) -> f64 {
+        self.amount as f64 / 10f64.powi(market.quote_decimals as i32)
+    }
+}
+
+/// Position information
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
     pub owner: String,
     pub market_id: String,
@@ -278,10 +1408,114 @@ pub struct Position {
     pub unrealized_pnl: String,
     #[serde(default)]
     pub cumulative_funding: Option<String>,
+    /// Margin mode the position is held in, when the node reports it
+    #[serde(default)]
+    pub margin_mode: Option<MarginMode>,
+    /// Leverage the position was opened at, when the node reports it
+    #[serde(default)]
+    pub leverage: Option<u64>,
 }
 
-/// Open order
+impl Position {
+    /// Signed position size in human base units (positive = long, negative = short).
+    pub fn size(&self, market: &MarketInfo) -> SdkResult<f64> {
+        parse_canonical(&self.base_position, market.base_decimals, "base_position")
+    }
+
+    /// Average entry price in human quote units.
+    pub fn entry_price(&self, market: &MarketInfo) -> SdkResult<f64> {
+        parse_canonical(
+            &self.average_entry_price,
+            market.quote_decimals,
+            "average_entry_price",
+        )
+    }
+
+    /// Mark price in human quote units.
+    pub fn mark(&self, market: &MarketInfo) -> SdkResult<f64> {
+        parse_canonical(&self.mark_price, market.quote_decimals, "mark_price")
+    }
+
+    /// This position with every figure in human units.
+    pub fn to_human(&self, market: &MarketInfo) -> SdkResult<HumanPosition> {
+        HumanPosition::new(self, market)
+    }
+}
+
+/// A [`Position`] with its size in human base units and its prices and
+/// PnL in human quote units.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HumanPosition {
+    pub owner: String,
+    pub market_id: String,
+    pub market_name: String,
+    /// Positive = long, negative = short
+    pub size: f64,
+    pub entry_price: f64,
+    pub mark_price: f64,
+    pub realized_pnl: f64,
+    pub unrealized_pnl: f64,
+    pub cumulative_funding: Option<f64>,
+}
+
+impl HumanPosition {
+    pub fn new(position: &Position, market: &MarketInfo) -> SdkResult<Self> {
+        let quote = |value: &str, field: &str| parse_canonical(value, market.quote_decimals, field);
+        Ok(Self {
+            owner: position.owner.clone(),
+            market_id: position.market_id.clone(),
+            market_name: position
+                .market_name
+                .clone()
+                .unwrap_or_else(|| market.name.clone()),
+            size: position.size(market)?,
+            entry_price: position.entry_price(market)?,
+            mark_price: position.mark(market)?,
+            realized_pnl: quote(&position.realized_pnl, "realized_pnl")?,
+            unrealized_pnl: quote(&position.unrealized_pnl, "unrealized_pnl")?,
+            cumulative_funding: position
+                .cumulative_funding
+                .as_deref()
+                .map(|f| quote(f, "cumulative_funding"))
+                .transpose()?,
+        })
+    }
+
+    /// Absolute size valued at the mark price.
+    pub fn notional(&self) -> f64 {
+        self.size.abs() * self.mark_price
+    }
+}
+
+/// A canonical integer scaled down by `decimals`.
+fn from_canonical(value: u64, decimals: u8) -> f64 {
+    value as f64 / 10f64.powi(decimals as i32)
+}
+
+/// Parse a canonical integer string and scale it down by `decimals`.
+fn parse_canonical(value: &str, decimals: u8, field: &str) -> SdkResult<f64> {
+    let raw: f64 = value
+        .trim()
+        .parse()
+        .map_err(|e| SdkError::DecimalConversion(format!("{} '{}': {}", field, value, e)))?;
+    Ok(raw / 10f64.powi(decimals as i32))
+}
+
+/// Auto-deleveraging ranking for a position
 #[derive(Debug, Clone, Deserialize)]
+pub struct AdlIndicator {
+    pub owner: String,
+    pub market_id: String,
+    /// Bucket from 0 (safest) to 4 (first in line for forced reduction)
+    pub quantile: u8,
+    #[serde(default)]
+    pub rank: Option<u64>,
+    #[serde(default)]
+    pub queue_size: Option<u64>,
+}
+
+/// Open order
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OpenOrder {
     pub order_id: u64,
     pub market_id: String,
@@ -316,6 +1550,12 @@ pub struct AccountSummary {
     pub free_collateral_snapshot: Option<f64>,
     #[serde(default)]
     pub available_withdrawal_snapshot: Option<f64>,
+    /// Whether portfolio margining is enabled (absent on nodes without it)
+    #[serde(default)]
+    pub portfolio_margin: Option<bool>,
+    /// Fraction of hedged cross notional waived under portfolio margining
+    #[serde(default)]
+    pub portfolio_margin_offset: Option<f64>,
 }
 
 /// Token balances
@@ -332,6 +1572,191 @@ pub struct TokenBalance {
     pub reserved: String,
 }
 
+/// Insurance fund balance at one point in time
+#[derive(Debug, Clone, Deserialize)]
+pub struct InsuranceFundSnapshot {
+    pub timestamp: u64,
+    /// Balance in canonical units of the fund's token
+    pub balance: String,
+}
+
+/// Insurance fund state and balance history
+#[derive(Debug, Clone, Deserialize)]
+pub struct InsuranceFund {
+    pub mint: String,
+    /// Current balance in canonical units of `mint`
+    pub balance: String,
+    /// Past balances, oldest first
+    #[serde(default)]
+    pub history: Vec<InsuranceFundSnapshot>,
+}
+
+impl InsuranceFund {
+    /// Current balance in human units.
+    pub fn balance_ui(&self, decimals: u8) -> SdkResult<f64> {
+        parse_canonical(&self.balance, decimals, "balance")
+    }
+
+    /// Largest peak-to-trough decline across `history` and the current
+    /// balance, as a fraction of the peak (0.25 = 25%).
+    pub fn max_drawdown(&self) -> f64 {
+        let balances = self
+            .history
+            .iter()
+            .map(|s| s.balance.as_str())
+            .chain(std::iter::once(self.balance.as_str()))
+            .filter_map(|b| b.trim().parse::<f64>().ok());
+
+        let mut peak = 0.0f64;
+        let mut worst = 0.0f64;
+        for balance in balances {
+            peak = peak.max(balance);
+            if peak > 0.0 {
+                worst = worst.max((peak - balance) / peak);
+            }
+        }
+        worst
+    }
+}
+
+/// Maker and taker fees in basis points of notional (negative = rebate)
+#[derive(Debug, Clone, Copy, PartialEq, Default, Deserialize)]
+pub struct FeeSchedule {
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+}
+
+impl FeeSchedule {
+    /// Rate charged for `liquidity`, in basis points.
+    pub fn rate_bps(&self, liquidity: Liquidity) -> f64 {
+        match liquidity {
+            Liquidity::Maker => self.maker_bps,
+            Liquidity::Taker => self.taker_bps,
+        }
+    }
+
+    /// Fee on `notional` quote units for `liquidity` (negative = rebate).
+    pub fn fee(&self, notional: f64, liquidity: Liquidity) -> f64 {
+        notional * self.rate_bps(liquidity) / 10_000.0
+    }
+}
+
+/// Which side of the book a fill takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Liquidity {
+    /// Rests on the book and is filled later
+    Maker,
+    /// Fills immediately against resting orders
+    Taker,
+}
+
+/// How `FermiClient::estimate_fees` decides whether an order makes or takes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LiquidityAssumption {
+    Maker,
+    Taker,
+    /// Taker if the order crosses the current book, maker otherwise
+    FromBook,
+}
+
+/// An account's fee tier
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct FeeTier {
+    pub tier: String,
+    pub maker_bps: f64,
+    pub taker_bps: f64,
+    /// Trailing 30-day volume in quote units that qualified for the tier
+    #[serde(default)]
+    pub volume_30d: Option<f64>,
+}
+
+impl FeeTier {
+    pub fn schedule(&self) -> FeeSchedule {
+        FeeSchedule {
+            maker_bps: self.maker_bps,
+            taker_bps: self.taker_bps,
+        }
+    }
+}
+
+/// Expected fee for an order, in human quote units
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FeeEstimate {
+    pub liquidity: Liquidity,
+    pub notional: f64,
+    pub rate_bps: f64,
+    /// Negative = rebate
+    pub fee: f64,
+}
+
+/// Margin requirement for positions up to a notional size
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MarginTier {
+    /// Upper notional bound in quote units (`None` = unbounded)
+    #[serde(default)]
+    pub max_notional: Option<f64>,
+    pub max_leverage: u64,
+    /// Fractions of notional, e.g. 0.05 for 5%
+    pub initial_margin_rate: f64,
+    pub maintenance_margin_rate: f64,
+}
+
+/// Trading rules for one market, with prices and sizes in human units
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct MarketRules {
+    pub market_id: String,
+    pub max_leverage: u64,
+    /// Prices must be a multiple of this (0 disables the check)
+    #[serde(default)]
+    pub tick_size: f64,
+    /// Quantities must be a multiple of this (0 disables the check)
+    #[serde(default)]
+    pub step_size: f64,
+    #[serde(default)]
+    pub min_quantity: f64,
+    #[serde(default)]
+    pub max_quantity: Option<f64>,
+    #[serde(default)]
+    pub min_notional: f64,
+    /// Ordered by `max_notional`, smallest first
+    #[serde(default)]
+    pub margin_tiers: Vec<MarginTier>,
+    /// Overrides the exchange-wide schedule
+    #[serde(default)]
+    pub fees: Option<FeeSchedule>,
+}
+
+impl MarketRules {
+    /// Tier that applies to a position of `notional` quote units.
+    pub fn margin_tier(&self, notional: f64) -> Option<&MarginTier> {
+        self.margin_tiers
+            .iter()
+            .find(|t| t.max_notional.is_none_or(|max| notional.abs() <= max))
+            .or(self.margin_tiers.last())
+    }
+}
+
+/// Global trading rules
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+pub struct ExchangeInfo {
+    pub fees: FeeSchedule,
+    pub markets: Vec<MarketRules>,
+}
+
+impl ExchangeInfo {
+    /// Rules for a market UUID.
+    pub fn market(&self, market_id: &str) -> Option<&MarketRules> {
+        self.markets.iter().find(|m| m.market_id == market_id)
+    }
+
+    /// Fees charged in a market, including any per-market override.
+    pub fn fees_for(&self, market_id: &str) -> FeeSchedule {
+        self.market(market_id)
+            .and_then(|m| m.fees)
+            .unwrap_or(self.fees)
+    }
+}
+
 // =============================================================================
 // Default token mints
 // =============================================================================
@@ -342,3 +1767,185 @@ pub const USDC_MINT: &str = "EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v";
 /// Testnet token mints
 pub const TESTNET_SOL: &str = "11111111111111111111111111111112";
 pub const TESTNET_USDC: &str = "11111111111111111111111111111113";
+
+/// Decimals of the default mints above, for tokens no listed market uses
+pub fn known_token_decimals(mint: &str) -> Option<u8> {
+    match mint {
+        SOL_MINT | TESTNET_SOL => Some(9),
+        USDC_MINT | TESTNET_USDC => Some(6),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_validates_order() {
+        let base = || {
+            PerpOrder::builder()
+                .buy()
+                .price(Decimal::new(18550, 2))
+                .quantity(Decimal::ONE)
+        };
+        let order = base().leverage(5).build().unwrap();
+        assert_eq!((order.price, order.leverage), (Decimal::new(18550, 2), 5));
+
+        let rejected = |builder: PerpOrderBuilder| {
+            assert!(matches!(builder.build(), Err(SdkError::Validation(_))));
+        };
+        rejected(base().leverage(0));
+        rejected(base().quantity(Decimal::ZERO));
+        rejected(base().quantity(-Decimal::ONE));
+        rejected(base().price(Decimal::ZERO));
+        rejected(base().price(-Decimal::ONE));
+        rejected(base().reduce_only());
+
+        // Market orders are priced at placement; reduce-only needs close
+        assert!(base().price(Decimal::ZERO).market().build().is_ok());
+        assert!(base().close().reduce_only().build().is_ok());
+    }
+
+    #[test]
+    fn test_perp_order_expiry_defaults_when_absent() {
+        let mut json = serde_json::to_value(PerpOrder {
+            expiry: OrderExpiry::Ticks(10),
+            ..Default::default()
+        })
+        .unwrap();
+        json.as_object_mut().unwrap().remove("expiry");
+        let order: PerpOrder = serde_json::from_value(json).unwrap();
+        assert_eq!(order.expiry, OrderExpiry::Default);
+    }
+
+    #[test]
+    fn test_non_finite_floats_are_rejected() {
+        assert!(matches!(
+            lossy_decimal(f64::NAN),
+            Err(SdkError::DecimalConversion(_))
+        ));
+        assert!(lossy_decimal(f64::INFINITY).is_err());
+        assert!(PerpOrder::from_f64(Side::Buy, f64::NAN, 1.0).is_err());
+
+        let builder = PerpOrder::builder().buy().limit(f64::NAN).qty(1.0);
+        assert!(matches!(
+            builder.build(),
+            Err(SdkError::DecimalConversion(_))
+        ));
+    }
+
+    #[test]
+    fn test_round_to_market_steps() {
+        let market = test_market(3, 2);
+        assert_eq!(
+            market.round_price(Decimal::new(185_537, 3)),
+            Decimal::new(18554, 2)
+        );
+        assert_eq!(
+            market.round_qty(Decimal::new(1_2345, 4)),
+            Decimal::new(1235, 3)
+        );
+
+        // The coarser of the quote lot and the price decimals wins
+        let coarse = MarketInfo {
+            quote_lot_size: 5,
+            ..test_market(3, 2)
+        };
+        assert_eq!(coarse.price_step(), Decimal::new(5, 2));
+        assert_eq!(
+            coarse.round_price(Decimal::new(18553, 2)),
+            Decimal::new(18555, 2)
+        );
+        let decimals = MarketInfo {
+            price_decimals: Some(1),
+            ..coarse
+        };
+        assert_eq!(
+            decimals.round_price(Decimal::new(18553, 2)),
+            Decimal::new(1855, 1)
+        );
+    }
+
+    #[test]
+    fn test_fit_order_rounds_and_checks_notional() {
+        let market = MarketInfo {
+            min_notional: Some(10.0),
+            ..test_market(3, 2)
+        };
+        let order = |price: i64, quantity: i64| PerpOrder {
+            price: Decimal::new(price, 3),
+            quantity: Decimal::new(quantity, 4),
+            ..PerpOrder::default()
+        };
+
+        let mut fitted = order(185_537, 1_2345);
+        market.fit_order(&mut fitted, Rounding::Down).unwrap();
+        assert_eq!(
+            (fitted.price, fitted.quantity),
+            (Decimal::new(18553, 2), Decimal::new(1234, 3))
+        );
+
+        let invalid = |mut order: PerpOrder, rounding| {
+            assert!(matches!(
+                market.fit_order(&mut order, rounding),
+                Err(SdkError::InvalidOrder(_))
+            ));
+        };
+        invalid(order(185_537, 1_2340), Rounding::Exact);
+        invalid(order(185_530, 1_2345), Rounding::Exact);
+        invalid(order(185_530, 4), Rounding::Nearest);
+        invalid(order(4, 1_0000), Rounding::Down);
+        // 9.99 notional is below the minimum unless the order only reduces
+        invalid(order(9_990, 1_0000), Rounding::Exact);
+        let mut reducing = PerpOrder {
+            reduce_only: true,
+            ..order(9_990, 1_0000)
+        };
+        assert!(market.fit_order(&mut reducing, Rounding::Exact).is_ok());
+    }
+
+    fn entry(order_id: u64, price: u64) -> OrderbookEntry {
+        OrderbookEntry {
+            order_id,
+            owner: "o".into(),
+            price,
+            quantity: 1,
+            side: String::new(),
+            expiry: 0,
+        }
+    }
+
+    #[test]
+    fn test_orderbook_validate() {
+        let book = |buys, sells| Orderbook { buys, sells };
+        let invalid = |book: Orderbook| {
+            assert!(matches!(
+                book.validate(),
+                Err(SdkError::InvalidOrderbook(_))
+            ));
+        };
+
+        // Equal neighbouring prices are several orders at one level
+        let valid = book(
+            vec![entry(1, 100), entry(2, 100), entry(3, 99)],
+            vec![entry(4, 101), entry(5, 102)],
+        );
+        assert!(valid.validate().is_ok());
+        assert!(book(vec![], vec![]).validate().is_ok());
+
+        invalid(book(vec![entry(1, 99), entry(2, 100)], vec![]));
+        invalid(book(vec![], vec![entry(1, 102), entry(2, 101)]));
+        invalid(book(vec![entry(1, 100)], vec![entry(1, 101)]));
+        invalid(book(vec![entry(1, 101)], vec![entry(2, 101)]));
+        invalid(book(vec![entry(1, 102)], vec![entry(2, 101)]));
+        invalid(book(vec![entry(1, 0)], vec![]));
+        invalid(book(
+            vec![OrderbookEntry {
+                quantity: 0,
+                ..entry(1, 100)
+            }],
+            vec![],
+        ));
+    }
+}